@@ -3,6 +3,8 @@ use crate::services::ollama::OllamaService;
 use crate::services::vector_search_service::VectorSearchService;
 use crate::services::database::Database;
 use crate::models::vector_index::CreateVectorIndex;
+use crate::models::task_extraction::{CommittedTask, TaskDraft, TaskExtraction, TaskSource};
+use crate::services::task_extraction::TaskExtractionService;
 use std::sync::Arc;
 use tauri::State;
 use tokio::sync::Mutex;
@@ -26,13 +28,38 @@ pub async fn rewrite(
     ollama.rewrite(&text, &style).await.map_err(|e| e.to_string())
 }
 
+/// Extract task drafts from text. With a `source` (message, document or
+/// file) relative due dates are read against the day it was written.
 #[tauri::command]
 pub async fn generate_tasks(
     text: String,
+    source: Option<TaskSource>,
     ollama: State<'_, Arc<Mutex<OllamaService>>>,
-) -> Result<Vec<String>, String> {
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<TaskExtraction, String> {
+    let source_date = match &source {
+        Some(source) => {
+            let db = db.lock().await;
+            TaskExtractionService::new(&db).source_date(source).map_err(|e| e.to_string())?
+        }
+        None => chrono::Local::now().date_naive(),
+    };
+
     let ollama = ollama.lock().await;
-    ollama.generate_tasks(&text).await.map_err(|e| e.to_string())
+    ollama.generate_tasks(&text, source_date).await.map_err(|e| e.to_string())
+}
+
+/// Save the drafts the user accepted, linked back to their source
+#[tauri::command]
+pub async fn commit_task_drafts(
+    drafts: Vec<TaskDraft>,
+    source: Option<TaskSource>,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<Vec<CommittedTask>, String> {
+    let db = db.lock().await;
+    TaskExtractionService::new(&db)
+        .commit_drafts(drafts, source.as_ref())
+        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
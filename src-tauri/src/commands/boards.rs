@@ -0,0 +1,133 @@
+use crate::models::board::{Board, BoardView, CreateBoard, CreateBoardColumn, MoveTask, UpdateBoard, UpdateBoardColumn};
+use crate::models::conflict::UpdateError;
+use crate::models::task::Task;
+use crate::services::boards::BoardService;
+use crate::services::database::Database;
+use std::sync::Arc;
+use tauri::State;
+use tokio::sync::Mutex;
+
+#[tauri::command]
+pub async fn get_all_boards(
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<Vec<Board>, String> {
+    let db = db.lock().await;
+    BoardService::new(&db)
+        .get_all_boards()
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_board(
+    id: i64,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<Board, String> {
+    let db = db.lock().await;
+    BoardService::new(&db)
+        .get_board(id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn create_board(
+    board: CreateBoard,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<Board, String> {
+    let db = db.lock().await;
+    BoardService::new(&db)
+        .create_board(board)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn update_board(
+    id: i64,
+    update: UpdateBoard,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<Board, String> {
+    let db = db.lock().await;
+    BoardService::new(&db)
+        .update_board(id, update)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_board(
+    id: i64,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<(), String> {
+    let db = db.lock().await;
+    BoardService::new(&db)
+        .delete_board(id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn add_board_column(
+    board_id: i64,
+    column: CreateBoardColumn,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<Board, String> {
+    let db = db.lock().await;
+    BoardService::new(&db)
+        .add_column(board_id, column)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn update_board_column(
+    id: i64,
+    update: UpdateBoardColumn,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<Board, String> {
+    let db = db.lock().await;
+    BoardService::new(&db)
+        .update_column(id, update)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_board_column(
+    id: i64,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<Board, String> {
+    let db = db.lock().await;
+    BoardService::new(&db)
+        .delete_column(id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn reorder_board_columns(
+    board_id: i64,
+    ordered_ids: Vec<i64>,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<Board, String> {
+    let db = db.lock().await;
+    BoardService::new(&db)
+        .reorder_columns(board_id, &ordered_ids)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_board_view(
+    id: i64,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<BoardView, String> {
+    let db = db.lock().await;
+    BoardService::new(&db)
+        .get_board_view(id)
+        .map_err(|e| e.to_string())
+}
+
+/// Drop a task into a column between two neighbours. Fails with a typed
+/// error when the task changed, is blocked, or the column is full.
+#[tauri::command]
+pub async fn move_task_on_board(
+    task_id: i64,
+    target: MoveTask,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<Task, UpdateError<Task>> {
+    let db = db.lock().await;
+    BoardService::new(&db).move_task(task_id, target)
+}
@@ -0,0 +1,63 @@
+use crate::models::calendar_subscription::{CalendarSubscription, CreateSubscription, UpdateSubscription};
+use crate::services::calendar_subscriptions::{refresh_subscription, SubscriptionService};
+use crate::services::database::Database;
+use std::sync::Arc;
+use tauri::State;
+use tokio::sync::Mutex;
+
+/// Create the subscription and fetch it right away
+#[tauri::command]
+pub async fn create_calendar_subscription(
+    subscription: CreateSubscription,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<CalendarSubscription, String> {
+    let created = {
+        let db = db.lock().await;
+        SubscriptionService::new(&db)
+            .create_subscription(subscription)
+            .map_err(|e| e.to_string())?
+    };
+    refresh_subscription(&db, created.id).await
+}
+
+#[tauri::command]
+pub async fn get_calendar_subscriptions(
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<Vec<CalendarSubscription>, String> {
+    let db = db.lock().await;
+    SubscriptionService::new(&db)
+        .get_all_subscriptions()
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn update_calendar_subscription(
+    id: i64,
+    update: UpdateSubscription,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<CalendarSubscription, String> {
+    let db = db.lock().await;
+    SubscriptionService::new(&db)
+        .update_subscription(id, update)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_calendar_subscription(
+    id: i64,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<(), String> {
+    let db = db.lock().await;
+    SubscriptionService::new(&db)
+        .delete_subscription(id)
+        .map_err(|e| e.to_string())
+}
+
+/// Fetch the feed now instead of waiting for the next scheduled refresh
+#[tauri::command]
+pub async fn refresh_calendar_subscription(
+    id: i64,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<CalendarSubscription, String> {
+    refresh_subscription(&db, id).await
+}
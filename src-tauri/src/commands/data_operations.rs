@@ -3,12 +3,12 @@ use crate::services::database::Database;
 use crate::services::ollama::OllamaService;
 use crate::services::file_processor::{FileProcessor, ProcessedContent};
 use crate::services::lancedb_service::LanceDBService;
+use crate::services::blob_store::BlobStore;
+use crate::services::file_manager::FileManager;
 use std::sync::Arc;
 use tauri::State;
 use tokio::sync::Mutex;
 use std::path::Path;
-use std::fs;
-use sha2::{Sha256, Digest};
 use serde_json;
 
 #[tauri::command]
@@ -114,15 +114,11 @@ pub async fn process_files_for_job(
     // Skip job status update for now to avoid hanging
     println!("Skipping job status update to avoid hanging");
     
-    // Create ingestion directory in app data directory
-    let app_data_dir = dirs::data_dir().ok_or("Could not get app data directory")?;
-    let ingest_dir = app_data_dir.join("PlayData").join("ingest");
-    if !ingest_dir.exists() {
-        std::fs::create_dir_all(&ingest_dir).map_err(|e| {
-            println!("Error creating ingest directory: {}", e);
-            e.to_string()
-        })?;
-    }
+    // Files are copied into the shared blob store, so each content is kept once
+    let ingest_dir = FileManager::blob_root().map_err(|e| {
+        println!("Error locating blob store: {}", e);
+        e.to_string()
+    })?;
     
     let mut processed_count = 0;
     let mut error_count = 0;
@@ -145,7 +141,7 @@ pub async fn process_files_for_job(
         println!("About to call tokio::time::timeout...");
         match tokio::time::timeout(
             std::time::Duration::from_secs(60), // 60 second timeout
-            process_single_file(&service, job_id, file_path, &ingest_dir, &ollama, &lancedb)
+            process_single_file(&service, db.inner(), job_id, file_path, &ingest_dir, &ollama, &lancedb)
         ).await {
             Ok(Ok(_)) => {
                 processed_count += 1;
@@ -177,6 +173,7 @@ pub async fn process_files_for_job(
 /// Process a single file through the complete pipeline
 async fn process_single_file(
     service: &DataOperationsService,
+    db: &Arc<Mutex<Database>>,
     job_id: i64,
     file_path: &str,
     ingest_dir: &Path,
@@ -195,18 +192,46 @@ async fn process_single_file(
         .to_string_lossy()
         .to_string();
     
-    let ingest_path = ingest_dir.join(&file_name);
-    
-    // Copy the actual file to ingestion directory
-    fs::copy(file_path, &ingest_path).map_err(|e| {
-        format!("Failed to copy file to ingest directory: {}", e)
-    })?;
+    // Copy the actual file into the blob store; identical content is shared.
+    // The copy is hashed as it is written, which also gives the duplicate key.
+    let blob = {
+        let db = db.lock().await;
+        BlobStore::new(db.get_connection(), ingest_dir.to_path_buf())
+            .map_err(|e| e.to_string())?
+            .store_file(Path::new(file_path))
+            .map_err(|e| format!("Failed to copy file to ingest directory: {}", e))?
+    };
+    let ingest_path = ingest_dir.join(&blob.path);
     
     println!("File copied to: {:?}", ingest_path);
     
+    // The processed file keeps the blob's reference; anything else gives it back
+    let outcome = process_stored_file(service, job_id, &file_name, &ingest_path, &blob.hash, ollama, lancedb).await;
+    if !matches!(outcome, Ok(true)) {
+        let db = db.lock().await;
+        if let Err(e) = BlobStore::new(db.get_connection(), ingest_dir.to_path_buf()).map_err(|e| e.to_string())
+            .and_then(|store| store.release(&blob.hash).map_err(|e| e.to_string())) {
+            println!("Failed to release blob {}: {}", blob.hash, e);
+        }
+    }
+    outcome.map(|_| ())
+}
+
+/// Extract, clean, chunk, embed and store a file already in the blob store.
+/// Returns whether a processed file was stored.
+async fn process_stored_file(
+    service: &DataOperationsService,
+    job_id: i64,
+    file_name: &str,
+    ingest_path: &Path,
+    file_hash: &str,
+    ollama: &Arc<Mutex<OllamaService>>,
+    lancedb: &Arc<Mutex<LanceDBService>>,
+) -> Result<bool, String> {
+    
     // Step 2: Process file content using FileProcessor
     println!("Starting file content processing...");
-    let processed_content = FileProcessor::process_file(&ingest_path).await.map_err(|e| {
+    let processed_content = FileProcessor::process_file(ingest_path).await.map_err(|e| {
         format!("Failed to process file content: {}", e)
     })?;
     
@@ -214,11 +239,10 @@ async fn process_single_file(
     
     if processed_content.text.is_empty() {
         println!("WARNING: No text extracted from file. File might be empty or unsupported format.");
-        return Ok(());
+        return Ok(false);
     }
     
-    // Step 3: Generate file hash for duplicate detection
-    let file_hash = generate_file_hash(&ingest_path)?;
+    // Step 3: The blob's hash is the file hash for duplicate detection
     println!("File hash: {}", file_hash);
     
     // Step 4: AI-powered text cleaning
@@ -244,7 +268,7 @@ async fn process_single_file(
     
     if chunks.is_empty() {
         println!("WARNING: No chunks generated from text. Text length: {}", cleaned_text.len());
-        return Ok(());
+        return Ok(false);
     }
     
     // Debug: Show first chunk
@@ -317,28 +341,16 @@ async fn process_single_file(
     let file_id = store_processed_file(
         service,
         job_id,
-        &file_name,
+        file_name,
         &ingest_path.to_string_lossy(),
         &processed_content,
-        &file_hash,
+        file_hash,
         &chunks_with_embeddings,
     ).await?;
     
     println!("Stored processed file with ID: {}", file_id);
     
-    Ok(())
-}
-
-/// Generate SHA256 hash of a file
-fn generate_file_hash(file_path: &Path) -> Result<String, String> {
-    let file_content = fs::read(file_path).map_err(|e| {
-        format!("Failed to read file for hashing: {}", e)
-    })?;
-    
-    let mut hasher = Sha256::new();
-    hasher.update(&file_content);
-    let hash = hasher.finalize();
-    Ok(format!("{:x}", hash))
+    Ok(true)
 }
 
 /// Clean text using AI
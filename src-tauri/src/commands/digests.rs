@@ -0,0 +1,33 @@
+use crate::models::digest::{Digest, DigestSchedule};
+use crate::services::database::Database;
+use crate::services::digests::{generate_digest as build_digest, DigestService};
+use crate::services::ollama::OllamaService;
+use std::sync::Arc;
+use tauri::State;
+use tokio::sync::Mutex;
+
+#[tauri::command]
+pub async fn get_digest_schedule(db: State<'_, Arc<Mutex<Database>>>) -> Result<DigestSchedule, String> {
+    let db = db.lock().await;
+    DigestService::new(&db).get_schedule().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn update_digest_schedule(
+    schedule: DigestSchedule,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<DigestSchedule, String> {
+    let db = db.lock().await;
+    DigestService::new(&db).set_schedule(schedule).map_err(|e| e.to_string())
+}
+
+/// Build the "morning" or "weekly" digest now; `save` (default true) keeps it as a document
+#[tauri::command]
+pub async fn generate_digest(
+    kind: String,
+    save: Option<bool>,
+    db: State<'_, Arc<Mutex<Database>>>,
+    ollama: State<'_, Arc<Mutex<OllamaService>>>,
+) -> Result<Digest, String> {
+    build_digest(db.inner(), ollama.inner(), &kind, save.unwrap_or(true)).await
+}
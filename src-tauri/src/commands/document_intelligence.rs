@@ -1,5 +1,6 @@
 use crate::services::document_intelligence_service::{DocumentIntelligenceService, DocumentIntelligenceStats};
 use crate::services::database::Database;
+use crate::services::tags::TagService;
 use crate::services::ollama::OllamaService;
 use tauri::State;
 use std::sync::Arc;
@@ -26,16 +27,27 @@ pub async fn process_document_intelligence(
 #[tauri::command]
 pub async fn auto_tag_document(
     content: String,
+    document_id: Option<i64>,
     db: State<'_, Arc<Mutex<Database>>>,
     ollama: State<'_, Arc<Mutex<OllamaService>>>,
 ) -> Result<Vec<String>, String> {
     let lancedb_path = PathBuf::from("data/lancedb");
     let service = DocumentIntelligenceService::new(&lancedb_path, db.inner().clone(), ollama.inner().clone()).await?;
     
-    service
+    let tags = service
         .auto_tag_document(&content)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())?;
+
+    // Keep AI tags as suggestions until the user accepts them
+    if let Some(document_id) = document_id {
+        let db = db.lock().await;
+        TagService::new(db.get_connection())
+            .record_suggestions("document", document_id, &tags, "ai")
+            .map_err(|e| e.to_string())?;
+    }
+
+    Ok(tags)
 }
 
 #[tauri::command]
@@ -71,6 +83,7 @@ pub async fn find_related_documents(
 
 #[tauri::command]
 pub async fn process_all_documents_intelligence(
+    folder_id: Option<i64>,
     db: State<'_, Arc<Mutex<Database>>>,
     ollama: State<'_, Arc<Mutex<OllamaService>>>,
 ) -> Result<i64, String> {
@@ -78,7 +91,7 @@ pub async fn process_all_documents_intelligence(
     let service = DocumentIntelligenceService::new(&lancedb_path, db.inner().clone(), ollama.inner().clone()).await?;
     
     service
-        .process_all_documents()
+        .process_all_documents(folder_id)
         .await
         .map_err(|e| e.to_string())
 }
@@ -1,5 +1,8 @@
+use crate::models::conflict::UpdateError;
 use crate::models::document::{CreateDocument, Document, UpdateDocument};
+use crate::models::outline::DocumentOutline;
 use crate::services::database::Database;
+use crate::services::outline::parse_outline;
 use std::sync::Arc;
 use tauri::State;
 use tokio::sync::Mutex;
@@ -32,14 +35,16 @@ pub async fn get_all_documents(
     db.get_all_documents(limit).map_err(|e| e.to_string())
 }
 
+/// Fails with `UpdateError::Conflict` (carrying the stored document) when
+/// `update.expected_version` / `expected_updated_at` is stale.
 #[tauri::command]
 pub async fn update_document(
     id: i64,
     update: UpdateDocument,
     db: State<'_, Arc<Mutex<Database>>>,
-) -> Result<Document, String> {
+) -> Result<Document, UpdateError<Document>> {
     let db = db.lock().await;
-    db.update_document(id, update).map_err(|e| e.to_string())
+    db.update_document(id, update)
 }
 
 #[tauri::command]
@@ -51,3 +56,17 @@ pub async fn delete_document(
     db.delete_document(id).map_err(|e| e.to_string())
 }
 
+
+#[tauri::command]
+pub async fn get_document_outline(
+    id: i64,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<DocumentOutline, String> {
+    let db = db.lock().await;
+    let document = db.get_document(id).map_err(|e| e.to_string())?;
+    Ok(DocumentOutline {
+        document_id: document.id,
+        sections: parse_outline(&document.content),
+        title: document.title,
+    })
+}
@@ -1,16 +1,36 @@
 use crate::models::event::{CalendarEvent, CreateEvent, UpdateEvent};
+use crate::models::scheduling::ScheduledEvent;
+use crate::services::calendar_subscriptions::SubscriptionService;
 use crate::services::database::Database;
+use crate::services::recurring_events::RecurringEventService;
+use crate::services::scheduling::SchedulingService;
+use crate::services::task_planner::follow_changes;
 use std::sync::Arc;
 use tauri::State;
 use tokio::sync::Mutex;
 
+/// Saved events come back with the events they now overlap, once the task
+/// plan has made room for them
+fn with_conflicts(db: &Database, event: CalendarEvent) -> Result<ScheduledEvent, String> {
+    follow_changes(db);
+    let conflicts = match db.get_event(event.id) {
+        Ok(_) => SchedulingService::new(db)
+            .get_event_conflicts(event.id)
+            .map_err(|e| e.to_string())?,
+        // A planned block the plan has just replaced
+        Err(_) => Vec::new(),
+    };
+    Ok(ScheduledEvent { event, conflicts })
+}
+
 #[tauri::command]
 pub async fn create_event(
     event: CreateEvent,
     db: State<'_, Arc<Mutex<Database>>>,
-) -> Result<CalendarEvent, String> {
+) -> Result<ScheduledEvent, String> {
     let db = db.lock().await;
-    db.create_event(event).map_err(|e| e.to_string())
+    let event = db.create_event(event).map_err(|e| e.to_string())?;
+    with_conflicts(&db, event)
 }
 
 #[tauri::command]
@@ -38,9 +58,11 @@ pub async fn update_event(
     id: i64,
     update: UpdateEvent,
     db: State<'_, Arc<Mutex<Database>>>,
-) -> Result<CalendarEvent, String> {
+) -> Result<ScheduledEvent, String> {
     let db = db.lock().await;
-    db.update_event(id, update).map_err(|e| e.to_string())
+    SubscriptionService::new(&db).check_editable(id).map_err(|e| e.to_string())?;
+    let event = db.update_event(id, update).map_err(|e| e.to_string())?;
+    with_conflicts(&db, event)
 }
 
 #[tauri::command]
@@ -49,6 +71,71 @@ pub async fn delete_event(
     db: State<'_, Arc<Mutex<Database>>>,
 ) -> Result<(), String> {
     let db = db.lock().await;
-    db.delete_event(id).map_err(|e| e.to_string())
+    SubscriptionService::new(&db).check_editable(id).map_err(|e| e.to_string())?;
+    db.delete_event(id).map_err(|e| e.to_string())?;
+    follow_changes(&db);
+    Ok(())
+}
+
+
+#[tauri::command]
+pub async fn edit_event_occurrence(
+    series_id: i64,
+    occurrence_date: String,
+    update: UpdateEvent,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<CalendarEvent, String> {
+    let db = db.lock().await;
+    SubscriptionService::new(&db).check_editable(series_id).map_err(|e| e.to_string())?;
+    let event = RecurringEventService::new(&db)
+        .edit_occurrence(series_id, &occurrence_date, update)
+        .map_err(|e| e.to_string())?;
+    follow_changes(&db);
+    Ok(event)
+}
+
+#[tauri::command]
+pub async fn delete_event_occurrence(
+    series_id: i64,
+    occurrence_date: String,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<(), String> {
+    let db = db.lock().await;
+    SubscriptionService::new(&db).check_editable(series_id).map_err(|e| e.to_string())?;
+    RecurringEventService::new(&db)
+        .delete_occurrence(series_id, &occurrence_date)
+        .map_err(|e| e.to_string())?;
+    follow_changes(&db);
+    Ok(())
 }
 
+#[tauri::command]
+pub async fn update_event_series_from(
+    series_id: i64,
+    from_date: String,
+    update: UpdateEvent,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<CalendarEvent, String> {
+    let db = db.lock().await;
+    SubscriptionService::new(&db).check_editable(series_id).map_err(|e| e.to_string())?;
+    let event = RecurringEventService::new(&db)
+        .update_following(series_id, &from_date, update)
+        .map_err(|e| e.to_string())?;
+    follow_changes(&db);
+    Ok(event)
+}
+
+#[tauri::command]
+pub async fn delete_event_series_from(
+    series_id: i64,
+    from_date: String,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<(), String> {
+    let db = db.lock().await;
+    SubscriptionService::new(&db).check_editable(series_id).map_err(|e| e.to_string())?;
+    RecurringEventService::new(&db)
+        .delete_following(series_id, &from_date)
+        .map_err(|e| e.to_string())?;
+    follow_changes(&db);
+    Ok(())
+}
@@ -1,5 +1,8 @@
 use crate::services::database::Database;
 use crate::services::file_manager::{FileManager, FileMetadata};
+use crate::services::previews::{self, FilePreview};
+use crate::services::uploads::{UploadService, UploadSession};
+use std::path::Path;
 use std::sync::Arc;
 use tauri::State;
 use tokio::sync::Mutex;
@@ -72,3 +75,121 @@ pub async fn list_files(
         .map_err(|e| e.to_string())
 }
 
+
+/// Import a file straight from disk; the bytes never pass through the webview
+#[tauri::command]
+pub async fn import_file_from_path(
+    path: String,
+    mimetype: Option<String>,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<FileMetadata, String> {
+    let db = db.lock().await;
+    let file_manager = FileManager::new(db.get_connection())
+        .map_err(|e| e.to_string())?;
+
+    file_manager.import_file(Path::new(&path), mimetype.as_deref())
+        .map_err(|e| e.to_string())
+}
+
+/// Up to `length` bytes of a file from `offset`
+#[tauri::command]
+pub async fn read_file_range(
+    id: i64,
+    offset: u64,
+    length: u64,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<Vec<u8>, String> {
+    let db = db.lock().await;
+    let file_manager = FileManager::new(db.get_connection())
+        .map_err(|e| e.to_string())?;
+
+    file_manager.read_range(id, offset, length)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn start_upload(
+    filename: String,
+    mimetype: String,
+    total_size: i64,
+    chunk_size: Option<i64>,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<UploadSession, String> {
+    let db = db.lock().await;
+    let uploads = UploadService::new(db.get_connection(), FileManager::blob_root().map_err(|e| e.to_string())?)
+        .map_err(|e| e.to_string())?;
+
+    uploads.start(&filename, &mimetype, total_size, chunk_size)
+        .map_err(|e| e.to_string())
+}
+
+/// The session with the chunks received so far, for resuming
+#[tauri::command]
+pub async fn get_upload(
+    id: String,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<UploadSession, String> {
+    let db = db.lock().await;
+    let uploads = UploadService::new(db.get_connection(), FileManager::blob_root().map_err(|e| e.to_string())?)
+        .map_err(|e| e.to_string())?;
+
+    uploads.get_session(&id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn upload_chunk(
+    id: String,
+    index: i64,
+    content: Vec<u8>,
+    sha256: String,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<UploadSession, String> {
+    let db = db.lock().await;
+    let uploads = UploadService::new(db.get_connection(), FileManager::blob_root().map_err(|e| e.to_string())?)
+        .map_err(|e| e.to_string())?;
+
+    uploads.put_chunk(&id, index, &content, &sha256)
+        .map_err(|e| e.to_string())
+}
+
+/// Complete an upload and record it as a file
+#[tauri::command]
+pub async fn finish_upload(
+    id: String,
+    sha256: Option<String>,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<FileMetadata, String> {
+    let db = db.lock().await;
+    let file_manager = FileManager::new(db.get_connection())
+        .map_err(|e| e.to_string())?;
+    let uploads = UploadService::new(db.get_connection(), FileManager::blob_root().map_err(|e| e.to_string())?)
+        .map_err(|e| e.to_string())?;
+
+    let (session, blob) = uploads.finish(&id, sha256.as_deref())
+        .map_err(|e| e.to_string())?;
+    file_manager.add_file(&session.filename, &session.mimetype, &blob)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn cancel_upload(
+    id: String,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<(), String> {
+    let db = db.lock().await;
+    let uploads = UploadService::new(db.get_connection(), FileManager::blob_root().map_err(|e| e.to_string())?)
+        .map_err(|e| e.to_string())?;
+
+    uploads.cancel(&id)
+        .map_err(|e| e.to_string())
+}
+
+/// Thumbnail or text excerpt of a file, cached after the first call
+#[tauri::command]
+pub async fn get_file_preview(
+    id: i64,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<FilePreview, String> {
+    previews::get_file_preview(db.inner(), id).await
+}
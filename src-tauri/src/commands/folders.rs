@@ -0,0 +1,193 @@
+use crate::models::document::Document;
+use crate::models::folder::{Collection, CollectionQuery, CreateCollection, CreateFolder, Folder, FolderNode, UpdateCollection};
+use crate::services::database::Database;
+use crate::services::folders::FolderService;
+use std::sync::Arc;
+use tauri::State;
+use tokio::sync::Mutex;
+
+#[tauri::command]
+pub async fn create_folder(
+    folder: CreateFolder,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<Folder, String> {
+    let db = db.lock().await;
+    FolderService::new(db.get_connection())
+        .create_folder(folder)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_folder_tree(
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<Vec<FolderNode>, String> {
+    let db = db.lock().await;
+    FolderService::new(db.get_connection())
+        .get_folder_tree()
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn rename_folder(
+    id: i64,
+    name: String,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<Folder, String> {
+    let db = db.lock().await;
+    FolderService::new(db.get_connection())
+        .rename_folder(id, &name)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn move_folder(
+    id: i64,
+    parent_id: Option<i64>,
+    position: Option<i64>,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<Folder, String> {
+    let db = db.lock().await;
+    FolderService::new(db.get_connection())
+        .move_folder(id, parent_id, position)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn reorder_folders(
+    parent_id: Option<i64>,
+    ordered_ids: Vec<i64>,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<Vec<Folder>, String> {
+    let db = db.lock().await;
+    FolderService::new(db.get_connection())
+        .reorder_folders(parent_id, &ordered_ids)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_folder(
+    id: i64,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<(), String> {
+    let db = db.lock().await;
+    FolderService::new(db.get_connection())
+        .delete_folder(id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_documents_in_folder(
+    folder_id: Option<i64>,
+    recursive: Option<bool>,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<Vec<Document>, String> {
+    let db = db.lock().await;
+    FolderService::new(db.get_connection())
+        .get_documents_in_folder(folder_id, recursive.unwrap_or(false))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn move_document(
+    document_id: i64,
+    folder_id: Option<i64>,
+    position: Option<i64>,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<(), String> {
+    let db = db.lock().await;
+    FolderService::new(db.get_connection())
+        .move_document(document_id, folder_id, position)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn reorder_documents(
+    folder_id: Option<i64>,
+    ordered_ids: Vec<i64>,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<Vec<Document>, String> {
+    let db = db.lock().await;
+    FolderService::new(db.get_connection())
+        .reorder_documents(folder_id, &ordered_ids)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn export_folder(
+    folder_id: i64,
+    format: String,
+    recursive: Option<bool>,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<String, String> {
+    let db = db.lock().await;
+    FolderService::new(db.get_connection())
+        .export_folder(folder_id, &format, recursive.unwrap_or(true))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn create_collection(
+    collection: CreateCollection,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<Collection, String> {
+    let db = db.lock().await;
+    FolderService::new(db.get_connection())
+        .create_collection(collection)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_all_collections(
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<Vec<Collection>, String> {
+    let db = db.lock().await;
+    FolderService::new(db.get_connection())
+        .get_all_collections()
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn update_collection(
+    id: i64,
+    update: UpdateCollection,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<Collection, String> {
+    let db = db.lock().await;
+    FolderService::new(db.get_connection())
+        .update_collection(id, update)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_collection(
+    id: i64,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<(), String> {
+    let db = db.lock().await;
+    FolderService::new(db.get_connection())
+        .delete_collection(id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_collection_documents(
+    id: i64,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<Vec<Document>, String> {
+    let db = db.lock().await;
+    FolderService::new(db.get_connection())
+        .get_collection_documents(id)
+        .map_err(|e| e.to_string())
+}
+
+/// Evaluate an unsaved collection query, e.g. to preview it while editing
+#[tauri::command]
+pub async fn query_documents(
+    query: CollectionQuery,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<Vec<Document>, String> {
+    let db = db.lock().await;
+    FolderService::new(db.get_connection())
+        .query_documents(&query)
+        .map_err(|e| e.to_string())
+}
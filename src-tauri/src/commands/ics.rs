@@ -1,5 +1,9 @@
+use crate::models::event::{IcsImportOptions, IcsImportSummary};
 use crate::services::ics::ICSService;
+use crate::services::ics_import::IcsImportService;
 use crate::services::database::Database;
+use crate::services::recurring_events::RecurringEventService;
+use crate::services::time_zones::user_zone;
 use std::sync::Arc;
 use tauri::State;
 use tokio::sync::Mutex;
@@ -11,11 +15,13 @@ pub async fn export_calendar_to_ics(
     db: State<'_, Arc<Mutex<Database>>>,
 ) -> Result<String, String> {
     let database = db.lock().await;
-    let events = database.get_events_in_range(&start, &end)
+    let events = RecurringEventService::new(&database)
+        .get_stored_events_in_range(&start, &end)
         .map_err(|e| e.to_string())?;
+    let zone = user_zone(database.get_connection());
     drop(database);
     
-    let ics_service = ICSService::new();
+    let ics_service = ICSService::with_zone(zone);
     ics_service.export_to_ics(&events)
         .map_err(|e| e.to_string())
 }
@@ -23,29 +29,16 @@ pub async fn export_calendar_to_ics(
 #[tauri::command]
 pub async fn import_calendar_from_ics(
     ics_content: String,
+    options: Option<IcsImportOptions>,
     db: State<'_, Arc<Mutex<Database>>>,
-) -> Result<usize, String> {
-    let ics_service = ICSService::new();
-    let events = ics_service.import_from_ics(&ics_content)
-        .map_err(|e| e.to_string())?;
-    
-    let mut count = 0;
+) -> Result<IcsImportSummary, String> {
     let database = db.lock().await;
-    
-    for event in events {
-        // Create event in database
-        if let Ok(_) = database.create_event(crate::models::event::CreateEvent {
-            title: event.title,
-            description: event.description,
-            start_time: event.start_time,
-            end_time: event.end_time,
-            reminder_time: event.reminder_time,
-            recurrence: event.recurrence,
-        }) {
-            count += 1;
-        }
+    let summary = IcsImportService::new(&database)
+        .import(&ics_content, &options.unwrap_or_default())
+        .map_err(|e| e.to_string())?;
+    for warning in &summary.warnings {
+        println!("ICS import: {}", warning);
     }
     
-    Ok(count)
+    Ok(summary)
 }
-
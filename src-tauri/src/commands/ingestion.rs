@@ -1,5 +1,6 @@
 use crate::models::ingestion::{CreateIngestionJob, IngestionJob, UpdateIngestionJob, IngestionJobStats};
 use crate::services::ingestion_service::IngestionService;
+use crate::services::task_transfer::TaskTransferService;
 use crate::services::database::Database;
 use tauri::State;
 use std::sync::Arc;
@@ -82,7 +83,15 @@ pub async fn start_ingestion_job(
     let job = ingestion_service
         .get_ingestion_job(id)
         .map_err(|e| e.to_string())?;
-    
+
+    // Task imports read one export file rather than a folder of documents
+    if TaskTransferService::is_import_job(&job) {
+        return TaskTransferService::new(&db)
+            .run_import_job(id)
+            .map(|_| ())
+            .map_err(|e| e.to_string());
+    }
+
     // Process the source synchronously
     let source_path = job.source_path.clone();
     if let Err(e) = ingestion_service.process_source(id, &source_path) {
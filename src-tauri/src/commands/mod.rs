@@ -4,11 +4,22 @@ pub mod files;
 pub mod documents;
 pub mod document_versions;
 pub mod tasks;
+pub mod time_tracking;
+pub mod boards;
+pub mod task_transfer;
 pub mod links;
+pub mod tags;
+pub mod folders;
+pub mod templates;
 pub mod events;
+pub mod quick_add;
 pub mod ai;
 pub mod notifications;
+pub mod digests;
 pub mod ics;
+pub mod calendar_subscriptions;
+pub mod scheduling;
+pub mod task_planner;
 pub mod indexer;
 pub mod setup;
 pub mod clear;
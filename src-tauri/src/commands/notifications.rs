@@ -1,27 +1,72 @@
+use crate::models::reminder::Reminder;
+use crate::services::database::Database;
 use crate::services::notifications::NotificationService;
+use crate::services::reminders::ReminderService;
+use std::sync::Arc;
+use tauri::{Manager, State};
+use tokio::sync::Mutex;
 
 #[tauri::command]
 pub async fn send_notification(
+    app_handle: tauri::AppHandle,
     title: String,
     body: String,
 ) -> Result<(), String> {
-    let service = NotificationService::new();
+    let service = NotificationService::new(app_handle.config().tauri.bundle.identifier.clone());
     service.send_notification(&title, &body)
         .map_err(|e| e.to_string())
 }
 
+/// Queue a one-off reminder; `time` is RFC 3339
 #[tauri::command]
 pub async fn schedule_reminder(
     title: String,
     body: String,
     time: String,
-) -> Result<(), String> {
-    let service = NotificationService::new();
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<Reminder, String> {
     let datetime = chrono::DateTime::parse_from_rfc3339(&time)
         .map_err(|e| e.to_string())?
         .with_timezone(&chrono::Utc);
-    
-    service.schedule_reminder(&title, &body, datetime)
+
+    let db = db.lock().await;
+    ReminderService::new(&db)
+        .schedule(&title, &body, datetime.naive_utc())
+        .map_err(|e| e.to_string())
+}
+
+/// Pending reminders for the next `days` days (one by default)
+#[tauri::command]
+pub async fn get_upcoming_reminders(
+    days: Option<i64>,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<Vec<Reminder>, String> {
+    let db = db.lock().await;
+    let service = ReminderService::new(&db);
+    let now = chrono::Utc::now().naive_utc();
+    service.sync_queue(now).map_err(|e| e.to_string())?;
+    service
+        .get_upcoming(now + chrono::Duration::days(days.unwrap_or(1).max(0)))
         .map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub async fn snooze_reminder(
+    id: i64,
+    minutes: i64,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<Reminder, String> {
+    let db = db.lock().await;
+    ReminderService::new(&db)
+        .snooze(id, minutes, chrono::Utc::now().naive_utc())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn dismiss_reminder(
+    id: i64,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<Reminder, String> {
+    let db = db.lock().await;
+    ReminderService::new(&db).dismiss(id).map_err(|e| e.to_string())
+}
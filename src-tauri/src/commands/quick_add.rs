@@ -0,0 +1,73 @@
+use crate::models::quick_add::{QuickAddCreated, QuickAddItem, QuickAddResult};
+use crate::services::database::Database;
+use crate::services::ollama::OllamaService;
+use crate::services::quick_add::{parse_ai_response, parse_quick_add, plain_task, quick_add_prompt};
+use crate::services::tags::TagService;
+use crate::services::time_zones::{user_zone, utc_to_zoned};
+use chrono_tz::Tz;
+use std::sync::Arc;
+use tauri::State;
+use tokio::sync::Mutex;
+
+/// Run the offline parser, asking Ollama only for lines it cannot read.
+/// Dates are resolved against the current wall time in `zone`.
+async fn resolve(input: &str, zone: Tz, ollama: &Arc<Mutex<OllamaService>>) -> Result<QuickAddResult, String> {
+    if input.trim().is_empty() {
+        return Err("Nothing to add".to_string());
+    }
+
+    let now = utc_to_zoned(zone, chrono::Utc::now().naive_utc());
+    if let Some(result) = parse_quick_add(input, now) {
+        return Ok(result);
+    }
+
+    let ollama = ollama.lock().await;
+    match ollama.generate("llama3.2", &quick_add_prompt(input, now)).await {
+        Ok(response) => Ok(parse_ai_response(&response, input).unwrap_or_else(|| plain_task(input))),
+        Err(e) => {
+            println!("Quick add fallback failed: {}", e);
+            Ok(plain_task(input))
+        }
+    }
+}
+
+/// Preview what a quick-add line would create without saving it
+#[tauri::command]
+pub async fn parse_quick_add_input(
+    input: String,
+    db: State<'_, Arc<Mutex<Database>>>,
+    ollama: State<'_, Arc<Mutex<OllamaService>>>,
+) -> Result<QuickAddResult, String> {
+    let zone = user_zone(db.lock().await.get_connection());
+    resolve(&input, zone, &ollama).await
+}
+
+#[tauri::command]
+pub async fn quick_add(
+    input: String,
+    db: State<'_, Arc<Mutex<Database>>>,
+    ollama: State<'_, Arc<Mutex<OllamaService>>>,
+) -> Result<QuickAddCreated, String> {
+    // Resolve before taking the database lock so Ollama never holds it
+    let zone = user_zone(db.lock().await.get_connection());
+    let result = resolve(&input, zone, &ollama).await?;
+
+    let db = db.lock().await;
+    let tags = TagService::new(db.get_connection());
+    match result.item {
+        QuickAddItem::Task(task) => {
+            let task = db.create_task(task).map_err(|e| e.to_string())?;
+            if !result.tags.is_empty() {
+                tags.set_item_tags("task", task.id, &result.tags).map_err(|e| e.to_string())?;
+            }
+            Ok(QuickAddCreated::Task { task, tags: result.tags, source: result.source })
+        }
+        QuickAddItem::Event(event) => {
+            let event = db.create_event(event).map_err(|e| e.to_string())?;
+            if !result.tags.is_empty() {
+                tags.set_item_tags("event", event.id, &result.tags).map_err(|e| e.to_string())?;
+            }
+            Ok(QuickAddCreated::Event { event, tags: result.tags, source: result.source })
+        }
+    }
+}
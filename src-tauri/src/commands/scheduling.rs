@@ -0,0 +1,55 @@
+use crate::models::event::CalendarEvent;
+use crate::models::scheduling::{EventConflict, FreeBusy, FreeSlotRequest, TimeSlot};
+use crate::services::database::Database;
+use crate::services::scheduling::SchedulingService;
+use std::sync::Arc;
+use tauri::State;
+use tokio::sync::Mutex;
+
+#[tauri::command]
+pub async fn get_free_busy(
+    start: String,
+    end: String,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<FreeBusy, String> {
+    let db = db.lock().await;
+    SchedulingService::new(&db)
+        .get_free_busy(&start, &end)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn find_free_slots(
+    request: FreeSlotRequest,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<Vec<TimeSlot>, String> {
+    let db = db.lock().await;
+    SchedulingService::new(&db)
+        .find_free_slots(&request)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_event_conflicts(
+    id: i64,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<Vec<EventConflict>, String> {
+    let db = db.lock().await;
+    SchedulingService::new(&db)
+        .get_event_conflicts(id)
+        .map_err(|e| e.to_string())
+}
+
+/// Check a time before saving an event there; `exclude_id` is the event being moved
+#[tauri::command]
+pub async fn check_event_conflicts(
+    start: String,
+    end: String,
+    exclude_id: Option<i64>,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<Vec<CalendarEvent>, String> {
+    let db = db.lock().await;
+    SchedulingService::new(&db)
+        .find_conflicts(&start, &end, exclude_id)
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,159 @@
+use crate::models::tag::{Tag, TagSuggestion, TagWithCount, TaggedItem};
+use crate::services::database::Database;
+use crate::services::tags::TagService;
+use std::sync::Arc;
+use tauri::State;
+use tokio::sync::Mutex;
+
+#[tauri::command]
+pub async fn create_tag(
+    name: String,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<Tag, String> {
+    let db = db.lock().await;
+    TagService::new(db.get_connection())
+        .ensure_tag(&name)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_all_tags(
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<Vec<TagWithCount>, String> {
+    let db = db.lock().await;
+    TagService::new(db.get_connection())
+        .get_all_tags()
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_tags_for_item(
+    item_type: String,
+    item_id: i64,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<Vec<Tag>, String> {
+    let db = db.lock().await;
+    TagService::new(db.get_connection())
+        .get_tags_for_item(&item_type, item_id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_items_for_tag(
+    tag_id: i64,
+    include_nested: Option<bool>,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<Vec<TaggedItem>, String> {
+    let db = db.lock().await;
+    TagService::new(db.get_connection())
+        .get_items_for_tag(tag_id, include_nested.unwrap_or(true))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn tag_item(
+    item_type: String,
+    item_id: i64,
+    name: String,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<Tag, String> {
+    let db = db.lock().await;
+    TagService::new(db.get_connection())
+        .tag_item(&item_type, item_id, &name)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn untag_item(
+    item_type: String,
+    item_id: i64,
+    tag_id: i64,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<(), String> {
+    let db = db.lock().await;
+    TagService::new(db.get_connection())
+        .untag_item(&item_type, item_id, tag_id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn set_item_tags(
+    item_type: String,
+    item_id: i64,
+    names: Vec<String>,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<Vec<Tag>, String> {
+    let db = db.lock().await;
+    TagService::new(db.get_connection())
+        .set_item_tags(&item_type, item_id, &names)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn rename_tag(
+    id: i64,
+    new_name: String,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<Tag, String> {
+    let db = db.lock().await;
+    TagService::new(db.get_connection())
+        .rename_tag(id, &new_name)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn merge_tags(
+    source_id: i64,
+    target_id: i64,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<Tag, String> {
+    let db = db.lock().await;
+    TagService::new(db.get_connection())
+        .merge_tags(source_id, target_id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_tag(
+    id: i64,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<(), String> {
+    let db = db.lock().await;
+    TagService::new(db.get_connection())
+        .delete_tag(id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_tag_suggestions(
+    item_type: String,
+    item_id: i64,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<Vec<TagSuggestion>, String> {
+    let db = db.lock().await;
+    TagService::new(db.get_connection())
+        .get_suggestions(&item_type, item_id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn accept_tag_suggestion(
+    id: i64,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<Tag, String> {
+    let db = db.lock().await;
+    TagService::new(db.get_connection())
+        .accept_suggestion(id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn dismiss_tag_suggestion(
+    id: i64,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<(), String> {
+    let db = db.lock().await;
+    TagService::new(db.get_connection())
+        .dismiss_suggestion(id)
+        .map_err(|e| e.to_string())
+}
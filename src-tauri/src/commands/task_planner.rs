@@ -0,0 +1,39 @@
+use crate::models::scheduling::{PlanRequest, PlannedBlock, TaskPlan};
+use crate::services::database::Database;
+use crate::services::task_planner::TaskPlannerService;
+use std::sync::Arc;
+use tauri::State;
+use tokio::sync::Mutex;
+
+/// Time-block open tasks into free calendar time; later changes to events
+/// and tasks re-plan with the same options
+#[tauri::command]
+pub async fn plan_tasks(
+    request: PlanRequest,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<TaskPlan, String> {
+    let db = db.lock().await;
+    TaskPlannerService::new(&db)
+        .plan(&request)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_task_plan(
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<Vec<PlannedBlock>, String> {
+    let db = db.lock().await;
+    TaskPlannerService::new(&db)
+        .get_plan()
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn clear_task_plan(
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<(), String> {
+    let db = db.lock().await;
+    TaskPlannerService::new(&db)
+        .clear_plan()
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,41 @@
+use crate::models::task_transfer::{TaskImportRequest, TaskImportResult};
+use crate::services::database::Database;
+use crate::services::task_transfer::TaskTransferService;
+use std::sync::Arc;
+use tauri::State;
+use tokio::sync::Mutex;
+
+#[tauri::command]
+pub async fn import_tasks(
+    request: TaskImportRequest,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<TaskImportResult, String> {
+    let db = db.lock().await;
+    TaskTransferService::new(&db)
+        .import_tasks(request)
+        .map_err(|e| e.to_string())
+}
+
+/// Run a previewed task import for real
+#[tauri::command]
+pub async fn run_task_import(
+    job_id: i64,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<TaskImportResult, String> {
+    let db = db.lock().await;
+    TaskTransferService::new(&db)
+        .run_import_job(job_id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn export_tasks(
+    format: String,
+    task_ids: Option<Vec<i64>>,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<String, String> {
+    let db = db.lock().await;
+    TaskTransferService::new(&db)
+        .export_tasks(&format, task_ids.as_deref())
+        .map_err(|e| e.to_string())
+}
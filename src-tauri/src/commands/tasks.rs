@@ -1,5 +1,9 @@
-use crate::models::task::{CreateTask, Task, UpdateTask};
+use crate::models::conflict::UpdateError;
+use crate::models::task::{ChecklistItem, CreateTask, Task, TaskDetails, UpdateChecklistItem, UpdateTask};
 use crate::services::database::Database;
+use crate::services::recurring_tasks::preview_recurrence;
+use crate::services::task_planner::follow_changes;
+use crate::services::task_structure::TaskStructureService;
 use std::sync::Arc;
 use tauri::State;
 use tokio::sync::Mutex;
@@ -10,7 +14,9 @@ pub async fn create_task(
     db: State<'_, Arc<Mutex<Database>>>,
 ) -> Result<Task, String> {
     let db = db.lock().await;
-    db.create_task(task).map_err(|e| e.to_string())
+    let task = db.create_task(task).map_err(|e| e.to_string())?;
+    follow_changes(&db);
+    Ok(task)
 }
 
 #[tauri::command]
@@ -30,14 +36,18 @@ pub async fn get_all_tasks(
     db.get_all_tasks().map_err(|e| e.to_string())
 }
 
+/// Fails with `UpdateError::Conflict` (carrying the stored task) when
+/// `update.expected_version` / `expected_updated_at` is stale.
 #[tauri::command]
 pub async fn update_task(
     id: i64,
     update: UpdateTask,
     db: State<'_, Arc<Mutex<Database>>>,
-) -> Result<Task, String> {
+) -> Result<Task, UpdateError<Task>> {
     let db = db.lock().await;
-    db.update_task(id, update).map_err(|e| e.to_string())
+    let task = db.update_task(id, update)?;
+    follow_changes(&db);
+    Ok(task)
 }
 
 #[tauri::command]
@@ -46,6 +56,99 @@ pub async fn delete_task(
     db: State<'_, Arc<Mutex<Database>>>,
 ) -> Result<(), String> {
     let db = db.lock().await;
-    db.delete_task(id).map_err(|e| e.to_string())
+    db.delete_task(id).map_err(|e| e.to_string())?;
+    follow_changes(&db);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_task_details(
+    id: i64,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<TaskDetails, String> {
+    let db = db.lock().await;
+    TaskStructureService::new(&db)
+        .get_task_details(id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn set_task_parent(
+    id: i64,
+    parent_id: Option<i64>,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<Task, String> {
+    let db = db.lock().await;
+    TaskStructureService::new(&db)
+        .set_parent(id, parent_id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn add_checklist_item(
+    task_id: i64,
+    text: String,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<ChecklistItem, String> {
+    let db = db.lock().await;
+    TaskStructureService::new(&db)
+        .add_checklist_item(task_id, &text, false)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn update_checklist_item(
+    id: i64,
+    update: UpdateChecklistItem,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<ChecklistItem, String> {
+    let db = db.lock().await;
+    TaskStructureService::new(&db)
+        .update_checklist_item(id, update)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_checklist_item(
+    id: i64,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<(), String> {
+    let db = db.lock().await;
+    TaskStructureService::new(&db)
+        .delete_checklist_item(id)
+        .map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub async fn add_task_dependency(
+    task_id: i64,
+    blocked_by_id: i64,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<(), String> {
+    let db = db.lock().await;
+    TaskStructureService::new(&db)
+        .add_dependency(task_id, blocked_by_id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn remove_task_dependency(
+    task_id: i64,
+    blocked_by_id: i64,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<(), String> {
+    let db = db.lock().await;
+    TaskStructureService::new(&db)
+        .remove_dependency(task_id, blocked_by_id)
+        .map_err(|e| e.to_string())
+}
+
+/// Upcoming dates of an RRULE, used to preview a recurrence before saving it
+#[tauri::command]
+pub async fn preview_task_recurrence(
+    rule: String,
+    start: String,
+    count: Option<usize>,
+) -> Result<Vec<String>, String> {
+    preview_recurrence(&rule, &start, count.unwrap_or(5)).map_err(|e| e.to_string())
+}
@@ -0,0 +1,100 @@
+use crate::models::document::Document;
+use crate::models::template::{CreateFromTemplate, CreateTemplate, DocumentTemplate, UpdateTemplate};
+use crate::services::database::Database;
+use crate::services::ollama::OllamaService;
+use crate::services::templates::{ai_section_prompt, TemplateService};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tauri::State;
+use tokio::sync::Mutex;
+
+#[tauri::command]
+pub async fn get_all_templates(
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<Vec<DocumentTemplate>, String> {
+    let db = db.lock().await;
+    TemplateService::new(&db)
+        .get_all_templates()
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_template(
+    id: i64,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<DocumentTemplate, String> {
+    let db = db.lock().await;
+    TemplateService::new(&db)
+        .get_template(id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn create_template(
+    template: CreateTemplate,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<DocumentTemplate, String> {
+    let db = db.lock().await;
+    TemplateService::new(&db)
+        .create_template(template)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn update_template(
+    id: i64,
+    update: UpdateTemplate,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<DocumentTemplate, String> {
+    let db = db.lock().await;
+    TemplateService::new(&db)
+        .update_template(id, update)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_template(
+    id: i64,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<(), String> {
+    let db = db.lock().await;
+    TemplateService::new(&db)
+        .delete_template(id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn create_document_from_template(
+    request: CreateFromTemplate,
+    db: State<'_, Arc<Mutex<Database>>>,
+    ollama: State<'_, Arc<Mutex<OllamaService>>>,
+) -> Result<Document, String> {
+    // Resolve variables first; the database lock is released before calling Ollama
+    let rendered = {
+        let db = db.lock().await;
+        TemplateService::new(&db)
+            .render(&request, chrono::Local::now())
+            .map_err(|e| e.to_string())?
+    };
+
+    let mut ai_outputs = HashMap::new();
+    if !request.skip_ai {
+        let ollama = ollama.lock().await;
+        for section in &rendered.ai_sections {
+            let prompt = ai_section_prompt(&rendered, section);
+            let text = match ollama.generate("llama3.2", &prompt).await.map_err(|e| e.to_string()) {
+                Ok(text) => text,
+                Err(e) => {
+                    println!("Template AI section failed: {}", e);
+                    format!("_AI section could not be generated: {}_", section.prompt)
+                }
+            };
+            ai_outputs.insert(section.placeholder.clone(), text);
+        }
+    }
+
+    let db = db.lock().await;
+    TemplateService::new(&db)
+        .create_document(rendered, &ai_outputs, &request)
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,119 @@
+use crate::models::time_entry::{CreateTimeEntry, TimeEntry, TimeReport, UpdateTimeEntry};
+use crate::services::database::Database;
+use crate::services::time_tracking::TimeTrackingService;
+use std::sync::Arc;
+use tauri::State;
+use tokio::sync::Mutex;
+
+#[tauri::command]
+pub async fn start_task_timer(
+    task_id: i64,
+    note: Option<String>,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<TimeEntry, String> {
+    let db = db.lock().await;
+    TimeTrackingService::new(&db)
+        .start_timer(task_id, note)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn stop_task_timer(
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<Option<TimeEntry>, String> {
+    let db = db.lock().await;
+    TimeTrackingService::new(&db)
+        .stop_timer()
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_running_timer(
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<Option<TimeEntry>, String> {
+    let db = db.lock().await;
+    TimeTrackingService::new(&db)
+        .get_running_timer()
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_task_time_entries(
+    task_id: i64,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<Vec<TimeEntry>, String> {
+    let db = db.lock().await;
+    TimeTrackingService::new(&db)
+        .get_task_time_entries(task_id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn add_time_entry(
+    entry: CreateTimeEntry,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<TimeEntry, String> {
+    let db = db.lock().await;
+    TimeTrackingService::new(&db)
+        .add_time_entry(entry)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn update_time_entry(
+    id: i64,
+    update: UpdateTimeEntry,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<TimeEntry, String> {
+    let db = db.lock().await;
+    TimeTrackingService::new(&db)
+        .update_time_entry(id, update)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_time_entry(
+    id: i64,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<(), String> {
+    let db = db.lock().await;
+    TimeTrackingService::new(&db)
+        .delete_time_entry(id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn create_event_for_time_entry(
+    id: i64,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<TimeEntry, String> {
+    let db = db.lock().await;
+    TimeTrackingService::new(&db)
+        .create_event_for_entry(id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn link_time_entry_to_event(
+    id: i64,
+    event_id: Option<i64>,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<TimeEntry, String> {
+    let db = db.lock().await;
+    TimeTrackingService::new(&db)
+        .link_entry_to_event(id, event_id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_time_report(
+    group_by: String,
+    start: Option<String>,
+    end: Option<String>,
+    db: State<'_, Arc<Mutex<Database>>>,
+) -> Result<TimeReport, String> {
+    let db = db.lock().await;
+    TimeTrackingService::new(&db)
+        .get_report(&group_by, start.as_deref(), end.as_deref())
+        .map_err(|e| e.to_string())
+}
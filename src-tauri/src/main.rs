@@ -37,7 +37,18 @@ async fn main() {
         }
     };
 
+    // Keep calendar subscriptions fresh in the background
+    services::calendar_subscriptions::spawn_refresh_loop(db.clone());
+
+    let (background_db, digest_ollama) = (db.clone(), ollama.clone());
     tauri::Builder::default()
+        .setup(move |app| {
+            // Fire task and event reminders, catching up on ones missed while closed
+            services::reminders::spawn_reminder_loop(app.handle(), background_db.clone());
+            // Deliver the morning agenda and weekly review on their schedule
+            services::digests::spawn_digest_loop(app.handle(), background_db, digest_ollama);
+            Ok(())
+        })
         .manage(db)
         .manage(ollama)
         .manage(lancedb)
@@ -57,11 +68,20 @@ async fn main() {
             commands::files::read_file_content,
             commands::files::delete_file,
             commands::files::list_files,
+            commands::files::import_file_from_path,
+            commands::files::read_file_range,
+            commands::files::start_upload,
+            commands::files::get_upload,
+            commands::files::upload_chunk,
+            commands::files::finish_upload,
+            commands::files::cancel_upload,
+            commands::files::get_file_preview,
             commands::documents::create_document,
             commands::documents::get_document,
             commands::documents::get_all_documents,
             commands::documents::update_document,
             commands::documents::delete_document,
+            commands::documents::get_document_outline,
             commands::document_versions::get_document_versions,
             commands::document_versions::restore_document_version,
             commands::tasks::create_task,
@@ -69,17 +89,91 @@ async fn main() {
             commands::tasks::get_all_tasks,
             commands::tasks::update_task,
             commands::tasks::delete_task,
+            commands::tasks::get_task_details,
+            commands::tasks::set_task_parent,
+            commands::tasks::add_checklist_item,
+            commands::tasks::update_checklist_item,
+            commands::tasks::delete_checklist_item,
+            commands::tasks::add_task_dependency,
+            commands::tasks::remove_task_dependency,
+            commands::tasks::preview_task_recurrence,
+            commands::time_tracking::start_task_timer,
+            commands::time_tracking::stop_task_timer,
+            commands::time_tracking::get_running_timer,
+            commands::time_tracking::get_task_time_entries,
+            commands::time_tracking::add_time_entry,
+            commands::time_tracking::update_time_entry,
+            commands::time_tracking::delete_time_entry,
+            commands::time_tracking::create_event_for_time_entry,
+            commands::time_tracking::link_time_entry_to_event,
+            commands::time_tracking::get_time_report,
+            commands::boards::get_all_boards,
+            commands::boards::get_board,
+            commands::boards::create_board,
+            commands::boards::update_board,
+            commands::boards::delete_board,
+            commands::boards::add_board_column,
+            commands::boards::update_board_column,
+            commands::boards::delete_board_column,
+            commands::boards::reorder_board_columns,
+            commands::boards::get_board_view,
+            commands::boards::move_task_on_board,
+            commands::task_transfer::import_tasks,
+            commands::task_transfer::run_task_import,
+            commands::task_transfer::export_tasks,
             commands::links::create_link,
             commands::links::get_links_for_item,
             commands::links::delete_link,
+            commands::tags::create_tag,
+            commands::tags::get_all_tags,
+            commands::tags::get_tags_for_item,
+            commands::tags::get_items_for_tag,
+            commands::tags::tag_item,
+            commands::tags::untag_item,
+            commands::tags::set_item_tags,
+            commands::tags::rename_tag,
+            commands::tags::merge_tags,
+            commands::tags::delete_tag,
+            commands::tags::get_tag_suggestions,
+            commands::tags::accept_tag_suggestion,
+            commands::tags::dismiss_tag_suggestion,
+            commands::folders::create_folder,
+            commands::folders::get_folder_tree,
+            commands::folders::rename_folder,
+            commands::folders::move_folder,
+            commands::folders::reorder_folders,
+            commands::folders::delete_folder,
+            commands::folders::get_documents_in_folder,
+            commands::folders::move_document,
+            commands::folders::reorder_documents,
+            commands::folders::export_folder,
+            commands::folders::create_collection,
+            commands::folders::get_all_collections,
+            commands::folders::update_collection,
+            commands::folders::delete_collection,
+            commands::folders::get_collection_documents,
+            commands::folders::query_documents,
+            commands::templates::get_all_templates,
+            commands::templates::get_template,
+            commands::templates::create_template,
+            commands::templates::update_template,
+            commands::templates::delete_template,
+            commands::templates::create_document_from_template,
             commands::events::create_event,
             commands::events::get_event,
             commands::events::get_events_in_range,
             commands::events::update_event,
             commands::events::delete_event,
+            commands::events::edit_event_occurrence,
+            commands::events::delete_event_occurrence,
+            commands::events::update_event_series_from,
+            commands::events::delete_event_series_from,
+            commands::quick_add::parse_quick_add_input,
+            commands::quick_add::quick_add,
             commands::ai::summarize,
             commands::ai::rewrite,
             commands::ai::generate_tasks,
+            commands::ai::commit_task_drafts,
             commands::ai::chat_with_ai,
             commands::ai::generate_embedding,
             commands::ai::index_content,
@@ -87,8 +181,26 @@ async fn main() {
             commands::ai::chat_with_context,
             commands::notifications::send_notification,
             commands::notifications::schedule_reminder,
+            commands::notifications::get_upcoming_reminders,
+            commands::notifications::snooze_reminder,
+            commands::notifications::dismiss_reminder,
+            commands::digests::get_digest_schedule,
+            commands::digests::update_digest_schedule,
+            commands::digests::generate_digest,
             commands::ics::export_calendar_to_ics,
             commands::ics::import_calendar_from_ics,
+            commands::calendar_subscriptions::create_calendar_subscription,
+            commands::calendar_subscriptions::get_calendar_subscriptions,
+            commands::calendar_subscriptions::update_calendar_subscription,
+            commands::calendar_subscriptions::delete_calendar_subscription,
+            commands::calendar_subscriptions::refresh_calendar_subscription,
+            commands::scheduling::get_free_busy,
+            commands::scheduling::find_free_slots,
+            commands::scheduling::get_event_conflicts,
+            commands::scheduling::check_event_conflicts,
+            commands::task_planner::plan_tasks,
+            commands::task_planner::get_task_plan,
+            commands::task_planner::clear_task_plan,
             commands::indexer::index_all_content,
             commands::setup::check_setup_status,
             commands::setup::install_ollama,
@@ -0,0 +1,85 @@
+use crate::models::task::Task;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Board {
+    pub id: i64,
+    pub name: String,
+    /// Group rows by "priority" or "tag"; None shows a single lane
+    pub swimlane_by: Option<String>,
+    pub columns: Vec<BoardColumn>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// A board column shows the tasks whose status equals `status`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BoardColumn {
+    pub id: i64,
+    pub board_id: i64,
+    pub name: String,
+    pub status: String,
+    pub position: i64,
+    /// Maximum number of tasks allowed in this status when moving on this board
+    pub wip_limit: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateBoard {
+    pub name: String,
+    pub swimlane_by: Option<String>,
+    pub columns: Vec<CreateBoardColumn>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateBoard {
+    pub name: Option<String>,
+    /// "priority", "tag", or an empty string to remove swimlanes
+    pub swimlane_by: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateBoardColumn {
+    pub name: String,
+    pub status: String,
+    pub wip_limit: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateBoardColumn {
+    pub name: Option<String>,
+    /// Renaming the status also moves the tasks in the column; refused while
+    /// another board shows the old status
+    pub status: Option<String>,
+    /// 0 removes the limit
+    pub wip_limit: Option<i64>,
+}
+
+/// Where to drop a task: into the column for `status` on `board_id`, directly
+/// after `after_id` and before `before_id` (either may be None at the ends)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MoveTask {
+    pub board_id: i64,
+    pub status: String,
+    pub after_id: Option<i64>,
+    pub before_id: Option<i64>,
+    pub expected_version: Option<i64>,
+}
+
+/// A board with its tasks laid out in lanes and columns
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BoardView {
+    pub board: Board,
+    pub lanes: Vec<BoardLane>,
+    /// Task count per column, across all lanes, in column order
+    pub column_counts: Vec<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BoardLane {
+    /// Priority, tag name, or "" for the single lane / untagged tasks
+    pub key: String,
+    pub label: String,
+    /// Tasks per column, in column order and sorted by position
+    pub cells: Vec<Vec<Task>>,
+}
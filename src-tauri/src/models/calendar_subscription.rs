@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+
+/// An ICS feed shown as a read-only calendar layer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalendarSubscription {
+    pub id: i64,
+    pub name: String,
+    /// File path, file:// URL or http(s) URL on localhost
+    pub url: String,
+    pub refresh_minutes: i64,
+    /// Disabled subscriptions are neither refreshed nor shown
+    pub enabled: bool,
+    /// UTC, "YYYY-MM-DD HH:MM:SS"
+    pub last_refreshed_at: Option<String>,
+    /// Why the last refresh failed; cleared by the next successful one
+    pub last_error: Option<String>,
+    pub event_count: i64,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateSubscription {
+    pub name: String,
+    pub url: String,
+    pub refresh_minutes: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateSubscription {
+    pub name: Option<String>,
+    pub url: Option<String>,
+    pub refresh_minutes: Option<i64>,
+    pub enabled: Option<bool>,
+}
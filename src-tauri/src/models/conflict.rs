@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Error returned by updates that carry an expected `version` / `updated_at`
+/// or that must respect task dependencies and board WIP limits.
+///
+/// Serialized with a `kind` tag so the frontend can tell a conflict (and
+/// merge against `current`) apart from an ordinary failure.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum UpdateError<T> {
+    /// The stored record changed since the caller last read it
+    Conflict { message: String, current: T },
+    /// The task is blocked by unfinished tasks and cannot be moved to done
+    Blocked { message: String, current: T, blocked_by: Vec<i64> },
+    /// Moving the task would put a board column over its WIP limit
+    WipLimit { message: String, current: T, status: String, limit: i64 },
+    /// Any other failure (missing record, database error, ...)
+    Failed { message: String },
+}
+
+impl<T> UpdateError<T> {
+    pub fn conflict(message: String, current: T) -> Self {
+        UpdateError::Conflict { message, current }
+    }
+}
+
+impl<T> fmt::Display for UpdateError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UpdateError::Conflict { message, .. }
+            | UpdateError::Blocked { message, .. }
+            | UpdateError::WipLimit { message, .. }
+            | UpdateError::Failed { message } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl<T> From<rusqlite::Error> for UpdateError<T> {
+    fn from(error: rusqlite::Error) -> Self {
+        UpdateError::Failed { message: error.to_string() }
+    }
+}
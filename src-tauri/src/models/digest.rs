@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+
+/// When digests are delivered; kept in `settings` as JSON
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DigestSchedule {
+    pub morning_enabled: bool,
+    /// "HH:MM" in the user's zone
+    pub morning_time: String,
+    pub weekly_enabled: bool,
+    /// ISO weekday, 1 = Monday
+    pub weekly_day: u32,
+    pub weekly_time: String,
+    /// Have Ollama summarize the weekly review
+    #[serde(default)]
+    pub summarize: bool,
+    /// Folder the digest documents are saved in; the root by default
+    #[serde(default)]
+    pub folder_id: Option<i64>,
+}
+
+impl Default for DigestSchedule {
+    fn default() -> Self {
+        Self {
+            morning_enabled: true,
+            morning_time: "08:00".to_string(),
+            weekly_enabled: true,
+            weekly_day: 5,
+            weekly_time: "16:00".to_string(),
+            summarize: false,
+            folder_id: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DigestSection {
+    pub heading: String,
+    pub items: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Digest {
+    /// "morning" or "weekly"
+    pub kind: String,
+    pub title: String,
+    /// The period covered; UTC, "YYYY-MM-DDTHH:MM:SSZ"
+    pub start: String,
+    pub end: String,
+    pub sections: Vec<DigestSection>,
+    /// Ollama's summary of the weekly review, when asked for
+    pub summary: Option<String>,
+    /// One line for the notification
+    pub headline: String,
+    /// The document the digest was saved as
+    pub document_id: Option<i64>,
+}
@@ -7,6 +7,8 @@ pub struct Document {
     pub content: String,
     pub version: i64,
     pub tags: Option<String>,
+    pub folder_id: Option<i64>,
+    pub position: i64,
     pub created_at: String,
     pub updated_at: String,
 }
@@ -16,6 +18,7 @@ pub struct CreateDocument {
     pub title: String,
     pub content: String,
     pub tags: Option<String>,
+    pub folder_id: Option<i64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,5 +26,9 @@ pub struct UpdateDocument {
     pub title: Option<String>,
     pub content: Option<String>,
     pub tags: Option<String>,
+    /// Reject the update unless the stored version still matches
+    pub expected_version: Option<i64>,
+    /// Reject the update unless the stored updated_at still matches
+    pub expected_updated_at: Option<String>,
 }
 
@@ -9,6 +9,21 @@ pub struct CalendarEvent {
     pub end_time: String,
     pub reminder_time: Option<String>,
     pub recurrence: Option<String>,
+    pub location: Option<String>,
+    /// All-day events store plain dates ("YYYY-MM-DD"); the end date is exclusive
+    pub all_day: bool,
+    /// Start times of cancelled occurrences (EXDATE), in the same format as `start_time`
+    pub exdates: Vec<String>,
+    /// Recurring series this event belongs to: set on expanded occurrences
+    /// and on edited occurrences, which are stored as their own rows
+    pub series_id: Option<i64>,
+    /// Original start of the occurrence within its series
+    pub occurrence_date: Option<String>,
+    /// Calendar subscription the event was copied from; such events are read-only
+    pub subscription_id: Option<i64>,
+    /// IANA zone the event's wall times (and recurrence) follow; times are
+    /// stored in UTC. None for all-day events.
+    pub time_zone: Option<String>,
     pub created_at: String,
 }
 
@@ -20,6 +35,14 @@ pub struct CreateEvent {
     pub end_time: String,
     pub reminder_time: Option<String>,
     pub recurrence: Option<String>,
+    pub location: Option<String>,
+    #[serde(default)]
+    pub all_day: bool,
+    #[serde(default)]
+    pub exdates: Vec<String>,
+    /// IANA zone for times given without an offset; defaults to the user's zone
+    #[serde(default)]
+    pub time_zone: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,5 +53,27 @@ pub struct UpdateEvent {
     pub end_time: Option<String>,
     pub reminder_time: Option<String>,
     pub recurrence: Option<String>,
+    pub location: Option<String>,
+    pub all_day: Option<bool>,
+    pub exdates: Option<Vec<String>>,
+    pub time_zone: Option<String>,
 }
 
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IcsImportOptions {
+    /// Name of the calendar being imported, such as its file path. Events are
+    /// matched by UID within a source, so re-importing it updates them.
+    pub source: Option<String>,
+    /// Delete events previously imported from this source that are no longer in it
+    #[serde(default)]
+    pub remove_missing: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IcsImportSummary {
+    pub created: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    pub removed: usize,
+    pub warnings: Vec<String>,
+}
@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Folder {
+    pub id: i64,
+    pub name: String,
+    pub parent_id: Option<i64>,
+    pub position: i64,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateFolder {
+    pub name: String,
+    pub parent_id: Option<i64>,
+}
+
+/// A folder with its nested subfolders, as returned by `get_folder_tree`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FolderNode {
+    pub folder: Folder,
+    pub document_count: i64,
+    pub children: Vec<FolderNode>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Collection {
+    pub id: i64,
+    pub name: String,
+    pub query: CollectionQuery,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateCollection {
+    pub name: String,
+    pub query: CollectionQuery,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateCollection {
+    pub name: Option<String>,
+    pub query: Option<CollectionQuery>,
+}
+
+/// Saved query behind a smart collection. Every criterion that is set must
+/// match; an empty query matches all documents.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CollectionQuery {
+    /// Tag paths; nested tags match their parents (`project` matches `project/alpha`)
+    pub tags: Vec<String>,
+    /// Require every tag ("all") or any of them ("any", the default)
+    pub tag_mode: Option<String>,
+    pub created_after: Option<String>,
+    pub created_before: Option<String>,
+    pub updated_after: Option<String>,
+    pub updated_before: Option<String>,
+    /// Only documents linked to an item of this type (e.g. "task")
+    pub linked_type: Option<String>,
+    /// Only documents linked to this specific item; requires `linked_type`
+    pub linked_id: Option<i64>,
+    /// Restrict to a folder and its subfolders
+    pub folder_id: Option<i64>,
+    /// Case-insensitive match on title or content
+    pub text: Option<String>,
+}
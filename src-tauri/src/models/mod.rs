@@ -2,6 +2,20 @@ pub mod message;
 pub mod document;
 pub mod task;
 pub mod event;
+pub mod tag;
+pub mod folder;
+pub mod conflict;
+pub mod template;
+pub mod outline;
+pub mod quick_add;
+pub mod time_entry;
+pub mod board;
+pub mod task_transfer;
+pub mod task_extraction;
+pub mod calendar_subscription;
+pub mod scheduling;
+pub mod reminder;
+pub mod digest;
 
 // Play v1.1 "Data Ready" models
 pub mod ingestion;
@@ -0,0 +1,33 @@
+use serde::{Deserialize, Serialize};
+
+/// One heading of a document together with the text that follows it up to
+/// the next heading. Text before the first heading is a level 0 section.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutlineSection {
+    pub index: i64,
+    pub level: i64,
+    pub heading: String,
+    /// Headings from the top level down to this one, e.g. ["Plan", "Budget"]
+    pub path: Vec<String>,
+    /// Slug of the heading, unique within the document
+    pub anchor: String,
+    /// Plain text of the section body
+    pub content: String,
+    pub word_count: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentOutline {
+    pub document_id: i64,
+    pub title: String,
+    pub sections: Vec<OutlineSection>,
+}
+
+/// Points a search hit at the section its chunk came from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SectionRef {
+    pub index: i64,
+    pub heading: String,
+    pub path: Vec<String>,
+    pub anchor: String,
+}
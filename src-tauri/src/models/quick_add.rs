@@ -0,0 +1,28 @@
+use crate::models::event::{CalendarEvent, CreateEvent};
+use crate::models::task::{CreateTask, Task};
+use serde::{Deserialize, Serialize};
+
+/// What a quick-add line turned into
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum QuickAddItem {
+    Task(CreateTask),
+    Event(CreateEvent),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuickAddResult {
+    pub item: QuickAddItem,
+    pub tags: Vec<String>,
+    /// "parser" for the offline parser, "ai" when Ollama filled in, or
+    /// "plain" when neither understood the input and it became a bare task
+    pub source: String,
+}
+
+/// Result of `quick_add` once the item has been saved
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum QuickAddCreated {
+    Task { task: Task, tags: Vec<String>, source: String },
+    Event { event: CalendarEvent, tags: Vec<String>, source: String },
+}
@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+
+/// A reminder waiting in, or already through, the notification queue
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Reminder {
+    pub id: i64,
+    /// "task", "event" or "custom"
+    pub item_type: String,
+    /// The task or event; series occurrences carry the id of their series
+    pub item_id: Option<i64>,
+    /// Start of the series occurrence the reminder is for; empty otherwise
+    pub occurrence: String,
+    pub title: String,
+    pub body: String,
+    /// UTC, "YYYY-MM-DDTHH:MM:SSZ"
+    pub fire_at: String,
+    /// Set while snoozed; the reminder fires then instead of at `fire_at`
+    pub snoozed_until: Option<String>,
+    /// "pending", "fired", "dismissed" or "missed"
+    pub status: String,
+    pub fired_at: Option<String>,
+}
@@ -0,0 +1,111 @@
+use crate::models::event::CalendarEvent;
+use serde::{Deserialize, Serialize};
+
+/// A span of time; both ends are UTC ("YYYY-MM-DDTHH:MM:SSZ")
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimeSlot {
+    pub start: String,
+    pub end: String,
+}
+
+/// Overlapping events merged into one busy span
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BusyBlock {
+    pub start: String,
+    pub end: String,
+    pub event_ids: Vec<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FreeBusy {
+    pub start: String,
+    pub end: String,
+    pub busy: Vec<BusyBlock>,
+    pub free: Vec<TimeSlot>,
+}
+
+/// Another event overlapping an event (or one of its occurrences)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventConflict {
+    /// Start of the clashing occurrence of the event that was checked
+    pub occurrence_start: String,
+    pub event: CalendarEvent,
+}
+
+/// An event as saved, with the events it now overlaps
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledEvent {
+    pub event: CalendarEvent,
+    pub conflicts: Vec<EventConflict>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FreeSlotRequest {
+    pub duration_minutes: i64,
+    /// Search from here (defaults to now) until `end` (defaults to two weeks later)
+    pub start: Option<String>,
+    pub end: Option<String>,
+    /// Working hours as "HH:MM" in the user's zone; 09:00 to 17:00 by default
+    pub work_start: Option<String>,
+    pub work_end: Option<String>,
+    /// ISO weekdays to search, 1 = Monday; Monday to Friday by default
+    pub work_days: Option<Vec<u32>>,
+    /// Free minutes kept before and after other events
+    #[serde(default)]
+    pub buffer_minutes: i64,
+    /// How many slots to return; 5 by default
+    pub count: Option<usize>,
+}
+
+/// Options for planning tasks into the calendar. The last plan's options are
+/// kept, without its range, so the plan can follow later changes.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PlanRequest {
+    /// Plan from here (defaults to now) until `end` (defaults to two weeks later)
+    pub start: Option<String>,
+    pub end: Option<String>,
+    /// Working hours as "HH:MM" in the user's zone; 09:00 to 17:00 by default
+    pub work_start: Option<String>,
+    pub work_end: Option<String>,
+    /// ISO weekdays to plan on, 1 = Monday; Monday to Friday by default
+    pub work_days: Option<Vec<u32>>,
+    /// Free minutes kept before and after other events
+    #[serde(default)]
+    pub buffer_minutes: i64,
+    /// Shortest block a task is split into; 30 minutes by default
+    pub min_block_minutes: Option<i64>,
+    /// Longest block; 120 minutes by default
+    pub max_block_minutes: Option<i64>,
+    /// Only plan these tasks; every open task with an estimate by default
+    pub task_ids: Option<Vec<i64>>,
+}
+
+/// A calendar event the planner booked for a task
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlannedBlock {
+    pub task_id: i64,
+    pub event: CalendarEvent,
+    /// Why the task landed here
+    pub reason: String,
+    /// Edited by hand since it was planned; re-planning leaves it as it is
+    pub pinned: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlannedTask {
+    pub task_id: i64,
+    pub title: String,
+    /// 1-based place in the planning order
+    pub rank: usize,
+    pub planned_minutes: i64,
+    /// Minutes that did not fit before the end of the range
+    pub unplanned_minutes: i64,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskPlan {
+    pub tasks: Vec<PlannedTask>,
+    /// Every block from the start of the range on, including pinned ones
+    pub blocks: Vec<PlannedBlock>,
+}
@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+
+/// Item types that can carry tags through the `item_tags` join table
+pub const TAGGABLE_TYPES: [&str; 5] = ["document", "task", "event", "file", "message"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tag {
+    pub id: i64,
+    pub name: String,
+    pub parent_id: Option<i64>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagWithCount {
+    pub id: i64,
+    pub name: String,
+    pub parent_id: Option<i64>,
+    pub item_count: i64,
+    pub total_count: i64,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaggedItem {
+    pub item_type: String,
+    pub item_id: i64,
+    pub tag_id: i64,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagSuggestion {
+    pub id: i64,
+    pub item_type: String,
+    pub item_id: i64,
+    pub name: String,
+    pub source: String,
+    pub created_at: String,
+}
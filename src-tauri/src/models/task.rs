@@ -7,10 +7,29 @@ pub struct Task {
     pub description: Option<String>,
     pub status: String,
     pub priority: String,
+    /// A date, or a UTC time ("YYYY-MM-DDTHH:MM:SSZ")
     pub due_date: Option<String>,
     pub reminder_time: Option<String>,
+    /// IANA zone the due time was set in; recurrences follow its wall time
+    pub time_zone: Option<String>,
+    pub parent_id: Option<i64>,
+    /// iCalendar RRULE, e.g. "FREQ=WEEKLY;BYDAY=MO"
+    pub recurrence: Option<String>,
+    /// Id of the first task of a recurring series
+    pub series_id: Option<i64>,
+    /// 1-based position of this task within its series
+    pub occurrence: i64,
+    /// Estimated effort in minutes
+    pub estimate: Option<i64>,
+    /// Fractional order within the task's board column
+    pub position: f64,
+    pub version: i64,
     pub created_at: String,
     pub updated_at: String,
+    /// Unfinished tasks this one waits on; only reported by an update that
+    /// completed it anyway under the "warn" blocked-task policy
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub open_blockers: Vec<i64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,6 +40,13 @@ pub struct CreateTask {
     pub priority: Option<String>,
     pub due_date: Option<String>,
     pub reminder_time: Option<String>,
+    pub parent_id: Option<i64>,
+    pub recurrence: Option<String>,
+    /// Estimated effort in minutes
+    pub estimate: Option<i64>,
+    /// IANA zone for times given without an offset; defaults to the user's zone
+    #[serde(default)]
+    pub time_zone: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -31,5 +57,62 @@ pub struct UpdateTask {
     pub priority: Option<String>,
     pub due_date: Option<String>,
     pub reminder_time: Option<String>,
+    /// New RRULE; an empty string stops the recurrence
+    pub recurrence: Option<String>,
+    /// Estimated effort in minutes; 0 clears the estimate
+    pub estimate: Option<i64>,
+    /// IANA zone for the due and reminder times given with this update
+    pub time_zone: Option<String>,
+    /// Order within the board column; a status change without one appends
+    pub position: Option<f64>,
+    /// Board the change is made on; only its WIP limits apply to a status
+    /// change. Without one, the limits of every board showing the status apply.
+    pub board_id: Option<i64>,
+    /// Reject the update unless the stored version still matches
+    pub expected_version: Option<i64>,
+    /// Reject the update unless the stored updated_at still matches
+    pub expected_updated_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChecklistItem {
+    pub id: i64,
+    pub task_id: i64,
+    pub text: String,
+    pub done: bool,
+    pub position: i64,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateChecklistItem {
+    pub text: Option<String>,
+    pub done: Option<bool>,
+    pub position: Option<i64>,
+}
+
+/// Completed vs. total work below a task: all subtasks (at any depth) plus
+/// the task's own checklist items.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskProgress {
+    pub completed: i64,
+    pub total: i64,
+    pub percent: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskDetails {
+    pub task: Task,
+    pub subtasks: Vec<Task>,
+    pub checklist: Vec<ChecklistItem>,
+    /// Tasks that must be done before this one
+    pub blocked_by: Vec<Task>,
+    /// Tasks waiting on this one
+    pub blocking: Vec<Task>,
+    pub progress: TaskProgress,
+    /// True while any task in `blocked_by` is not done
+    pub is_blocked: bool,
+    /// Seconds tracked on this task, including a running timer
+    pub tracked_seconds: i64,
 }
 
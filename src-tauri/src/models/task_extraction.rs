@@ -0,0 +1,38 @@
+use crate::models::task::{CreateTask, Task};
+use serde::{Deserialize, Serialize};
+
+/// A task suggested by the AI, waiting to be accepted or discarded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskDraft {
+    pub task: CreateTask,
+    /// Person the task was assigned to in the source, if named
+    pub owner: Option<String>,
+    /// The sentence the task was taken from
+    pub excerpt: Option<String>,
+}
+
+/// The message, document or file tasks were extracted from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskSource {
+    /// "message", "document" or "file"
+    pub source_type: String,
+    pub source_id: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskExtraction {
+    pub drafts: Vec<TaskDraft>,
+    /// Date relative due dates were resolved against ("YYYY-MM-DD")
+    pub source_date: String,
+    /// Items from the model that failed validation, with the reason
+    pub rejected: Vec<String>,
+}
+
+/// Result of `commit_task_drafts`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommittedTask {
+    pub task: Task,
+    pub owner: Option<String>,
+    /// Id of the link back to the source, when there was one
+    pub link_id: Option<i64>,
+}
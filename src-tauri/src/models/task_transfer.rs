@@ -0,0 +1,56 @@
+use crate::models::ingestion::IngestionJob;
+use serde::{Deserialize, Serialize};
+
+/// A task read from another tool, before it is saved
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ImportedTask {
+    pub title: String,
+    pub description: Option<String>,
+    pub status: String,
+    pub priority: String,
+    /// "YYYY-MM-DD", local "YYYY-MM-DDTHH:MM" or UTC "YYYY-MM-DDTHH:MM:SSZ"
+    pub due_date: Option<String>,
+    pub recurrence: Option<String>,
+    /// Same forms as `due_date`
+    #[serde(default)]
+    pub reminder_time: Option<String>,
+    /// IANA zone of the due time, when the source names one
+    #[serde(default)]
+    pub time_zone: Option<String>,
+    /// Estimated effort in minutes
+    #[serde(default)]
+    pub estimate: Option<i64>,
+    pub tags: Vec<String>,
+    pub checklist: Vec<ImportedChecklistItem>,
+    /// Index of the parent task within the same import
+    pub parent: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImportedChecklistItem {
+    pub text: String,
+    pub done: bool,
+}
+
+/// Input for `import_tasks`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskImportRequest {
+    pub path: String,
+    /// "trello", "todoist", "todotxt", "csv" or "ics"; detected from the file when None
+    pub format: Option<String>,
+    /// Parse and report without creating any tasks
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskImportResult {
+    /// The `ingestion_jobs` entry tracking this import
+    pub job: IngestionJob,
+    pub format: String,
+    pub dry_run: bool,
+    pub tasks: Vec<ImportedTask>,
+    pub created_task_ids: Vec<i64>,
+    /// Items that were skipped or only partly understood
+    pub warnings: Vec<String>,
+}
@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentTemplate {
+    pub id: i64,
+    pub name: String,
+    pub description: Option<String>,
+    /// Title used when none is given, e.g. "Meeting notes {{date}}"
+    pub title_pattern: Option<String>,
+    pub content: String,
+    pub tags: Option<String>,
+    pub builtin: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateTemplate {
+    pub name: String,
+    pub description: Option<String>,
+    pub title_pattern: Option<String>,
+    pub content: String,
+    pub tags: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateTemplate {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub title_pattern: Option<String>,
+    pub content: Option<String>,
+    pub tags: Option<String>,
+}
+
+/// Input for `create_document_from_template`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CreateFromTemplate {
+    pub template_id: i64,
+    /// Overrides the template's title pattern
+    pub title: Option<String>,
+    pub folder_id: Option<i64>,
+    /// Tasks rendered by `{{linked_tasks}}` and linked to the new document
+    pub task_ids: Vec<i64>,
+    /// Events rendered by `{{linked_events}}` and linked to the new document
+    pub event_ids: Vec<i64>,
+    /// Documents rendered by `{{linked_documents}}` and linked to the new document
+    pub document_ids: Vec<i64>,
+    /// Values for custom placeholders, e.g. `{{project}}`
+    pub variables: HashMap<String, String>,
+    /// Skip `{{ai: ...}}` sections instead of calling Ollama
+    pub skip_ai: bool,
+}
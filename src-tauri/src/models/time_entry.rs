@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeEntry {
+    pub id: i64,
+    pub task_id: i64,
+    /// UTC, "YYYY-MM-DD HH:MM:SS"
+    pub started_at: String,
+    /// None while the timer is running
+    pub ended_at: Option<String>,
+    /// Length of the entry; for a running timer, the time elapsed so far
+    pub duration_seconds: i64,
+    pub note: Option<String>,
+    /// Calendar event showing this block, if any
+    pub event_id: Option<i64>,
+    pub created_at: String,
+}
+
+/// A manually entered block of time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateTimeEntry {
+    pub task_id: i64,
+    pub started_at: String,
+    pub ended_at: String,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateTimeEntry {
+    pub started_at: Option<String>,
+    pub ended_at: Option<String>,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeReportRow {
+    /// Task id, tag name or ISO week ("2024-W20")
+    pub key: String,
+    pub label: String,
+    pub seconds: i64,
+    pub entries: i64,
+    /// Summed task estimates in minutes (task reports only)
+    pub estimate_minutes: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeReport {
+    /// "task", "tag" or "week"
+    pub group_by: String,
+    pub rows: Vec<TimeReportRow>,
+    pub total_seconds: i64,
+}
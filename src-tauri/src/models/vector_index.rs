@@ -1,3 +1,4 @@
+use crate::models::outline::SectionRef;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -37,6 +38,9 @@ pub struct SimilaritySearchResult {
     pub content: String,
     pub similarity_score: f32,
     pub metadata: Option<String>,
+    /// Section of the source document the hit came from, when known
+    #[serde(default)]
+    pub section: Option<SectionRef>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -0,0 +1,257 @@
+// Blob Store - content-addressed, reference-counted file storage
+//
+// Every stored file is named by the SHA-256 of its content, so the same bytes
+// are kept once however often they are uploaded, ingested or copied. Each
+// owner (a row in `files`, a processed file) holds one reference; the last
+// `release` deletes the bytes. Blobs keep the extension they were first
+// stored with, since content processors pick their parser by extension.
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// A stored blob; `path` is relative to the store's root
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Blob {
+    pub hash: String,
+    pub size: i64,
+    pub path: String,
+    pub refcount: i64,
+}
+
+fn io_error(e: io::Error) -> rusqlite::Error {
+    rusqlite::Error::InvalidParameterName(e.to_string())
+}
+
+pub fn hash_bytes(content: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(content))
+}
+
+/// SHA-256 of a file, read in pieces so large files are never held in memory
+pub fn hash_file(path: &Path) -> io::Result<String> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Where a blob lives under the root: fanned out by the first two hex digits
+fn blob_path(hash: &str, extension: Option<&str>) -> String {
+    let extension = extension
+        .map(|e| e.trim_start_matches('.').to_ascii_lowercase())
+        .filter(|e| !e.is_empty() && e.chars().all(|c| c.is_ascii_alphanumeric()));
+    match extension {
+        Some(extension) => format!("{}/{}.{}", &hash[..2], hash, extension),
+        None => format!("{}/{}", &hash[..2], hash),
+    }
+}
+
+fn extension_of(name: &str) -> Option<&str> {
+    Path::new(name).extension().and_then(|e| e.to_str())
+}
+
+pub struct BlobStore<'a> {
+    conn: &'a Connection,
+    root: PathBuf,
+}
+
+impl<'a> BlobStore<'a> {
+    pub fn new(conn: &'a Connection, root: PathBuf) -> io::Result<Self> {
+        fs::create_dir_all(root.join("tmp"))?;
+        Ok(Self { conn, root })
+    }
+
+    pub fn get(&self, hash: &str) -> Result<Option<Blob>> {
+        self.conn
+            .query_row(
+                "SELECT hash, size, path, refcount FROM blobs WHERE hash = ?1",
+                params![hash],
+                |row| {
+                    Ok(Blob {
+                        hash: row.get(0)?,
+                        size: row.get(1)?,
+                        path: row.get(2)?,
+                        refcount: row.get(3)?,
+                    })
+                },
+            )
+            .optional()
+    }
+
+    /// Absolute path of a stored blob
+    pub fn full_path(&self, blob: &Blob) -> PathBuf {
+        self.root.join(&blob.path)
+    }
+
+    /// Take a reference to `hash` if it is stored, or store the staged file
+    /// at `staged` under it. The staged file is consumed either way.
+    fn adopt(&self, hash: &str, size: i64, staged: &Path, name: &str) -> Result<Blob> {
+        if let Some(blob) = self.get(hash)? {
+            // The bytes may have gone missing on disk; put them back
+            if !self.full_path(&blob).exists() {
+                fs::rename(staged, self.full_path(&blob)).map_err(io_error)?;
+            } else {
+                fs::remove_file(staged).map_err(io_error)?;
+            }
+            self.conn.execute("UPDATE blobs SET refcount = refcount + 1 WHERE hash = ?1", params![hash])?;
+            return self.get(hash)?.ok_or(rusqlite::Error::QueryReturnedNoRows);
+        }
+        let path = blob_path(hash, extension_of(name));
+        let full_path = self.root.join(&path);
+        if let Some(dir) = full_path.parent() {
+            fs::create_dir_all(dir).map_err(io_error)?;
+        }
+        fs::rename(staged, &full_path).map_err(io_error)?;
+        self.conn.execute(
+            "INSERT INTO blobs (hash, size, path, refcount) VALUES (?1, ?2, ?3, 1)",
+            params![hash, size, path],
+        )?;
+        self.get(hash)?.ok_or(rusqlite::Error::QueryReturnedNoRows)
+    }
+
+    fn staging_path(&self) -> PathBuf {
+        self.staging_dir().join(uuid::Uuid::new_v4().to_string())
+    }
+
+    /// Where files are assembled before they are stored
+    pub fn staging_dir(&self) -> PathBuf {
+        self.root.join("tmp")
+    }
+
+    /// Store a file assembled in the staging directory, moving it into place,
+    /// and take a reference to it
+    pub fn store_staged(&self, staged: &Path, name: &str) -> Result<Blob> {
+        let hash = hash_file(staged).map_err(io_error)?;
+        let size = fs::metadata(staged).map_err(io_error)?.len() as i64;
+        self.adopt(&hash, size, staged, name)
+    }
+
+    /// Store `content` (named `name`, for its extension) and take a reference to it
+    pub fn store_bytes(&self, name: &str, content: &[u8]) -> Result<Blob> {
+        let hash = hash_bytes(content);
+        if self.get(&hash)?.map(|b| self.full_path(&b).exists()).unwrap_or(false) {
+            self.conn.execute("UPDATE blobs SET refcount = refcount + 1 WHERE hash = ?1", params![hash])?;
+            return self.get(&hash)?.ok_or(rusqlite::Error::QueryReturnedNoRows);
+        }
+        // Written aside first so a crash never leaves a half-written blob under its hash
+        let staged = self.staging_path();
+        fs::write(&staged, content).map_err(io_error)?;
+        self.adopt(&hash, content.len() as i64, &staged, name)
+    }
+
+    /// Copy the file at `source` into the store, hashing it on the way, and
+    /// take a reference to it
+    pub fn store_file(&self, source: &Path) -> Result<Blob> {
+        let staged = self.staging_path();
+        let copied = (|| -> io::Result<(String, i64)> {
+            let mut input = fs::File::open(source)?;
+            let mut output = fs::File::create(&staged)?;
+            let mut hasher = Sha256::new();
+            let mut buffer = vec![0u8; 1 << 16];
+            let mut size = 0i64;
+            loop {
+                let read = input.read(&mut buffer)?;
+                if read == 0 {
+                    break;
+                }
+                hasher.update(&buffer[..read]);
+                output.write_all(&buffer[..read])?;
+                size += read as i64;
+            }
+            output.sync_all()?;
+            Ok((format!("{:x}", hasher.finalize()), size))
+        })();
+        let (hash, size) = match copied {
+            Ok(copied) => copied,
+            Err(e) => {
+                let _ = fs::remove_file(&staged);
+                return Err(io_error(e));
+            }
+        };
+        let name = source.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        self.adopt(&hash, size, &staged, &name)
+    }
+
+    /// Drop a reference; the bytes are deleted with the last one. Returns
+    /// whether the blob is gone.
+    pub fn release(&self, hash: &str) -> Result<bool> {
+        let Some(blob) = self.get(hash)? else {
+            return Ok(true);
+        };
+        if blob.refcount > 1 {
+            self.conn.execute("UPDATE blobs SET refcount = refcount - 1 WHERE hash = ?1", params![hash])?;
+            return Ok(false);
+        }
+        let full_path = self.full_path(&blob);
+        if full_path.exists() {
+            fs::remove_file(full_path).map_err(io_error)?;
+        }
+        self.conn.execute("DELETE FROM blobs WHERE hash = ?1", params![hash])?;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blobs_are_named_by_hash_and_keep_safe_extensions() {
+        let hash = hash_bytes(b"hello");
+        assert_eq!(hash, "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824");
+        assert_eq!(blob_path(&hash, Some("PDF")), format!("2c/{}.pdf", hash));
+        assert_eq!(blob_path(&hash, Some("../x")), format!("2c/{}", hash));
+        assert_eq!(blob_path(&hash, None), format!("2c/{}", hash));
+    }
+
+    fn temp_root() -> PathBuf {
+        std::env::temp_dir().join(format!("play-blobs-{}", uuid::Uuid::new_v4()))
+    }
+
+    #[test]
+    fn identical_content_is_stored_once_and_released_with_its_last_reference() {
+        let db = crate::services::database::Database::in_memory().unwrap();
+        let root = temp_root();
+        let store = BlobStore::new(db.get_connection(), root.clone()).unwrap();
+
+        let first = store.store_bytes("report.pdf", b"same bytes").unwrap();
+        let second = store.store_bytes("copy.PDF", b"same bytes").unwrap();
+        assert_eq!((first.hash.as_str(), first.path.as_str()), (second.hash.as_str(), second.path.as_str()));
+        assert_eq!(second.refcount, 2);
+        let blobs: i64 = db.get_connection().query_row("SELECT COUNT(*) FROM blobs", [], |row| row.get(0)).unwrap();
+        assert_eq!(blobs, 1);
+
+        assert!(!store.release(&first.hash).unwrap());
+        assert!(store.full_path(&first).exists());
+        assert_eq!(store.get(&first.hash).unwrap().unwrap().refcount, 1);
+
+        assert!(store.release(&first.hash).unwrap());
+        assert!(!store.full_path(&first).exists());
+        assert!(store.get(&first.hash).unwrap().is_none());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn missing_bytes_are_restored_and_failed_copies_leave_nothing_staged() {
+        let db = crate::services::database::Database::in_memory().unwrap();
+        let root = temp_root();
+        let store = BlobStore::new(db.get_connection(), root.clone()).unwrap();
+
+        let blob = store.store_bytes("photo.jpg", b"pixels").unwrap();
+        fs::remove_file(store.full_path(&blob)).unwrap();
+        let source = root.join("photo.jpg");
+        fs::write(&source, b"pixels").unwrap();
+        let restored = store.store_file(&source).unwrap();
+        assert_eq!(restored.refcount, 2);
+        assert_eq!(fs::read(store.full_path(&restored)).unwrap(), b"pixels");
+
+        // Reading a directory fails after the staged file was created
+        assert!(store.store_file(&root).is_err());
+        assert!(store.store_file(&root.join("missing.txt")).is_err());
+        assert_eq!(fs::read_dir(store.staging_dir()).unwrap().count(), 0);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
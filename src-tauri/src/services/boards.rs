@@ -0,0 +1,638 @@
+// Board Service - Kanban boards with custom columns, task ordering, WIP limits and swimlanes
+//
+// Columns map onto task statuses, so a team can run its own workflow while
+// tasks keep a single `status`. Order inside a column is the task's
+// fractional `position`: a dropped task takes the midpoint of its neighbours,
+// and a column is only renumbered when two neighbours get too close.
+use crate::models::board::{
+    Board, BoardColumn, BoardLane, BoardView, CreateBoard, CreateBoardColumn, MoveTask, UpdateBoard, UpdateBoardColumn,
+};
+use crate::models::conflict::UpdateError;
+use crate::models::task::{Task, UpdateTask};
+use crate::services::database::Database;
+use crate::services::tags::TagService;
+use rusqlite::{params, OptionalExtension, Result};
+
+/// Neighbour positions closer than this trigger a renumbering of the column
+const MIN_GAP: f64 = 1e-9;
+
+const SWIMLANES: [&str; 2] = ["priority", "tag"];
+const PRIORITY_LANES: [(&str, &str); 3] = [("high", "High priority"), ("medium", "Medium priority"), ("low", "Low priority")];
+
+fn invalid(message: String) -> rusqlite::Error {
+    rusqlite::Error::InvalidParameterName(message)
+}
+
+fn normalize_swimlane(swimlane_by: Option<&str>) -> Result<Option<String>> {
+    match swimlane_by.map(str::trim).filter(|s| !s.is_empty()) {
+        Some(lane) if SWIMLANES.contains(&lane) => Ok(Some(lane.to_string())),
+        Some(lane) => Err(invalid(format!("Unknown swimlane grouping '{}'", lane))),
+        None => Ok(None),
+    }
+}
+
+fn normalize_wip_limit(limit: Option<i64>) -> Option<i64> {
+    limit.filter(|limit| *limit > 0)
+}
+
+pub struct BoardService<'a> {
+    db: &'a Database,
+}
+
+impl<'a> BoardService<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    /// Create the default todo / in progress / done board on first run
+    pub fn initialize_default_board(&self) -> Result<()> {
+        let count: i64 = self.db.get_connection().query_row("SELECT COUNT(*) FROM boards", [], |row| row.get(0))?;
+        if count > 0 {
+            return Ok(());
+        }
+        let column = |name: &str, status: &str| CreateBoardColumn {
+            name: name.to_string(),
+            status: status.to_string(),
+            wip_limit: None,
+        };
+        self.create_board(CreateBoard {
+            name: "Tasks".to_string(),
+            swimlane_by: None,
+            columns: vec![column("To Do", "todo"), column("In Progress", "in_progress"), column("Done", "done")],
+        })?;
+        Ok(())
+    }
+
+    // Boards
+
+    fn get_columns(&self, board_id: i64) -> Result<Vec<BoardColumn>> {
+        let conn = self.db.get_connection();
+        let mut stmt = conn.prepare(
+            "SELECT id, board_id, name, status, position, wip_limit
+             FROM board_columns WHERE board_id = ?1 ORDER BY position, id",
+        )?;
+        let columns = stmt.query_map(params![board_id], Self::map_column)?;
+        columns.collect()
+    }
+
+    fn map_column(row: &rusqlite::Row) -> Result<BoardColumn> {
+        Ok(BoardColumn {
+            id: row.get(0)?,
+            board_id: row.get(1)?,
+            name: row.get(2)?,
+            status: row.get(3)?,
+            position: row.get(4)?,
+            wip_limit: row.get(5)?,
+        })
+    }
+
+    fn get_column(&self, id: i64) -> Result<BoardColumn> {
+        self.db.get_connection().query_row(
+            "SELECT id, board_id, name, status, position, wip_limit FROM board_columns WHERE id = ?1",
+            params![id],
+            Self::map_column,
+        )
+    }
+
+    pub fn get_board(&self, id: i64) -> Result<Board> {
+        let (name, swimlane_by, created_at, updated_at) = self.db.get_connection().query_row(
+            "SELECT name, swimlane_by, created_at, updated_at FROM boards WHERE id = ?1",
+            params![id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )?;
+        Ok(Board {
+            id,
+            name,
+            swimlane_by,
+            columns: self.get_columns(id)?,
+            created_at,
+            updated_at,
+        })
+    }
+
+    pub fn get_all_boards(&self) -> Result<Vec<Board>> {
+        let conn = self.db.get_connection();
+        let mut stmt = conn.prepare("SELECT id FROM boards ORDER BY created_at, id")?;
+        let ids: Vec<i64> = stmt.query_map([], |row| row.get(0))?.collect::<Result<_>>()?;
+        ids.into_iter().map(|id| self.get_board(id)).collect()
+    }
+
+    pub fn create_board(&self, board: CreateBoard) -> Result<Board> {
+        let name = board.name.trim();
+        if name.is_empty() {
+            return Err(invalid("Board name cannot be empty".to_string()));
+        }
+        let swimlane_by = normalize_swimlane(board.swimlane_by.as_deref())?;
+
+        let conn = self.db.get_connection();
+        let tx = conn.unchecked_transaction()?;
+        conn.execute(
+            "INSERT INTO boards (name, swimlane_by) VALUES (?1, ?2)",
+            params![name, swimlane_by],
+        )?;
+        let id = conn.last_insert_rowid();
+        for column in board.columns {
+            self.insert_column(id, column)?;
+        }
+        tx.commit()?;
+
+        self.get_board(id)
+    }
+
+    pub fn update_board(&self, id: i64, update: UpdateBoard) -> Result<Board> {
+        self.get_board(id)?;
+        let conn = self.db.get_connection();
+        if let Some(name) = &update.name {
+            let name = name.trim();
+            if name.is_empty() {
+                return Err(invalid("Board name cannot be empty".to_string()));
+            }
+            conn.execute("UPDATE boards SET name = ?1 WHERE id = ?2", params![name, id])?;
+        }
+        if let Some(swimlane_by) = &update.swimlane_by {
+            let swimlane_by = normalize_swimlane(Some(swimlane_by))?;
+            conn.execute("UPDATE boards SET swimlane_by = ?1 WHERE id = ?2", params![swimlane_by, id])?;
+        }
+        conn.execute("UPDATE boards SET updated_at = CURRENT_TIMESTAMP WHERE id = ?1", params![id])?;
+        self.get_board(id)
+    }
+
+    /// Delete a board and its columns; the tasks themselves are kept
+    pub fn delete_board(&self, id: i64) -> Result<()> {
+        let conn = self.db.get_connection();
+        let tx = conn.unchecked_transaction()?;
+        conn.execute("DELETE FROM board_columns WHERE board_id = ?1", params![id])?;
+        conn.execute("DELETE FROM boards WHERE id = ?1", params![id])?;
+        tx.commit()
+    }
+
+    // Columns
+
+    fn insert_column(&self, board_id: i64, column: CreateBoardColumn) -> Result<()> {
+        let name = column.name.trim();
+        let status = column.status.trim();
+        if name.is_empty() || status.is_empty() {
+            return Err(invalid("Board columns need a name and a status".to_string()));
+        }
+        if self.get_columns(board_id)?.iter().any(|c| c.status == status) {
+            return Err(invalid(format!("Board {} already has a column for status '{}'", board_id, status)));
+        }
+
+        self.db.get_connection().execute(
+            "INSERT INTO board_columns (board_id, name, status, wip_limit, position)
+             VALUES (?1, ?2, ?3, ?4, (SELECT COALESCE(MAX(position), -1) + 1 FROM board_columns WHERE board_id = ?1))",
+            params![board_id, name, status, normalize_wip_limit(column.wip_limit)],
+        )?;
+        Ok(())
+    }
+
+    pub fn add_column(&self, board_id: i64, column: CreateBoardColumn) -> Result<Board> {
+        self.get_board(board_id)?;
+        self.insert_column(board_id, column)?;
+        self.get_board(board_id)
+    }
+
+    fn status_in_other_columns(&self, status: &str, column_id: i64) -> Result<bool> {
+        self.db.get_connection().query_row(
+            "SELECT EXISTS (SELECT 1 FROM board_columns WHERE status = ?1 AND id != ?2)",
+            params![status, column_id],
+            |row| row.get(0),
+        )
+    }
+
+    pub fn update_column(&self, id: i64, update: UpdateBoardColumn) -> Result<Board> {
+        let column = self.get_column(id)?;
+        let conn = self.db.get_connection();
+        let tx = conn.unchecked_transaction()?;
+
+        if let Some(name) = &update.name {
+            let name = name.trim();
+            if name.is_empty() {
+                return Err(invalid("Column name cannot be empty".to_string()));
+            }
+            conn.execute("UPDATE board_columns SET name = ?1 WHERE id = ?2", params![name, id])?;
+        }
+        if let Some(status) = &update.status {
+            let status = status.trim();
+            if status.is_empty() {
+                return Err(invalid("Column status cannot be empty".to_string()));
+            }
+            if status != column.status {
+                if self.get_columns(column.board_id)?.iter().any(|c| c.status == status) {
+                    return Err(invalid(format!("Board {} already has a column for status '{}'", column.board_id, status)));
+                }
+                // Tasks follow the rename, which would pull them out of other boards' columns
+                if self.status_in_other_columns(&column.status, id)? {
+                    return Err(invalid(format!(
+                        "Status '{}' is shown on another board; rename it there first or add a new column",
+                        column.status
+                    )));
+                }
+                conn.execute("UPDATE board_columns SET status = ?1 WHERE id = ?2", params![status, id])?;
+                conn.execute(
+                    "UPDATE tasks SET status = ?1, updated_at = CURRENT_TIMESTAMP, version = version + 1 WHERE status = ?2",
+                    params![status, column.status],
+                )?;
+            }
+        }
+        if let Some(limit) = update.wip_limit {
+            conn.execute(
+                "UPDATE board_columns SET wip_limit = ?1 WHERE id = ?2",
+                params![normalize_wip_limit(Some(limit)), id],
+            )?;
+        }
+
+        tx.commit()?;
+        self.get_board(column.board_id)
+    }
+
+    /// Remove a column. Refused while it holds tasks that no other board shows.
+    pub fn delete_column(&self, id: i64) -> Result<Board> {
+        let column = self.get_column(id)?;
+        let task_count = self.count_in_status(&column.status, None)?;
+        if task_count > 0 && !self.status_in_other_columns(&column.status, id)? {
+            return Err(invalid(format!(
+                "Column '{}' still holds {} task(s); move them before deleting it",
+                column.name, task_count
+            )));
+        }
+
+        let conn = self.db.get_connection();
+        conn.execute("DELETE FROM board_columns WHERE id = ?1", params![id])?;
+        let order: Vec<i64> = self.get_columns(column.board_id)?.into_iter().map(|c| c.id).collect();
+        self.resequence_columns(&order)?;
+        self.get_board(column.board_id)
+    }
+
+    pub fn reorder_columns(&self, board_id: i64, ordered_ids: &[i64]) -> Result<Board> {
+        let current: Vec<i64> = self.get_columns(board_id)?.into_iter().map(|c| c.id).collect();
+        if let Some(stray) = ordered_ids.iter().find(|id| !current.contains(id)) {
+            return Err(invalid(format!("Column {} does not belong to board {}", stray, board_id)));
+        }
+
+        let mut order: Vec<i64> = ordered_ids.to_vec();
+        order.extend(current.into_iter().filter(|id| !ordered_ids.contains(id)));
+
+        let tx = self.db.get_connection().unchecked_transaction()?;
+        self.resequence_columns(&order)?;
+        tx.commit()?;
+
+        self.get_board(board_id)
+    }
+
+    fn resequence_columns(&self, ordered_ids: &[i64]) -> Result<()> {
+        for (position, id) in ordered_ids.iter().enumerate() {
+            self.db.get_connection().execute(
+                "UPDATE board_columns SET position = ?1 WHERE id = ?2",
+                params![position as i64, id],
+            )?;
+        }
+        Ok(())
+    }
+
+    // WIP limits
+
+    fn count_in_status(&self, status: &str, excluding: Option<i64>) -> Result<i64> {
+        self.db.get_connection().query_row(
+            "SELECT COUNT(*) FROM tasks WHERE status = ?1 AND (?2 IS NULL OR id != ?2)",
+            params![status, excluding],
+            |row| row.get(0),
+        )
+    }
+
+    /// The WIP limit that one more task in `status` would break, if any. A
+    /// move on a board answers to that board's column; a plain status change
+    /// answers to every board with a column for the status.
+    pub fn wip_limit_exceeded(&self, board_id: Option<i64>, status: &str, task_id: Option<i64>) -> Result<Option<i64>> {
+        let limit: Option<i64> = self
+            .db
+            .get_connection()
+            .query_row(
+                "SELECT MIN(wip_limit) FROM board_columns WHERE (?1 IS NULL OR board_id = ?1) AND status = ?2",
+                params![board_id, status],
+                |row| row.get(0),
+            )
+            .optional()?
+            .flatten();
+        match limit {
+            Some(limit) if self.count_in_status(status, task_id)? >= limit => Ok(Some(limit)),
+            _ => Ok(None),
+        }
+    }
+
+    // Ordering
+
+    /// Position for a task appended to the end of a column
+    pub fn next_position(&self, status: &str) -> Result<f64> {
+        self.db.get_connection().query_row(
+            "SELECT COALESCE(MAX(position), 0) + 1 FROM tasks WHERE status = ?1",
+            params![status],
+            |row| row.get(0),
+        )
+    }
+
+    fn get_position(&self, task_id: i64, status: &str) -> Result<f64> {
+        let (task_status, position): (String, f64) = self.db.get_connection().query_row(
+            "SELECT status, position FROM tasks WHERE id = ?1",
+            params![task_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        if task_status != status {
+            return Err(invalid(format!("Task {} is not in the '{}' column", task_id, status)));
+        }
+        Ok(position)
+    }
+
+    /// Renumber a column 1, 2, 3, ... keeping its current order
+    fn renumber_column(&self, status: &str) -> Result<()> {
+        let conn = self.db.get_connection();
+        let mut stmt = conn.prepare("SELECT id FROM tasks WHERE status = ?1 ORDER BY position, id")?;
+        let ids: Vec<i64> = stmt.query_map(params![status], |row| row.get(0))?.collect::<Result<_>>()?;
+        let tx = conn.unchecked_transaction()?;
+        for (index, id) in ids.iter().enumerate() {
+            conn.execute("UPDATE tasks SET position = ?1 WHERE id = ?2", params![(index + 1) as f64, id])?;
+        }
+        tx.commit()
+    }
+
+    fn drop_position(&self, task_id: i64, target: &MoveTask) -> Result<f64> {
+        if target.after_id == Some(task_id) || target.before_id == Some(task_id) {
+            return Err(invalid("A task cannot be placed next to itself".to_string()));
+        }
+
+        let neighbours = |this: &Self| -> Result<(Option<f64>, Option<f64>)> {
+            Ok((
+                target.after_id.map(|id| this.get_position(id, &target.status)).transpose()?,
+                target.before_id.map(|id| this.get_position(id, &target.status)).transpose()?,
+            ))
+        };
+
+        let (mut after, mut before) = neighbours(self)?;
+        if let (Some(a), Some(b)) = (after, before) {
+            if b <= a {
+                return Err(invalid("Neighbouring tasks are not in order".to_string()));
+            }
+            if b - a < MIN_GAP {
+                self.renumber_column(&target.status)?;
+                (after, before) = neighbours(self)?;
+            }
+        }
+
+        Ok(match (after, before) {
+            (Some(a), Some(b)) => (a + b) / 2.0,
+            (Some(a), None) => a + 1.0,
+            (None, Some(b)) => b - 1.0,
+            (None, None) => self.next_position(&target.status)?,
+        })
+    }
+
+    /// Move a task to a column and slot it between two neighbours. Goes
+    /// through `update_task`, so conflicts, blockers and the board's WIP
+    /// limits apply.
+    pub fn move_task(&self, task_id: i64, target: MoveTask) -> std::result::Result<Task, UpdateError<Task>> {
+        let status = target.status.trim().to_string();
+        if status.is_empty() {
+            return Err(invalid("A task needs a status".to_string()).into());
+        }
+        if !self.get_columns(target.board_id)?.iter().any(|c| c.status == status) {
+            return Err(invalid(format!("Board {} has no column for status '{}'", target.board_id, status)).into());
+        }
+        let target = MoveTask { status: status.clone(), ..target };
+        let position = self.drop_position(task_id, &target)?;
+
+        self.db.update_task(
+            task_id,
+            UpdateTask {
+                title: None,
+                description: None,
+                status: Some(status),
+                priority: None,
+                due_date: None,
+                reminder_time: None,
+                recurrence: None,
+                estimate: None,
+                position: Some(position),
+                board_id: Some(target.board_id),
+                expected_version: target.expected_version,
+                expected_updated_at: None,
+                time_zone: None,
+            },
+        )
+    }
+
+    // Board view
+
+    pub fn get_board_view(&self, board_id: i64) -> Result<BoardView> {
+        let board = self.get_board(board_id)?;
+        let conn = self.db.get_connection();
+
+        let mut columns: Vec<Vec<Task>> = Vec::new();
+        for column in &board.columns {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM tasks WHERE status = ?1 ORDER BY position, id",
+                Database::TASK_COLUMNS
+            ))?;
+            let tasks = stmt.query_map(params![column.status], Database::task_from_row)?;
+            columns.push(tasks.collect::<Result<_>>()?);
+        }
+        let column_counts = columns.iter().map(|tasks| tasks.len() as i64).collect();
+
+        let lanes = match board.swimlane_by.as_deref() {
+            Some("priority") => self.priority_lanes(columns),
+            Some("tag") => self.tag_lanes(columns)?,
+            _ => vec![BoardLane {
+                key: String::new(),
+                label: board.name.clone(),
+                cells: columns,
+            }],
+        };
+
+        Ok(BoardView { board, lanes, column_counts })
+    }
+
+    /// Split column contents into lanes by a key, keeping task order
+    fn split_lanes(columns: &[Vec<Task>], lanes: Vec<(String, String)>, key_of: impl Fn(&Task) -> String) -> Vec<BoardLane> {
+        lanes
+            .into_iter()
+            .map(|(key, label)| BoardLane {
+                cells: columns
+                    .iter()
+                    .map(|tasks| tasks.iter().filter(|t| key_of(t) == key).cloned().collect())
+                    .collect(),
+                key,
+                label,
+            })
+            .collect()
+    }
+
+    fn priority_lanes(&self, columns: Vec<Vec<Task>>) -> Vec<BoardLane> {
+        let mut lanes: Vec<(String, String)> =
+            PRIORITY_LANES.iter().map(|(key, label)| (key.to_string(), label.to_string())).collect();
+        for task in columns.iter().flatten() {
+            if !lanes.iter().any(|(key, _)| *key == task.priority) {
+                lanes.push((task.priority.clone(), task.priority.clone()));
+            }
+        }
+        Self::split_lanes(&columns, lanes, |task| task.priority.clone())
+    }
+
+    /// One lane per tag; a task with several tags sits in the lane of its
+    /// alphabetically first tag so it is only shown once.
+    fn tag_lanes(&self, columns: Vec<Vec<Task>>) -> Result<Vec<BoardLane>> {
+        let tags = TagService::new(self.db.get_connection());
+        let mut lane_of = std::collections::HashMap::new();
+        for task in columns.iter().flatten() {
+            let mut names: Vec<String> = tags.get_tags_for_item("task", task.id)?.into_iter().map(|t| t.name).collect();
+            names.sort_by_key(|name| name.to_lowercase());
+            lane_of.insert(task.id, names.into_iter().next().unwrap_or_default());
+        }
+
+        let mut keys: Vec<String> = lane_of.values().filter(|key| !key.is_empty()).cloned().collect();
+        keys.sort_by_key(|key| key.to_lowercase());
+        keys.dedup();
+        let mut lanes: Vec<(String, String)> = keys.into_iter().map(|key| (key.clone(), key)).collect();
+        if lane_of.values().any(|key| key.is_empty()) || lanes.is_empty() {
+            lanes.push((String::new(), "Untagged".to_string()));
+        }
+
+        Ok(Self::split_lanes(&columns, lanes, |task| lane_of.get(&task.id).cloned().unwrap_or_default()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::task::CreateTask;
+
+    fn column(name: &str, status: &str, wip_limit: Option<i64>) -> CreateBoardColumn {
+        CreateBoardColumn { name: name.into(), status: status.into(), wip_limit }
+    }
+
+    fn board(boards: &BoardService, name: &str, columns: Vec<CreateBoardColumn>) -> Board {
+        boards.create_board(CreateBoard { name: name.into(), swimlane_by: None, columns }).unwrap()
+    }
+
+    fn task(db: &Database, title: &str, status: &str) -> i64 {
+        db.create_task(CreateTask {
+            title: title.into(),
+            description: None,
+            status: Some(status.into()),
+            priority: None,
+            due_date: None,
+            reminder_time: None,
+            parent_id: None,
+            recurrence: None,
+            estimate: None,
+            time_zone: None,
+        })
+        .unwrap()
+        .id
+    }
+
+    fn target(board_id: i64, status: &str, after_id: Option<i64>, before_id: Option<i64>) -> MoveTask {
+        MoveTask { board_id, status: status.into(), after_id, before_id, expected_version: None }
+    }
+
+    fn column_titles(boards: &BoardService, board_id: i64, index: usize) -> Vec<String> {
+        let view = boards.get_board_view(board_id).unwrap();
+        view.lanes[0].cells[index].iter().map(|t| t.title.clone()).collect()
+    }
+
+    #[test]
+    fn wip_limits_apply_only_on_the_board_being_used() {
+        let db = Database::in_memory().unwrap();
+        let boards = BoardService::new(&db);
+        let strict = board(&boards, "Strict", vec![column("Backlog", "backlog", None), column("Review", "review", Some(1))]);
+        let relaxed = board(&boards, "Relaxed", vec![column("Backlog", "backlog", None), column("Review", "review", None)]);
+        task(&db, "Already in review", "review");
+        let second = task(&db, "Second", "backlog");
+        let third = task(&db, "Third", "backlog");
+
+        match boards.move_task(second, target(strict.id, "review", None, None)) {
+            Err(UpdateError::WipLimit { limit, status, .. }) => assert_eq!((limit, status.as_str()), (1, "review")),
+            other => panic!("expected a WIP limit error, got {:?}", other.map(|t| t.status)),
+        }
+        assert_eq!(boards.move_task(second, target(relaxed.id, "review", None, None)).unwrap().status, "review");
+
+        assert_eq!(column_titles(&boards, strict.id, 1).len(), 2);
+        assert!(boards.wip_limit_exceeded(Some(strict.id), "review", Some(third)).unwrap().is_some());
+        assert!(boards.wip_limit_exceeded(Some(relaxed.id), "review", Some(third)).unwrap().is_none());
+        // Neither board has a done column
+        assert!(boards.move_task(third, target(strict.id, "done", None, None)).is_err());
+    }
+
+    #[test]
+    fn status_updates_without_a_board_respect_every_board_showing_the_status() {
+        let db = Database::in_memory().unwrap();
+        let boards = BoardService::new(&db);
+        board(&boards, "Strict", vec![column("Review", "review", Some(1))]);
+        board(&boards, "Relaxed", vec![column("Review", "review", None)]);
+        task(&db, "Already in review", "review");
+        let id = task(&db, "Next", "todo");
+
+        let update = UpdateTask {
+            title: None,
+            description: None,
+            status: Some("review".into()),
+            priority: None,
+            due_date: None,
+            reminder_time: None,
+            recurrence: None,
+            estimate: None,
+            time_zone: None,
+            position: None,
+            board_id: None,
+            expected_version: None,
+            expected_updated_at: None,
+        };
+        match db.update_task(id, update) {
+            Err(UpdateError::WipLimit { limit, .. }) => assert_eq!(limit, 1),
+            other => panic!("expected a WIP limit error, got {:?}", other.map(|t| t.status)),
+        }
+        assert_eq!(db.get_task(id).unwrap().status, "todo");
+    }
+
+    #[test]
+    fn dropped_tasks_take_the_midpoint_and_keep_their_order() {
+        let db = Database::in_memory().unwrap();
+        let boards = BoardService::new(&db);
+        let board_id = boards.get_all_boards().unwrap()[0].id;
+        let a = task(&db, "A", "todo");
+        let b = task(&db, "B", "todo");
+        let c = task(&db, "C", "in_progress");
+
+        let moved = boards.move_task(c, target(board_id, "todo", Some(a), Some(b))).unwrap();
+        assert_eq!(moved.position, 1.5);
+        assert_eq!(column_titles(&boards, board_id, 0), vec!["A", "C", "B"]);
+
+        // Repeated drops into the same gap stay ordered, renumbering when it runs out
+        let mut after = a;
+        for i in 0..60 {
+            let id = task(&db, &format!("N{}", i), "done");
+            boards.move_task(id, target(board_id, "todo", Some(after), Some(c))).unwrap();
+            after = id;
+        }
+        let titles = column_titles(&boards, board_id, 0);
+        assert_eq!(titles.len(), 63);
+        assert_eq!((titles[0].as_str(), titles[1].as_str(), titles[60].as_str()), ("A", "N0", "N59"));
+        assert_eq!(&titles[61..], ["C", "B"]);
+
+        assert!(boards.move_task(a, target(board_id, "todo", Some(b), Some(c))).is_err());
+        assert!(boards.move_task(a, target(board_id, "todo", Some(a), None)).is_err());
+    }
+
+    #[test]
+    fn renaming_a_shared_status_is_refused() {
+        let db = Database::in_memory().unwrap();
+        let boards = BoardService::new(&db);
+        let team = board(&boards, "Team", vec![column("Todo", "todo", None), column("QA", "qa", None)]);
+        let id = task(&db, "Check", "qa");
+        let rename = |status: &str| UpdateBoardColumn { name: None, status: Some(status.into()), wip_limit: None };
+
+        assert!(boards.update_column(team.columns[0].id, rename("backlog")).is_err());
+        assert_eq!(db.get_task(id).unwrap().status, "qa");
+
+        boards.update_column(team.columns[1].id, rename("testing")).unwrap();
+        assert_eq!(db.get_task(id).unwrap().status, "testing");
+    }
+}
@@ -0,0 +1,336 @@
+// Calendar Subscriptions - read-only layers fed from ICS files or local URLs
+//
+// A refresh fetches the feed without holding the database lock and then
+// imports it through `IcsImportService` under the source
+// "subscription:<id>", so unchanged events are left alone and events gone
+// from the feed are removed. Subscribed events carry `subscription_id` and
+// cannot be edited; deleting the subscription deletes them.
+use crate::models::calendar_subscription::{CalendarSubscription, CreateSubscription, UpdateSubscription};
+use crate::services::database::Database;
+use crate::services::ics_import::{IcsImportService, SUBSCRIPTION_SOURCE};
+use chrono::{Duration, NaiveDateTime, Utc};
+use rusqlite::{params, OptionalExtension, Result};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+pub const DEFAULT_REFRESH_MINUTES: i64 = 60;
+pub const MIN_REFRESH_MINUTES: i64 = 5;
+/// How often the background loop looks for subscriptions that are due
+const CHECK_INTERVAL_SECS: u64 = 60;
+const FETCH_TIMEOUT_SECS: u64 = 30;
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+fn invalid(message: String) -> rusqlite::Error {
+    rusqlite::Error::InvalidParameterName(message)
+}
+
+/// Where a feed is read from
+#[derive(Debug, Clone, PartialEq)]
+pub enum FeedLocation {
+    File(PathBuf),
+    Http(String),
+}
+
+/// Accept absolute file paths, file:// URLs and http(s)/webcal URLs on this machine
+pub fn parse_feed_location(url: &str) -> std::result::Result<FeedLocation, String> {
+    let url = url.trim();
+    if let Some(path) = url.strip_prefix("file://") {
+        return parse_feed_location(path);
+    }
+    let lower = url.to_lowercase();
+    if ["http://", "https://", "webcal://"].iter().any(|scheme| lower.starts_with(scheme)) {
+        let http = if lower.starts_with("webcal://") {
+            format!("http://{}", &url["webcal://".len()..])
+        } else {
+            url.to_string()
+        };
+        let parsed = reqwest::Url::parse(&http).map_err(|e| format!("Invalid URL '{}': {}", url, e))?;
+        let host = parsed.host_str().unwrap_or_default().trim_start_matches('[').trim_end_matches(']').to_lowercase();
+        let local = match host.parse::<std::net::IpAddr>() {
+            Ok(ip) => ip.is_loopback(),
+            Err(_) => host == "localhost" || host.ends_with(".localhost"),
+        };
+        if !local {
+            return Err(format!("Only feeds on this machine can be subscribed to, not '{}'", url));
+        }
+        return Ok(FeedLocation::Http(http));
+    }
+
+    let path = PathBuf::from(url);
+    if url.is_empty() || !path.is_absolute() {
+        return Err(format!("'{}' is not an absolute file path or a localhost URL", url));
+    }
+    Ok(FeedLocation::File(path))
+}
+
+/// Read the feed; runs without the database lock
+pub async fn fetch_feed(url: &str) -> std::result::Result<String, String> {
+    match parse_feed_location(url)? {
+        FeedLocation::File(path) => tokio::fs::read_to_string(&path)
+            .await
+            .map_err(|e| format!("Could not read {}: {}", path.display(), e)),
+        FeedLocation::Http(url) => {
+            let client = reqwest::Client::builder()
+                .timeout(std::time::Duration::from_secs(FETCH_TIMEOUT_SECS))
+                .build()
+                .map_err(|e| e.to_string())?;
+            let response = client.get(&url).send().await.map_err(|e| format!("Could not fetch {}: {}", url, e))?;
+            if !response.status().is_success() {
+                return Err(format!("{} answered {}", url, response.status()));
+            }
+            response.text().await.map_err(|e| format!("Could not read {}: {}", url, e))
+        }
+    }
+}
+
+/// Whether a subscription should be refreshed at `now` (UTC)
+pub fn is_due(subscription: &CalendarSubscription, now: NaiveDateTime) -> bool {
+    if !subscription.enabled {
+        return false;
+    }
+    match subscription
+        .last_refreshed_at
+        .as_deref()
+        .and_then(|at| NaiveDateTime::parse_from_str(at, TIMESTAMP_FORMAT).ok())
+    {
+        Some(last) => last + Duration::minutes(subscription.refresh_minutes) <= now,
+        None => true,
+    }
+}
+
+fn subscription_source(id: i64) -> String {
+    format!("{}{}", SUBSCRIPTION_SOURCE, id)
+}
+
+pub struct SubscriptionService<'a> {
+    db: &'a Database,
+}
+
+impl<'a> SubscriptionService<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    fn subscription_from_row(row: &rusqlite::Row) -> Result<CalendarSubscription> {
+        Ok(CalendarSubscription {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            url: row.get(2)?,
+            refresh_minutes: row.get(3)?,
+            enabled: row.get(4)?,
+            last_refreshed_at: row.get(5)?,
+            last_error: row.get(6)?,
+            event_count: row.get(7)?,
+            created_at: row.get(8)?,
+        })
+    }
+
+    const COLUMNS: &'static str = "s.id, s.name, s.url, s.refresh_minutes, s.enabled, s.last_refreshed_at, s.last_error,
+         (SELECT COUNT(*) FROM events WHERE subscription_id = s.id), s.created_at";
+
+    fn validate(name: &str, url: &str, refresh_minutes: i64) -> Result<()> {
+        if name.trim().is_empty() {
+            return Err(invalid("A subscription needs a name".to_string()));
+        }
+        parse_feed_location(url).map_err(invalid)?;
+        if refresh_minutes < MIN_REFRESH_MINUTES {
+            return Err(invalid(format!("Subscriptions refresh at most every {} minutes", MIN_REFRESH_MINUTES)));
+        }
+        Ok(())
+    }
+
+    pub fn create_subscription(&self, subscription: CreateSubscription) -> Result<CalendarSubscription> {
+        let refresh_minutes = subscription.refresh_minutes.unwrap_or(DEFAULT_REFRESH_MINUTES);
+        Self::validate(&subscription.name, &subscription.url, refresh_minutes)?;
+        self.db.get_connection().execute(
+            "INSERT INTO calendar_subscriptions (name, url, refresh_minutes) VALUES (?1, ?2, ?3)",
+            params![subscription.name.trim(), subscription.url.trim(), refresh_minutes],
+        )?;
+        self.get_subscription(self.db.get_connection().last_insert_rowid())
+    }
+
+    pub fn get_subscription(&self, id: i64) -> Result<CalendarSubscription> {
+        self.db.get_connection().query_row(
+            &format!("SELECT {} FROM calendar_subscriptions s WHERE s.id = ?1", Self::COLUMNS),
+            params![id],
+            Self::subscription_from_row,
+        )
+    }
+
+    pub fn get_all_subscriptions(&self) -> Result<Vec<CalendarSubscription>> {
+        let conn = self.db.get_connection();
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM calendar_subscriptions s ORDER BY s.name", Self::COLUMNS))?;
+        let subscriptions = stmt.query_map([], Self::subscription_from_row)?;
+        subscriptions.collect()
+    }
+
+    pub fn update_subscription(&self, id: i64, update: UpdateSubscription) -> Result<CalendarSubscription> {
+        let current = self.get_subscription(id)?;
+        let name = update.name.unwrap_or(current.name);
+        let url = update.url.unwrap_or_else(|| current.url.clone());
+        let refresh_minutes = update.refresh_minutes.unwrap_or(current.refresh_minutes);
+        Self::validate(&name, &url, refresh_minutes)?;
+
+        // A new feed is fetched on the next check instead of waiting out the interval
+        let last_refreshed_at = if url.trim() == current.url { current.last_refreshed_at } else { None };
+        self.db.get_connection().execute(
+            "UPDATE calendar_subscriptions SET name = ?1, url = ?2, refresh_minutes = ?3, enabled = ?4, last_refreshed_at = ?5
+             WHERE id = ?6",
+            params![
+                name.trim(),
+                url.trim(),
+                refresh_minutes,
+                update.enabled.unwrap_or(current.enabled),
+                last_refreshed_at,
+                id
+            ],
+        )?;
+        self.get_subscription(id)
+    }
+
+    /// Delete the subscription together with its events
+    pub fn delete_subscription(&self, id: i64) -> Result<()> {
+        let conn = self.db.get_connection();
+        let tx = conn.unchecked_transaction()?;
+        let mut stmt = conn.prepare("SELECT id FROM events WHERE subscription_id = ?1")?;
+        let events: Vec<i64> = stmt.query_map(params![id], |row| row.get(0))?.collect::<Result<_>>()?;
+        for event_id in events {
+            self.db.delete_event(event_id)?;
+        }
+        conn.execute("DELETE FROM event_sources WHERE source = ?1", params![subscription_source(id)])?;
+        conn.execute("DELETE FROM calendar_subscriptions WHERE id = ?1", params![id])?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn get_due_subscriptions(&self) -> Result<Vec<CalendarSubscription>> {
+        let now = Utc::now().naive_utc();
+        Ok(self.get_all_subscriptions()?.into_iter().filter(|s| is_due(s, now)).collect())
+    }
+
+    /// Store the result of fetching the feed: import it, or record why it failed
+    pub fn apply_refresh(&self, id: i64, content: std::result::Result<String, String>) -> Result<CalendarSubscription> {
+        self.get_subscription(id)?;
+        let error = match content {
+            Ok(content) => IcsImportService::new(self.db)
+                .import_source(&content, &subscription_source(id), true, Some(id))
+                .err()
+                .map(|e| e.to_string()),
+            Err(error) => Some(error),
+        };
+        self.db.get_connection().execute(
+            "UPDATE calendar_subscriptions SET last_refreshed_at = ?1, last_error = ?2 WHERE id = ?3",
+            params![Utc::now().naive_utc().format(TIMESTAMP_FORMAT).to_string(), error, id],
+        )?;
+        self.get_subscription(id)
+    }
+
+    /// Subscribed events mirror their feed and cannot be changed here
+    pub fn check_editable(&self, event_id: i64) -> Result<()> {
+        let subscription: Option<String> = self
+            .db
+            .get_connection()
+            .query_row(
+                "SELECT s.name FROM events e JOIN calendar_subscriptions s ON s.id = e.subscription_id WHERE e.id = ?1",
+                params![event_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        match subscription {
+            Some(name) => Err(invalid(format!(
+                "Event {} comes from the calendar subscription '{}' and is read-only",
+                event_id, name
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Fetch one subscription's feed and import it
+pub async fn refresh_subscription(db: &Arc<Mutex<Database>>, id: i64) -> std::result::Result<CalendarSubscription, String> {
+    let url = {
+        let db = db.lock().await;
+        SubscriptionService::new(&db).get_subscription(id).map_err(|e| e.to_string())?.url
+    };
+    let content = fetch_feed(&url).await;
+    let db = db.lock().await;
+    SubscriptionService::new(&db).apply_refresh(id, content).map_err(|e| e.to_string())
+}
+
+/// Refresh every subscription whose interval has passed
+pub async fn refresh_due_subscriptions(db: &Arc<Mutex<Database>>) -> usize {
+    let due = {
+        let db = db.lock().await;
+        SubscriptionService::new(&db).get_due_subscriptions().unwrap_or_default()
+    };
+    let mut refreshed = 0;
+    for subscription in due {
+        match refresh_subscription(db, subscription.id).await {
+            Ok(updated) => {
+                if let Some(error) = updated.last_error {
+                    eprintln!("Calendar subscription '{}' failed to refresh: {}", updated.name, error);
+                }
+                refreshed += 1;
+            }
+            Err(e) => eprintln!("Calendar subscription {} failed to refresh: {}", subscription.id, e),
+        }
+    }
+    refreshed
+}
+
+/// Keep subscriptions up to date in the background for the life of the app
+pub fn spawn_refresh_loop(db: Arc<Mutex<Database>>) {
+    tokio::spawn(async move {
+        loop {
+            refresh_due_subscriptions(&db).await;
+            tokio::time::sleep(std::time::Duration::from_secs(CHECK_INTERVAL_SECS)).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_only_local_feeds() {
+        assert_eq!(
+            parse_feed_location("webcal://localhost:8080/work.ics"),
+            Ok(FeedLocation::Http("http://localhost:8080/work.ics".to_string()))
+        );
+        assert!(parse_feed_location("http://127.0.0.1/cal.ics").is_ok());
+        assert!(parse_feed_location("http://[::1]:3000/cal.ics").is_ok());
+        assert!(parse_feed_location("https://calendar.example.com/cal.ics").is_err());
+        assert!(parse_feed_location("http://localhost.example.com/cal.ics").is_err());
+        assert!(parse_feed_location("calendars/work.ics").is_err());
+        let absolute = std::env::temp_dir().join("work.ics");
+        assert_eq!(
+            parse_feed_location(&format!("file://{}", absolute.display())),
+            Ok(FeedLocation::File(absolute))
+        );
+    }
+
+    #[test]
+    fn refreshes_when_the_interval_has_passed() {
+        let mut subscription = CalendarSubscription {
+            id: 1,
+            name: "Work".into(),
+            url: "/tmp/work.ics".into(),
+            refresh_minutes: 30,
+            enabled: true,
+            last_refreshed_at: None,
+            last_error: None,
+            event_count: 0,
+            created_at: String::new(),
+        };
+        let now = NaiveDateTime::parse_from_str("2024-05-06 10:00:00", TIMESTAMP_FORMAT).unwrap();
+        assert!(is_due(&subscription, now));
+        subscription.last_refreshed_at = Some("2024-05-06 09:45:00".into());
+        assert!(!is_due(&subscription, now));
+        subscription.last_refreshed_at = Some("2024-05-06 09:30:00".into());
+        assert!(is_due(&subscription, now));
+        subscription.enabled = false;
+        assert!(!is_due(&subscription, now));
+    }
+}
@@ -1,6 +1,6 @@
 use crate::services::database::Database;
 use crate::services::ollama::OllamaService;
-use rusqlite::{params, Result};
+use rusqlite::{params, OptionalExtension, Result};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use chrono::Utc;
@@ -471,17 +471,24 @@ impl DataOperationsService {
         filepath: &str,
         file_size: u64,
         mime_type: &str,
-        _file_hash: &str,
+        file_hash: &str,
         chunks_with_embeddings: &[(usize, String, Option<Vec<f32>>)],
     ) -> Result<i64> {
         let db = self.db.lock().await;
         let conn = db.get_connection();
         
+        // The same content processed before, by any job
+        let duplicate_of: Option<i64> = conn.query_row(
+            "SELECT id FROM processed_files WHERE file_hash = ?1 ORDER BY id LIMIT 1",
+            params![file_hash],
+            |row| row.get(0),
+        ).optional()?;
+        
         // Insert processed file
         let now = Utc::now().to_rfc3339();
         conn.execute(
-            "INSERT INTO processed_files (job_id, filename, original_path, file_size, mime_type, status, chunks_count, has_embeddings, is_duplicate, metadata, created_at) 
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            "INSERT INTO processed_files (job_id, filename, original_path, file_size, mime_type, status, chunks_count, has_embeddings, is_duplicate, duplicate_of, file_hash, metadata, created_at) 
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
             params![
                 job_id,
                 filename,
@@ -491,7 +498,9 @@ impl DataOperationsService {
                 "completed",
                 chunks_with_embeddings.len() as i64,
                 true, // has_embeddings
-                false, // is_duplicate
+                duplicate_of.is_some(),
+                duplicate_of,
+                file_hash,
                 "{}", // metadata as JSON string
                 now
             ],
@@ -2,7 +2,16 @@ use crate::models::message::{CreateMessage, Message};
 use crate::models::document::{CreateDocument, Document, UpdateDocument};
 use crate::models::task::{CreateTask, Task, UpdateTask};
 use crate::models::event::{CalendarEvent, CreateEvent, UpdateEvent};
+use crate::models::conflict::UpdateError;
 use crate::services::settings::SettingsService;
+use crate::services::tags::{parse_tag_list, TagService};
+use crate::services::boards::BoardService;
+use crate::services::recurring_tasks::{normalize_recurrence, RecurringTaskService};
+use crate::services::recurring_events::RecurringEventService;
+use crate::services::task_structure::TaskStructureService;
+use crate::services::templates::TemplateService;
+use crate::services::time_zones::{normalize_event, normalize_optional, normalize_time, user_zone, zone_or};
+use crate::services::rrule::{parse_stored_datetime, DateStyle};
 use rusqlite::{params, Connection, Result};
 use std::path::PathBuf;
 
@@ -41,6 +50,14 @@ impl Database {
         Ok(db)
     }
 
+    /// A fresh database in memory, for tests
+    #[cfg(test)]
+    pub fn in_memory() -> Result<Self> {
+        let db = Database { conn: Connection::open_in_memory()? };
+        db.init_schema()?;
+        Ok(db)
+    }
+
     fn get_db_path() -> PathBuf {
         let mut path = tauri::api::path::app_data_dir(&tauri::Config::default())
             .unwrap_or_else(|| PathBuf::from("."));
@@ -406,6 +423,309 @@ impl Database {
             [],
         )?;
 
+        // Tags table - hierarchical tag paths such as 'project/alpha'
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS tags (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+                parent_id INTEGER,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                FOREIGN KEY(parent_id) REFERENCES tags(id) ON DELETE CASCADE
+            )",
+            [],
+        )?;
+
+        // Item Tags table - many-to-many join between tags and documents, tasks, events, files and messages
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS item_tags (
+                tag_id INTEGER NOT NULL,
+                item_type TEXT NOT NULL,
+                item_id INTEGER NOT NULL,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                PRIMARY KEY(tag_id, item_type, item_id),
+                FOREIGN KEY(tag_id) REFERENCES tags(id) ON DELETE CASCADE
+            )",
+            [],
+        )?;
+
+        // Tag Suggestions table - AI-suggested tags awaiting user acceptance
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS tag_suggestions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                item_type TEXT NOT NULL,
+                item_id INTEGER NOT NULL,
+                name TEXT NOT NULL COLLATE NOCASE,
+                source TEXT NOT NULL DEFAULT 'ai',
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                UNIQUE(item_type, item_id, name)
+            )",
+            [],
+        )?;
+
+        // Folders table - nested folders for organizing documents
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS folders (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                parent_id INTEGER,
+                position INTEGER NOT NULL DEFAULT 0,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+            )",
+            [],
+        )?;
+
+        // Collections table - smart collections backed by a saved JSON query
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS collections (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                query TEXT NOT NULL,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+            )",
+            [],
+        )?;
+
+        // Document templates table - reusable skeletons with {{placeholders}}
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS document_templates (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL UNIQUE,
+                description TEXT,
+                title_pattern TEXT,
+                content TEXT NOT NULL,
+                tags TEXT,
+                builtin INTEGER NOT NULL DEFAULT 0,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+            )",
+            [],
+        )?;
+
+        // Add folder placement columns to documents if they don't exist (migration)
+        self.conn.execute("ALTER TABLE documents ADD COLUMN folder_id INTEGER", []).ok();
+        self.conn.execute("ALTER TABLE documents ADD COLUMN position INTEGER NOT NULL DEFAULT 0", []).ok();
+
+        // Add version column to tasks for optimistic concurrency (migration)
+        self.conn.execute("ALTER TABLE tasks ADD COLUMN version INTEGER NOT NULL DEFAULT 1", []).ok();
+
+        // Add parent column to tasks for subtasks (migration)
+        self.conn.execute("ALTER TABLE tasks ADD COLUMN parent_id INTEGER", []).ok();
+
+        // Add recurrence columns to tasks (migration)
+        self.conn.execute("ALTER TABLE tasks ADD COLUMN recurrence TEXT", []).ok();
+        self.conn.execute("ALTER TABLE tasks ADD COLUMN series_id INTEGER", []).ok();
+        self.conn.execute("ALTER TABLE tasks ADD COLUMN occurrence INTEGER NOT NULL DEFAULT 1", []).ok();
+
+        // SHA-256 of file content, for deduplication (migration)
+        self.conn.execute("ALTER TABLE files ADD COLUMN hash TEXT", []).ok();
+
+        // Add effort estimate (minutes) to tasks (migration)
+        self.conn.execute("ALTER TABLE tasks ADD COLUMN estimate INTEGER", []).ok();
+
+        // Add iCalendar fields to events (migration)
+        self.conn.execute("ALTER TABLE events ADD COLUMN location TEXT", []).ok();
+        self.conn.execute("ALTER TABLE events ADD COLUMN all_day INTEGER NOT NULL DEFAULT 0", []).ok();
+        self.conn.execute("ALTER TABLE events ADD COLUMN exdates TEXT", []).ok();
+
+        // Edited occurrences of recurring events point at their series (migration)
+        self.conn.execute("ALTER TABLE events ADD COLUMN series_id INTEGER", []).ok();
+        self.conn.execute("ALTER TABLE events ADD COLUMN recurrence_id TEXT", []).ok();
+
+        // Events copied from a calendar subscription are read-only (migration)
+        self.conn.execute("ALTER TABLE events ADD COLUMN subscription_id INTEGER", []).ok();
+
+        // Times are stored in UTC next to their IANA zone; earlier versions kept
+        // local wall time, which is converted in the user's zone (migration)
+        if self.conn.execute("ALTER TABLE events ADD COLUMN time_zone TEXT", []).is_ok() {
+            self.migrate_event_times()?;
+        }
+        if self.conn.execute("ALTER TABLE tasks ADD COLUMN time_zone TEXT", []).is_ok() {
+            self.migrate_task_times()?;
+        }
+
+        // Add board position to tasks (migration); existing tasks keep their creation order
+        if self.conn.execute("ALTER TABLE tasks ADD COLUMN position REAL NOT NULL DEFAULT 0", []).is_ok() {
+            self.conn.execute("UPDATE tasks SET position = id", [])?;
+        }
+
+        // Record when a task was completed (migration); the last update is the best guess for older ones
+        if self.conn.execute("ALTER TABLE tasks ADD COLUMN completed_at DATETIME", []).is_ok() {
+            self.conn.execute("UPDATE tasks SET completed_at = updated_at WHERE status = 'done'", [])?;
+        }
+
+        // Task checklist items - inline to-dos inside a single task
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS task_checklist_items (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                task_id INTEGER NOT NULL,
+                text TEXT NOT NULL,
+                done INTEGER NOT NULL DEFAULT 0,
+                position INTEGER NOT NULL DEFAULT 0,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                FOREIGN KEY (task_id) REFERENCES tasks(id)
+            )",
+            [],
+        )?;
+
+        // Task dependencies - task_id cannot be done before blocked_by_id
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS task_dependencies (
+                task_id INTEGER NOT NULL,
+                blocked_by_id INTEGER NOT NULL,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                PRIMARY KEY (task_id, blocked_by_id)
+            )",
+            [],
+        )?;
+
+        // Time entries - tracked time per task; ended_at is NULL while the timer runs
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS time_entries (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                task_id INTEGER NOT NULL,
+                started_at DATETIME NOT NULL,
+                ended_at DATETIME,
+                note TEXT,
+                event_id INTEGER,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                FOREIGN KEY (task_id) REFERENCES tasks(id),
+                FOREIGN KEY (event_id) REFERENCES events(id) ON DELETE SET NULL
+            )",
+            [],
+        )?;
+
+        // Kanban boards - each column shows the tasks with one status
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS boards (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                swimlane_by TEXT,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+            )",
+            [],
+        )?;
+
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS board_columns (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                board_id INTEGER NOT NULL,
+                name TEXT NOT NULL,
+                status TEXT NOT NULL,
+                position INTEGER NOT NULL DEFAULT 0,
+                wip_limit INTEGER,
+                FOREIGN KEY (board_id) REFERENCES boards(id),
+                UNIQUE (board_id, status)
+            )",
+            [],
+        )?;
+
+        // ICS feeds shown as read-only calendar layers
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS calendar_subscriptions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                url TEXT NOT NULL,
+                refresh_minutes INTEGER NOT NULL DEFAULT 60,
+                enabled INTEGER NOT NULL DEFAULT 1,
+                last_refreshed_at TEXT,
+                last_error TEXT,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+            )",
+            [],
+        )?;
+
+        // Calendar identity of imported events, so importing the same calendar
+        // again updates them; recurrence_id is '' for everything but edited occurrences
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS event_sources (
+                event_id INTEGER PRIMARY KEY,
+                source TEXT NOT NULL,
+                uid TEXT NOT NULL,
+                recurrence_id TEXT NOT NULL DEFAULT '',
+                sequence INTEGER NOT NULL DEFAULT 0,
+                last_modified TEXT,
+                FOREIGN KEY (event_id) REFERENCES events(id),
+                UNIQUE (source, uid, recurrence_id)
+            )",
+            [],
+        )?;
+
+        // Events the task planner booked; the task→event relation itself is in
+        // `links`. A block edited by hand is pinned, as is one whose times no
+        // longer match the planned ones.
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS planned_blocks (
+                event_id INTEGER PRIMARY KEY,
+                task_id INTEGER NOT NULL,
+                planned_start TEXT NOT NULL,
+                planned_end TEXT NOT NULL,
+                reason TEXT NOT NULL,
+                pinned INTEGER NOT NULL DEFAULT 0,
+                FOREIGN KEY (event_id) REFERENCES events(id),
+                FOREIGN KEY (task_id) REFERENCES tasks(id)
+            )",
+            [],
+        )?;
+
+        // Reminder queue, rebuilt from tasks and events by the reminder
+        // scheduler; kept across restarts so missed reminders can catch up
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS reminders (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                item_type TEXT NOT NULL,
+                item_id INTEGER,
+                occurrence TEXT NOT NULL DEFAULT '',
+                title TEXT NOT NULL,
+                body TEXT NOT NULL DEFAULT '',
+                fire_at TEXT NOT NULL,
+                snoozed_until TEXT,
+                status TEXT NOT NULL DEFAULT 'pending',
+                fired_at TEXT,
+                UNIQUE (item_type, item_id, occurrence)
+            )",
+            [],
+        )?;
+
+        // Content-addressed file storage; see BlobStore
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS blobs (
+                hash TEXT PRIMARY KEY,
+                size INTEGER NOT NULL,
+                path TEXT NOT NULL,
+                refcount INTEGER NOT NULL DEFAULT 1,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+            )",
+            [],
+        )?;
+
+        // Chunked uploads in progress; see UploadService
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS upload_sessions (
+                id TEXT PRIMARY KEY,
+                filename TEXT NOT NULL,
+                mimetype TEXT NOT NULL,
+                total_size INTEGER NOT NULL,
+                chunk_size INTEGER NOT NULL,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+            )",
+            [],
+        )?;
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS upload_chunks (
+                session_id TEXT NOT NULL,
+                chunk_index INTEGER NOT NULL,
+                size INTEGER NOT NULL,
+                sha256 TEXT NOT NULL,
+                PRIMARY KEY (session_id, chunk_index),
+                FOREIGN KEY (session_id) REFERENCES upload_sessions(id)
+            )",
+            [],
+        )?;
+
         // Create indexes
         self.create_indexes()?;
 
@@ -416,6 +736,12 @@ impl Database {
         let settings_service = SettingsService::new(&self.conn);
         settings_service.initialize_defaults()?;
 
+        // Seed built-in document templates
+        TemplateService::new(self).initialize_builtin_templates()?;
+
+        // Seed the default todo / in progress / done board
+        BoardService::new(self).initialize_default_board()?;
+
         Ok(())
     }
 
@@ -429,6 +755,8 @@ impl Database {
              CREATE INDEX IF NOT EXISTS idx_tasks_priority ON tasks(priority);
              CREATE INDEX IF NOT EXISTS idx_tasks_due_date ON tasks(due_date);
              CREATE INDEX IF NOT EXISTS idx_events_start ON events(start_time);
+             CREATE INDEX IF NOT EXISTS idx_events_series ON events(series_id);
+             CREATE INDEX IF NOT EXISTS idx_events_subscription ON events(subscription_id);
              CREATE INDEX IF NOT EXISTS idx_embeddings_content ON embeddings(content_type, content_id);
              CREATE INDEX IF NOT EXISTS idx_links_source ON links(source_type, source_id);
              CREATE INDEX IF NOT EXISTS idx_links_target ON links(target_type, target_id);
@@ -450,7 +778,24 @@ impl Database {
             CREATE INDEX IF NOT EXISTS idx_insights_priority ON insights(priority);
             CREATE INDEX IF NOT EXISTS idx_insights_read ON insights(is_read);
              CREATE INDEX IF NOT EXISTS idx_document_versions_doc_id ON document_versions(document_id);
-             CREATE INDEX IF NOT EXISTS idx_document_versions_version ON document_versions(document_id, version DESC);"
+             CREATE INDEX IF NOT EXISTS idx_document_versions_version ON document_versions(document_id, version DESC);
+             CREATE INDEX IF NOT EXISTS idx_tags_parent ON tags(parent_id);
+             CREATE INDEX IF NOT EXISTS idx_item_tags_item ON item_tags(item_type, item_id);
+             CREATE INDEX IF NOT EXISTS idx_tag_suggestions_item ON tag_suggestions(item_type, item_id);
+             CREATE INDEX IF NOT EXISTS idx_folders_parent ON folders(parent_id, position);
+             CREATE INDEX IF NOT EXISTS idx_documents_folder ON documents(folder_id, position);
+             CREATE INDEX IF NOT EXISTS idx_tasks_parent ON tasks(parent_id);
+             CREATE INDEX IF NOT EXISTS idx_tasks_series ON tasks(series_id, occurrence);
+             CREATE INDEX IF NOT EXISTS idx_checklist_task ON task_checklist_items(task_id, position);
+             CREATE INDEX IF NOT EXISTS idx_tasks_status_position ON tasks(status, position);
+             CREATE INDEX IF NOT EXISTS idx_board_columns_board ON board_columns(board_id, position);
+             CREATE INDEX IF NOT EXISTS idx_board_columns_status ON board_columns(status);
+             CREATE INDEX IF NOT EXISTS idx_time_entries_task ON time_entries(task_id);
+             CREATE INDEX IF NOT EXISTS idx_time_entries_started ON time_entries(started_at);
+             CREATE UNIQUE INDEX IF NOT EXISTS idx_time_entries_running ON time_entries((ended_at IS NULL)) WHERE ended_at IS NULL;
+             CREATE INDEX IF NOT EXISTS idx_task_dependencies_blocker ON task_dependencies(blocked_by_id);
+             CREATE INDEX IF NOT EXISTS idx_reminders_status ON reminders(status, fire_at);
+             CREATE INDEX IF NOT EXISTS idx_files_hash ON files(hash);"
         )?;
         Ok(())
     }
@@ -539,68 +884,99 @@ impl Database {
 
     pub fn delete_message(&self, id: i64) -> Result<()> {
         self.conn.execute("DELETE FROM messages WHERE id = ?1", params![id])?;
+        TagService::new(&self.conn).delete_item_tags("message", id)?;
         Ok(())
     }
 
     pub fn clear_messages(&self, channel_id: i64) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM item_tags WHERE item_type = 'message'
+             AND item_id IN (SELECT id FROM messages WHERE channel_id = ?1)",
+            params![channel_id],
+        )?;
         self.conn.execute("DELETE FROM messages WHERE channel_id = ?1", params![channel_id])?;
         Ok(())
     }
 
     // Document CRUD operations
     pub fn create_document(&self, doc: CreateDocument) -> Result<Document> {
+        // New documents go to the end of their folder
+        let position: i64 = self.conn.query_row(
+            "SELECT COALESCE(MAX(position), -1) + 1 FROM documents WHERE folder_id IS ?1",
+            params![doc.folder_id],
+            |row| row.get(0),
+        )?;
+
         self.conn.execute(
-            "INSERT INTO documents (title, content, tags) VALUES (?1, ?2, ?3)",
-            params![doc.title, doc.content, doc.tags],
+            "INSERT INTO documents (title, content, tags, folder_id, position) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![doc.title, doc.content, doc.tags, doc.folder_id, position],
         )?;
 
         let id = self.conn.last_insert_rowid();
+
+        // Mirror the free-form tags into the normalized tag tables
+        if let Some(tags) = &doc.tags {
+            TagService::new(&self.conn).set_item_tags("document", id, &parse_tag_list(tags))?;
+        }
+
         self.get_document(id)
     }
 
+    /// Column list matching `document_from_row`
+    pub const DOCUMENT_COLUMNS: &'static str =
+        "id, title, content, version, tags, folder_id, position, created_at, updated_at";
+
+    pub fn document_from_row(row: &rusqlite::Row) -> Result<Document> {
+        Ok(Document {
+            id: row.get(0)?,
+            title: row.get(1)?,
+            content: row.get(2)?,
+            version: row.get(3)?,
+            tags: row.get(4)?,
+            folder_id: row.get(5)?,
+            position: row.get(6)?,
+            created_at: row.get(7)?,
+            updated_at: row.get(8)?,
+        })
+    }
+
     pub fn get_document(&self, id: i64) -> Result<Document> {
         self.conn.query_row(
-            "SELECT id, title, content, version, tags, created_at, updated_at 
-             FROM documents WHERE id = ?1",
+            &format!("SELECT {} FROM documents WHERE id = ?1", Self::DOCUMENT_COLUMNS),
             params![id],
-            |row| {
-                Ok(Document {
-                    id: row.get(0)?,
-                    title: row.get(1)?,
-                    content: row.get(2)?,
-                    version: row.get(3)?,
-                    tags: row.get(4)?,
-                    created_at: row.get(5)?,
-                    updated_at: row.get(6)?,
-                })
-            },
+            Self::document_from_row,
         )
     }
 
     pub fn get_all_documents(&self, limit: i64) -> Result<Vec<Document>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, title, content, version, tags, created_at, updated_at 
-             FROM documents ORDER BY updated_at DESC LIMIT ?1",
-        )?;
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM documents ORDER BY updated_at DESC LIMIT ?1",
+            Self::DOCUMENT_COLUMNS
+        ))?;
 
-        let documents = stmt.query_map(params![limit], |row| {
-            Ok(Document {
-                id: row.get(0)?,
-                title: row.get(1)?,
-                content: row.get(2)?,
-                version: row.get(3)?,
-                tags: row.get(4)?,
-                created_at: row.get(5)?,
-                updated_at: row.get(6)?,
-            })
-        })?;
+        let documents = stmt.query_map(params![limit], Self::document_from_row)?;
 
         documents.collect()
     }
 
-    pub fn update_document(&self, id: i64, update: UpdateDocument) -> Result<Document> {
+    /// Update a document. When the update carries an expected version or
+    /// updated_at that no longer matches, nothing is written and a conflict
+    /// carrying the current document is returned instead.
+    pub fn update_document(&self, id: i64, update: UpdateDocument) -> std::result::Result<Document, UpdateError<Document>> {
         // Get current document to save version
         let current = self.get_document(id)?;
+
+        if let Some(reason) = Self::check_expected(
+            current.version,
+            &current.updated_at,
+            update.expected_version,
+            update.expected_updated_at.as_deref(),
+        ) {
+            return Err(UpdateError::conflict(
+                format!("Document {} was modified by someone else ({})", id, reason),
+                current,
+            ));
+        }
         
         // Save version history if content is being updated - per prd.md §3️⃣.C
         if update.content.is_some() {
@@ -618,15 +994,14 @@ impl Database {
         if let Some(content) = &update.content {
             updates.push("content = ?");
             params_vec.push(Box::new(content.clone()));
-            
-            // Increment version on content update
-            updates.push("version = version + 1");
         }
         if let Some(tags) = &update.tags {
             updates.push("tags = ?");
             params_vec.push(Box::new(tags.clone()));
         }
 
+        // Every edit bumps the version, so a stale writer always conflicts
+        updates.push("version = version + 1");
         updates.push("updated_at = CURRENT_TIMESTAMP");
         
         let query = format!("UPDATE documents SET {} WHERE id = ?", updates.join(", "));
@@ -635,8 +1010,33 @@ impl Database {
         let params_refs: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|p| p.as_ref()).collect();
         
         self.conn.execute(&query, params_refs.as_slice())?;
+
+        if let Some(tags) = &update.tags {
+            TagService::new(&self.conn).set_item_tags("document", id, &parse_tag_list(tags))?;
+        }
         
-        self.get_document(id)
+        Ok(self.get_document(id)?)
+    }
+
+    /// Compare stored concurrency markers against the ones a caller expects.
+    /// Returns a description of the mismatch, or None when the update may proceed.
+    fn check_expected(
+        version: i64,
+        updated_at: &str,
+        expected_version: Option<i64>,
+        expected_updated_at: Option<&str>,
+    ) -> Option<String> {
+        if let Some(expected) = expected_version {
+            if expected != version {
+                return Some(format!("expected version {}, found {}", expected, version));
+            }
+        }
+        if let Some(expected) = expected_updated_at {
+            if expected != updated_at {
+                return Some(format!("expected updated_at {}, found {}", expected, updated_at));
+            }
+        }
+        None
     }
 
     // Version history operations - per prd.md §3️⃣.C "Version history stored locally"
@@ -669,7 +1069,7 @@ impl Database {
         versions.collect()
     }
 
-    pub fn restore_document_version(&self, document_id: i64, version_id: i64) -> Result<Document> {
+    pub fn restore_document_version(&self, document_id: i64, version_id: i64) -> std::result::Result<Document, UpdateError<Document>> {
         // Get the version content
         let (_, _version_num, content, _): (i64, i64, String, String) = self.conn.query_row(
             "SELECT id, version, content, created_at FROM document_versions WHERE id = ?1",
@@ -682,11 +1082,84 @@ impl Database {
             content: Some(content),
             title: None,
             tags: None,
+            expected_version: None,
+            expected_updated_at: None,
         })
     }
 
     pub fn delete_document(&self, id: i64) -> Result<()> {
         self.conn.execute("DELETE FROM documents WHERE id = ?1", params![id])?;
+        TagService::new(&self.conn).delete_item_tags("document", id)?;
+        Ok(())
+    }
+
+    /// Wall times without an offset in the stored times of old databases
+    fn is_wall_time(value: &str) -> bool {
+        matches!(parse_stored_datetime(value), Some((_, DateStyle::Local { .. })))
+    }
+
+    /// Convert local wall times of existing events to UTC in the user's zone
+    fn migrate_event_times(&self) -> Result<()> {
+        let zone = user_zone(&self.conn);
+        let convert = |value: String| {
+            if Self::is_wall_time(&value) {
+                normalize_time(&value, zone).unwrap_or(value)
+            } else {
+                value
+            }
+        };
+        let mut stmt = self.conn.prepare(
+            "SELECT id, start_time, end_time, reminder_time, exdates, recurrence_id, all_day FROM events",
+        )?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, Option<String>>(3)?,
+                    row.get::<_, Option<String>>(4)?,
+                    row.get::<_, Option<String>>(5)?,
+                    row.get::<_, bool>(6)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>>>()?;
+        for (id, start, end, reminder, exdates, recurrence_id, all_day) in rows {
+            let exdates = exdates.map(|list| list.split(',').map(|d| convert(d.to_string())).collect::<Vec<_>>().join(","));
+            self.conn.execute(
+                "UPDATE events SET start_time = ?1, end_time = ?2, reminder_time = ?3, exdates = ?4, recurrence_id = ?5,
+                 time_zone = ?6 WHERE id = ?7",
+                params![
+                    convert(start),
+                    convert(end),
+                    reminder.map(convert),
+                    exdates,
+                    recurrence_id.map(convert),
+                    if all_day { None } else { Some(zone.name()) },
+                    id
+                ],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Convert local wall-time due dates and reminders of existing tasks to UTC
+    fn migrate_task_times(&self) -> Result<()> {
+        let zone = user_zone(&self.conn);
+        let mut stmt = self.conn.prepare("SELECT id, due_date, reminder_time FROM tasks")?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, Option<String>>(1)?, row.get::<_, Option<String>>(2)?)))?
+            .collect::<Result<Vec<_>>>()?;
+        for (id, due_date, reminder_time) in rows {
+            if !due_date.iter().chain(&reminder_time).any(|value| Self::is_wall_time(value)) {
+                continue;
+            }
+            let convert = |value: Option<String>| value.map(|v| normalize_time(&v, zone).unwrap_or(v));
+            self.conn.execute(
+                "UPDATE tasks SET due_date = ?1, reminder_time = ?2, time_zone = ?3 WHERE id = ?4",
+                params![convert(due_date), convert(reminder_time), zone.name(), id],
+            )?;
+        }
         Ok(())
     }
 
@@ -695,61 +1168,141 @@ impl Database {
         let status = task.status.unwrap_or_else(|| "todo".to_string());
         let priority = task.priority.unwrap_or_else(|| "medium".to_string());
         
+        if let Some(parent_id) = task.parent_id {
+            self.get_task(parent_id)?;
+        }
+        let recurrence = normalize_recurrence(task.recurrence.as_deref())?;
+        let zone = zone_or(task.time_zone.as_deref(), user_zone(&self.conn)).map_err(rusqlite::Error::InvalidParameterName)?;
+        let due_date = normalize_optional(task.due_date.as_deref(), zone).map_err(rusqlite::Error::InvalidParameterName)?;
+        let reminder_time = normalize_optional(task.reminder_time.as_deref(), zone).map_err(rusqlite::Error::InvalidParameterName)?;
+        let time_zone = (due_date.is_some() || reminder_time.is_some()).then(|| zone.name());
+        let position = BoardService::new(self).next_position(&status)?;
+        
         self.conn.execute(
-            "INSERT INTO tasks (title, description, status, priority, due_date, reminder_time) 
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-            params![task.title, task.description, status, priority, task.due_date, task.reminder_time],
+            "INSERT INTO tasks (title, description, status, priority, due_date, reminder_time, parent_id, recurrence, estimate, position, time_zone, completed_at) 
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, CASE WHEN ?3 = 'done' THEN CURRENT_TIMESTAMP END)",
+            params![
+                task.title,
+                task.description,
+                status,
+                priority,
+                due_date,
+                reminder_time,
+                task.parent_id,
+                recurrence,
+                task.estimate.filter(|minutes| *minutes > 0),
+                position,
+                time_zone
+            ],
         )?;
 
         let id = self.conn.last_insert_rowid();
+        if recurrence.is_some() {
+            // A recurring task starts its own series
+            self.conn.execute("UPDATE tasks SET series_id = id WHERE id = ?1", params![id])?;
+        }
         self.get_task(id)
     }
 
+    pub const TASK_COLUMNS: &'static str =
+        "id, title, description, status, priority, due_date, reminder_time, parent_id, recurrence, series_id, occurrence, estimate, position, version, time_zone, created_at, updated_at";
+
+    pub fn task_from_row(row: &rusqlite::Row) -> Result<Task> {
+        Ok(Task {
+            id: row.get(0)?,
+            title: row.get(1)?,
+            description: row.get(2)?,
+            status: row.get(3)?,
+            priority: row.get(4)?,
+            due_date: row.get(5)?,
+            reminder_time: row.get(6)?,
+            parent_id: row.get(7)?,
+            recurrence: row.get(8)?,
+            series_id: row.get(9)?,
+            occurrence: row.get(10)?,
+            estimate: row.get(11)?,
+            position: row.get(12)?,
+            version: row.get(13)?,
+            time_zone: row.get(14)?,
+            created_at: row.get(15)?,
+            updated_at: row.get(16)?,
+            open_blockers: Vec::new(),
+        })
+    }
+
     pub fn get_task(&self, id: i64) -> Result<Task> {
         self.conn.query_row(
-            "SELECT id, title, description, status, priority, due_date, reminder_time, created_at, updated_at 
-             FROM tasks WHERE id = ?1",
+            &format!("SELECT {} FROM tasks WHERE id = ?1", Self::TASK_COLUMNS),
             params![id],
-            |row| {
-                Ok(Task {
-                    id: row.get(0)?,
-                    title: row.get(1)?,
-                    description: row.get(2)?,
-                    status: row.get(3)?,
-                    priority: row.get(4)?,
-                    due_date: row.get(5)?,
-                    reminder_time: row.get(6)?,
-                    created_at: row.get(7)?,
-                    updated_at: row.get(8)?,
-                })
-            },
+            Self::task_from_row,
         )
     }
 
     pub fn get_all_tasks(&self) -> Result<Vec<Task>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, title, description, status, priority, due_date, reminder_time, created_at, updated_at 
-             FROM tasks ORDER BY created_at DESC",
-        )?;
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM tasks ORDER BY created_at DESC",
+            Self::TASK_COLUMNS
+        ))?;
 
-        let tasks = stmt.query_map([], |row| {
-            Ok(Task {
-                id: row.get(0)?,
-                title: row.get(1)?,
-                description: row.get(2)?,
-                status: row.get(3)?,
-                priority: row.get(4)?,
-                due_date: row.get(5)?,
-                reminder_time: row.get(6)?,
-                created_at: row.get(7)?,
-                updated_at: row.get(8)?,
-            })
-        })?;
+        let tasks = stmt.query_map([], Self::task_from_row)?;
 
         tasks.collect()
     }
 
-    pub fn update_task(&self, id: i64, update: UpdateTask) -> Result<Task> {
+    /// Update a task, rejecting it with a conflict carrying the current task
+    /// when the expected version or updated_at no longer matches.
+    pub fn update_task(&self, id: i64, update: UpdateTask) -> std::result::Result<Task, UpdateError<Task>> {
+        let current = self.get_task(id)?;
+
+        if let Some(reason) = Self::check_expected(
+            current.version,
+            &current.updated_at,
+            update.expected_version,
+            update.expected_updated_at.as_deref(),
+        ) {
+            return Err(UpdateError::conflict(
+                format!("Task {} was modified by someone else ({})", id, reason),
+                current,
+            ));
+        }
+
+        // Moving a blocked task to done is refused, or allowed with the blockers reported
+        let mut open_blockers = Vec::new();
+        if update.status.as_deref() == Some("done") && current.status != "done" {
+            let blocked_by = TaskStructureService::new(self).get_open_blockers(id)?;
+            if !blocked_by.is_empty() {
+                let policy = SettingsService::new(&self.conn)
+                    .get_setting("blocked_task_policy")?
+                    .unwrap_or_else(|| "warn".to_string());
+                if policy == "refuse" {
+                    return Err(UpdateError::Blocked {
+                        message: format!(
+                            "Task {} is blocked by {} unfinished task(s)",
+                            id,
+                            blocked_by.len()
+                        ),
+                        current,
+                        blocked_by,
+                    });
+                }
+                open_blockers = blocked_by;
+            }
+        }
+
+        // Moving into a board column must respect its WIP limit; without a
+        // board, every board showing the status has its say
+        let status_changed = update.status.as_ref().filter(|status| **status != current.status);
+        if let Some(status) = status_changed {
+            if let Some(limit) = BoardService::new(self).wip_limit_exceeded(update.board_id, status, Some(id))? {
+                return Err(UpdateError::WipLimit {
+                    message: format!("The '{}' column is at its WIP limit of {}", status, limit),
+                    current,
+                    status: status.clone(),
+                    limit,
+                });
+            }
+        }
+
         let mut updates = Vec::new();
         let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
 
@@ -765,19 +1318,54 @@ impl Database {
             updates.push("status = ?");
             params_vec.push(Box::new(status.clone()));
         }
+        match status_changed.map(String::as_str) {
+            Some("done") => updates.push("completed_at = CURRENT_TIMESTAMP"),
+            Some(_) => updates.push("completed_at = NULL"),
+            None => {}
+        }
+        match (update.position, status_changed) {
+            (Some(position), _) => {
+                updates.push("position = ?");
+                params_vec.push(Box::new(position));
+            }
+            (None, Some(status)) => {
+                updates.push("position = ?");
+                params_vec.push(Box::new(BoardService::new(self).next_position(status)?));
+            }
+            (None, None) => {}
+        }
         if let Some(priority) = &update.priority {
             updates.push("priority = ?");
             params_vec.push(Box::new(priority.clone()));
         }
-        if let Some(due_date) = &update.due_date {
-            updates.push("due_date = ?");
-            params_vec.push(Box::new(due_date.clone()));
+        if update.due_date.is_some() || update.reminder_time.is_some() || update.time_zone.is_some() {
+            let zone = zone_or(update.time_zone.as_deref().or(current.time_zone.as_deref()), user_zone(&self.conn))
+                .map_err(rusqlite::Error::InvalidParameterName)?;
+            if let Some(due_date) = &update.due_date {
+                updates.push("due_date = ?");
+                params_vec.push(Box::new(normalize_optional(Some(due_date), zone).map_err(rusqlite::Error::InvalidParameterName)?));
+            }
+            if let Some(reminder_time) = &update.reminder_time {
+                updates.push("reminder_time = ?");
+                params_vec.push(Box::new(normalize_optional(Some(reminder_time), zone).map_err(rusqlite::Error::InvalidParameterName)?));
+            }
+            updates.push("time_zone = ?");
+            params_vec.push(Box::new(zone.name()));
         }
-        if let Some(reminder_time) = &update.reminder_time {
-            updates.push("reminder_time = ?");
-            params_vec.push(Box::new(reminder_time.clone()));
+        if let Some(recurrence) = &update.recurrence {
+            let recurrence = normalize_recurrence(Some(recurrence))?;
+            updates.push("recurrence = ?");
+            if recurrence.is_some() && current.series_id.is_none() {
+                updates.push("series_id = id");
+            }
+            params_vec.push(Box::new(recurrence));
+        }
+        if let Some(estimate) = update.estimate {
+            updates.push("estimate = ?");
+            params_vec.push(Box::new(Some(estimate).filter(|minutes| *minutes > 0)));
         }
 
+        updates.push("version = version + 1");
         updates.push("updated_at = CURRENT_TIMESTAMP");
         
         let query = format!("UPDATE tasks SET {} WHERE id = ?", updates.join(", "));
@@ -786,27 +1374,57 @@ impl Database {
         let params_refs: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|p| p.as_ref()).collect();
         
         self.conn.execute(&query, params_refs.as_slice())?;
-        
-        self.get_task(id)
+
+        let mut task = self.get_task(id)?;
+
+        // Completing an occurrence of a recurring task schedules the next one
+        if task.status == "done" && current.status != "done" && task.recurrence.is_some() {
+            RecurringTaskService::new(self).spawn_next_occurrence(&task)?;
+        }
+
+        task.open_blockers = open_blockers;
+        Ok(task)
     }
 
+    /// Delete a task together with all of its subtasks
     pub fn delete_task(&self, id: i64) -> Result<()> {
-        self.conn.execute("DELETE FROM tasks WHERE id = ?1", params![id])?;
+        let structure = TaskStructureService::new(self);
+        let mut ids = structure.get_descendant_ids(id)?;
+        ids.push(id);
+
+        for task_id in ids {
+            structure.delete_task_structure(task_id)?;
+            self.conn.execute("DELETE FROM time_entries WHERE task_id = ?1", params![task_id])?;
+            self.conn.execute("DELETE FROM reminders WHERE item_type = 'task' AND item_id = ?1", params![task_id])?;
+            TagService::new(&self.conn).delete_item_tags("task", task_id)?;
+            self.conn.execute("DELETE FROM tasks WHERE id = ?1", params![task_id])?;
+        }
         Ok(())
     }
 
+    /// Event times in stored form: UTC in the event's zone (or the user's), dates for all-day events
+    fn normalize_event(&self, mut event: CreateEvent) -> Result<CreateEvent> {
+        normalize_event(&mut event, user_zone(&self.conn)).map_err(rusqlite::Error::InvalidParameterName)?;
+        Ok(event)
+    }
+
     // Calendar Event CRUD operations
     pub fn create_event(&self, event: CreateEvent) -> Result<CalendarEvent> {
+        let event = self.normalize_event(event)?;
         self.conn.execute(
-            "INSERT INTO events (title, description, start_time, end_time, reminder_time, recurrence) 
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            "INSERT INTO events (title, description, start_time, end_time, reminder_time, recurrence, location, all_day, exdates, time_zone) 
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
             params![
                 event.title,
                 event.description,
                 event.start_time,
                 event.end_time,
                 event.reminder_time,
-                event.recurrence
+                event.recurrence,
+                event.location,
+                event.all_day,
+                Self::join_exdates(&event.exdates),
+                event.time_zone
             ],
         )?;
 
@@ -814,48 +1432,74 @@ impl Database {
         self.get_event(id)
     }
 
+    pub const EVENT_COLUMNS: &'static str =
+        "id, title, description, start_time, end_time, reminder_time, recurrence, location, all_day, exdates, series_id, recurrence_id, subscription_id, time_zone, created_at";
+
+    pub fn event_from_row(row: &rusqlite::Row) -> Result<CalendarEvent> {
+        let exdates: Option<String> = row.get(9)?;
+        Ok(CalendarEvent {
+            id: row.get(0)?,
+            title: row.get(1)?,
+            description: row.get(2)?,
+            start_time: row.get(3)?,
+            end_time: row.get(4)?,
+            reminder_time: row.get(5)?,
+            recurrence: row.get(6)?,
+            location: row.get(7)?,
+            all_day: row.get(8)?,
+            exdates: exdates
+                .map(|list| list.split(',').filter(|d| !d.is_empty()).map(str::to_string).collect())
+                .unwrap_or_default(),
+            series_id: row.get(10)?,
+            occurrence_date: row.get(11)?,
+            subscription_id: row.get(12)?,
+            time_zone: row.get(13)?,
+            created_at: row.get(14)?,
+        })
+    }
+
+    fn join_exdates(exdates: &[String]) -> Option<String> {
+        if exdates.is_empty() {
+            None
+        } else {
+            Some(exdates.join(","))
+        }
+    }
+
     pub fn get_event(&self, id: i64) -> Result<CalendarEvent> {
         self.conn.query_row(
-            "SELECT id, title, description, start_time, end_time, reminder_time, recurrence, created_at 
-             FROM events WHERE id = ?1",
+            &format!("SELECT {} FROM events WHERE id = ?1", Self::EVENT_COLUMNS),
             params![id],
-            |row| {
-                Ok(CalendarEvent {
-                    id: row.get(0)?,
-                    title: row.get(1)?,
-                    description: row.get(2)?,
-                    start_time: row.get(3)?,
-                    end_time: row.get(4)?,
-                    reminder_time: row.get(5)?,
-                    recurrence: row.get(6)?,
-                    created_at: row.get(7)?,
-                })
-            },
+            Self::event_from_row,
         )
     }
 
+    /// Events in the range with recurring series expanded into occurrences
     pub fn get_events_in_range(&self, start: &str, end: &str) -> Result<Vec<CalendarEvent>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, title, description, start_time, end_time, reminder_time, recurrence, created_at 
-             FROM events 
-             WHERE start_time >= ?1 AND start_time <= ?2
-             ORDER BY start_time ASC",
-        )?;
-
-        let events = stmt.query_map(params![start, end], |row| {
-            Ok(CalendarEvent {
-                id: row.get(0)?,
-                title: row.get(1)?,
-                description: row.get(2)?,
-                start_time: row.get(3)?,
-                end_time: row.get(4)?,
-                reminder_time: row.get(5)?,
-                recurrence: row.get(6)?,
-                created_at: row.get(7)?,
-            })
-        })?;
+        RecurringEventService::new(self).get_events_in_range(start, end)
+    }
 
-        events.collect()
+    /// Overwrite every field of an event, clearing the ones `event` leaves empty
+    pub fn replace_event(&self, id: i64, event: CreateEvent) -> Result<CalendarEvent> {
+        let event = self.normalize_event(event)?;
+        self.conn.execute(
+            "UPDATE events SET title = ?1, description = ?2, start_time = ?3, end_time = ?4, reminder_time = ?5,
+             recurrence = ?6, location = ?7, all_day = ?8, exdates = ?9, time_zone = ?10 WHERE id = ?11",
+            params![
+                event.title,
+                event.description,
+                event.start_time,
+                event.end_time,
+                event.reminder_time,
+                event.recurrence,
+                event.location,
+                event.all_day,
+                Self::join_exdates(&event.exdates),
+                event.time_zone,
+                id
+            ],
+        )?;
+        self.get_event(id)
     }
 
     pub fn update_event(&self, id: i64, update: UpdateEvent) -> Result<CalendarEvent> {
@@ -870,22 +1514,44 @@ impl Database {
             updates.push("description = ?");
             params_vec.push(Box::new(description.clone()));
         }
-        if let Some(start_time) = &update.start_time {
-            updates.push("start_time = ?");
-            params_vec.push(Box::new(start_time.clone()));
-        }
-        if let Some(end_time) = &update.end_time {
-            updates.push("end_time = ?");
-            params_vec.push(Box::new(end_time.clone()));
-        }
-        if let Some(reminder_time) = &update.reminder_time {
-            updates.push("reminder_time = ?");
-            params_vec.push(Box::new(reminder_time.clone()));
+        // Times are normalized together, since the zone and all-day flag
+        // decide how each of them is read
+        let times_changed = update.start_time.is_some()
+            || update.end_time.is_some()
+            || update.reminder_time.is_some()
+            || update.all_day.is_some()
+            || update.exdates.is_some()
+            || update.time_zone.is_some();
+        if times_changed {
+            let current = self.get_event(id)?;
+            let times = self.normalize_event(CreateEvent {
+                title: current.title,
+                description: None,
+                start_time: update.start_time.clone().unwrap_or(current.start_time),
+                end_time: update.end_time.clone().unwrap_or(current.end_time),
+                reminder_time: update.reminder_time.clone().or(current.reminder_time),
+                all_day: update.all_day.unwrap_or(current.all_day),
+                exdates: update.exdates.clone().unwrap_or(current.exdates),
+                time_zone: update.time_zone.clone().or(current.time_zone),
+                recurrence: None,
+                location: None,
+            })?;
+            updates.extend(["start_time = ?", "end_time = ?", "reminder_time = ?", "all_day = ?", "exdates = ?", "time_zone = ?"]);
+            params_vec.push(Box::new(times.start_time));
+            params_vec.push(Box::new(times.end_time));
+            params_vec.push(Box::new(times.reminder_time));
+            params_vec.push(Box::new(times.all_day));
+            params_vec.push(Box::new(Self::join_exdates(&times.exdates)));
+            params_vec.push(Box::new(times.time_zone));
         }
         if let Some(recurrence) = &update.recurrence {
             updates.push("recurrence = ?");
             params_vec.push(Box::new(recurrence.clone()));
         }
+        if let Some(location) = &update.location {
+            updates.push("location = ?");
+            params_vec.push(Box::new(location.clone()));
+        }
 
         if updates.is_empty() {
             return self.get_event(id);
@@ -897,17 +1563,38 @@ impl Database {
         let params_refs: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|p| p.as_ref()).collect();
         
         self.conn.execute(&query, params_refs.as_slice())?;
+        // A planned block edited by hand stays as the user left it
+        self.conn.execute("UPDATE planned_blocks SET pinned = 1 WHERE event_id = ?1", params![id])?;
         
         self.get_event(id)
     }
 
     pub fn delete_event(&self, id: i64) -> Result<()> {
+        // Edited occurrences go with their series
+        let mut stmt = self.conn.prepare("SELECT id FROM events WHERE series_id = ?1")?;
+        let occurrences: Vec<i64> = stmt.query_map(params![id], |row| row.get(0))?.collect::<Result<_>>()?;
+        for occurrence in occurrences {
+            TagService::new(&self.conn).delete_item_tags("event", occurrence)?;
+        }
+        self.conn.execute(
+            "DELETE FROM event_sources WHERE event_id = ?1 OR event_id IN (SELECT id FROM events WHERE series_id = ?1)",
+            params![id],
+        )?;
+        self.conn.execute("DELETE FROM planned_blocks WHERE event_id = ?1", params![id])?;
+        self.conn.execute(
+            "DELETE FROM reminders WHERE item_type = 'event' AND (item_id = ?1 OR item_id IN (SELECT id FROM events WHERE series_id = ?1))",
+            params![id],
+        )?;
+        self.conn.execute("DELETE FROM events WHERE series_id = ?1", params![id])?;
         self.conn.execute("DELETE FROM events WHERE id = ?1", params![id])?;
+        TagService::new(&self.conn).delete_item_tags("event", id)?;
         Ok(())
     }
 
     // Clear all data methods
     pub fn clear_all_messages(&self) -> Result<usize> {
+        self.conn.execute("DELETE FROM item_tags WHERE item_type = 'message'", [])?;
+        self.conn.execute("DELETE FROM tag_suggestions WHERE item_type = 'message'", [])?;
         let count = self.conn.execute("DELETE FROM messages", [])?;
         Ok(count)
     }
@@ -915,16 +1602,30 @@ impl Database {
     pub fn clear_all_documents(&self) -> Result<usize> {
         // Also clear document versions
         self.conn.execute("DELETE FROM document_versions", [])?;
+        self.conn.execute("DELETE FROM item_tags WHERE item_type = 'document'", [])?;
+        self.conn.execute("DELETE FROM tag_suggestions WHERE item_type = 'document'", [])?;
         let count = self.conn.execute("DELETE FROM documents", [])?;
         Ok(count)
     }
 
     pub fn clear_all_tasks(&self) -> Result<usize> {
+        self.conn.execute("DELETE FROM item_tags WHERE item_type = 'task'", [])?;
+        self.conn.execute("DELETE FROM tag_suggestions WHERE item_type = 'task'", [])?;
+        self.conn.execute("DELETE FROM task_checklist_items", [])?;
+        self.conn.execute("DELETE FROM task_dependencies", [])?;
+        self.conn.execute("DELETE FROM time_entries", [])?;
+        self.conn.execute("DELETE FROM planned_blocks", [])?;
+        self.conn.execute("DELETE FROM reminders WHERE item_type = 'task'", [])?;
         let count = self.conn.execute("DELETE FROM tasks", [])?;
         Ok(count)
     }
 
     pub fn clear_all_events(&self) -> Result<usize> {
+        self.conn.execute("DELETE FROM item_tags WHERE item_type = 'event'", [])?;
+        self.conn.execute("DELETE FROM tag_suggestions WHERE item_type = 'event'", [])?;
+        self.conn.execute("DELETE FROM event_sources", [])?;
+        self.conn.execute("DELETE FROM planned_blocks", [])?;
+        self.conn.execute("DELETE FROM reminders WHERE item_type = 'event'", [])?;
         let count = self.conn.execute("DELETE FROM events", [])?;
         Ok(count)
     }
@@ -991,4 +1692,87 @@ impl Database {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
 
+    fn document_update(title: Option<&str>, expected_version: Option<i64>) -> UpdateDocument {
+        UpdateDocument {
+            title: title.map(str::to_string),
+            content: None,
+            tags: None,
+            expected_version,
+            expected_updated_at: None,
+        }
+    }
+
+    fn task_update() -> UpdateTask {
+        UpdateTask {
+            title: None,
+            description: None,
+            status: None,
+            priority: None,
+            due_date: None,
+            reminder_time: None,
+            recurrence: None,
+            estimate: None,
+            time_zone: None,
+            position: None,
+            board_id: None,
+            expected_version: None,
+            expected_updated_at: None,
+        }
+    }
+
+    fn new_task(title: &str) -> CreateTask {
+        CreateTask {
+            title: title.into(),
+            description: None,
+            status: None,
+            priority: None,
+            due_date: None,
+            reminder_time: None,
+            parent_id: None,
+            recurrence: None,
+            estimate: None,
+            time_zone: None,
+        }
+    }
+
+    #[test]
+    fn stale_document_updates_conflict_even_after_title_only_edits() {
+        let db = Database::in_memory().unwrap();
+        let document = db
+            .create_document(CreateDocument { title: "Plan".into(), content: "v1".into(), tags: None, folder_id: None })
+            .unwrap();
+
+        let renamed = db.update_document(document.id, document_update(Some("Plan B"), Some(document.version))).unwrap();
+        assert_eq!(renamed.version, document.version + 1);
+
+        match db.update_document(document.id, document_update(Some("Plan C"), Some(document.version))) {
+            Err(UpdateError::Conflict { current, .. }) => assert_eq!(current.title, "Plan B"),
+            other => panic!("expected a conflict, got {:?}", other.map(|d| d.title)),
+        }
+        assert_eq!(db.get_document(document.id).unwrap().title, "Plan B");
+    }
+
+    #[test]
+    fn stale_task_updates_conflict() {
+        let db = Database::in_memory().unwrap();
+        let task = db.create_task(new_task("Write report")).unwrap();
+
+        let mut first = task_update();
+        first.priority = Some("high".into());
+        first.expected_version = Some(task.version);
+        assert_eq!(db.update_task(task.id, first).unwrap().version, task.version + 1);
+
+        let mut stale = task_update();
+        stale.title = Some("Overwritten".into());
+        stale.expected_version = Some(task.version);
+        match db.update_task(task.id, stale) {
+            Err(UpdateError::Conflict { current, .. }) => assert_eq!(current.priority, "high"),
+            other => panic!("expected a conflict, got {:?}", other.map(|t| t.title)),
+        }
+        assert_eq!(db.get_task(task.id).unwrap().title, "Write report");
+    }
+}
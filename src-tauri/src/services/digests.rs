@@ -0,0 +1,505 @@
+// Digests - a morning agenda and a weekly review, delivered on a schedule
+//
+// The morning agenda lists today's events, overdue and due tasks and unread
+// insights; the weekly review looks back at completed tasks, new documents
+// and the busiest chat threads, optionally summarized by Ollama. Each digest
+// is saved as a document tagged "digest" and announced with a notification.
+// The schedule lives in `settings`; the time of the last delivery of each
+// kind is kept there too, so a digest missed while the app was closed is
+// delivered once when it opens.
+use crate::models::digest::{Digest, DigestSchedule, DigestSection};
+use crate::models::document::{CreateDocument, Document};
+use crate::models::event::CalendarEvent;
+use crate::services::database::Database;
+use crate::services::notifications::NotificationService;
+use crate::services::ollama::OllamaService;
+use crate::services::recurring_events::RecurringEventService;
+use crate::services::rrule::{format_stored_datetime, parse_stored_datetime, DateStyle};
+use crate::services::settings::SettingsService;
+use crate::services::time_zones::{date_in_zone, instant, user_zone, utc_to_zoned, zoned_to_utc};
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use chrono_tz::Tz;
+use rusqlite::{params, Result};
+use std::sync::Arc;
+use tauri::{AppHandle, Manager};
+use tokio::sync::Mutex;
+
+/// Settings key holding the digest schedule, as JSON
+pub const SCHEDULE_SETTING: &str = "digest_schedule";
+/// Frontend event sent with every digest delivered on schedule
+pub const DIGEST_EVENT: &str = "digest-ready";
+pub const MORNING: &str = "morning";
+pub const WEEKLY: &str = "weekly";
+const CHECK_INTERVAL_SECS: u64 = 60;
+/// Messages further apart than this belong to different threads
+const THREAD_GAP_MINUTES: i64 = 60;
+const TOP_THREADS: usize = 5;
+const MAX_INSIGHTS: usize = 10;
+const EXCERPT_CHARS: usize = 60;
+
+fn invalid(message: String) -> rusqlite::Error {
+    rusqlite::Error::InvalidParameterName(message)
+}
+
+fn last_sent_setting(kind: &str) -> String {
+    format!("digest_last_{}", kind)
+}
+
+fn parse_time(value: &str) -> Result<NaiveTime> {
+    NaiveTime::parse_from_str(value.trim(), "%H:%M").map_err(|_| invalid(format!("Invalid time of day '{}'", value)))
+}
+
+/// The latest scheduled moment at or before `now`, in local time
+fn last_slot(now: NaiveDateTime, time: NaiveTime, days: &[u32]) -> Option<NaiveDateTime> {
+    (0..7)
+        .map(|back| (now.date() - Duration::days(back)).and_time(time))
+        .find(|slot| *slot <= now && days.contains(&slot.weekday().number_from_monday()))
+}
+
+fn excerpt(text: &str) -> String {
+    let line = text.lines().map(str::trim).find(|l| !l.is_empty()).unwrap_or_default();
+    if line.chars().count() > EXCERPT_CHARS {
+        format!("{}…", line.chars().take(EXCERPT_CHARS).collect::<String>().trim_end())
+    } else {
+        line.to_string()
+    }
+}
+
+/// A run of chat messages with no long pause in between
+#[derive(Debug, Clone, PartialEq)]
+struct Thread {
+    title: String,
+    started: NaiveDateTime,
+    messages: usize,
+}
+
+/// Split (channel, sent at, content) messages, sorted by channel then time,
+/// into threads, busiest first
+fn top_threads(messages: &[(i64, NaiveDateTime, String)], limit: usize) -> Vec<Thread> {
+    let mut threads: Vec<Thread> = Vec::new();
+    let mut last: Option<(i64, NaiveDateTime)> = None;
+    for (channel, at, content) in messages {
+        let continues = last.map(|(c, previous)| c == *channel && *at - previous <= Duration::minutes(THREAD_GAP_MINUTES));
+        match threads.last_mut() {
+            Some(thread) if continues == Some(true) => thread.messages += 1,
+            _ => threads.push(Thread { title: excerpt(content), started: *at, messages: 1 }),
+        }
+        last = Some((*channel, *at));
+    }
+    threads.sort_by(|a, b| b.messages.cmp(&a.messages).then(a.started.cmp(&b.started)));
+    threads.truncate(limit);
+    threads
+}
+
+fn event_line(event: &CalendarEvent, zone: Tz) -> String {
+    let when = if event.all_day {
+        "All day".to_string()
+    } else {
+        let time = |value: &str| instant(value, zone).map(|at| utc_to_zoned(zone, at).format("%H:%M").to_string());
+        format!("{}–{}", time(&event.start_time).unwrap_or_default(), time(&event.end_time).unwrap_or_default())
+    };
+    match event.location.as_deref().filter(|l| !l.is_empty()) {
+        Some(location) => format!("{} {} ({})", when, event.title, location),
+        None => format!("{} {}", when, event.title),
+    }
+}
+
+/// The digest as a Markdown document
+pub fn to_markdown(digest: &Digest) -> String {
+    let mut out = format!("# {}\n", digest.title);
+    if let Some(summary) = &digest.summary {
+        out.push_str(&format!("\n{}\n", summary.trim()));
+    }
+    for section in &digest.sections {
+        out.push_str(&format!("\n## {}\n\n", section.heading));
+        if section.items.is_empty() {
+            out.push_str("Nothing.\n");
+        }
+        for item in &section.items {
+            out.push_str(&format!("- {}\n", item));
+        }
+    }
+    out
+}
+
+/// "Events: 3, Due today: 2" from the non-empty sections
+fn headline(sections: &[DigestSection]) -> String {
+    let parts: Vec<String> = sections
+        .iter()
+        .filter(|s| !s.items.is_empty())
+        .map(|s| format!("{}: {}", s.heading, s.items.len()))
+        .collect();
+    if parts.is_empty() {
+        "Nothing to report".to_string()
+    } else {
+        parts.join(", ")
+    }
+}
+
+pub struct DigestService<'a> {
+    db: &'a Database,
+}
+
+impl<'a> DigestService<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    fn settings(&self) -> SettingsService<'_> {
+        SettingsService::new(self.db.get_connection())
+    }
+
+    pub fn get_schedule(&self) -> Result<DigestSchedule> {
+        Ok(self
+            .settings()
+            .get_setting(SCHEDULE_SETTING)?
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default())
+    }
+
+    pub fn set_schedule(&self, schedule: DigestSchedule) -> Result<DigestSchedule> {
+        parse_time(&schedule.morning_time)?;
+        parse_time(&schedule.weekly_time)?;
+        if !(1..=7).contains(&schedule.weekly_day) {
+            return Err(invalid("Weekdays are numbered 1 (Monday) to 7 (Sunday)".to_string()));
+        }
+        let json = serde_json::to_string(&schedule).map_err(|e| invalid(e.to_string()))?;
+        self.settings().set_setting(SCHEDULE_SETTING, &json)?;
+        Ok(schedule)
+    }
+
+    /// The digests whose scheduled time has passed since they were last delivered
+    pub fn due_kinds(&self, now: NaiveDateTime) -> Result<Vec<&'static str>> {
+        let schedule = self.get_schedule()?;
+        let zone = user_zone(self.db.get_connection());
+        let local = utc_to_zoned(zone, now);
+        let mut due = Vec::new();
+        let planned = [
+            (MORNING, schedule.morning_enabled, &schedule.morning_time, vec![1, 2, 3, 4, 5, 6, 7]),
+            (WEEKLY, schedule.weekly_enabled, &schedule.weekly_time, vec![schedule.weekly_day]),
+        ];
+        for (kind, enabled, time, days) in planned {
+            if !enabled {
+                continue;
+            }
+            let Some(slot) = last_slot(local, parse_time(time)?, &days).and_then(|slot| zoned_to_utc(zone, slot)) else {
+                continue;
+            };
+            let last_sent = self
+                .settings()
+                .get_setting(&last_sent_setting(kind))?
+                .and_then(|value| parse_stored_datetime(&value))
+                .map(|(at, _)| at);
+            if last_sent.map(|sent| sent < slot).unwrap_or(true) {
+                due.push(kind);
+            }
+        }
+        Ok(due)
+    }
+
+    pub fn mark_delivered(&self, kind: &str, now: NaiveDateTime) -> Result<()> {
+        self.settings().set_setting(&last_sent_setting(kind), &format_stored_datetime(now, DateStyle::Utc))
+    }
+
+    pub fn build(&self, kind: &str, now: NaiveDateTime) -> Result<Digest> {
+        match kind {
+            MORNING => self.morning_agenda(now),
+            WEEKLY => self.weekly_review(now),
+            _ => Err(invalid(format!("Unknown digest '{}'; use 'morning' or 'weekly'", kind))),
+        }
+    }
+
+    /// Today's events, overdue and due tasks and unread insights
+    pub fn morning_agenda(&self, now: NaiveDateTime) -> Result<Digest> {
+        let conn = self.db.get_connection();
+        let zone = user_zone(conn);
+        let today = utc_to_zoned(zone, now).date();
+        let day_start = zoned_to_utc(zone, today.and_time(NaiveTime::MIN)).unwrap_or(now);
+        let day_end = zoned_to_utc(zone, (today + Duration::days(1)).and_time(NaiveTime::MIN)).unwrap_or(now);
+
+        let events: Vec<String> = RecurringEventService::new(self.db)
+            .get_events_overlapping(day_start, day_end)?
+            .iter()
+            .map(|e| event_line(e, zone))
+            .collect();
+
+        let (mut overdue, mut due_today) = (Vec::new(), Vec::new());
+        for task in self.db.get_all_tasks()? {
+            if task.status == "done" || task.status == "cancelled" {
+                continue;
+            }
+            let Some(due) = task.due_date.as_deref() else { continue };
+            let Some((at, style)) = parse_stored_datetime(due) else { continue };
+            let Some(date) = date_in_zone(due, zone).and_then(|d| NaiveDate::parse_from_str(&d, "%Y-%m-%d").ok()) else {
+                continue;
+            };
+            let timed = !matches!(style, DateStyle::Date);
+            let label = if timed {
+                utc_to_zoned(zone, instant(due, zone).unwrap_or(at)).format("%a %-d %b %H:%M").to_string()
+            } else {
+                date.format("%a %-d %b").to_string()
+            };
+            let line = format!("{} (due {}, {} priority)", task.title, label, task.priority);
+            if date < today || (timed && instant(due, zone).map(|d| d < now).unwrap_or(false)) {
+                overdue.push((date, line));
+            } else if date == today {
+                due_today.push((date, line));
+            }
+        }
+        overdue.sort();
+        due_today.sort();
+
+        let mut stmt = conn.prepare("SELECT title FROM insights WHERE is_read = 0 ORDER BY priority ASC, created_at DESC LIMIT ?1")?;
+        let insights = stmt.query_map(params![MAX_INSIGHTS as i64], |row| row.get(0))?.collect::<Result<Vec<String>>>()?;
+
+        let sections = vec![
+            DigestSection { heading: "Events".to_string(), items: events },
+            DigestSection { heading: "Overdue tasks".to_string(), items: overdue.into_iter().map(|(_, line)| line).collect() },
+            DigestSection { heading: "Due today".to_string(), items: due_today.into_iter().map(|(_, line)| line).collect() },
+            DigestSection { heading: "Unread insights".to_string(), items: insights },
+        ];
+        Ok(Digest {
+            kind: MORNING.to_string(),
+            title: format!("Morning agenda – {}", today.format("%a %-d %b %Y")),
+            start: format_stored_datetime(day_start, DateStyle::Utc),
+            end: format_stored_datetime(day_end, DateStyle::Utc),
+            headline: headline(&sections),
+            sections,
+            summary: None,
+            document_id: None,
+        })
+    }
+
+    /// Tasks completed, documents created and the busiest chat threads of the last seven days
+    pub fn weekly_review(&self, now: NaiveDateTime) -> Result<Digest> {
+        let conn = self.db.get_connection();
+        let zone = user_zone(conn);
+        let start = now - Duration::days(7);
+        let since = format_stored_datetime(start, DateStyle::Sql);
+
+        let mut stmt =
+            conn.prepare("SELECT title FROM tasks WHERE status = 'done' AND completed_at >= ?1 ORDER BY completed_at, id")?;
+        let completed = stmt.query_map(params![since], |row| row.get(0))?.collect::<Result<Vec<String>>>()?;
+
+        // Earlier digests are documents too; leave out everything tagged digest or digest/...
+        let mut stmt = conn.prepare(
+            "SELECT title FROM documents d WHERE created_at >= ?1 AND NOT EXISTS (
+                SELECT 1 FROM item_tags it JOIN tags t ON t.id = it.tag_id
+                WHERE it.item_type = 'document' AND it.item_id = d.id
+                  AND (lower(t.name) = 'digest' OR lower(substr(t.name, 1, 7)) = 'digest/')
+             ) ORDER BY created_at",
+        )?;
+        let documents = stmt.query_map(params![since], |row| row.get(0))?.collect::<Result<Vec<String>>>()?;
+
+        let mut stmt = conn.prepare("SELECT channel_id, created_at, content FROM messages WHERE created_at >= ?1 ORDER BY channel_id, created_at")?;
+        let messages: Vec<(i64, NaiveDateTime, String)> = stmt
+            .query_map(params![since], |row| Ok((row.get(0)?, row.get::<_, String>(1)?, row.get(2)?)))?
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .filter_map(|(channel, at, content)| parse_stored_datetime(&at).map(|(at, _)| (channel, at, content)))
+            .collect();
+        let threads = top_threads(&messages, TOP_THREADS)
+            .into_iter()
+            .map(|t| format!("{} ({} messages, {})", t.title, t.messages, utc_to_zoned(zone, t.started).format("%a %-d %b")))
+            .collect();
+
+        let sections = vec![
+            DigestSection { heading: "Completed tasks".to_string(), items: completed },
+            DigestSection { heading: "New documents".to_string(), items: documents },
+            DigestSection { heading: "Chat threads".to_string(), items: threads },
+        ];
+        let (first, last) = (utc_to_zoned(zone, start).date(), utc_to_zoned(zone, now).date());
+        Ok(Digest {
+            kind: WEEKLY.to_string(),
+            title: format!("Weekly review – {} to {}", first.format("%-d %b"), last.format("%-d %b %Y")),
+            start: format_stored_datetime(start, DateStyle::Utc),
+            end: format_stored_datetime(now, DateStyle::Utc),
+            headline: headline(&sections),
+            sections,
+            summary: None,
+            document_id: None,
+        })
+    }
+
+    /// Save the digest as a document in the scheduled folder
+    pub fn save(&self, digest: &mut Digest) -> Result<Document> {
+        let folder_id = self.get_schedule()?.folder_id;
+        let document = self.db.create_document(CreateDocument {
+            title: digest.title.clone(),
+            content: to_markdown(digest),
+            tags: Some(format!("digest, digest/{}", digest.kind)),
+            folder_id,
+        })?;
+        digest.document_id = Some(document.id);
+        Ok(document)
+    }
+}
+
+/// Build a digest, summarize the weekly review if the schedule asks for it,
+/// and save it as a document unless this is a preview
+pub async fn generate_digest(
+    db: &Arc<Mutex<Database>>,
+    ollama: &Arc<Mutex<OllamaService>>,
+    kind: &str,
+    save: bool,
+) -> std::result::Result<Digest, String> {
+    let now = Utc::now().naive_utc();
+    let (mut digest, summarize) = {
+        let db = db.lock().await;
+        let service = DigestService::new(&db);
+        (service.build(kind, now).map_err(|e| e.to_string())?, service.get_schedule().map_err(|e| e.to_string())?.summarize)
+    };
+    if summarize && digest.kind == WEEKLY && digest.sections.iter().any(|s| !s.items.is_empty()) {
+        let ollama = ollama.lock().await;
+        // A review without a summary is still worth delivering
+        match ollama.summarize(&to_markdown(&digest)).await {
+            Ok(summary) => digest.summary = Some(summary.trim().to_string()),
+            Err(e) => eprintln!("Could not summarize the weekly review: {}", e),
+        }
+    }
+    if save {
+        let db = db.lock().await;
+        DigestService::new(&db).save(&mut digest).map_err(|e| e.to_string())?;
+    }
+    Ok(digest)
+}
+
+/// Deliver the digests that are due as documents, notifications and frontend events
+pub async fn deliver_due_digests(app: &AppHandle, db: &Arc<Mutex<Database>>, ollama: &Arc<Mutex<OllamaService>>) -> usize {
+    let due = {
+        let db = db.lock().await;
+        let service = DigestService::new(&db);
+        let due = service.due_kinds(Utc::now().naive_utc()).unwrap_or_default();
+        // Marked up front so a failing digest is not retried every minute
+        for kind in &due {
+            if let Err(e) = service.mark_delivered(kind, Utc::now().naive_utc()) {
+                eprintln!("Failed to record the {} digest: {}", kind, e);
+            }
+        }
+        due
+    };
+    let notifications = NotificationService::new(app.config().tauri.bundle.identifier.clone());
+    let mut delivered = 0;
+    for kind in due {
+        match generate_digest(db, ollama, kind, true).await {
+            Ok(digest) => {
+                if let Err(e) = notifications.send_notification(&digest.title, &digest.headline) {
+                    eprintln!("Failed to show the {} digest: {}", kind, e);
+                }
+                let _ = app.emit_all(DIGEST_EVENT, digest);
+                delivered += 1;
+            }
+            Err(e) => eprintln!("Failed to build the {} digest: {}", kind, e),
+        }
+    }
+    delivered
+}
+
+/// Deliver digests in the background for the life of the app
+pub fn spawn_digest_loop(app: AppHandle, db: Arc<Mutex<Database>>, ollama: Arc<Mutex<OllamaService>>) {
+    tokio::spawn(async move {
+        loop {
+            deliver_due_digests(&app, &db, &ollama).await;
+            tokio::time::sleep(std::time::Duration::from_secs(CHECK_INTERVAL_SECS)).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(value: &str) -> NaiveDateTime {
+        parse_stored_datetime(value).unwrap().0
+    }
+
+    #[test]
+    fn finds_the_last_scheduled_slot() {
+        let eight = NaiveTime::from_hms_opt(8, 0, 0).unwrap();
+        // Wednesday 07:30: the morning slot is still yesterday's
+        assert_eq!(last_slot(at("2024-05-08T07:30"), eight, &[1, 2, 3, 4, 5, 6, 7]), Some(at("2024-05-07T08:00")));
+        assert_eq!(last_slot(at("2024-05-08T08:00"), eight, &[1, 2, 3, 4, 5, 6, 7]), Some(at("2024-05-08T08:00")));
+        // Weekly on Friday: last week's until this Friday comes round
+        assert_eq!(last_slot(at("2024-05-08T12:00"), eight, &[5]), Some(at("2024-05-03T08:00")));
+        assert_eq!(last_slot(at("2024-05-10T09:00"), eight, &[5]), Some(at("2024-05-10T08:00")));
+        assert_eq!(last_slot(at("2024-05-10T09:00"), eight, &[]), None);
+    }
+
+    #[test]
+    fn threads_split_on_long_pauses() {
+        let messages = vec![
+            (1, at("2024-05-06 09:00:00"), "How do I rotate the API keys?".to_string()),
+            (1, at("2024-05-06 09:05:00"), "Use the settings page".to_string()),
+            (1, at("2024-05-06 09:50:00"), "Thanks".to_string()),
+            (1, at("2024-05-06 14:00:00"), "\nPlan the offsite".to_string()),
+            (1, at("2024-05-06 14:10:00"), "Sure".to_string()),
+            (2, at("2024-05-06 14:20:00"), "Other channel".to_string()),
+        ];
+        let threads = top_threads(&messages, 2);
+        let summary: Vec<(&str, usize)> = threads.iter().map(|t| (t.title.as_str(), t.messages)).collect();
+        assert_eq!(summary, vec![("How do I rotate the API keys?", 3), ("Plan the offsite", 2)]);
+        assert_eq!(excerpt(&"word ".repeat(20)).chars().count(), EXCERPT_CHARS);
+    }
+
+    #[test]
+    fn weekly_review_lists_tasks_completed_this_week_and_new_documents() {
+        use crate::models::task::{CreateTask, UpdateTask};
+
+        let db = Database::in_memory().unwrap();
+        let task = |title: &str, status: &str| {
+            db.create_task(CreateTask {
+                title: title.into(),
+                description: None,
+                status: Some(status.into()),
+                priority: None,
+                due_date: None,
+                reminder_time: None,
+                parent_id: None,
+                recurrence: None,
+                estimate: None,
+                time_zone: None,
+            })
+            .unwrap()
+            .id
+        };
+        let update = |title: Option<&str>, status: Option<&str>| UpdateTask {
+            title: title.map(str::to_string),
+            description: None,
+            status: status.map(str::to_string),
+            priority: None,
+            due_date: None,
+            reminder_time: None,
+            recurrence: None,
+            estimate: None,
+            time_zone: None,
+            position: None,
+            board_id: None,
+            expected_version: None,
+            expected_updated_at: None,
+        };
+
+        // Finished last month, but retitled today
+        let old = task("Old chore", "done");
+        db.get_connection()
+            .execute("UPDATE tasks SET completed_at = datetime('now', '-30 days') WHERE id = ?1", params![old])
+            .unwrap();
+        db.update_task(old, update(Some("Old chore, renamed"), None)).unwrap();
+        let finished = task("Ship release", "in_progress");
+        db.update_task(finished, update(None, Some("done"))).unwrap();
+        // Reopened again, so no longer completed
+        let reopened = task("Flaky test", "done");
+        db.update_task(reopened, update(None, Some("todo"))).unwrap();
+
+        let document = |title: &str, tags: &str| {
+            db.create_document(CreateDocument { title: title.into(), content: String::new(), tags: Some(tags.into()), folder_id: None })
+                .unwrap()
+        };
+        document("Gut health notes", "digestion");
+        document("Weekly review – last week", "digest, digest/weekly");
+
+        let review = DigestService::new(&db).weekly_review(Utc::now().naive_utc() + Duration::minutes(1)).unwrap();
+        let items = |heading: &str| review.sections.iter().find(|s| s.heading == heading).unwrap().items.clone();
+        assert_eq!(items("Completed tasks"), vec!["Ship release"]);
+        assert_eq!(items("New documents"), vec!["Gut health notes"]);
+    }
+}
@@ -2,6 +2,8 @@ use crate::models::vector_index::SimilaritySearchResult;
 use crate::services::lancedb_service::LanceDBService;
 use crate::services::ollama::OllamaService;
 use crate::services::database::Database;
+use crate::services::tags::normalize_tag_name;
+use crate::services::folders::FolderService;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use std::path::Path;
@@ -29,7 +31,10 @@ impl DocumentIntelligenceService {
 
     /// Process a document and create intelligent insights
     pub async fn process_document(&self, document_id: i64, content: &str, title: &str) -> Result<Vec<SimilaritySearchResult>, String> {
-        // Index the document content
+        // Drop vectors from the previous version so hits point at current sections
+        self.lancedb.delete_content_vectors(document_id, "document").await?;
+
+        // Index the document content, one or more chunks per section
         let entries = self.lancedb
             .index_content(document_id, "document", content, Some("nomic-embed-text"))
             .await?;
@@ -79,12 +84,13 @@ impl DocumentIntelligenceService {
             .await
             .map_err(|e| format!("Failed to generate tags: {}", e))?;
 
-        // Parse tags from response
-        let tags: Vec<String> = response
-            .lines()
-            .map(|line| line.trim().to_string())
-            .filter(|tag| !tag.is_empty())
-            .collect();
+        // Parse tags from response, dropping bullets and numbering
+        let mut tags: Vec<String> = Vec::new();
+        for tag in response.lines().filter_map(normalize_tag_name) {
+            if !tags.iter().any(|t| t.eq_ignore_ascii_case(&tag)) {
+                tags.push(tag);
+            }
+        }
 
         Ok(tags)
     }
@@ -132,22 +138,31 @@ impl DocumentIntelligenceService {
         Ok(related)
     }
 
-    /// Process all documents for intelligence
-    pub async fn process_all_documents(&self) -> Result<i64, String> {
+    /// Process all documents for intelligence, optionally limited to a folder and its subfolders
+    pub async fn process_all_documents(&self, folder_id: Option<i64>) -> Result<i64, String> {
         // Get all documents first to avoid Send issues
         let documents = {
             let db_guard = self.db.lock().await;
             let conn = db_guard.get_connection();
-            
-            let mut stmt = conn.prepare("SELECT id, title, content FROM documents")
-                .map_err(|e| format!("Failed to prepare query: {}", e))?;
-            
-            let rows = stmt.query_map([], |row| {
-                Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?))
-            }).map_err(|e| format!("Failed to query documents: {}", e))?;
-            
-            rows.collect::<Result<Vec<_>, _>>()
-                .map_err(|e| format!("Failed to parse documents: {}", e))?
+
+            if let Some(folder_id) = folder_id {
+                FolderService::new(conn)
+                    .get_documents_in_folder(Some(folder_id), true)
+                    .map_err(|e| format!("Failed to query folder documents: {}", e))?
+                    .into_iter()
+                    .map(|doc| (doc.id, doc.title, doc.content))
+                    .collect::<Vec<_>>()
+            } else {
+                let mut stmt = conn.prepare("SELECT id, title, content FROM documents")
+                    .map_err(|e| format!("Failed to prepare query: {}", e))?;
+
+                let rows = stmt.query_map([], |row| {
+                    Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?))
+                }).map_err(|e| format!("Failed to query documents: {}", e))?;
+
+                rows.collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| format!("Failed to parse documents: {}", e))?
+            }
         };
 
         let mut processed_count = 0;
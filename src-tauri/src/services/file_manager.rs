@@ -1,7 +1,15 @@
+use crate::services::blob_store::{Blob, BlobStore};
+use crate::services::previews::{preview_key, PreviewCache, PREVIEW_DIR};
+use crate::services::tags::TagService;
 use rusqlite::{params, Connection, Result};
 use std::fs;
-use std::path::{Path, PathBuf};
-use uuid::Uuid;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Component, Path, PathBuf};
+
+/// Directory under the files root that holds content-addressed blobs
+pub const BLOB_DIR: &str = "blobs";
+/// Largest range returned by one `read_range`
+pub const MAX_RANGE: u64 = 16 * 1024 * 1024;
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct FileMetadata {
@@ -10,9 +18,25 @@ pub struct FileMetadata {
     pub filepath: String,
     pub filesize: i64,
     pub mimetype: String,
+    /// SHA-256 of the content; files with the same hash share their bytes
+    pub hash: Option<String>,
     pub created_at: String,
 }
 
+const FILE_COLUMNS: &str = "id, filename, filepath, filesize, mimetype, hash, created_at";
+
+fn file_from_row(row: &rusqlite::Row) -> Result<FileMetadata> {
+    Ok(FileMetadata {
+        id: row.get(0)?,
+        filename: row.get(1)?,
+        filepath: row.get(2)?,
+        filesize: row.get(3)?,
+        mimetype: row.get(4)?,
+        hash: row.get(5)?,
+        created_at: row.get(6)?,
+    })
+}
+
 pub struct FileManager<'a> {
     conn: &'a Connection,
     base_path: PathBuf,
@@ -30,6 +54,31 @@ impl<'a> FileManager<'a> {
         Ok(Self { conn, base_path })
     }
 
+    /// A file manager rooted at `base_path` instead of the app data directory
+    #[cfg(test)]
+    pub fn with_base_path(conn: &'a Connection, base_path: PathBuf) -> std::io::Result<Self> {
+        fs::create_dir_all(&base_path)?;
+        Ok(Self { conn, base_path })
+    }
+
+    /// Root of the blob store shared by uploads, ingestion and data operations
+    pub fn blob_root() -> std::io::Result<PathBuf> {
+        Ok(Self::get_base_path()?.join(BLOB_DIR))
+    }
+
+    pub fn blob_store(&self) -> std::io::Result<BlobStore<'a>> {
+        BlobStore::new(self.conn, self.base_path.join(BLOB_DIR))
+    }
+
+    pub fn preview_cache(&self) -> std::io::Result<PreviewCache> {
+        PreviewCache::new(self.base_path.join(PREVIEW_DIR))
+    }
+
+    /// Absolute path of a stored file; paths outside the files root are kept as given
+    pub fn full_path(&self, metadata: &FileMetadata) -> PathBuf {
+        self.base_path.join(&metadata.filepath)
+    }
+
     fn get_base_path() -> std::io::Result<PathBuf> {
         let mut path = tauri::api::path::app_data_dir(&tauri::Config::default())
             .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "Could not find app data directory"))?;
@@ -39,63 +88,41 @@ impl<'a> FileManager<'a> {
     }
 
     pub fn save_file(&self, filename: &str, content: &[u8], mimetype: &str) -> Result<FileMetadata, Box<dyn std::error::Error>> {
-        // Generate unique filename
-        let uuid = Uuid::new_v4();
-        let extension = Path::new(filename)
-            .extension()
-            .and_then(|e| e.to_str())
-            .unwrap_or("dat");
-        
-        let unique_filename = format!("{}.{}", uuid, extension);
-        
-        // Create subdirectory based on current date
-        let now = chrono::Local::now();
-        let date_path = format!("{}/{:02}", now.format("%Y"), now.format("%m"));
-        let file_dir = self.base_path.join("attachments").join(&date_path);
-        
-        fs::create_dir_all(&file_dir)?;
-        
-        // Full file path
-        let file_path = file_dir.join(&unique_filename);
-        
-        // Write file
-        fs::write(&file_path, content)?;
-        
-        // Store metadata in database
-        let filesize = content.len() as i64;
-        let relative_path = format!("attachments/{}/{}", date_path, unique_filename);
-        
+        // Identical content is stored once and shared
+        let blob = self.blob_store()?.store_bytes(filename, content)?;
+        self.add_file(filename, mimetype, &blob)
+    }
+
+    /// Record a file whose content is already in the blob store (and referenced for it)
+    pub fn add_file(&self, filename: &str, mimetype: &str, blob: &Blob) -> Result<FileMetadata, Box<dyn std::error::Error>> {
+        let relative_path = format!("{}/{}", BLOB_DIR, blob.path);
         self.conn.execute(
-            "INSERT INTO files (filename, filepath, filesize, mimetype) VALUES (?1, ?2, ?3, ?4)",
-            params![filename, relative_path, filesize, mimetype],
+            "INSERT INTO files (filename, filepath, filesize, mimetype, hash) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![filename, relative_path, blob.size, mimetype, blob.hash],
         )?;
-        
-        let id = self.conn.last_insert_rowid();
-        
-        Ok(FileMetadata {
-            id,
-            filename: filename.to_string(),
-            filepath: relative_path,
-            filesize,
-            mimetype: mimetype.to_string(),
-            created_at: now.to_rfc3339(),
-        })
+
+        self.get_file(self.conn.last_insert_rowid())
+    }
+
+    /// Copy a file from disk into storage without loading it into memory
+    pub fn import_file(&self, source: &Path, mimetype: Option<&str>) -> Result<FileMetadata, Box<dyn std::error::Error>> {
+        if !source.is_file() {
+            return Err(format!("{} is not a file", source.display()).into());
+        }
+        let filename = source.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        let mimetype = match mimetype {
+            Some(mimetype) => mimetype.to_string(),
+            None => mime_guess::from_path(source).first_or_octet_stream().to_string(),
+        };
+        let blob = self.blob_store()?.store_file(source)?;
+        self.add_file(&filename, &mimetype, &blob)
     }
 
     pub fn get_file(&self, id: i64) -> Result<FileMetadata, Box<dyn std::error::Error>> {
         let metadata = self.conn.query_row(
-            "SELECT id, filename, filepath, filesize, mimetype, created_at FROM files WHERE id = ?1",
+            &format!("SELECT {} FROM files WHERE id = ?1", FILE_COLUMNS),
             params![id],
-            |row| {
-                Ok(FileMetadata {
-                    id: row.get(0)?,
-                    filename: row.get(1)?,
-                    filepath: row.get(2)?,
-                    filesize: row.get(3)?,
-                    mimetype: row.get(4)?,
-                    created_at: row.get(5)?,
-                })
-            },
+            file_from_row,
         )?;
         
         Ok(metadata)
@@ -103,42 +130,77 @@ impl<'a> FileManager<'a> {
 
     pub fn read_file(&self, id: i64) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
         let metadata = self.get_file(id)?;
-        let full_path = self.base_path.parent().unwrap().join(&metadata.filepath);
-        let content = fs::read(full_path)?;
+        let content = fs::read(self.full_path(&metadata))?;
         Ok(content)
     }
 
+    /// Up to `length` bytes from `offset`; shorter at the end of the file
+    pub fn read_range(&self, id: i64, offset: u64, length: u64) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        if length > MAX_RANGE {
+            return Err(format!("Read at most {} bytes at a time", MAX_RANGE).into());
+        }
+        let metadata = self.get_file(id)?;
+        let mut file = fs::File::open(self.full_path(&metadata))?;
+        file.seek(SeekFrom::Start(offset))?;
+        let mut content = Vec::new();
+        file.take(length).read_to_end(&mut content)?;
+        Ok(content)
+    }
+
+    /// Whether the file is stored under the files root, as opposed to a
+    /// file elsewhere on disk that was only recorded (ingestion keeps the
+    /// user's own path)
+    fn is_owned(&self, metadata: &FileMetadata) -> bool {
+        let path = Path::new(&metadata.filepath);
+        path.is_relative() && path.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+    }
+
+    /// Whether another file record has the same content hash
+    fn hash_shared(&self, metadata: &FileMetadata) -> Result<bool> {
+        let Some(hash) = &metadata.hash else {
+            return Ok(false);
+        };
+        self.conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM files WHERE hash = ?1 AND id != ?2)",
+            params![hash, metadata.id],
+            |row| row.get(0),
+        )
+    }
+
     pub fn delete_file(&self, id: i64) -> Result<(), Box<dyn std::error::Error>> {
         let metadata = self.get_file(id)?;
-        let full_path = self.base_path.parent().unwrap().join(&metadata.filepath);
-        
-        // Delete physical file
-        if full_path.exists() {
-            fs::remove_file(full_path)?;
+
+        // Blobs go when their last file does; older attachments are deleted
+        // outright. Files outside the root belong to the user and are kept.
+        let gone = match &metadata.hash {
+            Some(hash) if metadata.filepath.starts_with(BLOB_DIR) => self.blob_store()?.release(hash)?,
+            _ => {
+                let full_path = self.full_path(&metadata);
+                if self.is_owned(&metadata) && full_path.exists() {
+                    fs::remove_file(full_path)?;
+                }
+                true
+            }
+        };
+        // Previews are shared like the bytes they show, by every record with the same hash
+        if gone && !self.hash_shared(&metadata)? {
+            self.preview_cache()?.evict(&preview_key(&metadata))?;
         }
         
         // Delete from database
         self.conn.execute("DELETE FROM files WHERE id = ?1", params![id])?;
+        TagService::new(self.conn).delete_item_tags("file", id)?;
         
         Ok(())
     }
 
     pub fn list_files(&self, limit: i64) -> Result<Vec<FileMetadata>, Box<dyn std::error::Error>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, filename, filepath, filesize, mimetype, created_at 
-             FROM files ORDER BY created_at DESC LIMIT ?1"
-        )?;
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM files ORDER BY created_at DESC LIMIT ?1",
+            FILE_COLUMNS
+        ))?;
         
-        let files = stmt.query_map(params![limit], |row| {
-            Ok(FileMetadata {
-                id: row.get(0)?,
-                filename: row.get(1)?,
-                filepath: row.get(2)?,
-                filesize: row.get(3)?,
-                mimetype: row.get(4)?,
-                created_at: row.get(5)?,
-            })
-        })?;
+        let files = stmt.query_map(params![limit], file_from_row)?;
         
         let mut result = Vec::new();
         for file in files {
@@ -159,3 +221,45 @@ impl<'a> FileManager<'a> {
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::database::Database;
+
+    #[test]
+    fn deleting_a_recorded_file_keeps_the_users_copy_and_shared_previews() {
+        let db = Database::in_memory().unwrap();
+        let root = std::env::temp_dir().join(format!("play-files-{}", uuid::Uuid::new_v4()));
+        let files = FileManager::with_base_path(db.get_connection(), root.join("files")).unwrap();
+
+        let uploaded = files.save_file("notes.txt", b"hello", "text/plain").unwrap();
+        let hash = uploaded.hash.clone().unwrap();
+        // Ingestion records the user's own file by its absolute path
+        let source = root.join("Documents").join("notes.txt");
+        fs::create_dir_all(source.parent().unwrap()).unwrap();
+        fs::write(&source, b"hello").unwrap();
+        db.get_connection()
+            .execute(
+                "INSERT INTO files (filename, filepath, filesize, mimetype, hash) VALUES ('notes.txt', ?1, 5, 'text/plain', ?2)",
+                params![source.display().to_string(), hash],
+            )
+            .unwrap();
+        let ingested = db.get_connection().last_insert_rowid();
+        let preview = root.join("files").join(PREVIEW_DIR).join(format!("{}.json", hash));
+        fs::create_dir_all(preview.parent().unwrap()).unwrap();
+        fs::write(&preview, b"{}").unwrap();
+
+        files.delete_file(ingested).unwrap();
+        assert!(source.exists());
+        assert!(preview.exists());
+        assert!(files.full_path(&uploaded).exists());
+
+        files.delete_file(uploaded.id).unwrap();
+        assert!(!files.full_path(&uploaded).exists());
+        assert!(!preview.exists());
+        assert!(source.exists());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,656 @@
+// Folder Service - nested folders and smart collections for documents
+use crate::models::document::Document;
+use crate::models::folder::{Collection, CollectionQuery, CreateCollection, CreateFolder, Folder, FolderNode, UpdateCollection};
+use crate::services::database::Database;
+use crate::services::tags::{normalize_tag_name, TagService};
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use serde_json::json;
+use std::collections::HashMap;
+
+fn invalid(message: String) -> rusqlite::Error {
+    rusqlite::Error::InvalidParameterName(message)
+}
+
+pub struct FolderService<'a> {
+    conn: &'a Connection,
+}
+
+impl<'a> FolderService<'a> {
+    pub fn new(conn: &'a Connection) -> Self {
+        Self { conn }
+    }
+
+    fn map_folder(row: &rusqlite::Row) -> Result<Folder> {
+        Ok(Folder {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            parent_id: row.get(2)?,
+            position: row.get(3)?,
+            created_at: row.get(4)?,
+            updated_at: row.get(5)?,
+        })
+    }
+
+    // Folder CRUD operations
+
+    pub fn create_folder(&self, folder: CreateFolder) -> Result<Folder> {
+        let name = folder.name.trim();
+        if name.is_empty() {
+            return Err(invalid("Folder name cannot be empty".to_string()));
+        }
+        if let Some(parent_id) = folder.parent_id {
+            self.get_folder(parent_id)?;
+        }
+
+        let position: i64 = self.conn.query_row(
+            "SELECT COALESCE(MAX(position), -1) + 1 FROM folders WHERE parent_id IS ?1",
+            params![folder.parent_id],
+            |row| row.get(0),
+        )?;
+
+        self.conn.execute(
+            "INSERT INTO folders (name, parent_id, position) VALUES (?1, ?2, ?3)",
+            params![name, folder.parent_id, position],
+        )?;
+
+        let id = self.conn.last_insert_rowid();
+        self.get_folder(id)
+    }
+
+    pub fn get_folder(&self, id: i64) -> Result<Folder> {
+        self.conn.query_row(
+            "SELECT id, name, parent_id, position, created_at, updated_at FROM folders WHERE id = ?1",
+            params![id],
+            Self::map_folder,
+        )
+    }
+
+    pub fn get_all_folders(&self) -> Result<Vec<Folder>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, name, parent_id, position, created_at, updated_at
+             FROM folders ORDER BY parent_id, position, name",
+        )?;
+        let folders = stmt.query_map([], Self::map_folder)?;
+        folders.collect()
+    }
+
+    fn get_child_folders(&self, parent_id: Option<i64>) -> Result<Vec<Folder>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, name, parent_id, position, created_at, updated_at
+             FROM folders WHERE parent_id IS ?1 ORDER BY position, name",
+        )?;
+        let folders = stmt.query_map(params![parent_id], Self::map_folder)?;
+        folders.collect()
+    }
+
+    /// The full folder hierarchy with per-folder document counts
+    pub fn get_folder_tree(&self) -> Result<Vec<FolderNode>> {
+        let folders = self.get_all_folders()?;
+
+        let mut stmt = self.conn.prepare(
+            "SELECT folder_id, COUNT(*) FROM documents WHERE folder_id IS NOT NULL GROUP BY folder_id",
+        )?;
+        let counts: HashMap<i64, i64> = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<_>>()?;
+
+        fn build(parent_id: Option<i64>, folders: &[Folder], counts: &HashMap<i64, i64>) -> Vec<FolderNode> {
+            let mut children: Vec<&Folder> = folders.iter().filter(|f| f.parent_id == parent_id).collect();
+            children.sort_by(|a, b| a.position.cmp(&b.position).then_with(|| a.name.cmp(&b.name)));
+            children
+                .into_iter()
+                .map(|folder| FolderNode {
+                    folder: folder.clone(),
+                    document_count: counts.get(&folder.id).copied().unwrap_or(0),
+                    children: build(Some(folder.id), folders, counts),
+                })
+                .collect()
+        }
+
+        Ok(build(None, &folders, &counts))
+    }
+
+    pub fn rename_folder(&self, id: i64, name: &str) -> Result<Folder> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(invalid("Folder name cannot be empty".to_string()));
+        }
+        self.conn.execute(
+            "UPDATE folders SET name = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+            params![name, id],
+        )?;
+        self.get_folder(id)
+    }
+
+    /// Move a folder under a new parent (or to the top level) at the given
+    /// position among its new siblings; it goes last when no position is given.
+    pub fn move_folder(&self, id: i64, new_parent_id: Option<i64>, position: Option<i64>) -> Result<Folder> {
+        let folder = self.get_folder(id)?;
+
+        if let Some(parent_id) = new_parent_id {
+            self.get_folder(parent_id)?;
+            if self.get_subtree_ids(id)?.contains(&parent_id) {
+                return Err(invalid(format!(
+                    "Cannot move folder '{}' into itself or one of its subfolders",
+                    folder.name
+                )));
+            }
+        }
+
+        let mut sibling_ids: Vec<i64> = self
+            .get_child_folders(new_parent_id)?
+            .into_iter()
+            .map(|f| f.id)
+            .filter(|sibling| *sibling != id)
+            .collect();
+        let index = position
+            .map(|p| p.clamp(0, sibling_ids.len() as i64) as usize)
+            .unwrap_or(sibling_ids.len());
+        sibling_ids.insert(index, id);
+
+        let tx = self.conn.unchecked_transaction()?;
+        self.conn.execute(
+            "UPDATE folders SET parent_id = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+            params![new_parent_id, id],
+        )?;
+        self.resequence_folders(&sibling_ids)?;
+        if folder.parent_id != new_parent_id {
+            let old_siblings: Vec<i64> = self.get_child_folders(folder.parent_id)?.into_iter().map(|f| f.id).collect();
+            self.resequence_folders(&old_siblings)?;
+        }
+        tx.commit()?;
+
+        self.get_folder(id)
+    }
+
+    /// Set the order of the subfolders of `parent_id` to `ordered_ids`.
+    /// Subfolders missing from the list keep their relative order after it.
+    pub fn reorder_folders(&self, parent_id: Option<i64>, ordered_ids: &[i64]) -> Result<Vec<Folder>> {
+        let current: Vec<i64> = self.get_child_folders(parent_id)?.into_iter().map(|f| f.id).collect();
+        if let Some(stray) = ordered_ids.iter().find(|id| !current.contains(id)) {
+            return Err(invalid(format!("Folder {} is not a child of the given parent", stray)));
+        }
+
+        let mut order: Vec<i64> = ordered_ids.to_vec();
+        order.extend(current.into_iter().filter(|id| !ordered_ids.contains(id)));
+
+        let tx = self.conn.unchecked_transaction()?;
+        self.resequence_folders(&order)?;
+        tx.commit()?;
+
+        self.get_child_folders(parent_id)
+    }
+
+    /// Delete a folder. Its documents and subfolders move up to the parent
+    /// folder rather than being deleted.
+    pub fn delete_folder(&self, id: i64) -> Result<()> {
+        let folder = self.get_folder(id)?;
+
+        let mut folder_order: Vec<i64> = self
+            .get_child_folders(folder.parent_id)?
+            .into_iter()
+            .map(|f| f.id)
+            .filter(|sibling| *sibling != id)
+            .collect();
+        folder_order.extend(self.get_child_folders(Some(id))?.into_iter().map(|f| f.id));
+
+        let mut document_order: Vec<i64> = self
+            .get_documents_in_folder(folder.parent_id, false)?
+            .into_iter()
+            .map(|d| d.id)
+            .collect();
+        document_order.extend(self.get_documents_in_folder(Some(id), false)?.into_iter().map(|d| d.id));
+
+        let tx = self.conn.unchecked_transaction()?;
+        self.conn.execute(
+            "UPDATE folders SET parent_id = ?1, updated_at = CURRENT_TIMESTAMP WHERE parent_id = ?2",
+            params![folder.parent_id, id],
+        )?;
+        self.conn.execute(
+            "UPDATE documents SET folder_id = ?1 WHERE folder_id = ?2",
+            params![folder.parent_id, id],
+        )?;
+        self.resequence_folders(&folder_order)?;
+        self.resequence_documents(&document_order)?;
+        self.conn.execute("DELETE FROM folders WHERE id = ?1", params![id])?;
+        tx.commit()
+    }
+
+    /// Ids of a folder and all of its descendants
+    pub fn get_subtree_ids(&self, id: i64) -> Result<Vec<i64>> {
+        let mut stmt = self.conn.prepare(
+            "WITH RECURSIVE subtree(id) AS (
+                SELECT ?1
+                UNION
+                SELECT f.id FROM folders f JOIN subtree s ON f.parent_id = s.id
+             )
+             SELECT id FROM subtree",
+        )?;
+        let ids = stmt.query_map(params![id], |row| row.get(0))?;
+        ids.collect()
+    }
+
+    /// Slash separated path of a folder, e.g. "Projects/Alpha"
+    pub fn get_folder_path(&self, id: i64) -> Result<String> {
+        let mut names = Vec::new();
+        let mut current = Some(id);
+        while let Some(folder_id) = current {
+            let folder = self.get_folder(folder_id)?;
+            names.push(folder.name);
+            current = folder.parent_id;
+            if names.len() > 256 {
+                return Err(invalid("Folder hierarchy contains a cycle".to_string()));
+            }
+        }
+        names.reverse();
+        Ok(names.join("/"))
+    }
+
+    // Document placement
+
+    /// Documents directly in a folder (`None` = top level) in their manual
+    /// order, optionally including every subfolder.
+    pub fn get_documents_in_folder(&self, folder_id: Option<i64>, recursive: bool) -> Result<Vec<Document>> {
+        let folder_ids = match (folder_id, recursive) {
+            (Some(id), true) => self.get_subtree_ids(id)?,
+            _ => Vec::new(),
+        };
+
+        if folder_ids.is_empty() {
+            let mut stmt = self.conn.prepare(&format!(
+                "SELECT {} FROM documents WHERE folder_id IS ?1 ORDER BY position, updated_at DESC",
+                Database::DOCUMENT_COLUMNS
+            ))?;
+            let documents = stmt.query_map(params![folder_id], Database::document_from_row)?;
+            return documents.collect();
+        }
+
+        let placeholders = vec!["?"; folder_ids.len()].join(", ");
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM documents WHERE folder_id IN ({}) ORDER BY folder_id, position, updated_at DESC",
+            Database::DOCUMENT_COLUMNS,
+            placeholders
+        ))?;
+        let documents = stmt.query_map(rusqlite::params_from_iter(folder_ids.iter()), Database::document_from_row)?;
+        documents.collect()
+    }
+
+    /// Move a document into a folder (or to the top level) at the given position
+    pub fn move_document(&self, document_id: i64, folder_id: Option<i64>, position: Option<i64>) -> Result<()> {
+        let old_folder_id: Option<i64> = self
+            .conn
+            .query_row("SELECT folder_id FROM documents WHERE id = ?1", params![document_id], |row| row.get(0))
+            .optional()?
+            .ok_or_else(|| invalid(format!("Document {} not found", document_id)))?;
+        if let Some(folder_id) = folder_id {
+            self.get_folder(folder_id)
+                .optional()?
+                .ok_or_else(|| invalid(format!("Folder {} not found", folder_id)))?;
+        }
+
+        let mut sibling_ids: Vec<i64> = self
+            .get_documents_in_folder(folder_id, false)?
+            .into_iter()
+            .map(|d| d.id)
+            .filter(|id| *id != document_id)
+            .collect();
+        let index = position
+            .map(|p| p.clamp(0, sibling_ids.len() as i64) as usize)
+            .unwrap_or(sibling_ids.len());
+        sibling_ids.insert(index, document_id);
+
+        let tx = self.conn.unchecked_transaction()?;
+        // Placement is not an edit, so updated_at is left untouched; changing
+        // folders still bumps the version so stale writers conflict
+        self.conn.execute(
+            "UPDATE documents SET folder_id = ?1,
+                 version = CASE WHEN folder_id IS ?1 THEN version ELSE version + 1 END
+             WHERE id = ?2",
+            params![folder_id, document_id],
+        )?;
+        self.resequence_documents(&sibling_ids)?;
+        if old_folder_id != folder_id {
+            let old_siblings: Vec<i64> =
+                self.get_documents_in_folder(old_folder_id, false)?.into_iter().map(|d| d.id).collect();
+            self.resequence_documents(&old_siblings)?;
+        }
+        tx.commit()
+    }
+
+    /// Set the manual order of the documents in a folder
+    pub fn reorder_documents(&self, folder_id: Option<i64>, ordered_ids: &[i64]) -> Result<Vec<Document>> {
+        let current: Vec<i64> = self.get_documents_in_folder(folder_id, false)?.into_iter().map(|d| d.id).collect();
+        if let Some(stray) = ordered_ids.iter().find(|id| !current.contains(id)) {
+            return Err(invalid(format!("Document {} is not in the given folder", stray)));
+        }
+
+        let mut order: Vec<i64> = ordered_ids.to_vec();
+        order.extend(current.into_iter().filter(|id| !ordered_ids.contains(id)));
+
+        let tx = self.conn.unchecked_transaction()?;
+        self.resequence_documents(&order)?;
+        tx.commit()?;
+
+        self.get_documents_in_folder(folder_id, false)
+    }
+
+    fn resequence_folders(&self, ordered_ids: &[i64]) -> Result<()> {
+        for (position, id) in ordered_ids.iter().enumerate() {
+            self.conn.execute(
+                "UPDATE folders SET position = ?1 WHERE id = ?2",
+                params![position as i64, id],
+            )?;
+        }
+        Ok(())
+    }
+
+    fn resequence_documents(&self, ordered_ids: &[i64]) -> Result<()> {
+        for (position, id) in ordered_ids.iter().enumerate() {
+            self.conn.execute(
+                "UPDATE documents SET position = ?1 WHERE id = ?2",
+                params![position as i64, id],
+            )?;
+        }
+        Ok(())
+    }
+
+    // Smart collections
+
+    pub fn create_collection(&self, collection: CreateCollection) -> Result<Collection> {
+        let query_json = serde_json::to_string(&collection.query)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+
+        self.conn.execute(
+            "INSERT INTO collections (name, query) VALUES (?1, ?2)",
+            params![collection.name, query_json],
+        )?;
+
+        let id = self.conn.last_insert_rowid();
+        self.get_collection(id)
+    }
+
+    pub fn get_collection(&self, id: i64) -> Result<Collection> {
+        self.conn.query_row(
+            "SELECT id, name, query, created_at, updated_at FROM collections WHERE id = ?1",
+            params![id],
+            Self::map_collection,
+        )
+    }
+
+    pub fn get_all_collections(&self) -> Result<Vec<Collection>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, name, query, created_at, updated_at FROM collections ORDER BY name",
+        )?;
+        let collections = stmt.query_map([], Self::map_collection)?;
+        collections.collect()
+    }
+
+    fn map_collection(row: &rusqlite::Row) -> Result<Collection> {
+        let query_json: String = row.get(2)?;
+        let query: CollectionQuery = serde_json::from_str(&query_json)
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(2, rusqlite::types::Type::Text, Box::new(e)))?;
+
+        Ok(Collection {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            query,
+            created_at: row.get(3)?,
+            updated_at: row.get(4)?,
+        })
+    }
+
+    pub fn update_collection(&self, id: i64, update: UpdateCollection) -> Result<Collection> {
+        if let Some(name) = &update.name {
+            self.conn.execute(
+                "UPDATE collections SET name = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+                params![name, id],
+            )?;
+        }
+        if let Some(query) = &update.query {
+            let query_json = serde_json::to_string(query)
+                .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+            self.conn.execute(
+                "UPDATE collections SET query = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+                params![query_json, id],
+            )?;
+        }
+        self.get_collection(id)
+    }
+
+    pub fn delete_collection(&self, id: i64) -> Result<()> {
+        self.conn.execute("DELETE FROM collections WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    pub fn get_collection_documents(&self, id: i64) -> Result<Vec<Document>> {
+        let collection = self.get_collection(id)?;
+        self.query_documents(&collection.query)
+    }
+
+    /// Evaluate a collection query against the documents table
+    pub fn query_documents(&self, query: &CollectionQuery) -> Result<Vec<Document>> {
+        let mut clauses: Vec<String> = Vec::new();
+        let mut values: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+
+        if !query.tags.is_empty() {
+            let match_all = query.tag_mode.as_deref() == Some("all");
+            let tag_service = TagService::new(self.conn);
+            let mut tag_clauses = Vec::new();
+
+            for raw in &query.tags {
+                let tag = match normalize_tag_name(raw) {
+                    Some(name) => tag_service.find_tag_by_name(&name)?,
+                    None => None,
+                };
+                match tag {
+                    Some(tag) => {
+                        tag_clauses.push(
+                            "EXISTS (SELECT 1 FROM item_tags it JOIN tags t ON t.id = it.tag_id
+                              WHERE it.item_type = 'document' AND it.item_id = d.id
+                                AND (t.id = ? OR substr(t.name, 1, length(?) + 1) = ? || '/'))"
+                                .to_string(),
+                        );
+                        values.push(Box::new(tag.id));
+                        values.push(Box::new(tag.name.clone()));
+                        values.push(Box::new(tag.name));
+                    }
+                    // An unknown tag can never be satisfied when all tags are required
+                    None if match_all => return Ok(Vec::new()),
+                    None => {}
+                }
+            }
+
+            if tag_clauses.is_empty() {
+                return Ok(Vec::new());
+            }
+            let joiner = if match_all { " AND " } else { " OR " };
+            clauses.push(format!("({})", tag_clauses.join(joiner)));
+        }
+
+        for (column, op, value) in [
+            ("created_at", ">=", &query.created_after),
+            ("created_at", "<=", &query.created_before),
+            ("updated_at", ">=", &query.updated_after),
+            ("updated_at", "<=", &query.updated_before),
+        ] {
+            if let Some(value) = value {
+                clauses.push(format!("datetime(d.{}) {} datetime(?)", column, op));
+                values.push(Box::new(value.clone()));
+            }
+        }
+
+        if let Some(linked_type) = &query.linked_type {
+            let (source_id_clause, target_id_clause) = if query.linked_id.is_some() {
+                (" AND l.target_id = ?", " AND l.source_id = ?")
+            } else {
+                ("", "")
+            };
+            clauses.push(format!(
+                "EXISTS (SELECT 1 FROM links l
+                  WHERE (l.source_type = 'document' AND l.source_id = d.id AND l.target_type = ?{})
+                     OR (l.target_type = 'document' AND l.target_id = d.id AND l.source_type = ?{}))",
+                source_id_clause, target_id_clause
+            ));
+            values.push(Box::new(linked_type.clone()));
+            if let Some(linked_id) = query.linked_id {
+                values.push(Box::new(linked_id));
+            }
+            values.push(Box::new(linked_type.clone()));
+            if let Some(linked_id) = query.linked_id {
+                values.push(Box::new(linked_id));
+            }
+        }
+
+        if let Some(folder_id) = query.folder_id {
+            let folder_ids = self.get_subtree_ids(folder_id)?;
+            clauses.push(format!("d.folder_id IN ({})", vec!["?"; folder_ids.len()].join(", ")));
+            for id in folder_ids {
+                values.push(Box::new(id));
+            }
+        }
+
+        if let Some(text) = query.text.as_ref().filter(|t| !t.trim().is_empty()) {
+            clauses.push("(d.title LIKE ? OR d.content LIKE ?)".to_string());
+            let pattern = format!("%{}%", text.trim());
+            values.push(Box::new(pattern.clone()));
+            values.push(Box::new(pattern));
+        }
+
+        let where_clause = if clauses.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", clauses.join(" AND "))
+        };
+        let columns: Vec<String> = Database::DOCUMENT_COLUMNS
+            .split(", ")
+            .map(|c| format!("d.{}", c))
+            .collect();
+        let sql = format!(
+            "SELECT {} FROM documents d {} ORDER BY d.updated_at DESC",
+            columns.join(", "),
+            where_clause
+        );
+
+        let mut stmt = self.conn.prepare(&sql)?;
+        let params_refs: Vec<&dyn rusqlite::ToSql> = values.iter().map(|p| p.as_ref()).collect();
+        let documents = stmt.query_map(params_refs.as_slice(), Database::document_from_row)?;
+        documents.collect()
+    }
+
+    // Folder-scoped export
+
+    /// Export the documents of a folder (and, when `recursive`, its
+    /// subfolders) as Markdown ("md") or JSON ("json").
+    pub fn export_folder(&self, folder_id: i64, format: &str, recursive: bool) -> Result<String> {
+        let folder_ids = if recursive { self.get_subtree_ids(folder_id)? } else { vec![folder_id] };
+
+        let mut sections: Vec<(String, Vec<Document>)> = Vec::new();
+        for id in folder_ids {
+            let path = self.get_folder_path(id)?;
+            let documents = self.get_documents_in_folder(Some(id), false)?;
+            sections.push((path, documents));
+        }
+        sections.sort_by(|a, b| a.0.cmp(&b.0));
+
+        match format {
+            "md" => {
+                let mut md = String::new();
+                for (path, documents) in sections {
+                    md.push_str(&format!("# {}\n\n", path));
+                    for document in documents {
+                        md.push_str(&format!("## {}\n\n", document.title));
+                        if let Some(tags) = &document.tags {
+                            md.push_str(&format!("_Tags: {}_\n\n", tags));
+                        }
+                        md.push_str(document.content.trim_end());
+                        md.push_str("\n\n");
+                    }
+                }
+                Ok(md)
+            }
+            "json" => {
+                let entries: Vec<serde_json::Value> = sections
+                    .into_iter()
+                    .flat_map(|(path, documents)| {
+                        documents.into_iter().map(move |document| {
+                            json!({
+                                "folder": path,
+                                "id": document.id,
+                                "title": document.title,
+                                "content": document.content,
+                                "tags": document.tags,
+                                "version": document.version,
+                                "created_at": document.created_at,
+                                "updated_at": document.updated_at,
+                            })
+                        })
+                    })
+                    .collect();
+                serde_json::to_string_pretty(&entries)
+                    .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
+            }
+            _ => Err(invalid(format!("Unsupported format: {}", format))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::document::CreateDocument;
+
+    fn folder(folders: &FolderService, name: &str, parent_id: Option<i64>) -> i64 {
+        folders.create_folder(CreateFolder { name: name.into(), parent_id }).unwrap().id
+    }
+
+    fn document(db: &Database, folder_id: Option<i64>) -> i64 {
+        db.create_document(CreateDocument { title: "Doc".into(), content: String::new(), tags: None, folder_id })
+            .unwrap()
+            .id
+    }
+
+    #[test]
+    fn folders_move_within_the_tree_but_not_into_themselves() {
+        let db = Database::in_memory().unwrap();
+        let folders = FolderService::new(db.get_connection());
+        let projects = folder(&folders, "Projects", None);
+        let alpha = folder(&folders, "Alpha", Some(projects));
+        let notes = folder(&folders, "Notes", Some(alpha));
+        let archive = folder(&folders, "Archive", None);
+
+        assert!(folders.move_folder(projects, Some(notes), None).is_err());
+        assert!(folders.move_folder(alpha, Some(alpha), None).is_err());
+
+        folders.move_folder(alpha, Some(archive), Some(0)).unwrap();
+        assert_eq!(folders.get_folder_path(notes).unwrap(), "Archive/Alpha/Notes");
+        let tree = folders.get_folder_tree().unwrap();
+        let names: Vec<&str> = tree.iter().map(|node| node.folder.name.as_str()).collect();
+        assert_eq!(names, vec!["Projects", "Archive"]);
+        assert!(tree[0].children.is_empty());
+        assert_eq!(tree[1].children[0].folder.id, alpha);
+    }
+
+    #[test]
+    fn moving_documents_checks_ids_and_renumbers_both_folders() {
+        let db = Database::in_memory().unwrap();
+        let folders = FolderService::new(db.get_connection());
+        let inbox = folder(&folders, "Inbox", None);
+        let done = folder(&folders, "Done", None);
+        let first = document(&db, Some(inbox));
+        let second = document(&db, Some(inbox));
+        let third = document(&db, Some(inbox));
+
+        assert!(folders.move_document(9999, Some(done), None).is_err());
+        assert!(folders.move_document(first, Some(9999), None).is_err());
+        assert_eq!(db.get_document(first).unwrap().folder_id, Some(inbox));
+
+        folders.move_document(first, Some(done), None).unwrap();
+        let remaining: Vec<(i64, i64)> =
+            folders.get_documents_in_folder(Some(inbox), false).unwrap().iter().map(|d| (d.id, d.position)).collect();
+        assert_eq!(remaining, vec![(second, 0), (third, 1)]);
+
+        folders.move_document(third, Some(done), Some(0)).unwrap();
+        let moved: Vec<i64> = folders.get_documents_in_folder(Some(done), false).unwrap().iter().map(|d| d.id).collect();
+        assert_eq!(moved, vec![third, first]);
+    }
+}
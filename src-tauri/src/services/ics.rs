@@ -1,112 +1,1406 @@
 // ICS (iCalendar) Service - per prd.md calendar features
-use crate::models::event::CalendarEvent;
+//
+// RFC 5545 reader and writer: line folding, parameters (TZID, VALUE=DATE),
+// escaped text, RRULE/EXDATE, VALARM and VTIMEZONE. Events travel as VEVENT
+// and tasks as VTODO. Zoned times are
+// resolved with the IANA database, falling back to the file's own
+// VTIMEZONE definitions for names like Outlook's "Pacific Standard Time".
+use crate::models::event::{CalendarEvent, CreateEvent};
+use crate::models::task::Task;
+use crate::models::task_transfer::ImportedTask;
+use crate::services::rrule::{format_stored_datetime, parse_stored_datetime, DateStyle, RecurrenceRule};
+use crate::services::task_transfer::ExportTask;
+use crate::services::time_zones::{instant, system_zone, utc_to_zoned, zoned_to_utc};
+use chrono::{Duration, NaiveDate, NaiveDateTime, Utc};
+use chrono_tz::Tz;
+use std::collections::HashMap;
 use std::error::Error;
 
-pub struct ICSService;
+pub const UID_DOMAIN: &str = "play.local";
+const PRODID: &str = "-//Play//Play MVP//EN";
+const MAX_LINE_OCTETS: usize = 75;
+
+/// One content line: `NAME;PARAM=value:VALUE`
+#[derive(Debug, Clone, PartialEq)]
+pub struct IcsProperty {
+    pub name: String,
+    pub params: Vec<(String, String)>,
+    pub value: String,
+}
+
+impl IcsProperty {
+    pub fn new(name: &str, value: impl Into<String>) -> Self {
+        Self { name: name.to_string(), params: Vec::new(), value: value.into() }
+    }
+
+    pub fn with_param(mut self, name: &str, value: &str) -> Self {
+        self.params.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn write(&self, out: &mut String) {
+        let mut line = self.name.clone();
+        for (key, value) in &self.params {
+            line.push(';');
+            line.push_str(key);
+            line.push('=');
+            if value.contains([':', ';', ',']) {
+                line.push('"');
+                line.push_str(value);
+                line.push('"');
+            } else {
+                line.push_str(value);
+            }
+        }
+        line.push(':');
+        line.push_str(&self.value);
+        out.push_str(&fold_line(&line));
+    }
+}
+
+/// A BEGIN/END block such as VCALENDAR, VEVENT, VALARM or VTIMEZONE
+#[derive(Debug, Clone, PartialEq)]
+pub struct IcsComponent {
+    pub name: String,
+    pub properties: Vec<IcsProperty>,
+    pub components: Vec<IcsComponent>,
+}
+
+impl IcsComponent {
+    pub fn new(name: &str) -> Self {
+        Self { name: name.to_string(), properties: Vec::new(), components: Vec::new() }
+    }
+
+    pub fn property(&self, name: &str) -> Option<&IcsProperty> {
+        self.properties.iter().find(|p| p.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn all<'c>(&'c self, name: &'c str) -> impl Iterator<Item = &'c IcsProperty> + 'c {
+        self.properties.iter().filter(move |p| p.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn children<'c>(&'c self, name: &'c str) -> impl Iterator<Item = &'c IcsComponent> + 'c {
+        self.components.iter().filter(move |c| c.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Unescaped TEXT value, None when missing or blank
+    pub fn text(&self, name: &str) -> Option<String> {
+        self.property(name)
+            .map(|p| unescape_text(&p.value))
+            .filter(|text| !text.trim().is_empty())
+    }
+
+    pub fn push(&mut self, property: IcsProperty) {
+        self.properties.push(property);
+    }
+
+    pub fn push_text(&mut self, name: &str, text: &str) {
+        self.properties.push(IcsProperty::new(name, escape_text(text)));
+    }
+
+    pub fn write(&self, out: &mut String) {
+        out.push_str(&format!("BEGIN:{}\r\n", self.name));
+        for property in &self.properties {
+            property.write(out);
+        }
+        for component in &self.components {
+            component.write(out);
+        }
+        out.push_str(&format!("END:{}\r\n", self.name));
+    }
+}
+
+/// Join folded lines: a line starting with a space or tab continues the previous one
+pub fn unfold(content: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for raw in content.trim_start_matches('\u{feff}').split('\n') {
+        let raw = raw.strip_suffix('\r').unwrap_or(raw);
+        match raw.strip_prefix([' ', '\t']) {
+            Some(rest) if !lines.is_empty() => lines.last_mut().unwrap().push_str(rest),
+            _ => lines.push(raw.to_string()),
+        }
+    }
+    lines.retain(|line| !line.trim().is_empty());
+    lines
+}
+
+/// Split a line into at most 75-octet pieces without breaking UTF-8 characters
+pub fn fold_line(line: &str) -> String {
+    let mut out = String::with_capacity(line.len() + 8);
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > MAX_LINE_OCTETS {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+    out
+}
+
+pub fn parse_content_line(line: &str) -> Option<IcsProperty> {
+    let mut in_quotes = false;
+    let mut value_start = None;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            ':' if !in_quotes => {
+                value_start = Some(i);
+                break;
+            }
+            _ => {}
+        }
+    }
+    let colon = value_start?;
+    let (head, value) = (&line[..colon], &line[colon + 1..]);
+
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    in_quotes = false;
+    for c in head.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            ';' if !in_quotes => parts.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    parts.push(current);
+
+    let mut parts = parts.into_iter();
+    let name = parts.next()?.trim().to_ascii_uppercase();
+    if name.is_empty() {
+        return None;
+    }
+    let params = parts
+        .filter_map(|p| {
+            let (key, value) = p.split_once('=')?;
+            Some((key.trim().to_ascii_uppercase(), value.to_string()))
+        })
+        .collect();
+    Some(IcsProperty { name, params, value: value.to_string() })
+}
+
+/// Parse the component tree. Unknown or malformed lines are skipped so one
+/// odd vendor extension does not sink the whole file.
+pub fn parse_components(content: &str) -> Result<Vec<IcsComponent>, String> {
+    let mut roots = Vec::new();
+    let mut stack: Vec<IcsComponent> = Vec::new();
+
+    for line in unfold(content) {
+        let property = match parse_content_line(&line) {
+            Some(property) => property,
+            None => continue,
+        };
+        match property.name.as_str() {
+            "BEGIN" => stack.push(IcsComponent::new(&property.value.trim().to_ascii_uppercase())),
+            "END" => {
+                let name = property.value.trim().to_ascii_uppercase();
+                if !stack.iter().any(|c| c.name == name) {
+                    continue;
+                }
+                // Close anything left open inside this component too
+                while let Some(component) = stack.pop() {
+                    let done = component.name == name;
+                    match stack.last_mut() {
+                        Some(parent) => parent.components.push(component),
+                        None => roots.push(component),
+                    }
+                    if done {
+                        break;
+                    }
+                }
+            }
+            _ => {
+                if let Some(component) = stack.last_mut() {
+                    component.properties.push(property);
+                }
+            }
+        }
+    }
+    while let Some(component) = stack.pop() {
+        match stack.last_mut() {
+            Some(parent) => parent.components.push(component),
+            None => roots.push(component),
+        }
+    }
+
+    if roots.iter().any(|c| c.name == "VCALENDAR") {
+        Ok(roots)
+    } else {
+        Err("Not an iCalendar file: no VCALENDAR found".to_string())
+    }
+}
+
+pub fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+pub fn unescape_text(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// Split a TEXT list such as CATEGORIES on its unescaped commas
+pub fn split_text_list(value: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut current = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                current.push(c);
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+            }
+            ',' => items.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    items.push(current);
+    items.into_iter().map(|item| unescape_text(item.trim())).filter(|item| !item.is_empty()).collect()
+}
+
+/// A DATE or DATE-TIME value as written in the file
+#[derive(Debug, Clone, PartialEq)]
+pub enum IcsDateTime {
+    Date(NaiveDate),
+    Floating(NaiveDateTime),
+    Utc(NaiveDateTime),
+    Zoned(NaiveDateTime, String),
+}
+
+fn parse_value(value: &str, is_date: bool, tzid: Option<&str>) -> Option<IcsDateTime> {
+    let value = value.trim();
+    if is_date || value.len() == 8 {
+        return NaiveDate::parse_from_str(value, "%Y%m%d").ok().map(IcsDateTime::Date);
+    }
+    let utc = value.ends_with('Z');
+    let value = value.trim_end_matches('Z');
+    let at = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M"))
+        .ok()?;
+    Some(match (utc, tzid) {
+        (true, _) => IcsDateTime::Utc(at),
+        (false, Some(tzid)) => IcsDateTime::Zoned(at, tzid.trim_matches('"').to_string()),
+        (false, None) => IcsDateTime::Floating(at),
+    })
+}
+
+/// All values of a (possibly comma separated) DATE / DATE-TIME property
+pub fn parse_datetimes(property: &IcsProperty) -> Vec<IcsDateTime> {
+    let is_date = property.param("VALUE").map(|v| v.eq_ignore_ascii_case("DATE")).unwrap_or(false);
+    property
+        .value
+        .split(',')
+        .filter_map(|value| parse_value(value, is_date, property.param("TZID")))
+        .collect()
+}
+
+/// ISO 8601 duration as used by DURATION and TRIGGER: `P1W`, `-PT15M`, `P1DT2H30M`
+pub fn parse_duration(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let (sign, rest) = match value.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let rest = rest.strip_prefix('P')?;
+    let mut seconds = 0i64;
+    let mut number = String::new();
+    let mut in_time = false;
+    for c in rest.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'T' => in_time = true,
+            'W' | 'D' | 'H' | 'M' | 'S' => {
+                let n: i64 = number.parse().ok()?;
+                number.clear();
+                seconds += n * match (c, in_time) {
+                    ('W', false) => 7 * 86400,
+                    ('D', false) => 86400,
+                    ('H', true) => 3600,
+                    ('M', true) => 60,
+                    ('S', true) => 1,
+                    _ => return None,
+                };
+            }
+            _ => return None,
+        }
+    }
+    if !number.is_empty() {
+        return None;
+    }
+    Some(Duration::seconds(sign * seconds))
+}
+
+pub fn format_duration(duration: Duration) -> String {
+    let total = duration.num_seconds();
+    let sign = if total < 0 { "-" } else { "" };
+    let mut rest = total.abs();
+    let days = rest / 86400;
+    rest %= 86400;
+    let mut out = format!("{}P", sign);
+    if days > 0 {
+        out.push_str(&format!("{}D", days));
+    }
+    if rest > 0 || days == 0 {
+        out.push('T');
+        let (hours, minutes, seconds) = (rest / 3600, rest % 3600 / 60, rest % 60);
+        if hours > 0 {
+            out.push_str(&format!("{}H", hours));
+        }
+        if minutes > 0 || (hours == 0 && seconds == 0) {
+            out.push_str(&format!("{}M", minutes));
+        }
+        if seconds > 0 {
+            out.push_str(&format!("{}S", seconds));
+        }
+    }
+    out
+}
+
+/// Windows zone names used by Outlook and Exchange
+const WINDOWS_ZONES: [(&str, &str); 24] = [
+    ("Dateline Standard Time", "Etc/GMT+12"),
+    ("Hawaiian Standard Time", "Pacific/Honolulu"),
+    ("Alaskan Standard Time", "America/Anchorage"),
+    ("Pacific Standard Time", "America/Los_Angeles"),
+    ("Mountain Standard Time", "America/Denver"),
+    ("US Mountain Standard Time", "America/Phoenix"),
+    ("Central Standard Time", "America/Chicago"),
+    ("Eastern Standard Time", "America/New_York"),
+    ("Atlantic Standard Time", "America/Halifax"),
+    ("E. South America Standard Time", "America/Sao_Paulo"),
+    ("UTC", "UTC"),
+    ("GMT Standard Time", "Europe/London"),
+    ("W. Europe Standard Time", "Europe/Berlin"),
+    ("Romance Standard Time", "Europe/Paris"),
+    ("Central Europe Standard Time", "Europe/Budapest"),
+    ("Central European Standard Time", "Europe/Warsaw"),
+    ("E. Europe Standard Time", "Europe/Chisinau"),
+    ("FLE Standard Time", "Europe/Kiev"),
+    ("Russian Standard Time", "Europe/Moscow"),
+    ("India Standard Time", "Asia/Kolkata"),
+    ("China Standard Time", "Asia/Shanghai"),
+    ("Tokyo Standard Time", "Asia/Tokyo"),
+    ("AUS Eastern Standard Time", "Australia/Sydney"),
+    ("New Zealand Standard Time", "Pacific/Auckland"),
+];
+
+/// Look up a TZID in the IANA database, accepting Windows names and the
+/// "/mozilla.org/20050126_1/Europe/Berlin" form older Thunderbird writes
+pub fn iana_zone(tzid: &str) -> Option<Tz> {
+    let tzid = tzid.trim().trim_matches('"');
+    if let Ok(tz) = tzid.parse::<Tz>() {
+        return Some(tz);
+    }
+    if let Some((_, iana)) = WINDOWS_ZONES.iter().find(|(name, _)| name.eq_ignore_ascii_case(tzid)) {
+        return iana.parse().ok();
+    }
+    let segments: Vec<&str> = tzid.split('/').filter(|s| !s.is_empty()).collect();
+    (2..=3)
+        .filter(|n| segments.len() >= *n)
+        .find_map(|n| segments[segments.len() - n..].join("/").parse().ok())
+}
+
+/// One STANDARD or DAYLIGHT block of a VTIMEZONE
+#[derive(Debug, Clone)]
+struct Observance {
+    start: NaiveDateTime,
+    offset_from: i64,
+    offset_to: i64,
+    rule: Option<RecurrenceRule>,
+    rdates: Vec<NaiveDateTime>,
+}
+
+impl Observance {
+    fn parse(component: &IcsComponent) -> Option<Self> {
+        let offset = |name: &str| component.property(name).and_then(|p| parse_utc_offset(&p.value));
+        let start = match parse_datetimes(component.property("DTSTART")?).first()? {
+            IcsDateTime::Date(date) => date.and_hms_opt(0, 0, 0)?,
+            IcsDateTime::Floating(at) | IcsDateTime::Utc(at) | IcsDateTime::Zoned(at, _) => *at,
+        };
+        Some(Self {
+            start,
+            offset_from: offset("TZOFFSETFROM")?,
+            offset_to: offset("TZOFFSETTO")?,
+            rule: component.property("RRULE").and_then(|p| RecurrenceRule::parse(&p.value).ok()),
+            rdates: component
+                .all("RDATE")
+                .flat_map(parse_datetimes)
+                .filter_map(|d| match d {
+                    IcsDateTime::Floating(at) | IcsDateTime::Utc(at) | IcsDateTime::Zoned(at, _) => Some(at),
+                    IcsDateTime::Date(date) => date.and_hms_opt(0, 0, 0),
+                })
+                .collect(),
+        })
+    }
+
+    /// The latest onset of this observance at or before `local`
+    fn last_onset(&self, local: NaiveDateTime) -> Option<NaiveDateTime> {
+        let from_rule = self
+            .rule
+            .as_ref()
+            .and_then(|rule| rule.iter(self.start).take(5000).take_while(|onset| *onset <= local).last());
+        let from_dates = self.rdates.iter().copied().filter(|d| *d <= local).max();
+        let start = Some(self.start).filter(|s| *s <= local);
+        [from_rule, from_dates, start].into_iter().flatten().max()
+    }
+}
+
+/// "+0100", "-0530" or "+013000" in seconds
+fn parse_utc_offset(value: &str) -> Option<i64> {
+    let value = value.trim();
+    let (sign, digits) = match value.split_at(1) {
+        ("+", rest) => (1, rest),
+        ("-", rest) => (-1, rest),
+        _ => return None,
+    };
+    if !(digits.len() == 4 || digits.len() == 6) || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let hours: i64 = digits[0..2].parse().ok()?;
+    let minutes: i64 = digits[2..4].parse().ok()?;
+    let seconds: i64 = digits.get(4..6).map(|s| s.parse().unwrap_or(0)).unwrap_or(0);
+    Some(sign * (hours * 3600 + minutes * 60 + seconds))
+}
+
+/// Time zones available while reading one VCALENDAR
+#[derive(Debug)]
+pub struct TimeZones {
+    custom: HashMap<String, Vec<Observance>>,
+    /// Zone for floating times and for custom zones with no IANA name
+    default: Tz,
+}
+
+impl Default for TimeZones {
+    fn default() -> Self {
+        Self { custom: HashMap::new(), default: Tz::UTC }
+    }
+}
+
+impl TimeZones {
+    pub fn from_calendar(calendar: &IcsComponent, default: Tz) -> Self {
+        let mut custom = HashMap::new();
+        for zone in calendar.children("VTIMEZONE") {
+            if let Some(tzid) = zone.property("TZID") {
+                let observances: Vec<Observance> = zone.components.iter().filter_map(Observance::parse).collect();
+                if !observances.is_empty() {
+                    custom.insert(tzid.value.trim().to_string(), observances);
+                }
+            }
+        }
+        Self { custom, default }
+    }
+
+    pub fn to_utc(&self, tzid: &str, local: NaiveDateTime) -> Option<NaiveDateTime> {
+        if let Some(tz) = iana_zone(tzid) {
+            return zoned_to_utc(tz, local);
+        }
+        let observances = self.custom.get(tzid.trim().trim_matches('"'))?;
+        let offset = observances
+            .iter()
+            .filter_map(|o| o.last_onset(local).map(|onset| (onset, o.offset_to)))
+            .max_by_key(|(onset, _)| *onset)
+            .map(|(_, offset)| offset)
+            .or_else(|| observances.iter().min_by_key(|o| o.start).map(|o| o.offset_from))?;
+        Some(local - Duration::seconds(offset))
+    }
+
+    /// A value in the form events are stored in: dates stay dates, times
+    /// become UTC ("YYYY-MM-DDTHH:MM:SSZ")
+    pub fn to_stored(&self, value: &IcsDateTime) -> Result<String, String> {
+        let utc = match value {
+            IcsDateTime::Date(date) => return Ok(date.format("%Y-%m-%d").to_string()),
+            IcsDateTime::Floating(at) => {
+                zoned_to_utc(self.default, *at).ok_or_else(|| format!("Invalid time in {}", self.default.name()))?
+            }
+            IcsDateTime::Utc(at) => *at,
+            IcsDateTime::Zoned(at, tzid) => self.to_utc(tzid, *at).ok_or_else(|| format!("Unknown time zone '{}'", tzid))?,
+        };
+        Ok(format_stored_datetime(utc, DateStyle::Utc))
+    }
+
+    /// The IANA zone a value's wall time belongs to; None for dates
+    pub fn zone_of(&self, value: &IcsDateTime) -> Option<Tz> {
+        match value {
+            IcsDateTime::Date(_) => None,
+            IcsDateTime::Floating(_) => Some(self.default),
+            IcsDateTime::Utc(_) => Some(Tz::UTC),
+            IcsDateTime::Zoned(_, tzid) => Some(iana_zone(tzid).unwrap_or(self.default)),
+        }
+    }
+}
+
+/// A VEVENT read from a file, with the identity needed to match it on re-import
+#[derive(Debug, Clone)]
+pub struct ImportedEvent {
+    pub uid: Option<String>,
+    pub sequence: i64,
+    /// LAST-MODIFIED as "YYYY-MM-DD HH:MM:SS" UTC
+    pub last_modified: Option<String>,
+    /// RECURRENCE-ID of an overridden occurrence, in stored form
+    pub recurrence_id: Option<String>,
+    pub event: CreateEvent,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct IcsImport {
+    pub events: Vec<ImportedEvent>,
+    /// Events or properties that were skipped or simplified
+    pub warnings: Vec<String>,
+}
+
+/// Task statuses with a VTODO STATUS of their own. Other statuses (custom
+/// board columns) are written as IN-PROCESS with the status itself in
+/// X-PLAY-STATUS, so they come back unchanged.
+const TODO_STATUSES: [(&str, &str); 4] =
+    [("todo", "NEEDS-ACTION"), ("in_progress", "IN-PROCESS"), ("done", "COMPLETED"), ("cancelled", "CANCELLED")];
+const STATUS_PROPERTY: &str = "X-PLAY-STATUS";
+
+/// VTODO STATUS for a task status, and whether it needs X-PLAY-STATUS too
+pub fn todo_status(status: &str) -> (&'static str, bool) {
+    match TODO_STATUSES.iter().find(|(task, _)| *task == status) {
+        Some((_, todo)) => (todo, false),
+        None => ("IN-PROCESS", true),
+    }
+}
+
+/// Task status for a VTODO; `custom` is X-PLAY-STATUS
+pub fn task_status(status: Option<&str>, custom: Option<&str>, completed: bool) -> String {
+    if let Some(custom) = custom.map(str::trim).filter(|c| !c.is_empty()) {
+        return custom.to_string();
+    }
+    let status = status.map(str::trim).unwrap_or_default();
+    match TODO_STATUSES.iter().find(|(_, todo)| todo.eq_ignore_ascii_case(status)) {
+        Some((task, _)) => task.to_string(),
+        None if completed => "done".to_string(),
+        None => "todo".to_string(),
+    }
+}
+
+/// VTODO PRIORITY runs from 1 (highest) to 9 (lowest), 0 meaning undefined
+pub fn todo_priority(priority: &str) -> u8 {
+    match priority {
+        "high" => 1,
+        "low" => 9,
+        _ => 5,
+    }
+}
+
+pub fn task_priority(priority: u8) -> &'static str {
+    match priority {
+        1..=4 => "high",
+        6..=9 => "low",
+        _ => "medium",
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct IcsTaskImport {
+    /// Tasks in file order; `parent` points at a task in this list
+    pub tasks: Vec<ImportedTask>,
+    pub warnings: Vec<String>,
+}
+
+pub struct ICSService {
+    /// Zone for floating times in imported files
+    zone: Tz,
+}
 
 impl ICSService {
     pub fn new() -> Self {
-        Self
+        Self::with_zone(system_zone())
+    }
+
+    pub fn with_zone(zone: Tz) -> Self {
+        Self { zone }
     }
 
     pub fn export_to_ics(&self, events: &[CalendarEvent]) -> Result<String, Box<dyn Error>> {
-        let mut ics = String::from("BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//Play//Play MVP//EN\r\n");
-        
+        let mut calendar = IcsComponent::new("VCALENDAR");
+        calendar.push(IcsProperty::new("VERSION", "2.0"));
+        calendar.push(IcsProperty::new("PRODID", PRODID));
+        calendar.push(IcsProperty::new("CALSCALE", "GREGORIAN"));
+        calendar.push(IcsProperty::new("METHOD", "PUBLISH"));
+
+        let stamp = Utc::now().naive_utc().format("%Y%m%dT%H%M%SZ").to_string();
         for event in events {
-            ics.push_str("BEGIN:VEVENT\r\n");
-            ics.push_str(&format!("UID:{}\r\n", event.id));
-            ics.push_str(&format!("SUMMARY:{}\r\n", escape_ics_text(&event.title)));
-            
-            if let Some(desc) = &event.description {
-                ics.push_str(&format!("DESCRIPTION:{}\r\n", escape_ics_text(desc)));
-            }
-            
-            // Convert ISO datetime to iCal format (YYYYMMDDTHHMMSSZ)
-            let start = parse_ics_datetime(&event.start_time)?;
-            let end = parse_ics_datetime(&event.end_time)?;
-            
-            ics.push_str(&format!("DTSTART:{}\r\n", start));
-            ics.push_str(&format!("DTEND:{}\r\n", end));
-            ics.push_str("END:VEVENT\r\n");
-        }
-        
-        ics.push_str("END:VCALENDAR\r\n");
-        Ok(ics)
-    }
-
-    pub fn import_from_ics(&self, ics_content: &str) -> Result<Vec<CalendarEvent>, Box<dyn Error>> {
-        // Simple ICS parser for MVP
-        let mut events = Vec::new();
-        let lines: Vec<&str> = ics_content.lines().collect();
-        
-        let mut current_event: Option<CalendarEvent> = None;
-        let mut in_event = false;
-        
-        for line in lines {
-            let line = line.trim();
-            
-            if line == "BEGIN:VEVENT" {
-                in_event = true;
-                current_event = Some(CalendarEvent {
-                    id: 0, // Will be assigned by database
-                    title: String::new(),
-                    description: None,
-                    start_time: String::new(),
-                    end_time: String::new(),
-                    reminder_time: None,
-                    recurrence: None,
-                    created_at: chrono::Utc::now().to_rfc3339(),
-                });
-            } else if line == "END:VEVENT" {
-                if let Some(event) = current_event.take() {
-                    events.push(event);
+            calendar.components.push(Self::event_component(event, events, &stamp, self.zone)?);
+        }
+
+        let mut out = String::new();
+        calendar.write(&mut out);
+        Ok(out)
+    }
+
+    /// Stable, globally unique UID for an event created in Play
+    pub fn event_uid(event: &CalendarEvent) -> String {
+        let created: String = event.created_at.chars().filter(|c| c.is_ascii_digit()).collect();
+        format!("play-event-{}-{}@{}", event.id, created, UID_DOMAIN)
+    }
+
+    /// Stable, globally unique UID for a task created in Play
+    pub fn task_uid(task: &Task) -> String {
+        let created: String = task.created_at.chars().filter(|c| c.is_ascii_digit()).collect();
+        format!("play-task-{}-{}@{}", task.id, created, UID_DOMAIN)
+    }
+
+    pub fn export_tasks_to_ics(&self, tasks: &[ExportTask]) -> Result<String, Box<dyn Error>> {
+        let mut calendar = IcsComponent::new("VCALENDAR");
+        calendar.push(IcsProperty::new("VERSION", "2.0"));
+        calendar.push(IcsProperty::new("PRODID", PRODID));
+        calendar.push(IcsProperty::new("CALSCALE", "GREGORIAN"));
+        calendar.push(IcsProperty::new("METHOD", "PUBLISH"));
+
+        let stamp = Utc::now().naive_utc().format("%Y%m%dT%H%M%SZ").to_string();
+        for task in tasks {
+            calendar.components.push(Self::todo_component(task, tasks, &stamp, self.zone)?);
+        }
+
+        let mut out = String::new();
+        calendar.write(&mut out);
+        Ok(out)
+    }
+
+    /// Due times are written in the task's zone, or `floating` when it has
+    /// none. A recurring task starts (DTSTART) when it is due, as RRULE needs one.
+    fn todo_component(item: &ExportTask, tasks: &[ExportTask], stamp: &str, floating: Tz) -> Result<IcsComponent, Box<dyn Error>> {
+        let task = &item.task;
+        let mut vtodo = IcsComponent::new("VTODO");
+        vtodo.push(IcsProperty::new("UID", Self::task_uid(task)));
+        vtodo.push(IcsProperty::new("DTSTAMP", stamp));
+        if let Some(created) = export_datetime(&task.created_at, false, None, Tz::UTC) {
+            vtodo.push(IcsProperty::new("CREATED", created.value));
+        }
+        if let Some(modified) = export_datetime(&task.updated_at, false, None, Tz::UTC) {
+            vtodo.push(IcsProperty::new("LAST-MODIFIED", modified.value));
+        }
+        vtodo.push_text("SUMMARY", &task.title);
+        if let Some(description) = task.description.as_deref().filter(|d| !d.is_empty()) {
+            vtodo.push_text("DESCRIPTION", description);
+        }
+
+        let (status, custom) = todo_status(&task.status);
+        vtodo.push(IcsProperty::new("STATUS", status));
+        if custom {
+            vtodo.push_text(STATUS_PROPERTY, &task.status);
+        }
+        if task.status == "done" {
+            if let Some(completed) = export_datetime(&task.updated_at, false, None, Tz::UTC) {
+                vtodo.push(IcsProperty::new("COMPLETED", completed.value));
+            }
+            vtodo.push(IcsProperty::new("PERCENT-COMPLETE", "100"));
+        }
+        vtodo.push(IcsProperty::new("PRIORITY", todo_priority(&task.priority).to_string()));
+
+        let zone = task.time_zone.as_deref().and_then(|zone| zone.parse::<Tz>().ok());
+        let due = match task.due_date.as_deref() {
+            Some(due) => Some(
+                export_datetime(due, false, zone, floating)
+                    .ok_or_else(|| format!("Invalid due date '{}' on task {}", due, task.id))?,
+            ),
+            None => None,
+        };
+        if let Some(due) = &due {
+            vtodo.push(due.named("DUE"));
+        }
+        if let Some(rule) = task.recurrence.as_deref().filter(|r| !r.trim().is_empty()) {
+            if let Some(due) = &due {
+                vtodo.push(due.named("DTSTART"));
+                let zone = if due.is_date { None } else { Some(zone.unwrap_or(floating)) };
+                vtodo.push(IcsProperty::new("RRULE", export_rule(rule, zone)?));
+            }
+        }
+        if let Some(estimate) = task.estimate.filter(|minutes| *minutes > 0) {
+            // ESTIMATED-DURATION as proposed for VTODO by the iCalendar task extensions
+            vtodo.push(IcsProperty::new("ESTIMATED-DURATION", format_duration(Duration::minutes(estimate))));
+        }
+        if !item.tags.is_empty() {
+            vtodo.push(IcsProperty::new("CATEGORIES", item.tags.iter().map(|tag| escape_text(tag)).collect::<Vec<_>>().join(",")));
+        }
+        if let Some(parent) = task.parent_id.and_then(|id| tasks.iter().find(|t| t.task.id == id)) {
+            vtodo.push(IcsProperty::new("RELATED-TO", Self::task_uid(&parent.task)).with_param("RELTYPE", "PARENT"));
+        }
+
+        if let Some(reminder) = task.reminder_time.as_deref().and_then(|r| instant(r, zone.unwrap_or(floating))) {
+            let mut alarm = IcsComponent::new("VALARM");
+            alarm.push(IcsProperty::new("ACTION", "DISPLAY"));
+            alarm.push_text("DESCRIPTION", &task.title);
+            alarm.push(IcsProperty::new("TRIGGER", reminder.format("%Y%m%dT%H%M%SZ").to_string()).with_param("VALUE", "DATE-TIME"));
+            vtodo.components.push(alarm);
+        }
+
+        Ok(vtodo)
+    }
+
+    /// Read the VTODOs of a file as tasks; subtasks point at their parent
+    /// through RELATED-TO
+    pub fn import_tasks_from_ics(&self, ics_content: &str) -> Result<IcsTaskImport, Box<dyn Error>> {
+        let mut import = IcsTaskImport::default();
+        let mut uids = Vec::new();
+        let mut parents = Vec::new();
+        for calendar in parse_components(ics_content)?.iter().filter(|c| c.name == "VCALENDAR") {
+            let zones = TimeZones::from_calendar(calendar, self.zone);
+            for vtodo in calendar.children("VTODO") {
+                let (task, uid, parent) = read_todo(vtodo, &zones, &mut import.warnings);
+                import.tasks.push(task);
+                uids.push(uid);
+                parents.push(parent);
+            }
+        }
+
+        for (index, parent) in parents.into_iter().enumerate() {
+            let Some(parent) = parent else { continue };
+            match uids.iter().position(|uid| uid.as_deref() == Some(parent.as_str())) {
+                Some(position) if position != index => import.tasks[index].parent = Some(position),
+                _ => import.warnings.push(format!("The parent of '{}' is not in the file", import.tasks[index].title)),
+            }
+        }
+        Ok(import)
+    }
+
+    /// Edited occurrences share the UID of their series and name the
+    /// occurrence they replace with RECURRENCE-ID. Values without a zone of
+    /// their own are read in `floating`.
+    fn event_component(
+        event: &CalendarEvent,
+        series: &[CalendarEvent],
+        stamp: &str,
+        floating: Tz,
+    ) -> Result<IcsComponent, Box<dyn Error>> {
+        let mut vevent = IcsComponent::new("VEVENT");
+        let parent = event
+            .series_id
+            .zip(event.occurrence_date.as_deref())
+            .and_then(|(id, date)| Some((series.iter().find(|s| s.id == id)?, date)));
+        match parent {
+            Some((parent, date)) => {
+                vevent.push(IcsProperty::new("UID", Self::event_uid(parent)));
+                if let Some(occurrence) = export_datetime(date, parent.all_day, event_zone(parent), floating) {
+                    vevent.push(occurrence.named("RECURRENCE-ID"));
+                }
+            }
+            None => vevent.push(IcsProperty::new("UID", Self::event_uid(event))),
+        }
+        vevent.push(IcsProperty::new("DTSTAMP", stamp));
+        if let Some(created) = export_datetime(&event.created_at, false, None, Tz::UTC) {
+            vevent.push(IcsProperty::new("CREATED", created.value));
+        }
+
+        let zone = event_zone(event);
+        let start = export_datetime(&event.start_time, event.all_day, zone, floating)
+            .ok_or_else(|| format!("Invalid start time '{}' on event {}", event.start_time, event.id))?;
+        let end = export_datetime(&event.end_time, event.all_day, zone, floating)
+            .ok_or_else(|| format!("Invalid end time '{}' on event {}", event.end_time, event.id))?;
+        vevent.push(start.named("DTSTART"));
+        vevent.push(end.named("DTEND"));
+
+        vevent.push_text("SUMMARY", &event.title);
+        if let Some(description) = event.description.as_deref().filter(|d| !d.is_empty()) {
+            vevent.push_text("DESCRIPTION", description);
+        }
+        if let Some(location) = event.location.as_deref().filter(|l| !l.is_empty()) {
+            vevent.push_text("LOCATION", location);
+        }
+
+        if let Some(rule) = event.recurrence.as_deref().filter(|r| !r.trim().is_empty()) {
+            vevent.push(IcsProperty::new("RRULE", export_rule(rule, if event.all_day { None } else { Some(zone.unwrap_or(floating)) })?));
+            let exdates: Vec<ExportedTime> = event
+                .exdates
+                .iter()
+                .filter_map(|d| export_datetime(d, event.all_day, zone, floating))
+                .collect();
+            if let Some(first) = exdates.first() {
+                let mut property = first.named("EXDATE");
+                property.value = exdates.iter().map(|d| d.value.as_str()).collect::<Vec<_>>().join(",");
+                vevent.push(property);
+            }
+        }
+
+        if let Some(reminder) = event.reminder_time.as_deref() {
+            let zone = zone.unwrap_or(floating);
+            let offset = instant(reminder, zone)
+                .zip(instant(&event.start_time, zone))
+                .map(|(reminder, start)| reminder - start);
+            if let Some(offset) = offset {
+                let mut alarm = IcsComponent::new("VALARM");
+                alarm.push(IcsProperty::new("ACTION", "DISPLAY"));
+                alarm.push_text("DESCRIPTION", &event.title);
+                alarm.push(IcsProperty::new("TRIGGER", format_duration(offset)));
+                vevent.components.push(alarm);
+            }
+        }
+
+        Ok(vevent)
+    }
+
+    pub fn import_from_ics(&self, ics_content: &str) -> Result<IcsImport, Box<dyn Error>> {
+        let mut import = IcsImport::default();
+        for calendar in parse_components(ics_content)?.iter().filter(|c| c.name == "VCALENDAR") {
+            let zones = TimeZones::from_calendar(calendar, self.zone);
+            let mut cancelled = Vec::new();
+            for vevent in calendar.children("VEVENT") {
+                match read_event(vevent, &zones, &mut import.warnings) {
+                    Ok((event, false)) => import.events.push(event),
+                    Ok((event, true)) => cancelled.push(event),
+                    Err(message) => import.warnings.push(message),
                 }
-                in_event = false;
-            } else if in_event {
-                if let Some(event) = current_event.as_mut() {
-                    if let Some(summary) = line.strip_prefix("SUMMARY:") {
-                        event.title = summary.to_string();
-                    } else if let Some(desc) = line.strip_prefix("DESCRIPTION:") {
-                        event.description = Some(desc.to_string());
-                    } else if let Some(start) = line.strip_prefix("DTSTART:") {
-                        event.start_time = parse_ics_to_iso(start)?;
-                    } else if let Some(end) = line.strip_prefix("DTEND:") {
-                        event.end_time = parse_ics_to_iso(end)?;
+            }
+
+            // A cancelled occurrence is removed from its series; edited ones
+            // stay in `events` with their RECURRENCE-ID
+            let removed: Vec<(String, String)> = cancelled
+                .iter()
+                .filter_map(|e| Some((e.uid.clone()?, e.recurrence_id.clone()?)))
+                .collect();
+            for (uid, date) in removed {
+                let master = import
+                    .events
+                    .iter_mut()
+                    .find(|e| e.recurrence_id.is_none() && e.uid.as_deref() == Some(uid.as_str()));
+                if let Some(master) = master {
+                    if !master.event.exdates.contains(&date) {
+                        master.event.exdates.push(date);
                     }
                 }
             }
         }
-        
-        Ok(events)
+        Ok(import)
     }
 }
 
-fn escape_ics_text(text: &str) -> String {
-    text.replace(',', "\\,")
-        .replace(';', "\\;")
-        .replace('\n', "\\n")
+impl Default for ICSService {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-fn parse_ics_datetime(iso_datetime: &str) -> Result<String, Box<dyn Error>> {
-    let dt = chrono::DateTime::parse_from_rfc3339(iso_datetime)?;
-    Ok(dt.format("%Y%m%dT%H%M%SZ").to_string())
-}
-
-fn parse_ics_to_iso(ics_datetime: &str) -> Result<String, Box<dyn Error>> {
-    // Parse YYYYMMDDTHHMMSSZ format to ISO8601
-    let clean = ics_datetime.trim_end_matches('Z');
-    
-    if clean.len() >= 15 {
-        let year = &clean[0..4];
-        let month = &clean[4..6];
-        let day = &clean[6..8];
-        let hour = &clean[9..11];
-        let minute = &clean[11..13];
-        let second = &clean[13..15];
-        
-        Ok(format!("{}-{}-{}T{}:{}:{}Z", year, month, day, hour, minute, second))
-    } else {
-        Err("Invalid iCal datetime format".into())
+/// The zone an event's wall times (and RRULE) are in; None for all-day events
+fn event_zone(event: &CalendarEvent) -> Option<Tz> {
+    if event.all_day {
+        return None;
+    }
+    event.time_zone.as_deref().and_then(|zone| zone.parse().ok())
+}
+
+/// A stored time written out as DATE (all-day), zoned or UTC DATE-TIME
+struct ExportedTime {
+    is_date: bool,
+    tzid: Option<String>,
+    value: String,
+}
+
+impl ExportedTime {
+    fn named(&self, name: &str) -> IcsProperty {
+        let property = IcsProperty::new(name, self.value.clone());
+        match (&self.tzid, self.is_date) {
+            (_, true) => property.with_param("VALUE", "DATE"),
+            (Some(tzid), false) => property.with_param("TZID", tzid),
+            (None, false) => property,
+        }
+    }
+}
+
+/// Times of events with a zone other than UTC are written as wall time with
+/// an IANA TZID, so readers repeat them at the same local time across DST
+fn export_datetime(value: &str, all_day: bool, zone: Option<Tz>, floating: Tz) -> Option<ExportedTime> {
+    let (at, style) = parse_stored_datetime(value)?;
+    if all_day || style == DateStyle::Date {
+        return Some(ExportedTime { is_date: true, tzid: None, value: at.format("%Y%m%d").to_string() });
+    }
+    let utc = instant(value, zone.unwrap_or(floating))?;
+    Some(match zone.filter(|zone| *zone != Tz::UTC) {
+        Some(zone) => ExportedTime {
+            is_date: false,
+            tzid: Some(zone.name().to_string()),
+            value: utc_to_zoned(zone, utc).format("%Y%m%dT%H%M%S").to_string(),
+        },
+        None => ExportedTime { is_date: false, tzid: None, value: utc.format("%Y%m%dT%H%M%SZ").to_string() },
+    })
+}
+
+/// RFC 5545 wants UNTIL in UTC for timed events and as a date for all-day
+/// ones; stored rules keep it as wall time in the event's zone
+fn export_rule(rule: &str, zone: Option<Tz>) -> Result<String, String> {
+    let mut parsed = RecurrenceRule::parse(rule)?;
+    let until = parsed.until.take();
+    let mut out = parsed.to_string();
+    if let Some(until) = until {
+        let value = match zone {
+            None => until.format("%Y%m%d").to_string(),
+            Some(zone) => zoned_to_utc(zone, until).unwrap_or(until).format("%Y%m%dT%H%M%SZ").to_string(),
+        };
+        out.push_str(&format!(";UNTIL={}", value));
+    }
+    Ok(out)
+}
+
+/// RRULE as stored: a UTC UNTIL becomes wall time in the event's zone
+fn import_rule(value: &str, zone: Option<Tz>) -> Result<String, String> {
+    let mut rule = RecurrenceRule::parse(value)?;
+    let until_utc = value
+        .split(';')
+        .any(|part| part.trim().to_ascii_uppercase().starts_with("UNTIL=") && part.trim().ends_with('Z'));
+    if let (true, Some(until)) = (until_utc, rule.until) {
+        rule.until = Some(utc_to_zoned(zone.unwrap_or(Tz::UTC), until));
+    }
+    Ok(rule.to_string())
+}
+
+/// Turn one VEVENT into an event; the flag is set for STATUS:CANCELLED
+fn read_event(vevent: &IcsComponent, zones: &TimeZones, warnings: &mut Vec<String>) -> Result<(ImportedEvent, bool), String> {
+    let title = vevent.text("SUMMARY").unwrap_or_else(|| "(No title)".to_string());
+    let uid = vevent.property("UID").map(|p| p.value.trim().to_string()).filter(|u| !u.is_empty());
+
+    let start = vevent
+        .property("DTSTART")
+        .and_then(|p| parse_datetimes(p).into_iter().next())
+        .ok_or_else(|| format!("Skipped '{}': it has no valid DTSTART", title))?;
+    let all_day = matches!(start, IcsDateTime::Date(_));
+    let zone = zones.zone_of(&start);
+    let start_time = zones.to_stored(&start).map_err(|e| format!("Skipped '{}': {}", title, e))?;
+    let (start_at, _) = parse_stored_datetime(&start_time).ok_or_else(|| format!("Skipped '{}': invalid start", title))?;
+
+    let end = vevent.property("DTEND").and_then(|p| parse_datetimes(p).into_iter().next());
+    let end_time = match end {
+        Some(end) => zones.to_stored(&end).map_err(|e| format!("Skipped '{}': {}", title, e))?,
+        None => {
+            let duration = vevent
+                .property("DURATION")
+                .and_then(|p| parse_duration(&p.value))
+                .unwrap_or_else(|| if all_day { Duration::days(1) } else { Duration::zero() });
+            let end_at = start_at + duration;
+            if all_day {
+                end_at.format("%Y-%m-%d").to_string()
+            } else {
+                format_stored_datetime(end_at, DateStyle::Utc)
+            }
+        }
+    };
+
+    let recurrence = match vevent.property("RRULE") {
+        Some(rule) => match import_rule(&rule.value, zone) {
+            Ok(rule) => Some(rule),
+            Err(e) => {
+                warnings.push(format!("'{}' imported as a single event: {}", title, e));
+                None
+            }
+        },
+        None => None,
+    };
+    if vevent.property("RDATE").is_some() {
+        warnings.push(format!("Extra dates (RDATE) of '{}' were not imported", title));
+    }
+    let exdates = vevent
+        .all("EXDATE")
+        .flat_map(parse_datetimes)
+        .filter_map(|d| zones.to_stored(&d).ok())
+        .collect();
+
+    // Reminders are instants; an all-day event starts at midnight in the default zone
+    let reminder_time = vevent.children("VALARM").find_map(|alarm| {
+        let trigger = alarm.property("TRIGGER")?;
+        let at = if trigger.param("VALUE").map(|v| v.eq_ignore_ascii_case("DATE-TIME")).unwrap_or(false) {
+            let at = parse_datetimes(trigger).into_iter().next()?;
+            parse_stored_datetime(&zones.to_stored(&at).ok()?)?.0
+        } else {
+            let base = match trigger.param("RELATED") {
+                Some(related) if related.eq_ignore_ascii_case("END") => parse_stored_datetime(&end_time)?.0,
+                _ => start_at,
+            };
+            let base = if all_day { zoned_to_utc(zones.default, base)? } else { base };
+            base + parse_duration(&trigger.value)?
+        };
+        Some(format_stored_datetime(at, DateStyle::Utc))
+    });
+
+    let recurrence_id = vevent
+        .property("RECURRENCE-ID")
+        .and_then(|p| parse_datetimes(p).into_iter().next())
+        .and_then(|d| zones.to_stored(&d).ok());
+    let last_modified = vevent
+        .property("LAST-MODIFIED")
+        .and_then(|p| parse_datetimes(p).into_iter().next())
+        .and_then(|d| match d {
+            IcsDateTime::Utc(at) | IcsDateTime::Floating(at) => Some(at.format("%Y-%m-%d %H:%M:%S").to_string()),
+            _ => None,
+        });
+    let cancelled = vevent
+        .property("STATUS")
+        .map(|s| s.value.trim().eq_ignore_ascii_case("CANCELLED"))
+        .unwrap_or(false);
+
+    Ok((
+        ImportedEvent {
+            uid,
+            sequence: vevent.property("SEQUENCE").and_then(|p| p.value.trim().parse().ok()).unwrap_or(0),
+            last_modified,
+            recurrence_id,
+            event: CreateEvent {
+                title,
+                description: vevent.text("DESCRIPTION"),
+                start_time,
+                end_time,
+                reminder_time,
+                recurrence,
+                location: vevent.text("LOCATION"),
+                all_day,
+                exdates,
+                time_zone: zone.map(|zone| zone.name().to_string()),
+            },
+        },
+        cancelled,
+    ))
+}
+
+/// A VTODO value in the forms task importers use: dates stay dates, zoned
+/// and UTC times become UTC and floating times stay wall time, which
+/// `create_task` reads in the user's zone
+fn todo_time(value: &IcsDateTime, zones: &TimeZones) -> Option<String> {
+    match value {
+        IcsDateTime::Floating(at) => Some(at.format("%Y-%m-%dT%H:%M").to_string()),
+        _ => zones.to_stored(value).ok(),
     }
 }
 
+/// Turn one VTODO into a task, with its UID and its parent's UID
+fn read_todo(vtodo: &IcsComponent, zones: &TimeZones, warnings: &mut Vec<String>) -> (ImportedTask, Option<String>, Option<String>) {
+    let title = vtodo.text("SUMMARY").unwrap_or_else(|| "(No title)".to_string());
+    let uid = vtodo.property("UID").map(|p| p.value.trim().to_string()).filter(|u| !u.is_empty());
+
+    let due = vtodo.property("DUE").and_then(|p| parse_datetimes(p).into_iter().next());
+    let due_date = due.as_ref().and_then(|due| todo_time(due, zones));
+    if due.is_some() && due_date.is_none() {
+        warnings.push(format!("The due date of '{}' could not be read", title));
+    }
+    let time_zone = match &due {
+        Some(IcsDateTime::Zoned(_, tzid)) => iana_zone(tzid).map(|zone| zone.name().to_string()),
+        _ => None,
+    };
+
+    let completed = vtodo.property("COMPLETED").is_some()
+        || vtodo.property("PERCENT-COMPLETE").map(|p| p.value.trim() == "100").unwrap_or(false);
+    let status = task_status(
+        vtodo.property("STATUS").map(|p| p.value.as_str()),
+        vtodo.text(STATUS_PROPERTY).as_deref(),
+        completed,
+    );
+    let priority = vtodo.property("PRIORITY").and_then(|p| p.value.trim().parse().ok()).unwrap_or(0);
+
+    let recurrence = match vtodo.property("RRULE") {
+        Some(rule) => match import_rule(&rule.value, due.as_ref().and_then(|due| zones.zone_of(due))) {
+            Ok(rule) => Some(rule),
+            Err(e) => {
+                warnings.push(format!("'{}' imported without its recurrence: {}", title, e));
+                None
+            }
+        },
+        None => None,
+    };
+    let estimate = vtodo
+        .property("ESTIMATED-DURATION")
+        .and_then(|p| parse_duration(&p.value))
+        .map(|duration| duration.num_minutes())
+        .filter(|minutes| *minutes > 0);
+
+    // Relative reminders are taken from the due time
+    let reminder_time = vtodo.children("VALARM").find_map(|alarm| {
+        let trigger = alarm.property("TRIGGER")?;
+        if trigger.param("VALUE").map(|v| v.eq_ignore_ascii_case("DATE-TIME")).unwrap_or(false) {
+            return todo_time(&parse_datetimes(trigger).into_iter().next()?, zones);
+        }
+        let (base, style) = parse_stored_datetime(due_date.as_deref()?)?;
+        let style = if style == DateStyle::Utc { style } else { DateStyle::Local { seconds: false } };
+        Some(format_stored_datetime(base + parse_duration(&trigger.value)?, style))
+    });
+
+    let tags = vtodo.all("CATEGORIES").flat_map(|p| split_text_list(&p.value)).collect();
+    let parent = vtodo
+        .all("RELATED-TO")
+        .find(|p| p.param("RELTYPE").map(|r| r.eq_ignore_ascii_case("PARENT")).unwrap_or(true))
+        .map(|p| p.value.trim().to_string())
+        .filter(|p| !p.is_empty());
+
+    let task = ImportedTask {
+        title,
+        description: vtodo.text("DESCRIPTION"),
+        status,
+        priority: task_priority(priority).to_string(),
+        due_date,
+        recurrence,
+        reminder_time,
+        time_zone,
+        estimate,
+        tags,
+        ..Default::default()
+    };
+    (task, uid, parent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OUTLOOK: &str = "BEGIN:VCALENDAR\r\n\
+PRODID:-//Microsoft Corporation//Outlook 16.0 MIMEDIR//EN\r\n\
+VERSION:2.0\r\n\
+BEGIN:VTIMEZONE\r\n\
+TZID:Custom Eastern\r\n\
+BEGIN:STANDARD\r\n\
+DTSTART:16011104T020000\r\n\
+RRULE:FREQ=YEARLY;BYDAY=1SU;BYMONTH=11\r\n\
+TZOFFSETFROM:-0400\r\n\
+TZOFFSETTO:-0500\r\n\
+END:STANDARD\r\n\
+BEGIN:DAYLIGHT\r\n\
+DTSTART:16010311T020000\r\n\
+RRULE:FREQ=YEARLY;BYDAY=2SU;BYMONTH=3\r\n\
+TZOFFSETFROM:-0500\r\n\
+TZOFFSETTO:-0400\r\n\
+END:DAYLIGHT\r\n\
+END:VTIMEZONE\r\n\
+BEGIN:VEVENT\r\n\
+UID:040000008200E00074C5B7101A82E00800000000\r\n\
+SUMMARY:Planning\\, Q3 review\r\n\
+DESCRIPTION:Agenda:\\n1. Budget\\; 2. Hiring and a long line that has been folded by the \r\n\
+\x20client at seventy five octets\r\n\
+LOCATION:Room \"A\"\r\n\
+DTSTART;TZID=\"Custom Eastern\":20240710T093000\r\n\
+DTEND;TZID=Custom Eastern:20240710T103000\r\n\
+RRULE:FREQ=WEEKLY;BYDAY=WE;UNTIL=20240807T133000Z\r\n\
+EXDATE;TZID=Custom Eastern:20240724T093000\r\n\
+SEQUENCE:2\r\n\
+BEGIN:VALARM\r\n\
+ACTION:DISPLAY\r\n\
+TRIGGER:-PT15M\r\n\
+END:VALARM\r\n\
+END:VEVENT\r\n\
+BEGIN:VEVENT\r\n\
+UID:holiday@example.com\r\n\
+SUMMARY:Offsite\r\n\
+DTSTART;VALUE=DATE:20240801\r\n\
+DTEND;VALUE=DATE:20240803\r\n\
+END:VEVENT\r\n\
+END:VCALENDAR\r\n";
+
+    #[test]
+    fn reads_outlook_files() {
+        let import = ICSService::with_zone(Tz::UTC).import_from_ics(OUTLOOK).unwrap();
+        assert!(import.warnings.is_empty(), "{:?}", import.warnings);
+        let meeting = &import.events[0];
+        assert_eq!(meeting.sequence, 2);
+        assert_eq!(meeting.event.title, "Planning, Q3 review");
+        assert_eq!(
+            meeting.event.description.as_deref(),
+            Some("Agenda:\n1. Budget; 2. Hiring and a long line that has been folded by the client at seventy five octets")
+        );
+        assert_eq!(meeting.event.location.as_deref(), Some("Room \"A\""));
+        // EDT is UTC-4 in July
+        assert_eq!(meeting.event.start_time, "2024-07-10T13:30:00Z");
+        assert_eq!(meeting.event.end_time, "2024-07-10T14:30:00Z");
+        assert_eq!(meeting.event.exdates, vec!["2024-07-24T13:30:00Z".to_string()]);
+        assert_eq!(meeting.event.reminder_time.as_deref(), Some("2024-07-10T13:15:00Z"));
+        // The custom zone has no IANA name, so the series is kept in the default zone
+        assert_eq!(meeting.event.time_zone.as_deref(), Some("UTC"));
+        assert!(meeting.event.recurrence.as_deref().unwrap().starts_with("FREQ=WEEKLY;BYDAY=WE;UNTIL="));
+
+        let offsite = &import.events[1].event;
+        assert!(offsite.all_day);
+        assert_eq!(offsite.time_zone, None);
+        assert_eq!((offsite.start_time.as_str(), offsite.end_time.as_str()), ("2024-08-01", "2024-08-03"));
+    }
+
+    #[test]
+    fn resolves_iana_and_windows_zones() {
+        let at = NaiveDateTime::parse_from_str("2024-01-15 09:00", "%Y-%m-%d %H:%M").unwrap();
+        let zones = TimeZones::default();
+        let berlin = zones.to_utc("Europe/Berlin", at).unwrap();
+        assert_eq!(berlin.format("%H:%M").to_string(), "08:00");
+        assert_eq!(zones.to_utc("/mozilla.org/20050126_1/Europe/Berlin", at), Some(berlin));
+        assert_eq!(zones.to_utc("Pacific Standard Time", at).unwrap().format("%H:%M").to_string(), "17:00");
+        // 02:30 does not exist on the spring-forward night in New York
+        let gap = NaiveDateTime::parse_from_str("2024-03-10 02:30", "%Y-%m-%d %H:%M").unwrap();
+        assert_eq!(zones.to_utc("America/New_York", gap).unwrap().format("%H:%M").to_string(), "07:30");
+        assert!(zones.to_utc("Mars/Olympus", at).is_none());
+    }
+
+    #[test]
+    fn round_trips_through_export() {
+        let event = CalendarEvent {
+            id: 7,
+            title: "Launch; party, with \\ friends".into(),
+            description: Some("Bring snacks\nand drinks. ".repeat(8)),
+            start_time: "2024-08-01".into(),
+            end_time: "2024-08-02".into(),
+            reminder_time: Some("2024-07-31T18:00".into()),
+            recurrence: Some("FREQ=YEARLY;UNTIL=20300801T000000".into()),
+            location: Some("Café Zürich".into()),
+            all_day: true,
+            exdates: vec!["2025-08-01".into()],
+            series_id: None,
+            occurrence_date: None,
+            subscription_id: None,
+            time_zone: None,
+            created_at: "2024-06-01 10:00:00".into(),
+        };
+        let ics = ICSService::with_zone(Tz::UTC).export_to_ics(std::slice::from_ref(&event)).unwrap();
+        assert!(ics.split("\r\n").all(|line| line.len() <= MAX_LINE_OCTETS));
+        assert!(ics.contains("UID:play-event-7-20240601100000@play.local\r\n"));
+        assert!(ics.contains("DTSTAMP:"));
+        assert!(ics.contains("DTSTART;VALUE=DATE:20240801\r\n"));
+        assert!(ics.contains("RRULE:FREQ=YEARLY;UNTIL=20300801\r\n"));
+        assert!(ics.contains("TRIGGER:-PT6H\r\n"));
+
+        let back = &ICSService::with_zone(Tz::UTC).import_from_ics(&ics).unwrap().events[0].event;
+        assert_eq!(back.title, event.title);
+        assert_eq!(back.description, event.description);
+        assert_eq!(back.location, event.location);
+        assert_eq!((back.start_time.as_str(), back.end_time.as_str()), ("2024-08-01", "2024-08-02"));
+        assert_eq!(back.exdates, event.exdates);
+        assert_eq!(back.reminder_time.as_deref(), Some("2024-07-31T18:00:00Z"));
+        assert_eq!(back.recurrence, event.recurrence);
+    }
+
+    #[test]
+    fn zoned_series_keep_their_wall_time() {
+        let event = CalendarEvent {
+            id: 3,
+            title: "Standup".into(),
+            description: None,
+            start_time: "2024-03-08T14:00:00Z".into(),
+            end_time: "2024-03-08T14:15:00Z".into(),
+            reminder_time: Some("2024-03-08T13:55:00Z".into()),
+            recurrence: Some("FREQ=DAILY;UNTIL=20240312T090000".into()),
+            location: None,
+            all_day: false,
+            exdates: vec!["2024-03-11T13:00:00Z".into()],
+            series_id: None,
+            occurrence_date: None,
+            subscription_id: None,
+            time_zone: Some("America/New_York".into()),
+            created_at: "2024-03-01 10:00:00".into(),
+        };
+        let ics = ICSService::with_zone(Tz::UTC).export_to_ics(std::slice::from_ref(&event)).unwrap();
+        assert!(ics.contains("DTSTART;TZID=America/New_York:20240308T090000\r\n"));
+        // 09:00 on the Monday after the clocks change is 13:00 UTC
+        assert!(ics.contains("EXDATE;TZID=America/New_York:20240311T090000\r\n"));
+        assert!(ics.contains("RRULE:FREQ=DAILY;UNTIL=20240312T130000Z\r\n"));
+        assert!(ics.contains("TRIGGER:-PT5M\r\n"));
+
+        let back = &ICSService::with_zone(Tz::UTC).import_from_ics(&ics).unwrap().events[0].event;
+        assert_eq!(back.start_time, event.start_time);
+        assert_eq!(back.exdates, event.exdates);
+        assert_eq!(back.reminder_time, event.reminder_time);
+        assert_eq!(back.time_zone, event.time_zone);
+        assert_eq!(back.recurrence, event.recurrence);
+    }
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("-PT15M"), Some(Duration::minutes(-15)));
+        assert_eq!(parse_duration("P1W"), Some(Duration::weeks(1)));
+        assert_eq!(parse_duration("P1DT2H30M"), Some(Duration::minutes(1590)));
+        assert_eq!(parse_duration("PT"), Some(Duration::zero()));
+        assert_eq!(parse_duration("P1H"), None);
+        assert_eq!(format_duration(Duration::minutes(-1590)), "-P1DT2H30M");
+        assert_eq!(format_duration(Duration::zero()), "PT0M");
+    }
+
+    #[test]
+    fn tasks_round_trip_as_vtodo() {
+        let task = |id: i64, status: &str, priority: &str| Task {
+            id,
+            title: format!("Task {}", id),
+            description: Some("notes, with a comma".into()),
+            status: status.into(),
+            priority: priority.into(),
+            due_date: Some("2024-03-11T21:00:00Z".into()),
+            reminder_time: Some("2024-03-11T20:30:00Z".into()),
+            time_zone: Some("America/New_York".into()),
+            parent_id: None,
+            recurrence: None,
+            series_id: None,
+            occurrence: 1,
+            estimate: Some(90),
+            position: 1.0,
+            version: 1,
+            open_blockers: Vec::new(),
+            created_at: "2024-03-01 10:00:00".into(),
+            updated_at: "2024-03-02 11:00:00".into(),
+        };
+        let statuses = [("todo", "high"), ("in_progress", "medium"), ("done", "low"), ("cancelled", "medium"), ("review", "high")];
+        let mut items: Vec<ExportTask> = statuses
+            .iter()
+            .enumerate()
+            .map(|(i, (status, priority))| ExportTask { task: task(i as i64 + 1, status, priority), tags: Vec::new(), checklist: Vec::new() })
+            .collect();
+        items[0].tags = vec!["work".into(), "a,b".into()];
+        items[0].task.recurrence = Some("FREQ=WEEKLY".into());
+        items[1].task.parent_id = Some(1);
+        items[2].task.due_date = Some("2024-03-12".into());
+
+        let ics = ICSService::with_zone(Tz::UTC).export_tasks_to_ics(&items).unwrap();
+        assert!(ics.contains("DUE;TZID=America/New_York:20240311T170000\r\n"));
+        assert!(ics.contains("DTSTART;TZID=America/New_York:20240311T170000\r\n"));
+        assert!(ics.contains("STATUS:COMPLETED\r\nCOMPLETED:20240302T110000Z\r\nPERCENT-COMPLETE:100\r\n"));
+        assert!(ics.contains("STATUS:IN-PROCESS\r\nX-PLAY-STATUS:review\r\n"));
+        assert!(ics.contains("CATEGORIES:work,a\\,b\r\n"));
+
+        let import = ICSService::with_zone(Tz::UTC).import_tasks_from_ics(&ics).unwrap();
+        assert!(import.warnings.is_empty(), "{:?}", import.warnings);
+        for (back, item) in import.tasks.iter().zip(&items) {
+            assert_eq!((back.status.as_str(), back.priority.as_str()), (item.task.status.as_str(), item.task.priority.as_str()));
+            assert_eq!(back.due_date, item.task.due_date);
+            assert_eq!(back.estimate, Some(90));
+            assert_eq!(back.description, item.task.description);
+        }
+        let first = &import.tasks[0];
+        assert_eq!(first.time_zone.as_deref(), Some("America/New_York"));
+        assert_eq!(first.reminder_time.as_deref(), Some("2024-03-11T20:30:00Z"));
+        assert_eq!(first.recurrence.as_deref(), Some("FREQ=WEEKLY"));
+        assert_eq!(first.tags, vec!["work", "a,b"]);
+        assert_eq!(import.tasks[1].parent, Some(0));
+        assert_eq!(import.tasks[2].time_zone, None);
+    }
+
+    #[test]
+    fn reads_vtodos_from_other_apps() {
+        let ics = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VTODO\r\nUID:a\r\nSUMMARY:Pay rent\r\nDUE:20240601T090000\r\n\
+PRIORITY:3\r\nPERCENT-COMPLETE:100\r\nBEGIN:VALARM\r\nACTION:DISPLAY\r\nTRIGGER:-PT1H\r\nEND:VALARM\r\nEND:VTODO\r\n\
+BEGIN:VTODO\r\nUID:b\r\nSUMMARY:Sub\r\nPRIORITY:0\r\nRELATED-TO:missing\r\nEND:VTODO\r\nEND:VCALENDAR\r\n";
+        let import = ICSService::with_zone(Tz::UTC).import_tasks_from_ics(ics).unwrap();
+        let rent = &import.tasks[0];
+        assert_eq!((rent.status.as_str(), rent.priority.as_str()), ("done", "high"));
+        assert_eq!(rent.due_date.as_deref(), Some("2024-06-01T09:00"));
+        assert_eq!(rent.reminder_time.as_deref(), Some("2024-06-01T08:00"));
+        assert_eq!((import.tasks[1].status.as_str(), import.tasks[1].priority.as_str()), ("todo", "medium"));
+        assert_eq!(import.tasks[1].parent, None);
+        assert_eq!(import.warnings.len(), 1);
+    }
+}
@@ -0,0 +1,294 @@
+// ICS Import - bring a calendar file into the events table, idempotently
+//
+// Every imported event is recorded in `event_sources` with its source name,
+// UID and RECURRENCE-ID. Importing the same source again matches on those,
+// updates what changed and skips what did not; SEQUENCE and LAST-MODIFIED
+// keep an older copy of the file from overwriting newer data.
+use crate::models::event::{CalendarEvent, CreateEvent, IcsImportOptions, IcsImportSummary};
+use crate::services::database::Database;
+use crate::services::ics::{ICSService, ImportedEvent};
+use crate::services::recurring_events::RecurringEventService;
+use crate::services::time_zones::user_zone;
+use rusqlite::{params, OptionalExtension, Result};
+use std::collections::HashSet;
+
+/// Source name used when the caller does not give one
+pub const DEFAULT_SOURCE: &str = "import";
+/// Prefix of the sources calendar subscriptions import into
+pub const SUBSCRIPTION_SOURCE: &str = "subscription:";
+
+fn invalid(message: String) -> rusqlite::Error {
+    rusqlite::Error::InvalidParameterName(message)
+}
+
+/// What was stored for an event the last time its source was imported
+#[derive(Debug, Clone)]
+struct StoredSource {
+    event_id: i64,
+    sequence: i64,
+    last_modified: Option<String>,
+}
+
+/// True when the file holds an older revision than the one already stored
+fn is_stale(imported: &ImportedEvent, stored: &StoredSource) -> bool {
+    if imported.sequence != stored.sequence {
+        return imported.sequence < stored.sequence;
+    }
+    match (&imported.last_modified, &stored.last_modified) {
+        // Both are "YYYY-MM-DD HH:MM:SS" in UTC, so they compare as text
+        (Some(imported), Some(stored)) => imported < stored,
+        _ => false,
+    }
+}
+
+fn has_changed(event: &CreateEvent, stored: &CalendarEvent) -> bool {
+    event.title != stored.title
+        || event.description != stored.description
+        || event.start_time != stored.start_time
+        || event.end_time != stored.end_time
+        || event.reminder_time != stored.reminder_time
+        || event.recurrence.as_deref().filter(|r| !r.is_empty()) != stored.recurrence.as_deref().filter(|r| !r.is_empty())
+        || event.location != stored.location
+        || event.all_day != stored.all_day
+        || event.exdates != stored.exdates
+        || event.time_zone != stored.time_zone
+}
+
+/// Events without a UID are identified by their start and title instead
+fn identity(imported: &ImportedEvent) -> (String, String) {
+    let uid = imported
+        .uid
+        .clone()
+        .unwrap_or_else(|| format!("no-uid:{}:{}", imported.event.start_time, imported.event.title));
+    (uid, imported.recurrence_id.clone().unwrap_or_default())
+}
+
+pub struct IcsImportService<'a> {
+    db: &'a Database,
+}
+
+impl<'a> IcsImportService<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    fn find_source(&self, source: &str, uid: &str, recurrence_id: &str) -> Result<Option<StoredSource>> {
+        self.db
+            .get_connection()
+            .query_row(
+                "SELECT event_id, sequence, last_modified FROM event_sources
+                 WHERE source = ?1 AND uid = ?2 AND recurrence_id = ?3",
+                params![source, uid, recurrence_id],
+                |row| {
+                    Ok(StoredSource {
+                        event_id: row.get(0)?,
+                        sequence: row.get(1)?,
+                        last_modified: row.get(2)?,
+                    })
+                },
+            )
+            .optional()
+    }
+
+    fn record_source(&self, event_id: i64, source: &str, uid: &str, imported: &ImportedEvent) -> Result<()> {
+        self.db.get_connection().execute(
+            "INSERT OR REPLACE INTO event_sources (event_id, source, uid, recurrence_id, sequence, last_modified)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                event_id,
+                source,
+                uid,
+                imported.recurrence_id.clone().unwrap_or_default(),
+                imported.sequence,
+                imported.last_modified
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Import `ics_content`, creating new events and updating the ones this
+    /// source created before
+    pub fn import(&self, ics_content: &str, options: &IcsImportOptions) -> Result<IcsImportSummary> {
+        let source = options
+            .source
+            .as_deref()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .unwrap_or(DEFAULT_SOURCE);
+        if source.starts_with(SUBSCRIPTION_SOURCE) {
+            return Err(invalid(format!("'{}' is reserved for calendar subscriptions", source)));
+        }
+        self.import_source(ics_content, source, options.remove_missing, None)
+    }
+
+    /// Import into `source`; events created for a subscription are marked with its id
+    pub fn import_source(
+        &self,
+        ics_content: &str,
+        source: &str,
+        remove_missing: bool,
+        subscription_id: Option<i64>,
+    ) -> Result<IcsImportSummary> {
+        // Floating times in the file are read in the user's zone
+        let import = ICSService::with_zone(user_zone(self.db.get_connection()))
+            .import_from_ics(ics_content)
+            .map_err(|e| invalid(format!("Could not read the calendar: {}", e)))?;
+        let mut summary = IcsImportSummary {
+            warnings: import.warnings,
+            ..Default::default()
+        };
+
+        let conn = self.db.get_connection();
+        let tx = conn.unchecked_transaction()?;
+        let recurring = RecurringEventService::new(self.db);
+        let mut seen = HashSet::new();
+
+        // Series before their edited occurrences, which are attached to them
+        let (occurrences, events): (Vec<_>, Vec<_>) = import.events.into_iter().partition(|e| e.recurrence_id.is_some());
+        for imported in events.into_iter().chain(occurrences) {
+            let (uid, recurrence_id) = identity(&imported);
+            if !seen.insert((uid.clone(), recurrence_id.clone())) {
+                summary.warnings.push(format!("Skipped a second copy of '{}'", imported.event.title));
+                continue;
+            }
+
+            match self.find_source(source, &uid, &recurrence_id)? {
+                Some(stored) => {
+                    let current = self.db.get_event(stored.event_id)?;
+                    if is_stale(&imported, &stored) || !has_changed(&imported.event, &current) {
+                        summary.unchanged += 1;
+                    } else {
+                        self.db.replace_event(stored.event_id, imported.event.clone())?;
+                        summary.updated += 1;
+                    }
+                    if !is_stale(&imported, &stored) {
+                        self.record_source(stored.event_id, source, &uid, &imported)?;
+                    }
+                }
+                None => {
+                    let series = if imported.recurrence_id.is_some() {
+                        self.find_source(source, &uid, "")?
+                    } else {
+                        None
+                    };
+                    let created = match (series, &imported.recurrence_id) {
+                        (Some(series), Some(date)) => recurring.attach_occurrence(series.event_id, date, imported.event.clone())?,
+                        _ => self.db.create_event(imported.event.clone())?,
+                    };
+                    if subscription_id.is_some() {
+                        conn.execute("UPDATE events SET subscription_id = ?1 WHERE id = ?2", params![subscription_id, created.id])?;
+                    }
+                    self.record_source(created.id, source, &uid, &imported)?;
+                    summary.created += 1;
+                }
+            }
+        }
+
+        if remove_missing {
+            let mut stmt = conn.prepare("SELECT event_id, uid, recurrence_id FROM event_sources WHERE source = ?1")?;
+            let recorded = stmt
+                .query_map(params![source], |row| Ok((row.get::<_, i64>(0)?, (row.get::<_, String>(1)?, row.get::<_, String>(2)?))))?
+                .collect::<Result<Vec<_>>>()?;
+            for (event_id, key) in recorded {
+                if seen.contains(&key) {
+                    continue;
+                }
+                // The series may already be gone, taking its edited occurrences with it
+                let exists: bool = conn.query_row("SELECT EXISTS(SELECT 1 FROM events WHERE id = ?1)", params![event_id], |row| row.get(0))?;
+                if exists {
+                    self.db.delete_event(event_id)?;
+                    summary.removed += 1;
+                } else {
+                    conn.execute("DELETE FROM event_sources WHERE event_id = ?1", params![event_id])?;
+                }
+            }
+        }
+
+        tx.commit()?;
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn imported(sequence: i64, last_modified: Option<&str>) -> ImportedEvent {
+        ImportedEvent {
+            uid: None,
+            sequence,
+            last_modified: last_modified.map(str::to_string),
+            recurrence_id: None,
+            event: CreateEvent {
+                title: "Review".into(),
+                description: None,
+                start_time: "2024-05-06T09:00".into(),
+                end_time: "2024-05-06T10:00".into(),
+                reminder_time: None,
+                recurrence: None,
+                location: None,
+                all_day: false,
+                exdates: Vec::new(),
+                time_zone: None,
+            },
+        }
+    }
+
+    #[test]
+    fn older_revisions_are_stale() {
+        let stored = StoredSource { event_id: 1, sequence: 2, last_modified: Some("2024-05-01 10:00:00".into()) };
+        assert!(is_stale(&imported(1, Some("2024-06-01 10:00:00")), &stored));
+        assert!(!is_stale(&imported(3, None), &stored));
+        assert!(is_stale(&imported(2, Some("2024-04-30 23:59:59")), &stored));
+        assert!(!is_stale(&imported(2, Some("2024-05-01 10:00:00")), &stored));
+        assert!(!is_stale(&imported(2, None), &stored));
+    }
+
+    #[test]
+    fn events_without_uid_are_keyed_by_start_and_title() {
+        let mut event = imported(0, None);
+        assert_eq!(identity(&event), ("no-uid:2024-05-06T09:00:Review".to_string(), String::new()));
+        event.uid = Some("abc@example.com".into());
+        event.recurrence_id = Some("2024-05-13T09:00".into());
+        assert_eq!(identity(&event), ("abc@example.com".to_string(), "2024-05-13T09:00".to_string()));
+    }
+
+    fn calendar(events: &[(&str, i64, &str)]) -> String {
+        let mut ics = String::from("BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//Test//EN\r\n");
+        for (uid, sequence, title) in events {
+            ics.push_str(&format!(
+                "BEGIN:VEVENT\r\nUID:{}\r\nSEQUENCE:{}\r\nSUMMARY:{}\r\nDTSTART:20240506T090000Z\r\nDTEND:20240506T100000Z\r\nEND:VEVENT\r\n",
+                uid, sequence, title
+            ));
+        }
+        ics.push_str("END:VCALENDAR\r\n");
+        ics
+    }
+
+    #[test]
+    fn reimports_update_changed_events_and_remove_missing_ones() {
+        let db = Database::in_memory().unwrap();
+        crate::services::settings::SettingsService::new(db.get_connection()).set_setting("time_zone", "UTC").unwrap();
+        let service = IcsImportService::new(&db);
+        let options = IcsImportOptions { source: Some("team.ics".into()), remove_missing: true };
+        let counts = |s: &IcsImportSummary| (s.created, s.updated, s.unchanged, s.removed);
+        let event_count = || -> i64 { db.get_connection().query_row("SELECT COUNT(*) FROM events", [], |row| row.get(0)).unwrap() };
+
+        let original = calendar(&[("review@example.com", 0, "Review"), ("retro@example.com", 0, "Retro")]);
+        assert_eq!(counts(&service.import(&original, &options).unwrap()), (2, 0, 0, 0));
+        assert_eq!(counts(&service.import(&original, &options).unwrap()), (0, 0, 2, 0));
+        assert_eq!(event_count(), 2);
+
+        // The organiser renamed the review and cancelled the retro
+        let revised = calendar(&[("review@example.com", 1, "Design review")]);
+        assert_eq!(counts(&service.import(&revised, &options).unwrap()), (0, 1, 0, 1));
+        assert_eq!(event_count(), 1);
+        let review = service.find_source("team.ics", "review@example.com", "").unwrap().unwrap();
+        assert_eq!(db.get_event(review.event_id).unwrap().title, "Design review");
+        assert!(service.find_source("team.ics", "retro@example.com", "").unwrap().is_none());
+
+        // An older copy of the file does not undo the rename
+        assert_eq!(counts(&service.import(&original, &IcsImportOptions { remove_missing: false, ..options.clone() }).unwrap()), (1, 0, 1, 0));
+        assert_eq!(db.get_event(review.event_id).unwrap().title, "Design review");
+    }
+}
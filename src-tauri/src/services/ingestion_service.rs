@@ -1,10 +1,11 @@
 use crate::models::ingestion::{CreateIngestionJob, IngestionJob, UpdateIngestionJob, IngestionJobStats};
 use crate::models::metadata::MetadataExtractionResult;
 use crate::services::database::Database;
+use crate::services::tags::TagService;
 use rusqlite::{params, Result};
 use std::path::Path;
 use std::fs;
-use sha2::{Sha256, Digest};
+use crate::services::blob_store::hash_file;
 use mime_guess::from_path;
 use zip::ZipArchive;
 
@@ -278,34 +279,42 @@ impl<'a> IngestionService<'a> {
         }
         
         // Calculate file hash for duplicate detection
-        let _file_hash = self.calculate_file_hash(file_path)?;
+        let file_hash = self.calculate_file_hash(file_path)?;
         
-        // Check if file already exists (by hash)
-        let existing_file: Option<i64> = conn.query_row(
-            "SELECT id FROM files WHERE filepath = ?1",
-            params![file_path.to_string_lossy()],
-            |row| row.get(0)
+        // Check if file already exists: the same content from any source
+        // (an upload, or another path), else an earlier version of this path
+        let existing_file: Option<(i64, bool)> = conn.query_row(
+            "SELECT id, hash IS ?1 FROM files WHERE hash = ?1 OR filepath = ?2
+             ORDER BY hash IS ?1 DESC, id LIMIT 1",
+            params![file_hash, file_path.to_string_lossy()],
+            |row| Ok((row.get(0)?, row.get(1)?))
         ).ok();
 
-        let file_id = if let Some(id) = existing_file {
-            // File already exists, update metadata
-            conn.execute(
-                "UPDATE files SET filesize = ?1, mimetype = ?2 WHERE id = ?3",
-                params![file_size, mime_type, id],
-            )?;
-            id
-        } else {
-            // Insert new file
-            conn.execute(
-                "INSERT INTO files (filename, filepath, filesize, mimetype) VALUES (?1, ?2, ?3, ?4)",
-                params![
-                    file_path.file_name().unwrap_or_default().to_string_lossy(),
-                    file_path.to_string_lossy(),
-                    file_size,
-                    mime_type
-                ],
-            )?;
-            conn.last_insert_rowid()
+        let file_id = match existing_file {
+            // Same content: nothing new to store
+            Some((id, true)) => id,
+            Some((id, false)) => {
+                // This path changed since it was ingested, update metadata
+                conn.execute(
+                    "UPDATE files SET filesize = ?1, mimetype = ?2, hash = ?3 WHERE id = ?4",
+                    params![file_size, mime_type, file_hash, id],
+                )?;
+                id
+            }
+            None => {
+                // Insert new file
+                conn.execute(
+                    "INSERT INTO files (filename, filepath, filesize, mimetype, hash) VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![
+                        file_path.file_name().unwrap_or_default().to_string_lossy(),
+                        file_path.to_string_lossy(),
+                        file_size,
+                        mime_type,
+                        file_hash
+                    ],
+                )?;
+                conn.last_insert_rowid()
+            }
         };
 
         // Try to read file content and create cleaning tasks
@@ -369,11 +378,7 @@ impl<'a> IngestionService<'a> {
 
     /// Calculate SHA256 hash of a file
     fn calculate_file_hash(&self, file_path: &Path) -> Result<String> {
-        let mut file = fs::File::open(file_path).map_err(|e| rusqlite::Error::InvalidParameterName(e.to_string()))?;
-        let mut hasher = Sha256::new();
-        std::io::copy(&mut file, &mut hasher).map_err(|e| rusqlite::Error::InvalidParameterName(e.to_string()))?;
-        let hash = hasher.finalize();
-        Ok(format!("{:x}", hash))
+        hash_file(file_path).map_err(|e| rusqlite::Error::InvalidParameterName(e.to_string()))
     }
 
     /// Check if file is text-based
@@ -495,6 +500,9 @@ impl<'a> IngestionService<'a> {
 
         let tags_json = serde_json::to_string(&metadata.tags).unwrap_or_default();
 
+        // Extracted tags are only hints; surface them as suggestions for the file
+        TagService::new(conn).record_suggestions("file", file_id, &metadata.tags, "metadata")?;
+
         if let Some(id) = existing {
             // Update existing metadata
             conn.execute(
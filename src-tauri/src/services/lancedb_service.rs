@@ -1,5 +1,6 @@
 use crate::models::vector_index::{VectorIndex, CreateVectorIndex, SimilaritySearchResult, VectorIndexStats};
 use crate::services::ollama::OllamaService;
+use crate::services::outline::{chunk_by_section, section_from_metadata, SectionChunk};
 use crate::services::database::Database;
 use std::sync::Arc;
 use tokio::sync::Mutex;
@@ -176,6 +177,7 @@ impl LanceDBService {
                     content: vector_index.content.clone(),
                     similarity_score: similarity,
                     metadata: vector_index.metadata.clone(),
+                    section: section_from_metadata(vector_index.metadata.as_deref()),
                 });
             }
         }
@@ -209,23 +211,23 @@ impl LanceDBService {
         content: &str,
         model: Option<&str>,
     ) -> Result<Vec<VectorIndex>, String> {
-        // Split content into chunks
+        // Split content into per-section chunks
         let chunks = self.chunk_content(content, 1000);
         let mut entries = Vec::new();
 
         for (chunk_index, chunk) in chunks.iter().enumerate() {
-            // Generate embedding for this chunk
-            let embedding = self.generate_embedding(chunk, model).await?;
+            // Embed the chunk together with its heading path
+            let embedding = self.generate_embedding(&chunk.embedding_text, model).await?;
 
             // Create vector index entry
             let entry = CreateVectorIndex {
                 content_id,
                 content_type: content_type.to_string(),
-                content: chunk.clone(),
+                content: chunk.text.clone(),
                 embedding_vector: embedding,
                 model_name: model.unwrap_or("nomic-embed-text").to_string(),
                 chunk_index: Some(chunk_index as i64),
-                metadata: Some(format!("chunk_length:{}", chunk.len())),
+                metadata: Some(chunk.metadata.clone()),
             };
 
             let created_entry = self.create_vector_entry(entry).await?;
@@ -286,6 +288,14 @@ impl LanceDBService {
 
     /// Delete vector entries for specific content
     pub async fn delete_content_vectors(&self, content_id: i64, content_type: &str) -> Result<(), String> {
+        {
+            let db_guard = self.database.lock().await;
+            db_guard.get_connection().execute(
+                "DELETE FROM vector_index WHERE content_id = ?1 AND content_type = ?2",
+                rusqlite::params![content_id, content_type],
+            ).map_err(|e| format!("Failed to delete vectors: {}", e))?;
+        }
+
         let mut storage = self.vector_storage.lock().await;
         
         // Remove entries matching content_id and content_type
@@ -401,35 +411,9 @@ impl LanceDBService {
         }
     }
 
-    /// Split content into chunks
-    fn chunk_content(&self, content: &str, max_chunk_size: usize) -> Vec<String> {
-        if content.len() <= max_chunk_size {
-            return vec![content.to_string()];
-        }
-
-        let mut chunks = Vec::new();
-        let mut start = 0;
-
-        while start < content.len() {
-            let end = std::cmp::min(start + max_chunk_size, content.len());
-            let chunk = &content[start..end];
-            
-            // Try to break at word boundary
-            let actual_end = if end < content.len() {
-                if let Some(space_pos) = chunk.rfind(' ') {
-                    start + space_pos
-                } else {
-                    end
-                }
-            } else {
-                end
-            };
-
-            chunks.push(content[start..actual_end].to_string());
-            start = if actual_end == end { end } else { actual_end + 1 };
-        }
-
-        chunks
+    /// Split content into chunks that follow the document's heading structure
+    fn chunk_content(&self, content: &str, max_chunk_size: usize) -> Vec<SectionChunk> {
+        chunk_by_section(content, max_chunk_size)
     }
 
     /// Clear all vector data from both memory and database
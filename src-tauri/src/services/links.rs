@@ -30,13 +30,23 @@ impl<'a> LinksService<'a> {
     }
 
     pub fn create_link(&self, link: CreateLink) -> Result<Link> {
-        self.conn.execute(
+        let inserted = self.conn.execute(
             "INSERT OR IGNORE INTO links (source_type, source_id, target_type, target_id) 
              VALUES (?1, ?2, ?3, ?4)",
             params![link.source_type, link.source_id, link.target_type, link.target_id],
         )?;
 
-        let id = self.conn.last_insert_rowid();
+        // The link may already exist; last_insert_rowid is stale in that case
+        let id = if inserted > 0 {
+            self.conn.last_insert_rowid()
+        } else {
+            self.conn.query_row(
+                "SELECT id FROM links 
+                 WHERE source_type = ?1 AND source_id = ?2 AND target_type = ?3 AND target_id = ?4",
+                params![link.source_type, link.source_id, link.target_type, link.target_id],
+                |row| row.get(0),
+            )?
+        };
         self.get_link(id)
     }
 
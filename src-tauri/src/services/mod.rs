@@ -1,10 +1,33 @@
 pub mod database;
 pub mod settings;
 pub mod file_manager;
+pub mod blob_store;
+pub mod uploads;
+pub mod previews;
 pub mod links;
+pub mod tags;
+pub mod folders;
+pub mod templates;
+pub mod outline;
+pub mod task_structure;
+pub mod rrule;
+pub mod time_zones;
+pub mod recurring_tasks;
+pub mod recurring_events;
+pub mod quick_add;
+pub mod time_tracking;
+pub mod boards;
+pub mod task_transfer;
+pub mod task_extraction;
 pub mod ollama;
 pub mod notifications;
 pub mod ics;
+pub mod ics_import;
+pub mod calendar_subscriptions;
+pub mod scheduling;
+pub mod task_planner;
+pub mod reminders;
+pub mod digests;
 pub mod indexer;
 pub mod embedded_ollama;
 
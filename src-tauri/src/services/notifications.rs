@@ -1,36 +1,21 @@
 // Notification Service - per prd.md OS notifications
 use std::error::Error;
 
-pub struct NotificationService;
+pub struct NotificationService {
+    /// Bundle identifier the notifications are shown under
+    identifier: String,
+}
 
 impl NotificationService {
-    pub fn new() -> Self {
-        Self
+    pub fn new(identifier: String) -> Self {
+        Self { identifier }
     }
 
     pub fn send_notification(&self, title: &str, body: &str) -> Result<(), Box<dyn Error>> {
-        // For MVP: Log to console
-        // Production: Use tauri::api::notification
-        println!("📢 Notification: {} - {}", title, body);
-        
-        // TODO: Implement with Tauri's notification API
-        // This requires enabling notification permissions
-        
-        Ok(())
-    }
-
-    pub fn schedule_reminder(
-        &self,
-        title: &str,
-        body: &str,
-        time: chrono::DateTime<chrono::Utc>,
-    ) -> Result<(), Box<dyn Error>> {
-        println!("⏰ Scheduled reminder for {}: {} - {}", time, title, body);
-        
-        // TODO: Implement background task scheduler
-        // Options: tokio::time, or OS-level cron/Task Scheduler
-        
+        tauri::api::notification::Notification::new(&self.identifier)
+            .title(title)
+            .body(body)
+            .show()?;
         Ok(())
     }
 }
-
@@ -1,4 +1,7 @@
 // Ollama Service - per ARCHITECTURE.md §6️⃣ AI Integration
+use crate::models::task_extraction::TaskExtraction;
+use crate::services::task_extraction::{extraction_prompt, extraction_schema, parse_extraction};
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 use std::error::Error;
 
@@ -7,6 +10,9 @@ pub struct OllamaRequest {
     pub model: String,
     pub prompt: String,
     pub stream: bool,
+    /// "json" or a JSON schema the response must follow
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -72,11 +78,27 @@ impl OllamaService {
     }
 
     pub async fn generate(&self, model: &str, prompt: &str) -> Result<String, Box<dyn Error>> {
-        let request = OllamaRequest {
+        self.send_generate(OllamaRequest {
             model: model.to_string(),
             prompt: prompt.to_string(),
             stream: false,
-        };
+            format: None,
+        })
+        .await
+    }
+
+    /// Generate with the output constrained to the given JSON schema
+    pub async fn generate_json(&self, model: &str, prompt: &str, schema: serde_json::Value) -> Result<String, Box<dyn Error>> {
+        self.send_generate(OllamaRequest {
+            model: model.to_string(),
+            prompt: prompt.to_string(),
+            stream: false,
+            format: Some(schema),
+        })
+        .await
+    }
+
+    async fn send_generate(&self, request: OllamaRequest) -> Result<String, Box<dyn Error>> {
 
         let response = self.client
             .post(format!("{}/api/generate", self.base_url))
@@ -137,20 +159,12 @@ impl OllamaService {
         self.generate("llama3.2", &prompt).await
     }
 
-    pub async fn generate_tasks(&self, text: &str) -> Result<Vec<String>, Box<dyn Error>> {
-        let prompt = format!(
-            "Extract actionable tasks from this text. Return only the tasks, one per line:\n\n{}\n\nTasks:",
-            text
-        );
-        let response = self.generate("llama3.2", &prompt).await?;
-        
-        let tasks: Vec<String> = response
-            .lines()
-            .filter(|line| !line.trim().is_empty())
-            .map(|line| line.trim().to_string())
-            .collect();
-        
-        Ok(tasks)
+    /// Extract task drafts from text, resolving relative due dates against
+    /// `source_date`. The reply is schema-constrained and then validated.
+    pub async fn generate_tasks(&self, text: &str, source_date: NaiveDate) -> Result<TaskExtraction, Box<dyn Error>> {
+        let prompt = extraction_prompt(text, source_date);
+        let response = self.generate_json("llama3.2", &prompt, extraction_schema()).await?;
+        Ok(parse_extraction(&response, source_date)?)
     }
 
     pub async fn chat(&self, message: &str) -> Result<String, Box<dyn Error>> {
@@ -0,0 +1,307 @@
+// Outline Service - split documents into heading sections for navigation and embeddings
+use crate::models::outline::{OutlineSection, SectionRef};
+use regex::Regex;
+use std::collections::HashMap;
+
+/// A piece of a section ready to be embedded
+#[derive(Debug, Clone)]
+pub struct SectionChunk {
+    /// Chunk text as stored in the index
+    pub text: String,
+    /// Chunk text prefixed with its heading path, used for the embedding
+    pub embedding_text: String,
+    /// JSON metadata carrying the section reference
+    pub metadata: String,
+}
+
+/// Parse a document into sections. Handles both the HTML produced by the
+/// editor (`<h1>`..`<h6>`) and Markdown ATX headings (`#`..`######`).
+pub fn parse_outline(content: &str) -> Vec<OutlineSection> {
+    let headings = if looks_like_html(content) {
+        html_headings(content)
+    } else {
+        markdown_headings(content)
+    };
+
+    let mut sections = Vec::new();
+    let mut stack: Vec<(i64, String)> = Vec::new();
+    let mut anchors: HashMap<String, usize> = HashMap::new();
+
+    let preamble_end = headings.first().map(|h| h.start).unwrap_or(content.len());
+    let preamble = body_text(&content[..preamble_end]);
+    if !preamble.is_empty() {
+        sections.push(build_section(0, 0, String::new(), Vec::new(), String::new(), preamble));
+    }
+
+    for (i, heading) in headings.iter().enumerate() {
+        let body_end = headings.get(i + 1).map(|h| h.start).unwrap_or(content.len());
+        let body = body_text(&content[heading.end..body_end]);
+
+        while stack.last().map(|(level, _)| *level >= heading.level).unwrap_or(false) {
+            stack.pop();
+        }
+        stack.push((heading.level, heading.text.clone()));
+        let path: Vec<String> = stack.iter().map(|(_, text)| text.clone()).collect();
+
+        let base = slugify(&heading.text);
+        let count = anchors.entry(base.clone()).or_insert(0);
+        let anchor = if *count == 0 { base } else { format!("{}-{}", base, count) };
+        *count += 1;
+
+        sections.push(build_section(sections.len() as i64, heading.level, heading.text.clone(), path, anchor, body));
+    }
+
+    sections
+}
+
+/// Split a document into chunks that never cross a section boundary. Long
+/// sections are split further at word boundaries; every chunk keeps its
+/// heading path so it can be embedded with context and cited afterwards.
+pub fn chunk_by_section(content: &str, max_chunk_size: usize) -> Vec<SectionChunk> {
+    let mut chunks = Vec::new();
+
+    for section in parse_outline(content) {
+        let context = section.path.join(" > ");
+        let section_ref = SectionRef {
+            index: section.index,
+            heading: section.heading.clone(),
+            path: section.path.clone(),
+            anchor: section.anchor.clone(),
+        };
+
+        let body = if section.content.is_empty() { section.heading.clone() } else { section.content.clone() };
+        for piece in split_text(&body, max_chunk_size) {
+            let embedding_text = if context.is_empty() {
+                piece.clone()
+            } else {
+                format!("{}\n\n{}", context, piece)
+            };
+            let metadata = serde_json::json!({
+                "chunk_length": piece.len(),
+                "section": section_ref,
+            });
+            chunks.push(SectionChunk {
+                text: piece,
+                embedding_text,
+                metadata: metadata.to_string(),
+            });
+        }
+    }
+
+    chunks
+}
+
+/// Read the section reference back out of a vector entry's metadata
+pub fn section_from_metadata(metadata: Option<&str>) -> Option<SectionRef> {
+    let value: serde_json::Value = serde_json::from_str(metadata?).ok()?;
+    let section = serde_json::from_value::<SectionRef>(value.get("section")?.clone()).ok()?;
+    // Text before the first heading has nothing to point at
+    if section.heading.is_empty() {
+        None
+    } else {
+        Some(section)
+    }
+}
+
+/// Split text into pieces of at most `max_chunk_size` bytes, preferring
+/// paragraph and then word boundaries and never cutting inside a character.
+pub fn split_text(text: &str, max_chunk_size: usize) -> Vec<String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Vec::new();
+    }
+
+    let mut pieces = Vec::new();
+    let mut rest = text;
+    while rest.len() > max_chunk_size {
+        let mut limit = max_chunk_size;
+        while !rest.is_char_boundary(limit) {
+            limit -= 1;
+        }
+        let window = &rest[..limit];
+        let cut = window
+            .rfind("\n\n")
+            .or_else(|| window.rfind(char::is_whitespace))
+            .filter(|pos| *pos > 0)
+            .unwrap_or(limit);
+
+        pieces.push(rest[..cut].trim().to_string());
+        rest = rest[cut..].trim_start();
+    }
+    if !rest.is_empty() {
+        pieces.push(rest.to_string());
+    }
+
+    pieces
+}
+
+struct Heading {
+    level: i64,
+    text: String,
+    /// Byte offset where the heading starts
+    start: usize,
+    /// Byte offset just past the heading
+    end: usize,
+}
+
+fn looks_like_html(content: &str) -> bool {
+    let trimmed = content.trim_start();
+    trimmed.starts_with('<') && Regex::new(r"(?i)</(p|h[1-6]|ul|ol|div|li)>").unwrap().is_match(content)
+}
+
+fn html_headings(content: &str) -> Vec<Heading> {
+    let re = Regex::new(r"(?is)<h([1-6])\b[^>]*>(.*?)</h[1-6]\s*>").unwrap();
+    re.captures_iter(content)
+        .filter_map(|caps| {
+            let whole = caps.get(0)?;
+            let text = strip_html(&caps[2]).split_whitespace().collect::<Vec<_>>().join(" ");
+            if text.is_empty() {
+                return None;
+            }
+            Some(Heading {
+                level: caps[1].parse().ok()?,
+                text,
+                start: whole.start(),
+                end: whole.end(),
+            })
+        })
+        .collect()
+}
+
+fn markdown_headings(content: &str) -> Vec<Heading> {
+    let re = Regex::new(r"^ {0,3}(#{1,6})[ \t]+(.*?)(?:[ \t]+#+)?[ \t]*$").unwrap();
+    let mut headings = Vec::new();
+    let mut fence: Option<&str> = None;
+    let mut offset = 0;
+
+    for line in content.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+        let trimmed = line.trim_end_matches(['\r', '\n']);
+        let marker = trimmed.trim_start();
+
+        // Headings inside fenced code blocks are code, not structure
+        if let Some(open) = fence {
+            if marker.starts_with(open) {
+                fence = None;
+            }
+            continue;
+        }
+        if marker.starts_with("```") || marker.starts_with("~~~") {
+            fence = Some(&marker[..3]);
+            continue;
+        }
+
+        if let Some(caps) = re.captures(trimmed) {
+            let text = caps[2].trim().to_string();
+            if text.is_empty() {
+                continue;
+            }
+            headings.push(Heading {
+                level: caps[1].len() as i64,
+                text,
+                start,
+                end: offset,
+            });
+        }
+    }
+
+    headings
+}
+
+fn body_text(raw: &str) -> String {
+    let text = if looks_like_html(raw) || raw.trim_start().starts_with('<') {
+        strip_html(raw)
+    } else {
+        raw.to_string()
+    };
+    // Collapse runs of blank lines left behind by removed markup
+    let mut lines: Vec<&str> = Vec::new();
+    for line in text.lines().map(str::trim_end) {
+        if line.trim().is_empty() && lines.last().map(|l| l.trim().is_empty()).unwrap_or(true) {
+            continue;
+        }
+        lines.push(line);
+    }
+    lines.join("\n").trim().to_string()
+}
+
+fn strip_html(html: &str) -> String {
+    let breaks = Regex::new(r"(?i)<br\s*/?>|</(p|li|div|h[1-6]|blockquote|pre|tr)>").unwrap();
+    let tags = Regex::new(r"<[^>]+>").unwrap();
+    let text = breaks.replace_all(html, "\n");
+    let text = tags.replace_all(&text, "");
+    text.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+fn slugify(heading: &str) -> String {
+    let mut slug = String::new();
+    for c in heading.to_lowercase().chars() {
+        if c.is_alphanumeric() {
+            slug.push(c);
+        } else if (c.is_whitespace() || c == '-' || c == '_') && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_matches('-').to_string();
+    if slug.is_empty() {
+        "section".to_string()
+    } else {
+        slug
+    }
+}
+
+fn build_section(index: i64, level: i64, heading: String, path: Vec<String>, anchor: String, content: String) -> OutlineSection {
+    OutlineSection {
+        index,
+        level,
+        heading,
+        path,
+        anchor,
+        word_count: content.split_whitespace().count() as i64,
+        content,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_markdown_sections_with_paths() {
+        let doc = "Intro text\n\n# Plan\nOverview\n## Budget\nCosts\n```\n# not a heading\n```\n## Budget\nMore\n# Risks\n";
+        let sections = parse_outline(doc);
+        let headings: Vec<_> = sections.iter().map(|s| (s.level, s.heading.as_str(), s.anchor.as_str())).collect();
+        assert_eq!(
+            headings,
+            vec![(0, "", ""), (1, "Plan", "plan"), (2, "Budget", "budget"), (2, "Budget", "budget-1"), (1, "Risks", "risks")]
+        );
+        assert_eq!(sections[2].path, vec!["Plan", "Budget"]);
+        assert!(sections[2].content.contains("# not a heading"));
+    }
+
+    #[test]
+    fn parses_editor_html() {
+        let doc = "<h1>Report</h1><p>Summary &amp; scope</p><h2>Budget</h2><p>Q1</p><p>Q2</p>";
+        let sections = parse_outline(doc);
+        assert_eq!(sections.len(), 2);
+        assert_eq!(sections[0].content, "Summary & scope");
+        assert_eq!(sections[1].path, vec!["Report", "Budget"]);
+        assert_eq!(sections[1].content, "Q1\nQ2");
+    }
+
+    #[test]
+    fn chunks_carry_their_section() {
+        let chunks = chunk_by_section("# Budget\nwords words words words", 12);
+        assert!(chunks.len() > 1);
+        for chunk in &chunks {
+            assert!(chunk.embedding_text.starts_with("Budget\n\n"));
+            assert_eq!(section_from_metadata(Some(&chunk.metadata)).unwrap().anchor, "budget");
+        }
+    }
+}
@@ -0,0 +1,383 @@
+// Previews - thumbnails and excerpts for stored files
+//
+// Images get a PNG thumbnail. PDF pages are not rendered (there is no
+// PDF renderer to draw text and vector graphics with): a PDF's thumbnail is
+// the largest image embedded in its first page, which for scans and photos
+// fills the page. A first page without images - any PDF that is only text -
+// gets a text excerpt instead. Office documents, text and HTML get an
+// excerpt from `FileProcessor`.
+//
+// Previews are cached on disk by content hash, so files sharing their
+// bytes share a preview; the cache entry is evicted with the last file
+// that uses it.
+use crate::services::database::Database;
+use crate::services::file_manager::{FileManager, FileMetadata};
+use crate::services::file_processor::FileProcessor;
+use image::{DynamicImage, GrayImage, ImageFormat, ImageReader, RgbImage};
+use lopdf::xobject::PdfImage;
+use std::fs;
+use std::io::{self, Cursor};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Directory under the files root that holds cached previews
+pub const PREVIEW_DIR: &str = "previews";
+/// Thumbnails fit in a square of this many pixels
+pub const THUMBNAIL_SIZE: u32 = 320;
+const EXCERPT_CHARS: usize = 1200;
+
+pub const PREVIEW_IMAGE: &str = "image";
+pub const PREVIEW_PDF_PAGE: &str = "pdf_page";
+pub const PREVIEW_TEXT: &str = "text";
+pub const PREVIEW_NONE: &str = "none";
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct FilePreview {
+    pub file_id: i64,
+    /// "image", "pdf_page", "text" or "none". A "pdf_page" thumbnail is the
+    /// largest image on the first page, not a rendering of the page; PDFs
+    /// whose first page has no image are "text"
+    pub kind: String,
+    /// PNG thumbnail, for "image" and "pdf_page"
+    pub thumbnail: Option<Vec<u8>>,
+    /// Size of the original image or of the page's image
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub excerpt: Option<String>,
+    pub page_count: Option<u32>,
+}
+
+/// What is kept on disk besides the thumbnail
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+struct CachedPreview {
+    kind: String,
+    width: Option<u32>,
+    height: Option<u32>,
+    excerpt: Option<String>,
+    page_count: Option<u32>,
+}
+
+impl CachedPreview {
+    fn none() -> Self {
+        Self { kind: PREVIEW_NONE.to_string(), ..Default::default() }
+    }
+
+    fn into_preview(self, file_id: i64, thumbnail: Option<Vec<u8>>) -> FilePreview {
+        FilePreview {
+            file_id,
+            kind: self.kind,
+            thumbnail,
+            width: self.width,
+            height: self.height,
+            excerpt: self.excerpt,
+            page_count: self.page_count,
+        }
+    }
+}
+
+/// Cache key of a file's preview: its content hash, or its id for files stored before hashing
+pub fn preview_key(metadata: &FileMetadata) -> String {
+    match &metadata.hash {
+        Some(hash) if !hash.is_empty() && hash.chars().all(|c| c.is_ascii_hexdigit()) => hash.to_ascii_lowercase(),
+        _ => format!("file-{}", metadata.id),
+    }
+}
+
+pub struct PreviewCache {
+    root: PathBuf,
+}
+
+impl PreviewCache {
+    pub fn new(root: PathBuf) -> io::Result<Self> {
+        fs::create_dir_all(&root)?;
+        Ok(Self { root })
+    }
+
+    fn record_path(&self, key: &str) -> PathBuf {
+        self.root.join(format!("{}.json", key))
+    }
+
+    fn thumbnail_path(&self, key: &str) -> PathBuf {
+        self.root.join(format!("{}.png", key))
+    }
+
+    fn get(&self, key: &str) -> Option<(CachedPreview, Option<Vec<u8>>)> {
+        let record: CachedPreview = serde_json::from_slice(&fs::read(self.record_path(key)).ok()?).ok()?;
+        let thumbnail = match record.kind.as_str() {
+            PREVIEW_IMAGE | PREVIEW_PDF_PAGE => Some(fs::read(self.thumbnail_path(key)).ok()?),
+            _ => None,
+        };
+        Some((record, thumbnail))
+    }
+
+    fn put(&self, key: &str, record: &CachedPreview, thumbnail: Option<&[u8]>) -> io::Result<()> {
+        // The thumbnail goes first so a record never points at a missing one
+        if let Some(thumbnail) = thumbnail {
+            fs::write(self.thumbnail_path(key), thumbnail)?;
+        }
+        fs::write(self.record_path(key), serde_json::to_vec(record)?)
+    }
+
+    /// Forget the preview stored under `key`
+    pub fn evict(&self, key: &str) -> io::Result<()> {
+        for path in [self.record_path(key), self.thumbnail_path(key)] {
+            if path.exists() {
+                fs::remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn is_office_document(mimetype: &str) -> bool {
+    matches!(
+        mimetype,
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document"
+            | "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"
+            | "application/vnd.openxmlformats-officedocument.presentationml.presentation"
+            | "application/vnd.ms-excel"
+    )
+}
+
+fn is_text(mimetype: &str) -> bool {
+    mimetype.starts_with("text/") || matches!(mimetype, "application/json" | "application/xhtml+xml")
+}
+
+/// The start of `text`, without blank lines, cut at a word boundary
+fn excerpt(text: &str) -> Option<String> {
+    let lines: Vec<&str> = text.lines().map(str::trim).filter(|l| !l.is_empty()).collect();
+    let text = lines.join("\n");
+    if text.is_empty() {
+        return None;
+    }
+    if text.chars().count() <= EXCERPT_CHARS {
+        return Some(text);
+    }
+    let cut: String = text.chars().take(EXCERPT_CHARS).collect();
+    let cut = match cut.rfind(char::is_whitespace) {
+        Some(end) if end > EXCERPT_CHARS / 2 => &cut[..end],
+        _ => cut.as_str(),
+    };
+    Some(format!("{}…", cut.trim_end()))
+}
+
+fn encode_thumbnail(image: &DynamicImage) -> Result<Vec<u8>, String> {
+    let thumbnail = DynamicImage::ImageRgba8(image.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE).to_rgba8());
+    let mut png = Cursor::new(Vec::new());
+    thumbnail.write_to(&mut png, ImageFormat::Png).map_err(|e| e.to_string())?;
+    Ok(png.into_inner())
+}
+
+fn image_preview(path: &Path) -> Result<(CachedPreview, Vec<u8>), String> {
+    let image = ImageReader::open(path)
+        .map_err(|e| e.to_string())?
+        .with_guessed_format()
+        .map_err(|e| e.to_string())?
+        .decode()
+        .map_err(|e| e.to_string())?;
+    let record = CachedPreview {
+        kind: PREVIEW_IMAGE.to_string(),
+        width: Some(image.width()),
+        height: Some(image.height()),
+        ..Default::default()
+    };
+    Ok((record, encode_thumbnail(&image)?))
+}
+
+/// Decode a page image: JPEG as is, or 8-bit RGB and grayscale samples
+fn decode_pdf_image(document: &lopdf::Document, image: &PdfImage) -> Option<DynamicImage> {
+    let filters = image.filters.clone().unwrap_or_default();
+    let (width, height) = (u32::try_from(image.width).ok()?, u32::try_from(image.height).ok()?);
+    match filters.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        ["DCTDecode"] => image::load_from_memory_with_format(image.content, ImageFormat::Jpeg).ok(),
+        [] | ["FlateDecode"] => {
+            if image.bits_per_component != Some(8) {
+                return None;
+            }
+            let samples = document.get_object(image.id).ok()?.as_stream().ok()?.decompressed_content().ok()?;
+            match image.color_space.as_deref() {
+                Some("DeviceRGB") => RgbImage::from_raw(width, height, samples).map(DynamicImage::ImageRgb8),
+                Some("DeviceGray") => GrayImage::from_raw(width, height, samples).map(DynamicImage::ImageLuma8),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// The first page's largest image that can be decoded, and the page count
+fn pdf_first_page(path: &Path) -> Result<(Option<DynamicImage>, u32), String> {
+    let document = lopdf::Document::load(path).map_err(|e| e.to_string())?;
+    let pages = document.get_pages();
+    let Some(&page_id) = pages.values().next() else {
+        return Ok((None, 0));
+    };
+    // Pages without images have no XObject resources, which lopdf reports as an error
+    let mut images = document.get_page_images(page_id).unwrap_or_default();
+    images.sort_by_key(|image| std::cmp::Reverse(image.width.saturating_mul(image.height)));
+    let image = images.iter().find_map(|image| decode_pdf_image(&document, image));
+    Ok((image, pages.len() as u32))
+}
+
+async fn text_excerpt(path: &Path) -> Option<String> {
+    match FileProcessor::process_file(path).await {
+        Ok(content) => excerpt(&content.text),
+        Err(e) => {
+            eprintln!("Could not extract a preview excerpt from {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+async fn generate(path: PathBuf, mimetype: &str) -> (CachedPreview, Option<Vec<u8>>) {
+    if mimetype.starts_with("image/") {
+        let source = path.clone();
+        return match tokio::task::spawn_blocking(move || image_preview(&source)).await {
+            Ok(Ok((record, thumbnail))) => (record, Some(thumbnail)),
+            Ok(Err(e)) => {
+                eprintln!("Could not make a thumbnail of {}: {}", path.display(), e);
+                (CachedPreview::none(), None)
+            }
+            Err(e) => {
+                eprintln!("Thumbnail task failed: {}", e);
+                (CachedPreview::none(), None)
+            }
+        };
+    }
+    if mimetype == "application/pdf" {
+        let source = path.clone();
+        let rendered = tokio::task::spawn_blocking(move || {
+            let (image, page_count) = pdf_first_page(&source)?;
+            let thumbnail = match &image {
+                Some(image) => Some((image.width(), image.height(), encode_thumbnail(image)?)),
+                None => None,
+            };
+            Ok::<_, String>((thumbnail, page_count))
+        })
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|rendered| rendered);
+        let page_count = match rendered {
+            Ok((Some((width, height, thumbnail)), page_count)) => {
+                let record = CachedPreview {
+                    kind: PREVIEW_PDF_PAGE.to_string(),
+                    width: Some(width),
+                    height: Some(height),
+                    page_count: Some(page_count),
+                    ..Default::default()
+                };
+                return (record, Some(thumbnail));
+            }
+            Ok((None, page_count)) => Some(page_count),
+            Err(e) => {
+                eprintln!("Could not render the first page of {}: {}", path.display(), e);
+                None
+            }
+        };
+        let record = match text_excerpt(&path).await {
+            Some(excerpt) => CachedPreview { kind: PREVIEW_TEXT.to_string(), excerpt: Some(excerpt), page_count, ..Default::default() },
+            None => CachedPreview { page_count, ..CachedPreview::none() },
+        };
+        return (record, None);
+    }
+    if is_office_document(mimetype) || is_text(mimetype) {
+        if let Some(excerpt) = text_excerpt(&path).await {
+            return (CachedPreview { kind: PREVIEW_TEXT.to_string(), excerpt: Some(excerpt), ..Default::default() }, None);
+        }
+    }
+    (CachedPreview::none(), None)
+}
+
+/// The preview of file `file_id`, generated and cached on first use
+pub async fn get_file_preview(db: &Arc<Mutex<Database>>, file_id: i64) -> Result<FilePreview, String> {
+    let (metadata, path, cache) = {
+        let db = db.lock().await;
+        let file_manager = FileManager::new(db.get_connection()).map_err(|e| e.to_string())?;
+        let metadata = file_manager.get_file(file_id).map_err(|e| e.to_string())?;
+        let path = file_manager.full_path(&metadata);
+        (metadata, path, file_manager.preview_cache().map_err(|e| e.to_string())?)
+    };
+    let key = preview_key(&metadata);
+    if let Some((record, thumbnail)) = cache.get(&key) {
+        return Ok(record.into_preview(file_id, thumbnail));
+    }
+    if !path.exists() {
+        return Err(format!("The content of {} is missing", metadata.filename));
+    }
+
+    // Uploads without a declared type are recognized by name
+    let mimetype = match metadata.mimetype.as_str() {
+        "" | "application/octet-stream" => mime_guess::from_path(&metadata.filename).first_or_octet_stream().to_string(),
+        mimetype => mimetype.to_string(),
+    };
+    let (record, thumbnail) = generate(path, &mimetype).await;
+    // A preview that could not be cached is still worth showing
+    if let Err(e) = cache.put(&key, &record, thumbnail.as_deref()) {
+        eprintln!("Could not cache the preview of {}: {}", metadata.filename, e);
+    }
+    Ok(record.into_preview(file_id, thumbnail))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn excerpts_drop_blank_lines_and_stop_at_a_word() {
+        assert_eq!(excerpt("  Title \n\n\n body  \n"), Some("Title\nbody".to_string()));
+        assert_eq!(excerpt(" \n "), None);
+
+        let long = "word ".repeat(EXCERPT_CHARS);
+        let cut = excerpt(&long).unwrap();
+        assert!(cut.ends_with("word…"));
+        assert!(cut.chars().count() <= EXCERPT_CHARS + 1);
+    }
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut png = Cursor::new(Vec::new());
+        DynamicImage::ImageRgb8(RgbImage::new(width, height)).write_to(&mut png, ImageFormat::Png).unwrap();
+        png.into_inner()
+    }
+
+    #[test]
+    fn thumbnails_fit_the_square_and_keep_the_original_size() {
+        let root = std::env::temp_dir().join(format!("play-previews-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&root).unwrap();
+        let path = root.join("wide.png");
+        fs::write(&path, png(1280, 640)).unwrap();
+
+        let (record, thumbnail) = image_preview(&path).unwrap();
+        assert_eq!((record.kind.as_str(), record.width, record.height), (PREVIEW_IMAGE, Some(1280), Some(640)));
+        let thumbnail = image::load_from_memory_with_format(&thumbnail, ImageFormat::Png).unwrap();
+        assert_eq!((thumbnail.width(), thumbnail.height()), (THUMBNAIL_SIZE, THUMBNAIL_SIZE / 2));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn files_with_the_same_bytes_share_a_preview_until_the_last_is_deleted() {
+        let db = Database::in_memory().unwrap();
+        let root = std::env::temp_dir().join(format!("play-previews-{}", uuid::Uuid::new_v4()));
+        let files = FileManager::with_base_path(db.get_connection(), root.clone()).unwrap();
+        let cache = files.preview_cache().unwrap();
+
+        let first = files.save_file("photo.png", &png(40, 20), "image/png").unwrap();
+        let copy = files.save_file("copy of photo.png", &png(40, 20), "image/png").unwrap();
+        assert_eq!(preview_key(&first), preview_key(&copy));
+        let (record, thumbnail) = image_preview(&files.full_path(&first)).unwrap();
+        cache.put(&preview_key(&first), &record, Some(&thumbnail)).unwrap();
+
+        let (cached, cached_thumbnail) = cache.get(&preview_key(&copy)).unwrap();
+        assert_eq!((cached.kind.as_str(), cached.width), (PREVIEW_IMAGE, Some(40)));
+        assert_eq!(cached_thumbnail, Some(thumbnail));
+
+        files.delete_file(first.id).unwrap();
+        assert!(cache.get(&preview_key(&copy)).is_some());
+        files.delete_file(copy.id).unwrap();
+        assert!(cache.get(&preview_key(&copy)).is_none());
+        assert!(!cache.thumbnail_path(&preview_key(&copy)).exists());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
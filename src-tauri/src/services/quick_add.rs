@@ -0,0 +1,845 @@
+// Quick Add - turn one line of text into a task or an event
+//
+// "Send budget to Ana tomorrow 5pm !high #finance" becomes a high priority
+// task due tomorrow at 17:00 tagged "finance"; "Standup every weekday 9:30
+// for 15m" becomes a recurring 15 minute event. The parser is deterministic
+// and offline. Dates are resolved against `now`, the current wall-clock time
+// in the user's timezone, and are returned as local times.
+use crate::models::event::CreateEvent;
+use crate::models::quick_add::{QuickAddItem, QuickAddResult};
+use crate::models::task::CreateTask;
+use crate::services::rrule::{parse_stored_datetime, weekday_code, RecurrenceRule};
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Timelike, Weekday};
+
+const CONNECTORS: [&str; 8] = ["at", "on", "by", "due", "from", "starting", "until", "@"];
+
+/// Words that mean the input talks about time. If one of them is left over
+/// after parsing, the line was not fully understood.
+const TIME_WORDS: [&str; 23] = [
+    "today", "tonight", "tomorrow", "yesterday", "every", "noon", "midnight", "am", "pm",
+    "monday", "tuesday", "wednesday", "thursday", "friday", "saturday", "sunday", "weekend",
+    "weekday", "weekdays", "daily", "weekly", "monthly", "fortnight",
+];
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+const DATETIME_FORMAT: &str = "%Y-%m-%dT%H:%M";
+
+#[derive(Debug, Clone, PartialEq)]
+struct Repeat {
+    freq: &'static str,
+    interval: u32,
+    by_day: Vec<Weekday>,
+}
+
+#[derive(Debug, Default)]
+struct Parsed {
+    date: Option<NaiveDate>,
+    time: Option<NaiveTime>,
+    end_time: Option<NaiveTime>,
+    duration: Option<Duration>,
+    priority: Option<String>,
+    tags: Vec<String>,
+    repeat: Option<Repeat>,
+}
+
+/// Parse a quick-add line. Returns None when the line is empty or mentions
+/// dates or times the parser does not understand; callers can then ask
+/// Ollama with `quick_add_prompt` and `parse_ai_response`.
+pub fn parse_quick_add(input: &str, now: NaiveDateTime) -> Option<QuickAddResult> {
+    let words: Vec<&str> = input.split_whitespace().collect();
+    let lower: Vec<String> = words
+        .iter()
+        .map(|w| w.trim_end_matches([',', ';', '.']).to_lowercase())
+        .collect();
+    let lower: Vec<&str> = lower.iter().map(String::as_str).collect();
+
+    let mut parsed = Parsed::default();
+    let mut consumed = vec![false; words.len()];
+    let mut i = 0;
+    while i < words.len() {
+        // Tags keep the user's spelling
+        if let Some(tag) = words[i].strip_prefix('#') {
+            let tag = tag.trim_end_matches([',', ';', '.']);
+            if !tag.is_empty() {
+                if !parsed.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                    parsed.tags.push(tag.to_string());
+                }
+                consumed[i] = true;
+                i += 1;
+                continue;
+            }
+        }
+
+        let after_match = i > 0 && consumed[i - 1];
+        let mut matched = match_at(&lower[i..], &mut parsed, now, after_match).map(|len| (0, len));
+        if matched.is_none() {
+            let skip = lower[i..].iter().take(2).take_while(|w| CONNECTORS.contains(w)).count();
+            if skip > 0 && i + skip < words.len() {
+                matched = match_at(&lower[i + skip..], &mut parsed, now, true).map(|len| (skip, len));
+            }
+        }
+
+        match matched {
+            Some((skip, len)) => {
+                for flag in consumed.iter_mut().skip(i).take(skip + len) {
+                    *flag = true;
+                }
+                i += skip + len;
+            }
+            None => i += 1,
+        }
+    }
+
+    let leftover: Vec<&str> = words
+        .iter()
+        .zip(&consumed)
+        .filter(|(_, consumed)| !**consumed)
+        .map(|(word, _)| *word)
+        .collect();
+    if leftover.iter().any(|w| looks_temporal(w)) {
+        return None;
+    }
+    let title = leftover.join(" ").trim_matches(|c: char| c == ',' || c == '-' || c.is_whitespace()).to_string();
+    if title.is_empty() {
+        return None;
+    }
+
+    Some(build_result(title, parsed, now, "parser"))
+}
+
+/// The bare task used when neither the parser nor Ollama understood the line
+pub fn plain_task(input: &str) -> QuickAddResult {
+    QuickAddResult {
+        item: QuickAddItem::Task(CreateTask {
+            title: input.trim().to_string(),
+            description: None,
+            status: None,
+            priority: None,
+            due_date: None,
+            reminder_time: None,
+            parent_id: None,
+            recurrence: None,
+            estimate: None,
+            time_zone: None,
+        }),
+        tags: Vec::new(),
+        source: "plain".to_string(),
+    }
+}
+
+/// Prompt asking Ollama for the same structure the parser produces
+pub fn quick_add_prompt(input: &str, now: NaiveDateTime) -> String {
+    format!(
+        "Turn this note into a task or a calendar event. It is now {} ({}).\n\
+         Reply with JSON only, using these fields:\n\
+         {{\"kind\": \"task\" or \"event\", \"title\": string, \"priority\": \"low\"|\"medium\"|\"high\"|null, \
+         \"tags\": [string], \"due\": \"YYYY-MM-DDTHH:MM\" or \"YYYY-MM-DD\" or null (tasks), \
+         \"start\": \"YYYY-MM-DDTHH:MM\" or null (events), \"end\": \"YYYY-MM-DDTHH:MM\" or null (events), \
+         \"recurrence\": an RFC 5545 RRULE such as \"FREQ=WEEKLY;BYDAY=MO\" or null}}\n\n\
+         Note: {}\n\nJSON:",
+        now.format(DATETIME_FORMAT),
+        now.format("%A"),
+        input.trim()
+    )
+}
+
+/// Validate Ollama's answer to `quick_add_prompt`. Anything that does not
+/// fit the expected shape is rejected rather than guessed at.
+pub fn parse_ai_response(response: &str, input: &str) -> Option<QuickAddResult> {
+    let start = response.find('{')?;
+    let end = response.rfind('}')?;
+    let value: serde_json::Value = serde_json::from_str(response.get(start..=end)?).ok()?;
+
+    let text = |key: &str| {
+        value
+            .get(key)
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+    };
+    let date = |key: &str| text(key).filter(|s| parse_stored_datetime(s).is_some());
+
+    let title = text("title").unwrap_or_else(|| input.trim().to_string());
+    let priority = text("priority")
+        .map(|p| p.to_lowercase())
+        .filter(|p| ["low", "medium", "high"].contains(&p.as_str()));
+    let recurrence = text("recurrence")
+        .and_then(|rule| RecurrenceRule::parse(&rule).ok())
+        .map(|rule| rule.to_string());
+    let tags = value
+        .get("tags")
+        .and_then(|v| v.as_array())
+        .map(|tags| {
+            tags.iter()
+                .filter_map(|t| t.as_str())
+                .map(|t| t.trim().trim_start_matches('#').to_string())
+                .filter(|t| !t.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let item = match text("kind").as_deref() {
+        Some("event") => {
+            let start = date("start")?;
+            let (start_at, _) = parse_stored_datetime(&start)?;
+            let end = date("end")
+                .filter(|end| parse_stored_datetime(end).map(|(end, _)| end > start_at).unwrap_or(false))
+                .unwrap_or_else(|| (start_at + Duration::hours(1)).format(DATETIME_FORMAT).to_string());
+            QuickAddItem::Event(CreateEvent {
+                title,
+                description: None,
+                start_time: start,
+                end_time: end,
+                reminder_time: None,
+                recurrence,
+                location: None,
+                all_day: false,
+                exdates: Vec::new(),
+                time_zone: None,
+            })
+        }
+        Some("task") | None => QuickAddItem::Task(CreateTask {
+            title,
+            description: None,
+            status: None,
+            priority,
+            due_date: date("due"),
+            reminder_time: None,
+            parent_id: None,
+            recurrence,
+            estimate: None,
+            time_zone: None,
+        }),
+        Some(_) => return None,
+    };
+
+    Some(QuickAddResult { item, tags, source: "ai".to_string() })
+}
+
+fn build_result(title: String, parsed: Parsed, now: NaiveDateTime, source: &str) -> QuickAddResult {
+    let recurrence = parsed.repeat.as_ref().map(|repeat| {
+        let rule = rrule_string(repeat);
+        RecurrenceRule::parse(&rule).map(|rule| rule.to_string()).unwrap_or(rule)
+    });
+    let is_event = parsed.duration.is_some() || parsed.end_time.is_some();
+
+    let mut date = parsed.date;
+    if date.is_none() {
+        if let Some(repeat) = &parsed.repeat {
+            date = Some(first_occurrence(repeat, parsed.time, now));
+        } else if let Some(time) = parsed.time {
+            // A bare time means the next time the clock shows it
+            date = Some(if time > now.time() { now.date() } else { now.date() + Duration::days(1) });
+        }
+    }
+
+    let item = if is_event {
+        let date = date.unwrap_or(now.date());
+        let start = date.and_time(parsed.time.unwrap_or_else(|| NaiveTime::from_hms_opt(9, 0, 0).unwrap()));
+        let end = match (parsed.end_time, parsed.duration) {
+            (Some(end_time), _) => {
+                let end = date.and_time(end_time);
+                if end > start { end } else { end + Duration::days(1) }
+            }
+            (None, Some(duration)) => start + duration,
+            (None, None) => start + Duration::hours(1),
+        };
+        QuickAddItem::Event(CreateEvent {
+            title,
+            description: None,
+            start_time: start.format(DATETIME_FORMAT).to_string(),
+            end_time: end.format(DATETIME_FORMAT).to_string(),
+            reminder_time: None,
+            recurrence,
+            location: None,
+            all_day: false,
+            exdates: Vec::new(),
+            time_zone: None,
+        })
+    } else {
+        let due_date = date.map(|date| match parsed.time {
+            Some(time) => date.and_time(time).format(DATETIME_FORMAT).to_string(),
+            None => date.format(DATE_FORMAT).to_string(),
+        });
+        QuickAddItem::Task(CreateTask {
+            title,
+            description: None,
+            status: None,
+            priority: parsed.priority,
+            due_date,
+            reminder_time: None,
+            parent_id: None,
+            recurrence,
+            estimate: None,
+            time_zone: None,
+        })
+    };
+
+    QuickAddResult { item, tags: parsed.tags, source: source.to_string() }
+}
+
+fn rrule_string(repeat: &Repeat) -> String {
+    let mut rule = format!("FREQ={}", repeat.freq);
+    if repeat.interval > 1 {
+        rule.push_str(&format!(";INTERVAL={}", repeat.interval));
+    }
+    if !repeat.by_day.is_empty() {
+        let days: Vec<&str> = repeat.by_day.iter().map(|d| weekday_code(*d)).collect();
+        rule.push_str(&format!(";BYDAY={}", days.join(",")));
+    }
+    rule
+}
+
+/// First date of a series that starts without an explicit date: the next
+/// matching weekday, or today unless a daily time has already passed.
+fn first_occurrence(repeat: &Repeat, time: Option<NaiveTime>, now: NaiveDateTime) -> NaiveDate {
+    let passed = |date: NaiveDate| date == now.date() && time.map(|t| t <= now.time()).unwrap_or(false);
+    if !repeat.by_day.is_empty() {
+        let mut date = now.date();
+        while !repeat.by_day.contains(&date.weekday()) || passed(date) {
+            date += Duration::days(1);
+        }
+        return date;
+    }
+    if repeat.freq == "DAILY" && passed(now.date()) {
+        return now.date() + Duration::days(1);
+    }
+    now.date()
+}
+
+fn looks_temporal(word: &str) -> bool {
+    let word = word.trim_end_matches([',', ';', '.']).to_lowercase();
+    TIME_WORDS.contains(&word.as_str()) || parse_clock(&word).is_some()
+}
+
+/// Try every matcher at the start of `words`; returns how many words matched.
+/// `relaxed` allows forms that are too ambiguous on their own, such as
+/// weekday abbreviations and bare hours, after "on", "at" or another match.
+fn match_at(words: &[&str], parsed: &mut Parsed, now: NaiveDateTime, relaxed: bool) -> Option<usize> {
+    let first = *words.first()?;
+
+    if let Some(priority) = priority_marker(first) {
+        parsed.priority = Some(priority.to_string());
+        return Some(1);
+    }
+
+    if let Some((len, repeat, time)) = repeat_phrase(words) {
+        parsed.repeat = Some(repeat);
+        if time.is_some() && parsed.time.is_none() {
+            parsed.time = time;
+        }
+        return Some(len);
+    }
+
+    if first == "for" {
+        let (len, duration) = duration_phrase(&words[1..])?;
+        parsed.duration = Some(duration);
+        return Some(len + 1);
+    }
+
+    if let Some((len, date, time)) = relative_phrase(words, now, relaxed) {
+        parsed.date = Some(date);
+        if time.is_some() && parsed.time.is_none() {
+            parsed.time = time;
+        }
+        return Some(len);
+    }
+
+    if let Some((len, date)) = absolute_date(words, now) {
+        parsed.date = Some(date);
+        return Some(len);
+    }
+
+    if let Some((len, start, end)) = time_range(words, relaxed) {
+        parsed.time = Some(start);
+        parsed.end_time = Some(end);
+        return Some(len);
+    }
+
+    if let Some((len, time)) = time_phrase(words, relaxed) {
+        parsed.time = Some(time);
+        return Some(len);
+    }
+
+    None
+}
+
+fn priority_marker(word: &str) -> Option<&'static str> {
+    match word {
+        "!high" | "!h" | "!!!" | "!1" | "!urgent" => Some("high"),
+        "!medium" | "!med" | "!m" | "!!" | "!2" => Some("medium"),
+        "!low" | "!l" | "!" | "!3" => Some("low"),
+        _ => None,
+    }
+}
+
+fn number_word(word: &str) -> Option<u32> {
+    let n = match word {
+        "a" | "an" | "one" => 1,
+        "two" => 2,
+        "three" => 3,
+        "four" => 4,
+        "five" => 5,
+        "six" => 6,
+        "seven" => 7,
+        "eight" => 8,
+        "nine" => 9,
+        "ten" => 10,
+        "eleven" => 11,
+        "twelve" => 12,
+        _ => return word.parse().ok().filter(|n| *n > 0),
+    };
+    Some(n)
+}
+
+fn weekday(word: &str, relaxed: bool) -> Option<Weekday> {
+    let full = match word {
+        "monday" | "mondays" => Some(Weekday::Mon),
+        "tuesday" | "tuesdays" => Some(Weekday::Tue),
+        "wednesday" | "wednesdays" => Some(Weekday::Wed),
+        "thursday" | "thursdays" => Some(Weekday::Thu),
+        "friday" | "fridays" => Some(Weekday::Fri),
+        "saturday" | "saturdays" => Some(Weekday::Sat),
+        "sunday" | "sundays" => Some(Weekday::Sun),
+        _ => None,
+    };
+    if full.is_some() || !relaxed {
+        return full;
+    }
+    match word {
+        "mon" => Some(Weekday::Mon),
+        "tue" | "tues" => Some(Weekday::Tue),
+        "wed" => Some(Weekday::Wed),
+        "thu" | "thur" | "thurs" => Some(Weekday::Thu),
+        "fri" => Some(Weekday::Fri),
+        "sat" => Some(Weekday::Sat),
+        "sun" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+fn month(word: &str) -> Option<u32> {
+    const MONTHS: [&str; 12] = [
+        "january", "february", "march", "april", "may", "june", "july", "august", "september", "october", "november",
+        "december",
+    ];
+    // Full names or abbreviations of at least three letters ("mar", "sept")
+    MONTHS
+        .iter()
+        .position(|m| word.len() >= 3 && m.starts_with(word))
+        .map(|i| i as u32 + 1)
+}
+
+fn day_of_month(word: &str) -> Option<u32> {
+    let digits = word.trim_end_matches("st").trim_end_matches("nd").trim_end_matches("rd").trim_end_matches("th");
+    digits.parse().ok().filter(|d| (1..=31).contains(d))
+}
+
+fn next_weekday(from: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let ahead = (weekday.num_days_from_monday() + 7 - from.weekday().num_days_from_monday()) % 7;
+    from + Duration::days(if ahead == 0 { 7 } else { ahead as i64 })
+}
+
+fn add_months(date: NaiveDate, months: u32) -> NaiveDate {
+    let total = date.year() * 12 + date.month0() as i32 + months as i32;
+    let (year, month) = (total / 12, total as u32 % 12 + 1);
+    (1..=date.day())
+        .rev()
+        .find_map(|day| NaiveDate::from_ymd_opt(year, month, day))
+        .unwrap_or(date)
+}
+
+/// "every weekday", "every other week", "every 2 months", "every mon, thu",
+/// "daily", ... Returns the matched length, the rule and an implied time.
+fn repeat_phrase(words: &[&str]) -> Option<(usize, Repeat, Option<NaiveTime>)> {
+    let simple = |freq| Repeat { freq, interval: 1, by_day: Vec::new() };
+    match words[0] {
+        "daily" => return Some((1, simple("DAILY"), None)),
+        "weekly" => return Some((1, simple("WEEKLY"), None)),
+        "monthly" => return Some((1, simple("MONTHLY"), None)),
+        "yearly" | "annually" => return Some((1, simple("YEARLY"), None)),
+        "every" => {}
+        _ => return None,
+    }
+
+    let mut len = 1;
+    let mut interval = 1;
+    match words.get(len).copied() {
+        Some("other") => {
+            interval = 2;
+            len += 1;
+        }
+        Some(word) if word.parse::<u32>().is_ok() => {
+            interval = number_word(word)?;
+            len += 1;
+        }
+        _ => {}
+    }
+
+    let unit = *words.get(len)?;
+    len += 1;
+    let weekdays = vec![Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri];
+    let (freq, by_day, time) = match unit {
+        "day" | "days" => ("DAILY", Vec::new(), None),
+        "morning" => ("DAILY", Vec::new(), NaiveTime::from_hms_opt(9, 0, 0)),
+        "evening" => ("DAILY", Vec::new(), NaiveTime::from_hms_opt(18, 0, 0)),
+        "night" => ("DAILY", Vec::new(), NaiveTime::from_hms_opt(20, 0, 0)),
+        "week" | "weeks" => ("WEEKLY", Vec::new(), None),
+        "fortnight" => {
+            interval *= 2;
+            ("WEEKLY", Vec::new(), None)
+        }
+        "month" | "months" => ("MONTHLY", Vec::new(), None),
+        "year" | "years" => ("YEARLY", Vec::new(), None),
+        "weekday" | "weekdays" => ("WEEKLY", weekdays, None),
+        "weekend" | "weekends" => ("WEEKLY", vec![Weekday::Sat, Weekday::Sun], None),
+        _ => {
+            // A list of weekdays: "mon,wed", "monday and thursday", "tue, fri"
+            let mut days = Vec::new();
+            len -= 1;
+            while let Some(word) = words.get(len) {
+                let parts: Vec<&str> = word.split(',').filter(|p| !p.is_empty()).collect();
+                let list: Option<Vec<Weekday>> = parts.iter().map(|p| weekday(p, true)).collect();
+                match list {
+                    Some(list) if !list.is_empty() => {
+                        days.extend(list);
+                        len += 1;
+                    }
+                    _ if (*word == "and" || *word == "&") && !days.is_empty() => {
+                        if words.get(len + 1).and_then(|w| weekday(w, true)).is_none() {
+                            break;
+                        }
+                        len += 1;
+                    }
+                    _ => break,
+                }
+            }
+            if days.is_empty() {
+                return None;
+            }
+            days.sort_by_key(|d| d.num_days_from_monday());
+            days.dedup();
+            ("WEEKLY", days, None)
+        }
+    };
+
+    Some((len, Repeat { freq, interval, by_day }, time))
+}
+
+/// "today", "tonight", "tomorrow", "next week", "in 3 days", "friday", ...
+fn relative_phrase(words: &[&str], now: NaiveDateTime, relaxed: bool) -> Option<(usize, NaiveDate, Option<NaiveTime>)> {
+    let today = now.date();
+    let at = |h| NaiveTime::from_hms_opt(h, 0, 0);
+    match words[0] {
+        "today" => return Some((1, today, None)),
+        "tonight" => return Some((1, today, at(20))),
+        "tomorrow" | "tmrw" | "tmr" => return Some((1, today + Duration::days(1), None)),
+        "the" if words.get(1..4) == Some(&["day", "after", "tomorrow"][..]) => {
+            return Some((4, today + Duration::days(2), None))
+        }
+        "day" if words.get(1..3) == Some(&["after", "tomorrow"][..]) => return Some((3, today + Duration::days(2), None)),
+        "next" | "this" => {
+            let word = *words.get(1)?;
+            let date = match word {
+                "week" if words[0] == "next" => next_weekday(today, Weekday::Mon),
+                "month" if words[0] == "next" => add_months(today.with_day(1)?, 1),
+                "year" if words[0] == "next" => NaiveDate::from_ymd_opt(today.year() + 1, 1, 1)?,
+                "weekend" => {
+                    if today.weekday() == Weekday::Sat && words[0] == "this" {
+                        today
+                    } else {
+                        next_weekday(today, Weekday::Sat)
+                    }
+                }
+                _ => {
+                    let day = weekday(word, true)?;
+                    if words[0] == "this" && today.weekday() == day {
+                        today
+                    } else {
+                        next_weekday(today, day)
+                    }
+                }
+            };
+            return Some((2, date, None));
+        }
+        "in" => {
+            // "in 3 days", "in two weeks", "in 2h", "in 30 minutes"
+            let (len, amount, unit) = match words.get(1).and_then(|w| split_amount(w)) {
+                Some((amount, unit)) => (2, amount, unit.to_string()),
+                None => (3, number_word(words.get(1)?)?, words.get(2)?.to_string()),
+            };
+            let target = match unit.as_str() {
+                "day" | "days" | "d" => (today + Duration::days(amount as i64)).and_time(NaiveTime::MIN),
+                "week" | "weeks" | "w" => (today + Duration::weeks(amount as i64)).and_time(NaiveTime::MIN),
+                "month" | "months" => add_months(today, amount).and_time(NaiveTime::MIN),
+                "hour" | "hours" | "h" | "hr" | "hrs" => now + Duration::hours(amount as i64),
+                "minute" | "minutes" | "min" | "mins" | "m" => now + Duration::minutes(amount as i64),
+                _ => return None,
+            };
+            let time = if target.time() == NaiveTime::MIN { None } else { target.time().with_second(0) };
+            return Some((len, target.date(), time));
+        }
+        _ => {}
+    }
+
+    let day = weekday(words[0], relaxed)?;
+    Some((1, next_weekday(today, day), None))
+}
+
+/// "3d", "2h", "30m" into (3, "d")
+fn split_amount(word: &str) -> Option<(u32, &str)> {
+    let split = word.find(|c: char| !c.is_ascii_digit())?;
+    let (digits, unit) = word.split_at(split);
+    Some((digits.parse().ok().filter(|n| *n > 0)?, unit))
+}
+
+/// "2024-03-05", "march 5", "mar 5th 2025", "5 march", "5th of march"
+fn absolute_date(words: &[&str], now: NaiveDateTime) -> Option<(usize, NaiveDate)> {
+    if let Ok(date) = NaiveDate::parse_from_str(words[0], DATE_FORMAT) {
+        return Some((1, date));
+    }
+
+    let (len, month, day) = if let Some(month) = month(words[0]) {
+        (2, month, day_of_month(words.get(1)?)?)
+    } else {
+        let day = day_of_month(words[0])?;
+        match words.get(1).copied() {
+            Some("of") => (3, month(words.get(2)?)?, day),
+            Some(word) => (2, month(word)?, day),
+            None => return None,
+        }
+    };
+
+    if let Some(year) = words.get(len).and_then(|w| w.parse::<i32>().ok()).filter(|y| (1970..=2100).contains(y)) {
+        return Some((len + 1, NaiveDate::from_ymd_opt(year, month, day)?));
+    }
+    // Without a year, a date that has passed means next year
+    let this_year = NaiveDate::from_ymd_opt(now.year(), month, day);
+    let date = match this_year {
+        Some(date) if date >= now.date() => date,
+        _ => NaiveDate::from_ymd_opt(now.year() + 1, month, day)?,
+    };
+    Some((len, date))
+}
+
+/// A clock time in one word: "5pm", "5:30pm", "17:00", "9.30am", "noon".
+/// Returns the time and whether it carried am/pm.
+fn parse_clock(word: &str) -> Option<(NaiveTime, bool)> {
+    match word {
+        "noon" | "midday" => return Some((NaiveTime::from_hms_opt(12, 0, 0)?, true)),
+        "midnight" => return Some((NaiveTime::MIN, true)),
+        _ => {}
+    }
+
+    let (digits, meridiem) = if let Some(d) = word.strip_suffix("am") {
+        (d, Some(false))
+    } else if let Some(d) = word.strip_suffix("pm") {
+        (d, Some(true))
+    } else {
+        (word, None)
+    };
+    let (hour, minute) = match digits.split_once([':', '.']) {
+        Some((h, m)) if m.len() == 2 => (h.parse::<u32>().ok()?, m.parse::<u32>().ok()?),
+        // A bare number is only a time with am/pm attached
+        None if meridiem.is_some() => (digits.parse::<u32>().ok()?, 0),
+        _ => return None,
+    };
+    if digits.is_empty() || minute > 59 {
+        return None;
+    }
+
+    let hour = match meridiem {
+        Some(pm) if (1..=12).contains(&hour) => hour % 12 + if pm { 12 } else { 0 },
+        Some(_) => return None,
+        None if hour <= 23 => hour,
+        None => return None,
+    };
+    Some((NaiveTime::from_hms_opt(hour, minute, 0)?, meridiem.is_some()))
+}
+
+/// A clock time, possibly split over two words ("5 pm") or given as a bare
+/// hour after "at". Bare hours from 1 to 6 are read as afternoon times.
+fn time_phrase(words: &[&str], relaxed: bool) -> Option<(usize, NaiveTime)> {
+    if let Some(next) = words.get(1) {
+        if *next == "am" || *next == "pm" {
+            if let Some((time, _)) = parse_clock(&format!("{}{}", words[0], next)) {
+                return Some((2, time));
+            }
+        }
+    }
+    if let Some((time, _)) = parse_clock(words[0]) {
+        return Some((1, time));
+    }
+    if relaxed {
+        match words[0] {
+            "morning" => return Some((1, NaiveTime::from_hms_opt(9, 0, 0)?)),
+            "afternoon" => return Some((1, NaiveTime::from_hms_opt(14, 0, 0)?)),
+            "evening" => return Some((1, NaiveTime::from_hms_opt(18, 0, 0)?)),
+            "night" => return Some((1, NaiveTime::from_hms_opt(20, 0, 0)?)),
+            _ => {}
+        }
+        if let Ok(hour) = words[0].parse::<u32>() {
+            let hour = if (1..=6).contains(&hour) { hour + 12 } else { hour };
+            return Some((1, NaiveTime::from_hms_opt(hour, 0, 0)?));
+        }
+    }
+    None
+}
+
+/// "9-10am", "9am-10:30am", "14:00-15:00", "2pm to 3pm", "9 to 10am"
+fn time_range(words: &[&str], relaxed: bool) -> Option<(usize, NaiveTime, NaiveTime)> {
+    let (len, start, end) = if let Some((start, end)) = words[0].split_once('-').filter(|(a, b)| !a.is_empty() && !b.is_empty()) {
+        (1, start.to_string(), end.to_string())
+    } else {
+        let separator = *words.get(1)?;
+        if !["-", "to", "until", "till"].contains(&separator) {
+            return None;
+        }
+        let end = words.get(2)?;
+        let (len, end) = match words.get(3) {
+            Some(m) if *m == "am" || *m == "pm" => (4, format!("{}{}", end, m)),
+            _ => (3, end.to_string()),
+        };
+        (len, words[0].to_string(), end)
+    };
+
+    let (end, end_meridiem) = parse_clock(&end)?;
+    let start = match parse_clock(&start) {
+        Some((time, _)) => time,
+        // "9-10am": the start borrows the end's am/pm unless that puts it after the end
+        None if end_meridiem || relaxed => {
+            let hour: u32 = start.parse().ok().filter(|h| (1..=12).contains(h))?;
+            let same_half = (hour % 12) + if end.hour() >= 12 { 12 } else { 0 };
+            let hour = if same_half <= end.hour() { same_half } else { hour % 12 };
+            NaiveTime::from_hms_opt(hour, 0, 0)?
+        }
+        None => return None,
+    };
+    Some((len, start, end))
+}
+
+/// "15m", "1h30m", "1.5h", "90 minutes", "2 hours", "an hour", "half an hour"
+fn duration_phrase(words: &[&str]) -> Option<(usize, Duration)> {
+    let first = *words.first()?;
+    if first == "half" && words.get(1..3) == Some(&["an", "hour"][..]) {
+        return Some((3, Duration::minutes(30)));
+    }
+
+    if let Some(unit) = words.get(1) {
+        if let Some(minutes) = unit_minutes(unit) {
+            let amount: f64 = match number_word(first) {
+                Some(n) => n as f64,
+                None => first.parse().ok().filter(|n: &f64| *n > 0.0)?,
+            };
+            return Some((2, Duration::minutes((amount * minutes) as i64)));
+        }
+    }
+
+    // Compact forms: "15m", "1h30m", "1h30", "1.5h"
+    let mut total = 0.0;
+    let mut rest = first;
+    while !rest.is_empty() {
+        let split = rest.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(rest.len());
+        let amount: f64 = rest[..split].parse().ok()?;
+        rest = &rest[split..];
+        let unit_end = rest.find(|c: char| c.is_ascii_digit()).unwrap_or(rest.len());
+        let unit = &rest[..unit_end];
+        rest = &rest[unit_end..];
+        // "1h30": trailing number after hours is minutes
+        let minutes = if unit.is_empty() && total > 0.0 { 1.0 } else { unit_minutes(unit)? };
+        total += amount * minutes;
+    }
+    if total <= 0.0 {
+        return None;
+    }
+    Some((1, Duration::minutes(total as i64)))
+}
+
+fn unit_minutes(unit: &str) -> Option<f64> {
+    match unit {
+        "m" | "min" | "mins" | "minute" | "minutes" => Some(1.0),
+        "h" | "hr" | "hrs" | "hour" | "hours" => Some(60.0),
+        "d" | "day" | "days" => Some(1440.0),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn now() -> NaiveDateTime {
+        // A Wednesday afternoon
+        NaiveDateTime::parse_from_str("2024-05-15 14:00", "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn task(input: &str) -> (CreateTask, Vec<String>) {
+        let result = parse_quick_add(input, now()).unwrap();
+        match result.item {
+            QuickAddItem::Task(task) => (task, result.tags),
+            QuickAddItem::Event(event) => panic!("expected a task, got event {:?}", event),
+        }
+    }
+
+    fn event(input: &str) -> CreateEvent {
+        match parse_quick_add(input, now()).unwrap().item {
+            QuickAddItem::Event(event) => event,
+            QuickAddItem::Task(task) => panic!("expected an event, got task {:?}", task),
+        }
+    }
+
+    #[test]
+    fn parses_tasks_with_dates_priority_and_tags() {
+        let (t, tags) = task("Send budget to Ana tomorrow 5pm !high #finance");
+        assert_eq!(t.title, "Send budget to Ana");
+        assert_eq!(t.due_date.as_deref(), Some("2024-05-16T17:00"));
+        assert_eq!(t.priority.as_deref(), Some("high"));
+        assert_eq!(tags, vec!["finance"]);
+
+        assert_eq!(task("Pay rent on fri").0.due_date.as_deref(), Some("2024-05-17"));
+        assert_eq!(task("Renew passport march 3rd").0.due_date.as_deref(), Some("2025-03-03"));
+        assert_eq!(task("Call back in 2 hours").0.due_date.as_deref(), Some("2024-05-15T16:00"));
+        assert_eq!(task("Book flights next week").0.due_date.as_deref(), Some("2024-05-20"));
+        assert_eq!(task("Water plants at 9").0.due_date.as_deref(), Some("2024-05-16T09:00"));
+        assert_eq!(task("Buy sun cream").0.due_date, None);
+    }
+
+    #[test]
+    fn parses_recurring_events() {
+        let e = event("Standup every weekday 9:30 for 15m");
+        assert_eq!(e.title, "Standup");
+        assert_eq!(e.start_time, "2024-05-16T09:30");
+        assert_eq!(e.end_time, "2024-05-16T09:45");
+        assert_eq!(e.recurrence.as_deref(), Some("FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR"));
+
+        let e = event("Gym every mon and thu 6-7pm");
+        assert_eq!(e.start_time, "2024-05-16T18:00");
+        assert_eq!(e.end_time, "2024-05-16T19:00");
+        assert_eq!(e.recurrence.as_deref(), Some("FREQ=WEEKLY;BYDAY=MO,TH"));
+
+        let (t, _) = task("Review budget every other week");
+        assert_eq!(t.recurrence.as_deref(), Some("FREQ=WEEKLY;INTERVAL=2"));
+    }
+
+    #[test]
+    fn leaves_unclear_input_to_the_fallback() {
+        assert!(parse_quick_add("Dentist every second tuesday", now()).is_none());
+        assert!(parse_quick_add("#finance !high", now()).is_none());
+
+        let ai = parse_ai_response(
+            "Sure! {\"kind\": \"event\", \"title\": \"Dentist\", \"start\": \"2024-05-29T10:00\", \"tags\": [\"#health\"], \"recurrence\": \"bogus\"}",
+            "Dentist",
+        )
+        .unwrap();
+        assert_eq!(ai.tags, vec!["health"]);
+        match ai.item {
+            QuickAddItem::Event(e) => {
+                assert_eq!(e.end_time, "2024-05-29T11:00");
+                assert_eq!(e.recurrence, None);
+            }
+            _ => panic!("expected an event"),
+        }
+        assert!(parse_ai_response("no json here", "x").is_none());
+    }
+}
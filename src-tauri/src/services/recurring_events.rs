@@ -0,0 +1,555 @@
+// Recurring Event Service - expand RRULE series into occurrences and manage
+// their exceptions
+//
+// A series is one stored event with a `recurrence` rule. Deleted occurrences
+// are listed in its `exdates`; an edited occurrence is stored as its own row
+// with `series_id` pointing at the series and `recurrence_id` holding the
+// start it replaces. Changing a series "from this date on" splits it in two.
+//
+// Timed series repeat in the wall time of their own zone, so a 09:00 meeting
+// stays at 09:00 across DST changes; comparisons are made on UTC instants.
+use crate::models::event::{CalendarEvent, CreateEvent, UpdateEvent};
+use crate::services::database::Database;
+use crate::services::rrule::{format_stored_datetime, parse_stored_datetime, DateStyle, RecurrenceRule};
+use crate::services::time_zones::{instant, parse_zone, user_zone, utc_to_zoned, zoned_to_utc};
+use chrono::NaiveDateTime;
+use chrono_tz::Tz;
+use rusqlite::{params, Result};
+
+/// Upper bound on occurrences produced per series and query
+const MAX_OCCURRENCES: usize = 2000;
+
+/// Local events plus those of enabled calendar subscriptions
+const VISIBLE_LAYERS: &str =
+    "(subscription_id IS NULL OR subscription_id IN (SELECT id FROM calendar_subscriptions WHERE enabled = 1))";
+
+fn invalid(message: String) -> rusqlite::Error {
+    rusqlite::Error::InvalidParameterName(message)
+}
+
+/// Range bounds as UTC instants; dates and wall times are in the user's zone
+fn range_bound(value: &str, zone: Tz) -> Result<NaiveDateTime> {
+    instant(value, zone).ok_or_else(|| invalid(format!("Invalid date: {}", value)))
+}
+
+/// Stored times come as dates, wall times, SQL times and RFC 3339 with any
+/// offset, so SQL can only compare their text. Padding the window by a day
+/// on each side keeps every row that might fall inside it; the exact check
+/// is then made on UTC instants.
+fn text_window(from: NaiveDateTime, to: NaiveDateTime) -> (String, String) {
+    let day = chrono::Duration::days(1);
+    ((from.date() - day).to_string(), (to.date() + day + day).to_string())
+}
+
+/// The zone a series repeats in: its own, or `zone` for all-day and older events
+fn series_zone(series: &CalendarEvent, zone: Tz) -> Tz {
+    series.time_zone.as_deref().and_then(|name| parse_zone(name).ok()).unwrap_or(zone)
+}
+
+/// Starts of a series stepped in the wall time of its zone, each as
+/// (value in the series' stored form, UTC instant)
+fn series_starts<'r>(
+    series: &CalendarEvent,
+    rule: &'r RecurrenceRule,
+    zone: Tz,
+) -> Option<impl Iterator<Item = (NaiveDateTime, NaiveDateTime)> + 'r> {
+    let tz = series_zone(series, zone);
+    let (first, style) = parse_stored_datetime(&series.start_time)?;
+    let utc = matches!(style, DateStyle::Utc | DateStyle::Sql);
+    let first_wall = if utc { utc_to_zoned(tz, first) } else { first };
+    Some(rule.iter(first_wall).filter_map(move |wall| {
+        let at = zoned_to_utc(tz, wall)?;
+        Some((if utc { at } else { wall }, at))
+    }))
+}
+
+/// Move a stored time by the same amount as the occurrence moved from the series start
+fn shift(value: &str, by: chrono::Duration) -> Option<String> {
+    let (at, style) = parse_stored_datetime(value)?;
+    Some(format_stored_datetime(at + by, style))
+}
+
+/// Occurrences of `series` starting within [start, end], skipping EXDATEs and
+/// the starts in `replaced` (occurrences stored as edited rows). Bounds and
+/// `replaced` are UTC instants; all-day dates are read in `zone`, the user's.
+pub fn expand_series(
+    series: &CalendarEvent,
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+    replaced: &[NaiveDateTime],
+    zone: Tz,
+) -> Vec<CalendarEvent> {
+    let rule = match series.recurrence.as_deref().map(RecurrenceRule::parse) {
+        Some(Ok(rule)) => rule,
+        _ => return Vec::new(),
+    };
+    let (first, style) = match parse_stored_datetime(&series.start_time) {
+        Some(parsed) => parsed,
+        None => return Vec::new(),
+    };
+    let starts = match series_starts(series, &rule, zone) {
+        Some(starts) => starts,
+        None => return Vec::new(),
+    };
+    let tz = series_zone(series, zone);
+    let excluded: Vec<NaiveDateTime> = series.exdates.iter().filter_map(|d| instant(d, tz)).chain(replaced.iter().copied()).collect();
+    let first_at = instant(&series.start_time, tz).unwrap_or(first);
+    let reminder = series.reminder_time.as_deref().and_then(|r| instant(r, tz)).map(|r| r - first_at);
+
+    starts
+        .take_while(|(_, at)| *at <= end)
+        .filter(|(_, at)| !excluded.contains(at))
+        .map(|(stored, at)| {
+            let mut occurrence = series.clone();
+            occurrence.start_time = format_stored_datetime(stored, style);
+            occurrence.end_time = shift(&series.end_time, stored - first).unwrap_or_else(|| series.end_time.clone());
+            occurrence.reminder_time = reminder.map(|r| format_stored_datetime(at + r, DateStyle::Utc));
+            occurrence.series_id = Some(series.id);
+            occurrence.occurrence_date = Some(occurrence.start_time.clone());
+            occurrence
+        })
+        // Occurrences that started before the window but are still running count too
+        .filter(|occurrence| instant(&occurrence.end_time, tz).map(|e| e >= start).unwrap_or(false))
+        .take(MAX_OCCURRENCES)
+        .collect()
+}
+
+pub struct RecurringEventService<'a> {
+    db: &'a Database,
+}
+
+impl<'a> RecurringEventService<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    fn query(&self, condition: &str, values: &[&dyn rusqlite::ToSql]) -> Result<Vec<CalendarEvent>> {
+        let mut stmt = self.db.get_connection().prepare(&format!(
+            "SELECT {} FROM events WHERE ({}) AND {} ORDER BY start_time ASC",
+            Database::EVENT_COLUMNS,
+            condition,
+            VISIBLE_LAYERS
+        ))?;
+        let events = stmt.query_map(values, Database::event_from_row)?;
+        events.collect()
+    }
+
+    fn zone(&self) -> Tz {
+        user_zone(self.db.get_connection())
+    }
+
+    /// Start instants of the occurrences a series' edited rows replace
+    fn replaced_starts(&self, series: &CalendarEvent, zone: Tz) -> Result<Vec<NaiveDateTime>> {
+        let tz = series_zone(series, zone);
+        Ok(self
+            .get_edited_occurrences(series.id)?
+            .iter()
+            .filter_map(|e| e.occurrence_date.as_deref().and_then(|d| instant(d, tz)))
+            .collect())
+    }
+
+    /// Events and expanded occurrences starting within [start, end]. Bounds
+    /// without an offset are wall time in the user's zone.
+    pub fn get_events_in_range(&self, start: &str, end: &str) -> Result<Vec<CalendarEvent>> {
+        let zone = self.zone();
+        let (from, to) = (range_bound(start, zone)?, range_bound(end, zone)?);
+        let (after, before) = text_window(from, to);
+        let mut events: Vec<CalendarEvent> = self
+            .query(
+                "(recurrence IS NULL OR recurrence = '' OR recurrence_id IS NOT NULL) AND start_time >= ?1 AND start_time < ?2",
+                &[&after, &before],
+            )?
+            .into_iter()
+            .filter(|e| instant(&e.start_time, zone).map(|at| at >= from && at <= to).unwrap_or(false))
+            .collect();
+
+        for series in self.get_series(to, zone)? {
+            let replaced = self.replaced_starts(&series, zone)?;
+            events.extend(expand_series(&series, from, to, &replaced, zone));
+        }
+
+        events.sort_by_key(|e| instant(&e.start_time, zone));
+        Ok(events)
+    }
+
+    /// Events and occurrences overlapping [from, to), given as UTC instants.
+    /// Events without duration count when they start inside the span.
+    pub fn get_events_overlapping(&self, from: NaiveDateTime, to: NaiveDateTime) -> Result<Vec<CalendarEvent>> {
+        let zone = self.zone();
+        let overlaps = |e: &CalendarEvent| match (instant(&e.start_time, zone), instant(&e.end_time, zone)) {
+            (Some(start), Some(end)) => start < to && (end > from || start >= from),
+            _ => false,
+        };
+        let (after, before) = text_window(from, to);
+        let mut events: Vec<CalendarEvent> = self
+            .query(
+                "(recurrence IS NULL OR recurrence = '' OR recurrence_id IS NOT NULL)
+                 AND start_time < ?2 AND (end_time >= ?1 OR start_time >= ?1)",
+                &[&after, &before],
+            )?
+            .into_iter()
+            .filter(|e| overlaps(e))
+            .collect();
+
+        for series in self.get_series(to, zone)? {
+            let replaced = self.replaced_starts(&series, zone)?;
+            events.extend(expand_series(&series, from, to, &replaced, zone).into_iter().filter(|e| overlaps(e)));
+        }
+
+        events.sort_by_key(|e| instant(&e.start_time, zone));
+        Ok(events)
+    }
+
+    /// Local rows touching [start, end] without expansion: single events and
+    /// edited occurrences in the range plus every series with an occurrence in it.
+    /// Events from calendar subscriptions are left out.
+    pub fn get_stored_events_in_range(&self, start: &str, end: &str) -> Result<Vec<CalendarEvent>> {
+        let zone = self.zone();
+        let (from, to) = (range_bound(start, zone)?, range_bound(end, zone)?);
+        let mut events = self.get_events_in_range(start, end)?;
+        // Expanded occurrences carry the id of their series; keep the stored rows only
+        events.retain(|e| e.series_id != Some(e.id) && e.subscription_id.is_none());
+        for series in self.get_series(to, zone)? {
+            if series.subscription_id.is_none() && !expand_series(&series, from, to, &[], zone).is_empty() {
+                events.push(series);
+            }
+        }
+        events.sort_by_key(|e| instant(&e.start_time, zone));
+        Ok(events)
+    }
+
+    /// Series whose first occurrence is no later than `until`
+    fn get_series(&self, until: NaiveDateTime, zone: Tz) -> Result<Vec<CalendarEvent>> {
+        let (_, before) = text_window(until, until);
+        Ok(self
+            .query(
+                "recurrence IS NOT NULL AND recurrence != '' AND recurrence_id IS NULL AND start_time < ?1",
+                &[&before],
+            )?
+            .into_iter()
+            .filter(|e| instant(&e.start_time, zone).map(|at| at <= until).unwrap_or(false))
+            .collect())
+    }
+
+    pub fn get_edited_occurrences(&self, series_id: i64) -> Result<Vec<CalendarEvent>> {
+        self.query("series_id = ?1 AND recurrence_id IS NOT NULL", &[&series_id])
+    }
+
+    fn get_series_event(&self, series_id: i64) -> Result<(CalendarEvent, RecurrenceRule)> {
+        let series = self.db.get_event(series_id)?;
+        if series.occurrence_date.is_some() {
+            return Err(invalid(format!("Event {} is an occurrence, not a series", series_id)));
+        }
+        let rule = series
+            .recurrence
+            .as_deref()
+            .filter(|r| !r.trim().is_empty())
+            .ok_or_else(|| invalid(format!("Event {} does not repeat", series_id)))
+            .and_then(|r| RecurrenceRule::parse(r).map_err(invalid))?;
+        Ok((series, rule))
+    }
+
+    /// Check that `date` is an occurrence of the series; returns its start
+    /// in the series' stored form and as a UTC instant
+    fn occurrence_at(series: &CalendarEvent, rule: &RecurrenceRule, date: &str, zone: Tz) -> Result<(NaiveDateTime, NaiveDateTime)> {
+        let tz = series_zone(series, zone);
+        let at = instant(date, tz).ok_or_else(|| invalid(format!("Invalid occurrence date: {}", date)))?;
+        series_starts(series, rule, zone)
+            .ok_or_else(|| invalid(format!("Invalid start: {}", series.start_time)))?
+            .take_while(|(_, o)| *o <= at)
+            .find(|(_, o)| *o == at)
+            .ok_or_else(|| invalid(format!("{} is not an occurrence of event {}", date, series.id)))
+    }
+
+    fn find_edited(&self, series: &CalendarEvent, at: NaiveDateTime, zone: Tz) -> Result<Option<CalendarEvent>> {
+        let tz = series_zone(series, zone);
+        Ok(self
+            .get_edited_occurrences(series.id)?
+            .into_iter()
+            .find(|e| e.occurrence_date.as_deref().and_then(|d| instant(d, tz)) == Some(at)))
+    }
+
+    /// Whether a stored value of the series is at or after the instant `at`
+    fn is_from(series: &CalendarEvent, value: &str, at: NaiveDateTime, zone: Tz) -> bool {
+        instant(value, series_zone(series, zone)).map(|d| d >= at).unwrap_or(false)
+    }
+
+    /// Store an occurrence as its own row, replacing that date of the series
+    pub fn attach_occurrence(&self, series_id: i64, occurrence_date: &str, event: CreateEvent) -> Result<CalendarEvent> {
+        let created = self.db.create_event(event)?;
+        self.db.get_connection().execute(
+            "UPDATE events SET series_id = ?1, recurrence_id = ?2, recurrence = NULL, exdates = NULL WHERE id = ?3",
+            params![series_id, occurrence_date, created.id],
+        )?;
+        self.db.get_event(created.id)
+    }
+
+    /// Change a single occurrence, leaving the rest of the series as it is
+    pub fn edit_occurrence(&self, series_id: i64, occurrence_date: &str, update: UpdateEvent) -> Result<CalendarEvent> {
+        let (series, rule) = self.get_series_event(series_id)?;
+        let zone = self.zone();
+        let (_, at) = Self::occurrence_at(&series, &rule, occurrence_date, zone)?;
+        let update = UpdateEvent { recurrence: None, exdates: None, ..update };
+
+        let conn = self.db.get_connection();
+        let tx = conn.unchecked_transaction()?;
+        let edited = match self.find_edited(&series, at, zone)? {
+            Some(edited) => self.db.update_event(edited.id, update)?,
+            None => {
+                let occurrence = expand_series(&series, at, at, &[], zone)
+                    .pop()
+                    .ok_or_else(|| invalid(format!("{} was removed from event {}", occurrence_date, series_id)))?;
+                let date = occurrence.start_time.clone();
+                let created = self.attach_occurrence(series_id, &date, CreateEvent {
+                    title: occurrence.title,
+                    description: occurrence.description,
+                    start_time: occurrence.start_time,
+                    end_time: occurrence.end_time,
+                    reminder_time: occurrence.reminder_time,
+                    recurrence: None,
+                    location: occurrence.location,
+                    all_day: occurrence.all_day,
+                    exdates: Vec::new(),
+                    time_zone: occurrence.time_zone,
+                })?;
+                self.db.update_event(created.id, update)?
+            }
+        };
+        tx.commit()?;
+        Ok(edited)
+    }
+
+    /// Remove one occurrence from the series
+    pub fn delete_occurrence(&self, series_id: i64, occurrence_date: &str) -> Result<()> {
+        let (series, rule) = self.get_series_event(series_id)?;
+        let zone = self.zone();
+        let (stored, at) = Self::occurrence_at(&series, &rule, occurrence_date, zone)?;
+
+        let conn = self.db.get_connection();
+        let tx = conn.unchecked_transaction()?;
+        if let Some(edited) = self.find_edited(&series, at, zone)? {
+            self.db.delete_event(edited.id)?;
+        }
+        let style = parse_stored_datetime(&series.start_time).map(|(_, style)| style).unwrap_or(DateStyle::Utc);
+        let tz = series_zone(&series, zone);
+        let mut exdates = series.exdates.clone();
+        if !exdates.iter().any(|d| instant(d, tz) == Some(at)) {
+            exdates.push(format_stored_datetime(stored, style));
+        }
+        self.update_series(series_id, None, Some(exdates))?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn update_series(&self, series_id: i64, recurrence: Option<String>, exdates: Option<Vec<String>>) -> Result<CalendarEvent> {
+        self.db.update_event(series_id, UpdateEvent {
+            title: None,
+            description: None,
+            start_time: None,
+            end_time: None,
+            reminder_time: None,
+            recurrence,
+            location: None,
+            all_day: None,
+            exdates,
+            time_zone: None,
+        })
+    }
+
+    /// End the series just before the instant `at`: the rule gets an UNTIL at
+    /// the previous occurrence, in the series' wall time like the rule itself
+    fn truncate(&self, series: &CalendarEvent, rule: &RecurrenceRule, at: NaiveDateTime, zone: Tz) -> Result<usize> {
+        let before: Vec<NaiveDateTime> = series_starts(series, rule, zone)
+            .into_iter()
+            .flatten()
+            .map(|(_, o)| o)
+            .take_while(|o| *o < at)
+            .collect();
+        let mut ended = rule.clone();
+        ended.count = None;
+        ended.until = before.last().map(|o| utc_to_zoned(series_zone(series, zone), *o));
+
+        let kept: Vec<String> = series.exdates.iter().filter(|d| !Self::is_from(series, d, at, zone)).cloned().collect();
+        self.update_series(series.id, Some(ended.to_string()), Some(kept))?;
+        Ok(before.len())
+    }
+
+    /// Change the series from `from_date` on. The earlier occurrences keep
+    /// the old details; later ones move to a new series, which is returned.
+    pub fn update_following(&self, series_id: i64, from_date: &str, update: UpdateEvent) -> Result<CalendarEvent> {
+        let (series, rule) = self.get_series_event(series_id)?;
+        let zone = self.zone();
+        let (stored, at) = Self::occurrence_at(&series, &rule, from_date, zone)?;
+        let (first, style) = parse_stored_datetime(&series.start_time).ok_or_else(|| invalid(format!("Invalid start: {}", series.start_time)))?;
+        if first == stored {
+            return self.db.update_event(series_id, update);
+        }
+
+        let conn = self.db.get_connection();
+        let tx = conn.unchecked_transaction()?;
+        let done = self.truncate(&series, &rule, at, zone)?;
+
+        let mut rest = rule.clone();
+        rest.count = rule.count.map(|count| count.saturating_sub(done as u32));
+        let by = stored - first;
+        let next = self.db.create_event(CreateEvent {
+            title: series.title.clone(),
+            description: series.description.clone(),
+            start_time: format_stored_datetime(stored, style),
+            end_time: shift(&series.end_time, by).unwrap_or_else(|| series.end_time.clone()),
+            reminder_time: series.reminder_time.as_deref().and_then(|r| shift(r, by)),
+            recurrence: Some(rest.to_string()),
+            location: series.location.clone(),
+            all_day: series.all_day,
+            exdates: series.exdates.iter().filter(|d| Self::is_from(&series, d, at, zone)).cloned().collect(),
+            time_zone: series.time_zone.clone(),
+        })?;
+        for edited in self.get_edited_occurrences(series_id)? {
+            if edited.occurrence_date.as_deref().map(|d| Self::is_from(&series, d, at, zone)).unwrap_or(false) {
+                conn.execute("UPDATE events SET series_id = ?1 WHERE id = ?2", params![next.id, edited.id])?;
+            }
+        }
+        let next = self.db.update_event(next.id, update)?;
+        tx.commit()?;
+        Ok(next)
+    }
+
+    /// Delete the occurrences from `from_date` on; from the first one this deletes the series
+    pub fn delete_following(&self, series_id: i64, from_date: &str) -> Result<()> {
+        let (series, rule) = self.get_series_event(series_id)?;
+        let zone = self.zone();
+        let (_, at) = Self::occurrence_at(&series, &rule, from_date, zone)?;
+        if instant(&series.start_time, series_zone(&series, zone)) == Some(at) {
+            return self.db.delete_event(series_id);
+        }
+
+        let conn = self.db.get_connection();
+        let tx = conn.unchecked_transaction()?;
+        self.truncate(&series, &rule, at, zone)?;
+        for edited in self.get_edited_occurrences(series_id)? {
+            if edited.occurrence_date.as_deref().map(|d| Self::is_from(&series, d, at, zone)).unwrap_or(false) {
+                self.db.delete_event(edited.id)?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dt(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M").unwrap()
+    }
+
+    fn weekly() -> CalendarEvent {
+        CalendarEvent {
+            id: 3,
+            title: "Standup".into(),
+            description: None,
+            start_time: "2024-05-06T09:00".into(),
+            end_time: "2024-05-06T09:15".into(),
+            reminder_time: Some("2024-05-06T08:55".into()),
+            recurrence: Some("FREQ=WEEKLY;BYDAY=MO,WE".into()),
+            location: None,
+            all_day: false,
+            exdates: vec!["2024-05-08T09:00".into()],
+            series_id: None,
+            occurrence_date: None,
+            subscription_id: None,
+            time_zone: None,
+            created_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn expands_occurrences_in_the_window() {
+        let series = weekly();
+        let found = expand_series(&series, dt("2024-05-07T00:00"), dt("2024-05-20T09:00"), &[dt("2024-05-15T09:00")], Tz::UTC);
+        let starts: Vec<&str> = found.iter().map(|e| e.start_time.as_str()).collect();
+        // the 8th is an EXDATE, the 15th is stored as an edited occurrence
+        assert_eq!(starts, vec!["2024-05-13T09:00", "2024-05-20T09:00"]);
+        assert_eq!(found[0].end_time, "2024-05-13T09:15");
+        assert_eq!(found[0].reminder_time.as_deref(), Some("2024-05-13T08:55:00Z"));
+        assert_eq!(found[0].series_id, Some(3));
+        assert_eq!(found[0].occurrence_date.as_deref(), Some("2024-05-13T09:00"));
+    }
+
+    #[test]
+    fn keeps_all_day_dates_and_running_occurrences() {
+        let mut series = weekly();
+        series.start_time = "2024-05-06".into();
+        series.end_time = "2024-05-08".into();
+        series.all_day = true;
+        series.reminder_time = None;
+        series.exdates.clear();
+        // The occurrence from the 6th runs into the window that starts on the 7th
+        let found = expand_series(&series, dt("2024-05-07T12:00"), dt("2024-05-08T23:59"), &[], Tz::UTC);
+        let spans: Vec<(&str, &str)> = found.iter().map(|e| (e.start_time.as_str(), e.end_time.as_str())).collect();
+        assert_eq!(spans, vec![("2024-05-06", "2024-05-08"), ("2024-05-08", "2024-05-10")]);
+    }
+
+    fn event(db: &Database, title: &str, start_time: &str, end_time: &str, recurrence: Option<&str>) -> i64 {
+        db.create_event(CreateEvent {
+            title: title.into(),
+            description: None,
+            start_time: start_time.into(),
+            end_time: end_time.into(),
+            reminder_time: None,
+            recurrence: recurrence.map(str::to_string),
+            location: None,
+            all_day: false,
+            exdates: Vec::new(),
+            time_zone: Some("UTC".into()),
+        })
+        .unwrap()
+        .id
+    }
+
+    #[test]
+    fn range_queries_skip_far_rows_but_keep_offsets_and_series() {
+        let db = Database::in_memory().unwrap();
+        let service = RecurringEventService::new(&db);
+        event(&db, "Last year", "2023-05-06T09:00:00Z", "2023-05-06T10:00:00Z", None);
+        // Older rows can keep their offset: 23:30 on the 5th in Honolulu is the 6th in UTC
+        db.get_connection()
+            .execute(
+                "INSERT INTO events (title, start_time, end_time) VALUES ('Late call', '2024-05-05T23:30:00-10:00', '2024-05-06T00:30:00-10:00')",
+                [],
+            )
+            .unwrap();
+        event(&db, "Workshop", "2024-05-01T09:00:00Z", "2024-05-09T17:00:00Z", None);
+        event(&db, "Standup", "2024-01-01T09:00:00Z", "2024-01-01T09:15:00Z", Some("FREQ=DAILY"));
+
+        let titles = |events: Vec<CalendarEvent>| events.into_iter().map(|e| e.title).collect::<Vec<_>>();
+        assert_eq!(
+            titles(service.get_events_in_range("2024-05-06T00:00:00Z", "2024-05-06T23:59:59Z").unwrap()),
+            vec!["Standup", "Late call"]
+        );
+        assert_eq!(
+            titles(service.get_events_overlapping(dt("2024-05-06T00:00"), dt("2024-05-07T00:00")).unwrap()),
+            vec!["Workshop", "Standup", "Late call"]
+        );
+    }
+
+    #[test]
+    fn zoned_series_keep_their_wall_time_across_dst() {
+        let mut series = weekly();
+        // Mondays at 09:00 in New York: 14:00 UTC in winter, 13:00 UTC after March 10
+        series.start_time = "2024-03-04T14:00:00Z".into();
+        series.end_time = "2024-03-04T15:00:00Z".into();
+        series.reminder_time = Some("2024-03-04T13:45:00Z".into());
+        series.recurrence = Some("FREQ=WEEKLY;UNTIL=20240318T090000".into());
+        series.exdates = vec!["2024-03-11T13:00:00Z".into()];
+        series.time_zone = Some("America/New_York".into());
+        let found = expand_series(&series, dt("2024-03-01T00:00"), dt("2024-03-31T00:00"), &[], Tz::UTC);
+        let spans: Vec<(&str, &str)> = found.iter().map(|e| (e.start_time.as_str(), e.end_time.as_str())).collect();
+        assert_eq!(
+            spans,
+            vec![("2024-03-04T14:00:00Z", "2024-03-04T15:00:00Z"), ("2024-03-18T13:00:00Z", "2024-03-18T14:00:00Z")]
+        );
+        assert_eq!(found[1].reminder_time.as_deref(), Some("2024-03-18T12:45:00Z"));
+    }
+}
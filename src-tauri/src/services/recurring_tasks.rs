@@ -0,0 +1,165 @@
+// Recurring Task Service - generate the next occurrence when a recurring task is completed
+use crate::models::task::{CreateTask, Task};
+use crate::services::database::Database;
+use crate::services::links::{CreateLink, LinksService};
+use crate::services::rrule::{format_stored_datetime, parse_stored_datetime, DateStyle, RecurrenceRule};
+use crate::services::tags::TagService;
+use crate::services::task_structure::TaskStructureService;
+use crate::services::time_zones::{instant, user_zone, utc_to_zoned, zone_or, zoned_to_utc};
+use chrono::{NaiveDateTime, NaiveTime, Utc};
+use rusqlite::{params, Result};
+
+/// Validate an RRULE and return it in canonical form. Empty rules mean "no recurrence".
+pub fn normalize_recurrence(rule: Option<&str>) -> Result<Option<String>> {
+    match rule.map(str::trim).filter(|r| !r.is_empty()) {
+        Some(rule) => RecurrenceRule::parse(rule)
+            .map(|parsed| Some(parsed.to_string()))
+            .map_err(rusqlite::Error::InvalidParameterName),
+        None => Ok(None),
+    }
+}
+
+/// The next `count` dates of a rule starting at `start` (any stored date format)
+pub fn preview_recurrence(rule: &str, start: &str, count: usize) -> Result<Vec<String>> {
+    let rule = RecurrenceRule::parse(rule).map_err(rusqlite::Error::InvalidParameterName)?;
+    let (start, style): (NaiveDateTime, DateStyle) = parse_stored_datetime(start)
+        .ok_or_else(|| rusqlite::Error::InvalidParameterName(format!("Invalid start date: {}", start)))?;
+    Ok(rule
+        .iter(start)
+        .take(count)
+        .map(|occurrence| format_stored_datetime(occurrence, style))
+        .collect())
+}
+
+pub struct RecurringTaskService<'a> {
+    db: &'a Database,
+}
+
+impl<'a> RecurringTaskService<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    /// Create the task for the occurrence after `task`, carrying over its
+    /// checklist (unchecked), links and tags. Occurrences that are already in
+    /// the past are skipped. Returns None when the series has ended or the
+    /// next occurrence already exists.
+    pub fn spawn_next_occurrence(&self, task: &Task) -> Result<Option<Task>> {
+        let rule = match normalize_recurrence(task.recurrence.as_deref())? {
+            Some(rule) => RecurrenceRule::parse(&rule).map_err(rusqlite::Error::InvalidParameterName)?,
+            None => return Ok(None),
+        };
+        let series_id = task.series_id.unwrap_or(task.id);
+        let occurrence = task.occurrence + 1;
+
+        if let Some(count) = rule.count {
+            if task.occurrence >= count as i64 {
+                return Ok(None);
+            }
+        }
+
+        let exists: bool = self.db.get_connection().query_row(
+            "SELECT EXISTS (SELECT 1 FROM tasks WHERE series_id = ?1 AND occurrence = ?2)",
+            params![series_id, occurrence],
+            |row| row.get(0),
+        )?;
+        if exists {
+            return Ok(None);
+        }
+
+        // Step in the wall time of the task's zone so a 09:00 due time stays 09:00 across DST
+        let tz = zone_or(task.time_zone.as_deref(), user_zone(self.db.get_connection()))
+            .map_err(rusqlite::Error::InvalidParameterName)?;
+        let now = utc_to_zoned(tz, Utc::now().naive_utc());
+        let (base, style) = task
+            .due_date
+            .as_deref()
+            .and_then(parse_stored_datetime)
+            .map(|(at, style)| match style {
+                DateStyle::Utc | DateStyle::Sql => (utc_to_zoned(tz, at), style),
+                _ => (at, style),
+            })
+            .unwrap_or_else(|| (now.date().and_time(NaiveTime::MIN), DateStyle::Date));
+
+        // COUNT is enforced through `occurrence`, so step with an open-ended rule
+        let mut stepping = rule.clone();
+        stepping.count = None;
+        let earliest = match style {
+            DateStyle::Date => now.date().and_time(NaiveTime::MIN),
+            _ => now,
+        };
+        let mut upcoming = stepping.iter(base).skip(1);
+        let next_due = if base >= earliest {
+            upcoming.next()
+        } else {
+            upcoming.find(|candidate| *candidate >= earliest)
+        };
+        let next_due = match next_due {
+            Some(next_due) => next_due,
+            None => return Ok(None),
+        };
+
+        let next_due_at = zoned_to_utc(tz, next_due).unwrap_or(next_due);
+        let next_reminder = task.reminder_time.as_deref().and_then(|reminder| {
+            let lead = instant(reminder, tz)? - zoned_to_utc(tz, base)?;
+            Some(format_stored_datetime(next_due_at + lead, DateStyle::Utc))
+        });
+        let next_due = match style {
+            DateStyle::Utc | DateStyle::Sql => format_stored_datetime(next_due_at, DateStyle::Utc),
+            _ => format_stored_datetime(next_due, style),
+        };
+
+        let next = self.db.create_task(CreateTask {
+            title: task.title.clone(),
+            description: task.description.clone(),
+            status: Some("todo".to_string()),
+            priority: Some(task.priority.clone()),
+            due_date: Some(next_due),
+            reminder_time: next_reminder,
+            parent_id: task.parent_id,
+            recurrence: None,
+            estimate: task.estimate,
+            time_zone: Some(tz.name().to_string()),
+        })?;
+
+        let conn = self.db.get_connection();
+        conn.execute(
+            "UPDATE tasks SET recurrence = ?1, series_id = ?2, occurrence = ?3 WHERE id = ?4",
+            params![task.recurrence, series_id, occurrence, next.id],
+        )?;
+
+        self.carry_over(task.id, next.id)?;
+
+        self.db.get_task(next.id).map(Some)
+    }
+
+    fn carry_over(&self, from_id: i64, to_id: i64) -> Result<()> {
+        let structure = TaskStructureService::new(self.db);
+        for item in structure.get_checklist(from_id)? {
+            structure.add_checklist_item(to_id, &item.text, false)?;
+        }
+
+        let links = LinksService::new(self.db.get_connection());
+        for link in links.get_links_for_item("task", from_id)? {
+            let is_source = link.source_type == "task" && link.source_id == from_id;
+            links.create_link(CreateLink {
+                source_type: link.source_type.clone(),
+                source_id: if is_source { to_id } else { link.source_id },
+                target_type: link.target_type.clone(),
+                target_id: if is_source { link.target_id } else { to_id },
+            })?;
+        }
+
+        let tags = TagService::new(self.db.get_connection());
+        let names: Vec<String> = tags
+            .get_tags_for_item("task", from_id)?
+            .into_iter()
+            .map(|tag| tag.name)
+            .collect();
+        if !names.is_empty() {
+            tags.set_item_tags("task", to_id, &names)?;
+        }
+
+        Ok(())
+    }
+}
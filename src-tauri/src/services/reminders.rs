@@ -0,0 +1,505 @@
+// Reminders - a persistent queue of task and event reminders and the loop
+// that fires them as native notifications
+//
+// The queue is rebuilt from `tasks.reminder_time` and `events.reminder_time`
+// on every tick; occurrences of recurring events each get their own entry,
+// keyed by their start. Entries remember whether they fired, were snoozed or
+// dismissed, so a restart neither repeats them nor loses them: reminders that
+// came due while the app was closed fire on the first tick after startup.
+use crate::models::event::CalendarEvent;
+use crate::models::reminder::Reminder;
+use crate::models::task::Task;
+use crate::services::database::Database;
+use crate::services::notifications::NotificationService;
+use crate::services::recurring_events::RecurringEventService;
+use crate::services::rrule::{format_stored_datetime, parse_stored_datetime, DateStyle};
+use crate::services::time_zones::{instant, user_zone, utc_to_zoned, zone_or};
+use chrono::{Duration, NaiveDateTime, Utc};
+use chrono_tz::Tz;
+use rusqlite::{params, OptionalExtension, Result};
+use std::collections::HashSet;
+use std::sync::Arc;
+use tauri::{AppHandle, Manager};
+use tokio::sync::Mutex;
+
+/// Seconds between two checks of the queue
+const CHECK_INTERVAL_SECS: u64 = 30;
+/// Reminders missed by more than this are not shown late
+const CATCH_UP_HOURS: i64 = 24;
+/// How far ahead event occurrences are queued
+const HORIZON_DAYS: i64 = 14;
+/// Fired, dismissed and missed entries are kept this long
+const KEEP_DAYS: i64 = 30;
+/// Frontend event sent for every reminder that fires
+pub const REMINDER_EVENT: &str = "reminder-fired";
+
+const REMINDER_COLUMNS: &str = "id, item_type, item_id, occurrence, title, body, fire_at, snoozed_until, status, fired_at";
+
+fn invalid(message: String) -> rusqlite::Error {
+    rusqlite::Error::InvalidParameterName(message)
+}
+
+fn stamp(at: NaiveDateTime) -> String {
+    format_stored_datetime(at, DateStyle::Utc)
+}
+
+fn reminder_from_row(row: &rusqlite::Row) -> Result<Reminder> {
+    Ok(Reminder {
+        id: row.get(0)?,
+        item_type: row.get(1)?,
+        item_id: row.get(2)?,
+        occurrence: row.get(3)?,
+        title: row.get(4)?,
+        body: row.get(5)?,
+        fire_at: row.get(6)?,
+        snoozed_until: row.get(7)?,
+        status: row.get(8)?,
+        fired_at: row.get(9)?,
+    })
+}
+
+/// When a pending reminder should go off: at its snooze time if it has one
+fn fires_at(reminder: &Reminder) -> Option<NaiveDateTime> {
+    let value = reminder.snoozed_until.as_deref().unwrap_or(&reminder.fire_at);
+    parse_stored_datetime(value).map(|(at, _)| at)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Due {
+    Wait,
+    Fire,
+    /// Came due too long ago to be worth showing
+    Missed,
+}
+
+fn due(at: NaiveDateTime, now: NaiveDateTime) -> Due {
+    if at > now {
+        Due::Wait
+    } else if now - at > Duration::hours(CATCH_UP_HOURS) {
+        Due::Missed
+    } else {
+        Due::Fire
+    }
+}
+
+/// A reminder the queue should hold, worked out from a task or event
+#[derive(Debug, Clone, PartialEq)]
+struct Wanted {
+    item_type: &'static str,
+    item_id: i64,
+    occurrence: String,
+    title: String,
+    body: String,
+    fire_at: NaiveDateTime,
+}
+
+/// "Mon 6 May 14:00" in the user's zone; dates stay dates
+fn when_label(value: &str, zone: Tz) -> Option<String> {
+    match parse_stored_datetime(value)? {
+        (at, DateStyle::Date) => Some(at.format("%a %-d %b").to_string()),
+        (at, DateStyle::Utc | DateStyle::Sql) => Some(utc_to_zoned(zone, at).format("%a %-d %b %H:%M").to_string()),
+        (at, DateStyle::Local { .. }) => Some(at.format("%a %-d %b %H:%M").to_string()),
+    }
+}
+
+fn task_reminder(task: &Task, zone: Tz) -> Option<Wanted> {
+    let tz = zone_or(task.time_zone.as_deref(), zone).unwrap_or(zone);
+    let fire_at = instant(task.reminder_time.as_deref()?, tz)?;
+    let body = match task.due_date.as_deref().and_then(|due| when_label(due, zone)) {
+        Some(due) => format!("Due {}", due),
+        None => task.description.as_deref().and_then(|d| d.lines().next()).unwrap_or_default().to_string(),
+    };
+    Some(Wanted {
+        item_type: "task",
+        item_id: task.id,
+        occurrence: String::new(),
+        title: task.title.clone(),
+        body,
+        fire_at,
+    })
+}
+
+fn event_reminder(event: &CalendarEvent, zone: Tz) -> Option<Wanted> {
+    let tz = zone_or(event.time_zone.as_deref(), zone).unwrap_or(zone);
+    let fire_at = instant(event.reminder_time.as_deref()?, tz)?;
+    let start = when_label(&event.start_time, zone)?;
+    let mut body = if event.all_day { format!("All day, {}", start) } else { format!("Starts {}", start) };
+    if let Some(location) = event.location.as_deref().filter(|l| !l.is_empty()) {
+        body = format!("{} at {}", body, location);
+    }
+    // Expanded occurrences carry the id of their series; edited ones are rows of their own
+    let occurrence = if event.series_id == Some(event.id) { event.start_time.clone() } else { String::new() };
+    Some(Wanted {
+        item_type: "event",
+        item_id: event.id,
+        occurrence,
+        title: event.title.clone(),
+        body,
+        fire_at,
+    })
+}
+
+pub struct ReminderService<'a> {
+    db: &'a Database,
+}
+
+impl<'a> ReminderService<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    pub fn get_reminder(&self, id: i64) -> Result<Reminder> {
+        self.db.get_connection().query_row(
+            &format!("SELECT {} FROM reminders WHERE id = ?1", REMINDER_COLUMNS),
+            params![id],
+            reminder_from_row,
+        )
+    }
+
+    fn query(&self, condition: &str, values: &[&dyn rusqlite::ToSql]) -> Result<Vec<Reminder>> {
+        let mut stmt = self.db.get_connection().prepare(&format!(
+            "SELECT {} FROM reminders WHERE {} ORDER BY COALESCE(snoozed_until, fire_at), id",
+            REMINDER_COLUMNS, condition
+        ))?;
+        let reminders = stmt.query_map(values, reminder_from_row)?;
+        reminders.collect()
+    }
+
+    fn wanted(&self, now: NaiveDateTime) -> Result<Vec<Wanted>> {
+        let zone = user_zone(self.db.get_connection());
+        let mut wanted: Vec<Wanted> = self
+            .db
+            .get_all_tasks()?
+            .iter()
+            .filter(|t| t.status != "done" && t.status != "cancelled")
+            .filter_map(|t| task_reminder(t, zone))
+            .collect();
+        let from = now - Duration::hours(CATCH_UP_HOURS);
+        let to = now + Duration::days(HORIZON_DAYS);
+        let events = RecurringEventService::new(self.db).get_events_overlapping(from, to)?;
+        wanted.extend(events.iter().filter_map(|e| event_reminder(e, zone)));
+        Ok(wanted)
+    }
+
+    /// Bring the queue in line with the reminders set on tasks and events.
+    /// A reminder whose time changed is queued again even if it already fired.
+    pub fn sync_queue(&self, now: NaiveDateTime) -> Result<()> {
+        let wanted = self.wanted(now)?;
+        let conn = self.db.get_connection();
+        let tx = conn.unchecked_transaction()?;
+        let mut seen = HashSet::new();
+        for reminder in &wanted {
+            let fire_at = stamp(reminder.fire_at);
+            let stored: Option<(i64, String)> = conn
+                .query_row(
+                    "SELECT id, fire_at FROM reminders WHERE item_type = ?1 AND item_id = ?2 AND occurrence = ?3",
+                    params![reminder.item_type, reminder.item_id, reminder.occurrence],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .optional()?;
+            let id = match stored {
+                Some((id, stored_fire_at)) if stored_fire_at == fire_at => {
+                    conn.execute(
+                        "UPDATE reminders SET title = ?1, body = ?2 WHERE id = ?3",
+                        params![reminder.title, reminder.body, id],
+                    )?;
+                    id
+                }
+                Some((id, _)) => {
+                    conn.execute(
+                        "UPDATE reminders SET title = ?1, body = ?2, fire_at = ?3, snoozed_until = NULL,
+                         status = 'pending', fired_at = NULL WHERE id = ?4",
+                        params![reminder.title, reminder.body, fire_at, id],
+                    )?;
+                    id
+                }
+                None => {
+                    conn.execute(
+                        "INSERT INTO reminders (item_type, item_id, occurrence, title, body, fire_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                        params![reminder.item_type, reminder.item_id, reminder.occurrence, reminder.title, reminder.body, fire_at],
+                    )?;
+                    conn.last_insert_rowid()
+                }
+            };
+            seen.insert(id);
+        }
+
+        // Pending reminders of items that no longer ask for one
+        for stale in self.query("status = 'pending' AND item_type IN ('task', 'event')", &[])? {
+            if !seen.contains(&stale.id) {
+                conn.execute("DELETE FROM reminders WHERE id = ?1", params![stale.id])?;
+            }
+        }
+        conn.execute(
+            "DELETE FROM reminders WHERE status != 'pending' AND COALESCE(fired_at, fire_at) < ?1",
+            params![stamp(now - Duration::days(KEEP_DAYS))],
+        )?;
+        tx.commit()
+    }
+
+    /// Mark the reminders due at `now` as fired and return them. Ones missed
+    /// by more than a day are marked missed instead.
+    pub fn take_due(&self, now: NaiveDateTime) -> Result<Vec<Reminder>> {
+        let conn = self.db.get_connection();
+        let mut fired = Vec::new();
+        for mut reminder in self.query("status = 'pending' AND COALESCE(snoozed_until, fire_at) <= ?1", &[&stamp(now)])? {
+            let status = match fires_at(&reminder).map(|at| due(at, now)) {
+                Some(Due::Wait) => continue,
+                Some(Due::Fire) => "fired",
+                Some(Due::Missed) | None => "missed",
+            };
+            conn.execute(
+                "UPDATE reminders SET status = ?1, fired_at = ?2 WHERE id = ?3",
+                params![status, stamp(now), reminder.id],
+            )?;
+            if status == "fired" {
+                reminder.status = status.to_string();
+                reminder.fired_at = Some(stamp(now));
+                fired.push(reminder);
+            }
+        }
+        Ok(fired)
+    }
+
+    /// Pending reminders up to `until`, soonest first
+    pub fn get_upcoming(&self, until: NaiveDateTime) -> Result<Vec<Reminder>> {
+        self.query("status = 'pending' AND COALESCE(snoozed_until, fire_at) <= ?1", &[&stamp(until)])
+    }
+
+    /// Queue a one-off reminder that belongs to no task or event
+    pub fn schedule(&self, title: &str, body: &str, at: NaiveDateTime) -> Result<Reminder> {
+        if title.trim().is_empty() {
+            return Err(invalid("A reminder needs a title".to_string()));
+        }
+        self.db.get_connection().execute(
+            "INSERT INTO reminders (item_type, item_id, occurrence, title, body, fire_at) VALUES ('custom', NULL, '', ?1, ?2, ?3)",
+            params![title.trim(), body, stamp(at)],
+        )?;
+        self.get_reminder(self.db.get_connection().last_insert_rowid())
+    }
+
+    /// Fire the reminder again `minutes` from `now`
+    pub fn snooze(&self, id: i64, minutes: i64, now: NaiveDateTime) -> Result<Reminder> {
+        if minutes <= 0 {
+            return Err(invalid("Snooze for at least one minute".to_string()));
+        }
+        let updated = self.db.get_connection().execute(
+            "UPDATE reminders SET status = 'pending', snoozed_until = ?1 WHERE id = ?2",
+            params![stamp(now + Duration::minutes(minutes)), id],
+        )?;
+        if updated == 0 {
+            return Err(invalid(format!("Reminder {} not found", id)));
+        }
+        self.get_reminder(id)
+    }
+
+    /// Stop a reminder from firing, or from firing again after a snooze
+    pub fn dismiss(&self, id: i64) -> Result<Reminder> {
+        let updated = self
+            .db
+            .get_connection()
+            .execute("UPDATE reminders SET status = 'dismissed', snoozed_until = NULL WHERE id = ?1", params![id])?;
+        if updated == 0 {
+            return Err(invalid(format!("Reminder {} not found", id)));
+        }
+        self.get_reminder(id)
+    }
+}
+
+/// Sync the queue and fire whatever is due, as a notification and a frontend event
+pub async fn fire_due_reminders(app: &AppHandle, db: &Arc<Mutex<Database>>) -> usize {
+    let due = {
+        let db = db.lock().await;
+        let service = ReminderService::new(&db);
+        let now = Utc::now().naive_utc();
+        if let Err(e) = service.sync_queue(now) {
+            eprintln!("Failed to update the reminder queue: {}", e);
+        }
+        match service.take_due(now) {
+            Ok(due) => due,
+            Err(e) => {
+                eprintln!("Failed to read due reminders: {}", e);
+                return 0;
+            }
+        }
+    };
+    let notifications = NotificationService::new(app.config().tauri.bundle.identifier.clone());
+    for reminder in &due {
+        if let Err(e) = notifications.send_notification(&reminder.title, &reminder.body) {
+            eprintln!("Failed to show reminder '{}': {}", reminder.title, e);
+        }
+        let _ = app.emit_all(REMINDER_EVENT, reminder.clone());
+    }
+    due.len()
+}
+
+/// Fire reminders in the background for the life of the app; the first
+/// check runs right away so reminders missed while it was closed catch up
+pub fn spawn_reminder_loop(app: AppHandle, db: Arc<Mutex<Database>>) {
+    tokio::spawn(async move {
+        loop {
+            fire_due_reminders(&app, &db).await;
+            tokio::time::sleep(std::time::Duration::from_secs(CHECK_INTERVAL_SECS)).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(value: &str) -> NaiveDateTime {
+        parse_stored_datetime(value).unwrap().0
+    }
+
+    #[test]
+    fn late_reminders_catch_up_for_a_day() {
+        let now = at("2024-05-06T09:00:00Z");
+        assert_eq!(due(at("2024-05-06T09:00:30Z"), now), Due::Wait);
+        assert_eq!(due(at("2024-05-06T09:00:00Z"), now), Due::Fire);
+        assert_eq!(due(at("2024-05-05T09:30:00Z"), now), Due::Fire);
+        assert_eq!(due(at("2024-05-05T08:59:00Z"), now), Due::Missed);
+
+        let mut reminder = Reminder {
+            id: 1,
+            item_type: "custom".into(),
+            item_id: None,
+            occurrence: String::new(),
+            title: "Stretch".into(),
+            body: String::new(),
+            fire_at: "2024-05-06T08:00:00Z".into(),
+            snoozed_until: None,
+            status: "pending".into(),
+            fired_at: None,
+        };
+        assert_eq!(fires_at(&reminder), Some(at("2024-05-06T08:00:00Z")));
+        reminder.snoozed_until = Some("2024-05-06T09:10:00Z".into());
+        assert_eq!(fires_at(&reminder).map(|a| due(a, now)), Some(Due::Wait));
+    }
+
+    #[test]
+    fn occurrences_are_keyed_by_their_start() {
+        let zone: Tz = "Europe/Berlin".parse().unwrap();
+        let mut event = CalendarEvent {
+            id: 7,
+            title: "Standup".into(),
+            description: None,
+            start_time: "2024-05-06T07:00:00Z".into(),
+            end_time: "2024-05-06T07:15:00Z".into(),
+            reminder_time: Some("2024-05-06T06:50:00Z".into()),
+            recurrence: Some("FREQ=DAILY".into()),
+            location: Some("Room 2".into()),
+            all_day: false,
+            exdates: Vec::new(),
+            series_id: Some(7),
+            occurrence_date: Some("2024-05-06T07:00:00Z".into()),
+            subscription_id: None,
+            time_zone: Some("Europe/Berlin".into()),
+            created_at: String::new(),
+        };
+        let wanted = event_reminder(&event, zone).unwrap();
+        assert_eq!(wanted.occurrence, "2024-05-06T07:00:00Z");
+        assert_eq!(wanted.fire_at, at("2024-05-06T06:50:00Z"));
+        assert_eq!(wanted.body, "Starts Mon 6 May 09:00 at Room 2");
+
+        // An edited occurrence is its own row
+        event.id = 9;
+        assert_eq!(event_reminder(&event, zone).unwrap().occurrence, "");
+        event.reminder_time = None;
+        assert!(event_reminder(&event, zone).is_none());
+    }
+
+    fn task_with_reminder(db: &Database, title: &str, reminder_time: &str) -> i64 {
+        db.create_task(crate::models::task::CreateTask {
+            title: title.into(),
+            description: None,
+            status: None,
+            priority: None,
+            due_date: None,
+            reminder_time: Some(reminder_time.into()),
+            parent_id: None,
+            recurrence: None,
+            estimate: None,
+            time_zone: None,
+        })
+        .unwrap()
+        .id
+    }
+
+    fn queued(service: &ReminderService, task_id: i64) -> Reminder {
+        service.query("item_type = 'task' AND item_id = ?1", &[&task_id]).unwrap().remove(0)
+    }
+
+    fn utc_db() -> Database {
+        let db = Database::in_memory().unwrap();
+        crate::services::settings::SettingsService::new(db.get_connection()).set_setting("time_zone", "UTC").unwrap();
+        db
+    }
+
+    #[test]
+    fn the_queue_remembers_what_fired_was_snoozed_or_dismissed() {
+        let db = utc_db();
+        let service = ReminderService::new(&db);
+        let call = task_with_reminder(&db, "Call the bank", "2024-05-06T09:00:00Z");
+        let rent = task_with_reminder(&db, "Pay rent", "2024-05-06T09:20:00Z");
+
+        let now = at("2024-05-06T09:00:30Z");
+        service.sync_queue(now).unwrap();
+        let fired = service.take_due(now).unwrap();
+        assert_eq!(fired.iter().map(|r| r.item_id).collect::<Vec<_>>(), vec![Some(call)]);
+        // A later tick, as after a restart, does not fire it again
+        service.sync_queue(now + Duration::minutes(1)).unwrap();
+        assert!(service.take_due(now + Duration::minutes(1)).unwrap().is_empty());
+
+        let snoozed = service.snooze(queued(&service, call).id, 10, now).unwrap();
+        assert_eq!(snoozed.snoozed_until.as_deref(), Some("2024-05-06T09:10:30Z"));
+        service.sync_queue(at("2024-05-06T09:05:00Z")).unwrap();
+        assert_eq!(queued(&service, call).snoozed_until.as_deref(), Some("2024-05-06T09:10:30Z"));
+        assert!(service.take_due(at("2024-05-06T09:05:00Z")).unwrap().is_empty());
+        assert_eq!(service.take_due(at("2024-05-06T09:11:00Z")).unwrap().len(), 1);
+
+        service.dismiss(queued(&service, rent).id).unwrap();
+        service.sync_queue(at("2024-05-06T09:30:00Z")).unwrap();
+        assert!(service.take_due(at("2024-05-06T09:30:00Z")).unwrap().is_empty());
+        assert_eq!(queued(&service, rent).status, "dismissed");
+
+        // Moving the reminder queues it again
+        let update = crate::models::task::UpdateTask {
+            title: None,
+            description: None,
+            status: None,
+            priority: None,
+            due_date: None,
+            reminder_time: Some("2024-05-06T10:00:00Z".into()),
+            recurrence: None,
+            estimate: None,
+            time_zone: None,
+            position: None,
+            board_id: None,
+            expected_version: None,
+            expected_updated_at: None,
+        };
+        db.update_task(call, update).unwrap();
+        service.sync_queue(at("2024-05-06T09:45:00Z")).unwrap();
+        let requeued = queued(&service, call);
+        assert_eq!((requeued.status.as_str(), requeued.snoozed_until.as_deref()), ("pending", None));
+        assert_eq!(requeued.fire_at, "2024-05-06T10:00:00Z");
+        assert_eq!(service.take_due(at("2024-05-06T10:00:00Z")).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn reminders_missed_by_more_than_a_day_are_not_shown() {
+        let db = utc_db();
+        let service = ReminderService::new(&db);
+        let old = task_with_reminder(&db, "Renew passport", "2024-05-04T09:00:00Z");
+        let recent = task_with_reminder(&db, "Water plants", "2024-05-05T18:00:00Z");
+
+        let now = at("2024-05-06T09:00:00Z");
+        service.sync_queue(now).unwrap();
+        let fired = service.take_due(now).unwrap();
+        assert_eq!(fired.iter().map(|r| r.item_id).collect::<Vec<_>>(), vec![Some(recent)]);
+        assert_eq!(queued(&service, old).status, "missed");
+        service.sync_queue(now).unwrap();
+        assert_eq!(queued(&service, old).status, "missed");
+    }
+}
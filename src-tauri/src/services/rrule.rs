@@ -0,0 +1,591 @@
+// RRULE Service - iCalendar (RFC 5545) recurrence rules for tasks and events
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// A weekday, optionally with an ordinal: `MO` (every Monday), `2TU` (second
+/// Tuesday of the month), `-1FR` (last Friday).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WeekdayNum {
+    pub ordinal: Option<i32>,
+    pub weekday: Weekday,
+}
+
+/// The subset of RFC 5545 RRULE used by tasks and events: FREQ, INTERVAL,
+/// BYDAY, BYMONTHDAY, BYMONTH, BYSETPOS, COUNT and UNTIL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecurrenceRule {
+    pub freq: Frequency,
+    pub interval: u32,
+    pub by_day: Vec<WeekdayNum>,
+    pub by_month_day: Vec<i32>,
+    pub by_month: Vec<u32>,
+    pub by_set_pos: Vec<i32>,
+    pub count: Option<u32>,
+    pub until: Option<NaiveDateTime>,
+}
+
+/// Upper bound on empty periods scanned before giving up on a rule that can
+/// never match (e.g. BYMONTHDAY=31 with BYMONTH=2)
+const MAX_EMPTY_PERIODS: u32 = 1000;
+/// Largest INTERVAL accepted; larger ones only ever reach dates chrono cannot represent
+pub const MAX_INTERVAL: u32 = 1000;
+
+impl RecurrenceRule {
+    pub fn parse(rule: &str) -> Result<Self, String> {
+        let body = rule.trim();
+        let body = body.strip_prefix("RRULE:").unwrap_or(body);
+
+        let mut freq = None;
+        let mut parsed = RecurrenceRule {
+            freq: Frequency::Daily,
+            interval: 1,
+            by_day: Vec::new(),
+            by_month_day: Vec::new(),
+            by_month: Vec::new(),
+            by_set_pos: Vec::new(),
+            count: None,
+            until: None,
+        };
+
+        for part in body.split(';').filter(|p| !p.trim().is_empty()) {
+            let (key, value) = part
+                .split_once('=')
+                .ok_or_else(|| format!("Invalid RRULE part: {}", part))?;
+            let value = value.trim();
+            match key.trim().to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    freq = Some(match value.to_ascii_uppercase().as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        other => return Err(format!("Unsupported FREQ: {}", other)),
+                    })
+                }
+                "INTERVAL" => {
+                    parsed.interval = value.parse().map_err(|_| format!("Invalid INTERVAL: {}", value))?;
+                    if parsed.interval == 0 {
+                        return Err("INTERVAL must be at least 1".to_string());
+                    }
+                    if parsed.interval > MAX_INTERVAL {
+                        return Err(format!("INTERVAL cannot be more than {}", MAX_INTERVAL));
+                    }
+                }
+                "BYDAY" => {
+                    parsed.by_day = value.split(',').map(parse_weekday_num).collect::<Result<_, _>>()?;
+                }
+                "BYMONTHDAY" => {
+                    parsed.by_month_day = parse_int_list(value, -31, 31, "BYMONTHDAY")?;
+                }
+                "BYMONTH" => {
+                    parsed.by_month = parse_int_list(value, 1, 12, "BYMONTH")?
+                        .into_iter()
+                        .map(|m| m as u32)
+                        .collect();
+                }
+                "BYSETPOS" => {
+                    parsed.by_set_pos = parse_int_list(value, -366, 366, "BYSETPOS")?;
+                }
+                "COUNT" => {
+                    parsed.count = Some(value.parse().map_err(|_| format!("Invalid COUNT: {}", value))?);
+                }
+                "UNTIL" => {
+                    parsed.until = Some(parse_ical_datetime(value).ok_or_else(|| format!("Invalid UNTIL: {}", value))?);
+                }
+                // Weeks always start on Monday here
+                "WKST" => {}
+                other => return Err(format!("Unsupported RRULE part: {}", other)),
+            }
+        }
+
+        parsed.freq = freq.ok_or_else(|| "RRULE is missing FREQ".to_string())?;
+        if parsed.count.is_some() && parsed.until.is_some() {
+            return Err("RRULE cannot have both COUNT and UNTIL".to_string());
+        }
+        if parsed.freq == Frequency::Weekly && parsed.by_day.iter().any(|d| d.ordinal.is_some()) {
+            return Err("Weekly rules cannot use numbered weekdays".to_string());
+        }
+
+        Ok(parsed)
+    }
+
+    /// All occurrences starting at `dtstart`, which is always the first one
+    pub fn iter(&self, dtstart: NaiveDateTime) -> Occurrences<'_> {
+        Occurrences {
+            rule: self,
+            dtstart,
+            period: 0,
+            pending: Vec::new(),
+            emitted: 0,
+            started: false,
+            done: false,
+        }
+    }
+
+    /// First occurrence strictly after `after`
+    pub fn next_after(&self, dtstart: NaiveDateTime, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        self.iter(dtstart).find(|occurrence| *occurrence > after)
+    }
+
+    /// Occurrences in `[start, end)`, at most `limit` of them
+    pub fn between(&self, dtstart: NaiveDateTime, start: NaiveDateTime, end: NaiveDateTime, limit: usize) -> Vec<NaiveDateTime> {
+        self.iter(dtstart)
+            .take_while(|occurrence| *occurrence < end)
+            .filter(|occurrence| *occurrence >= start)
+            .take(limit)
+            .collect()
+    }
+
+    /// Candidates for the `period`-th period after the one containing `dtstart`,
+    /// or `None` once the period lies beyond the dates chrono can represent
+    fn period_candidates(&self, dtstart: NaiveDateTime, period: u32) -> Option<Vec<NaiveDateTime>> {
+        let time = dtstart.time();
+        let step = i64::from(period.checked_mul(self.interval)?);
+        let start = dtstart.date();
+
+        let mut dates: Vec<NaiveDate> = match self.freq {
+            Frequency::Daily => {
+                let day = start.checked_add_signed(Duration::try_days(step)?)?;
+                vec![day]
+                    .into_iter()
+                    .filter(|d| self.by_day.is_empty() || self.by_day.iter().any(|w| w.weekday == d.weekday()))
+                    .filter(|d| self.by_month.is_empty() || self.by_month.contains(&d.month()))
+                    .collect()
+            }
+            Frequency::Weekly => {
+                let monday = (start - Duration::days(start.weekday().num_days_from_monday() as i64))
+                    .checked_add_signed(Duration::try_weeks(step)?)?;
+                let weekdays: Vec<Weekday> = if self.by_day.is_empty() {
+                    vec![start.weekday()]
+                } else {
+                    self.by_day.iter().map(|w| w.weekday).collect()
+                };
+                weekdays
+                    .into_iter()
+                    .filter_map(|w| monday.checked_add_signed(Duration::days(w.num_days_from_monday() as i64)))
+                    .filter(|d| self.by_month.is_empty() || self.by_month.contains(&d.month()))
+                    .collect()
+            }
+            Frequency::Monthly => {
+                let (year, month) = add_months(start.year(), start.month(), step);
+                if !self.by_month.is_empty() && !self.by_month.contains(&month) {
+                    Vec::new()
+                } else {
+                    self.month_dates(year, month, start.day())
+                }
+            }
+            Frequency::Yearly => {
+                let year = start.year().checked_add(i32::try_from(step).ok()?)?;
+                let months = if self.by_month.is_empty() { vec![start.month()] } else { self.by_month.clone() };
+                months
+                    .into_iter()
+                    .flat_map(|month| self.month_dates(year, month, start.day()))
+                    .collect()
+            }
+        };
+
+        dates.sort();
+        dates.dedup();
+        let mut candidates: Vec<NaiveDateTime> = dates.into_iter().map(|d| d.and_time(time)).collect();
+
+        if !self.by_set_pos.is_empty() {
+            let len = candidates.len() as i32;
+            let mut selected: Vec<NaiveDateTime> = self
+                .by_set_pos
+                .iter()
+                .filter_map(|pos| {
+                    let index = if *pos > 0 { pos - 1 } else { len + pos };
+                    if index >= 0 && index < len {
+                        Some(candidates[index as usize])
+                    } else {
+                        None
+                    }
+                })
+                .collect();
+            selected.sort();
+            selected.dedup();
+            candidates = selected;
+        }
+
+        Some(candidates)
+    }
+
+    /// Days of one month matched by BYDAY / BYMONTHDAY (intersected when both
+    /// are given), or `default_day` when neither is
+    fn month_dates(&self, year: i32, month: u32, default_day: u32) -> Vec<NaiveDate> {
+        let last = days_in_month(year, month);
+
+        let by_month_day: Vec<NaiveDate> = self
+            .by_month_day
+            .iter()
+            .filter_map(|day| {
+                let day = if *day > 0 { *day } else { last as i32 + day + 1 };
+                if day >= 1 && day <= last as i32 {
+                    NaiveDate::from_ymd_opt(year, month, day as u32)
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        let by_day: Vec<NaiveDate> = self
+            .by_day
+            .iter()
+            .flat_map(|w| {
+                let all: Vec<NaiveDate> = (1..=last)
+                    .filter_map(|d| NaiveDate::from_ymd_opt(year, month, d))
+                    .filter(|d| d.weekday() == w.weekday)
+                    .collect();
+                match w.ordinal {
+                    None => all,
+                    Some(n) if n > 0 => all.get(n as usize - 1).copied().into_iter().collect(),
+                    Some(n) => {
+                        let index = all.len() as i32 + n;
+                        if index >= 0 {
+                            vec![all[index as usize]]
+                        } else {
+                            Vec::new()
+                        }
+                    }
+                }
+            })
+            .collect();
+
+        match (self.by_day.is_empty(), self.by_month_day.is_empty()) {
+            (true, true) => NaiveDate::from_ymd_opt(year, month, default_day).into_iter().collect(),
+            (false, true) => by_day,
+            (true, false) => by_month_day,
+            (false, false) => by_month_day.into_iter().filter(|d| by_day.contains(d)).collect(),
+        }
+    }
+}
+
+impl fmt::Display for RecurrenceRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let freq = match self.freq {
+            Frequency::Daily => "DAILY",
+            Frequency::Weekly => "WEEKLY",
+            Frequency::Monthly => "MONTHLY",
+            Frequency::Yearly => "YEARLY",
+        };
+        let mut parts = vec![format!("FREQ={}", freq)];
+        if self.interval != 1 {
+            parts.push(format!("INTERVAL={}", self.interval));
+        }
+        if !self.by_day.is_empty() {
+            let days: Vec<String> = self
+                .by_day
+                .iter()
+                .map(|w| format!("{}{}", w.ordinal.map(|n| n.to_string()).unwrap_or_default(), weekday_code(w.weekday)))
+                .collect();
+            parts.push(format!("BYDAY={}", days.join(",")));
+        }
+        if !self.by_month_day.is_empty() {
+            parts.push(format!("BYMONTHDAY={}", join_ints(&self.by_month_day)));
+        }
+        if !self.by_month.is_empty() {
+            let months: Vec<i32> = self.by_month.iter().map(|m| *m as i32).collect();
+            parts.push(format!("BYMONTH={}", join_ints(&months)));
+        }
+        if !self.by_set_pos.is_empty() {
+            parts.push(format!("BYSETPOS={}", join_ints(&self.by_set_pos)));
+        }
+        if let Some(count) = self.count {
+            parts.push(format!("COUNT={}", count));
+        }
+        if let Some(until) = self.until {
+            parts.push(format!("UNTIL={}", until.format("%Y%m%dT%H%M%S")));
+        }
+        write!(f, "{}", parts.join(";"))
+    }
+}
+
+pub struct Occurrences<'a> {
+    rule: &'a RecurrenceRule,
+    dtstart: NaiveDateTime,
+    period: u32,
+    pending: Vec<NaiveDateTime>,
+    emitted: u32,
+    started: bool,
+    done: bool,
+}
+
+impl<'a> Iterator for Occurrences<'a> {
+    type Item = NaiveDateTime;
+
+    fn next(&mut self) -> Option<NaiveDateTime> {
+        if self.done {
+            return None;
+        }
+
+        let next = if !self.started {
+            self.started = true;
+            Some(self.dtstart)
+        } else {
+            let mut empty_periods = 0;
+            loop {
+                if let Some(candidate) = self.pending.first().copied() {
+                    self.pending.remove(0);
+                    break Some(candidate);
+                }
+                if empty_periods > MAX_EMPTY_PERIODS {
+                    break None;
+                }
+                let Some(candidates) = self.rule.period_candidates(self.dtstart, self.period) else {
+                    break None;
+                };
+                self.pending = candidates.into_iter().filter(|candidate| *candidate > self.dtstart).collect();
+                self.period += 1;
+                if self.pending.is_empty() {
+                    empty_periods += 1;
+                }
+            }
+        };
+
+        let within_limits = next
+            .map(|occurrence| {
+                self.rule.until.map(|until| occurrence <= until).unwrap_or(true)
+                    && self.rule.count.map(|count| self.emitted < count).unwrap_or(true)
+            })
+            .unwrap_or(false);
+
+        if within_limits {
+            self.emitted += 1;
+            next
+        } else {
+            self.done = true;
+            None
+        }
+    }
+}
+
+fn parse_weekday_num(value: &str) -> Result<WeekdayNum, String> {
+    let value = value.trim().to_ascii_uppercase();
+    if value.len() < 2 {
+        return Err(format!("Invalid BYDAY value: {}", value));
+    }
+    // The weekday code is the last two characters; anything else there is not a weekday
+    let split = value.len() - 2;
+    if !value.is_char_boundary(split) {
+        return Err(format!("Invalid BYDAY value: {}", value));
+    }
+    let (ordinal, code) = value.split_at(split);
+    let weekday = match code {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return Err(format!("Invalid BYDAY value: {}", value)),
+    };
+    let ordinal = if ordinal.is_empty() {
+        None
+    } else {
+        let n: i32 = ordinal
+            .trim_start_matches('+')
+            .parse()
+            .map_err(|_| format!("Invalid BYDAY value: {}", value))?;
+        if n == 0 || n.abs() > 53 {
+            return Err(format!("Invalid BYDAY value: {}", value));
+        }
+        Some(n)
+    };
+    Ok(WeekdayNum { ordinal, weekday })
+}
+
+fn parse_int_list(value: &str, min: i32, max: i32, name: &str) -> Result<Vec<i32>, String> {
+    value
+        .split(',')
+        .map(|v| {
+            let n: i32 = v.trim().trim_start_matches('+').parse().map_err(|_| format!("Invalid {}: {}", name, value))?;
+            if n == 0 || n < min || n > max {
+                Err(format!("Invalid {}: {}", name, value))
+            } else {
+                Ok(n)
+            }
+        })
+        .collect()
+}
+
+fn join_ints(values: &[i32]) -> String {
+    values.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(",")
+}
+
+pub fn weekday_code(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "MO",
+        Weekday::Tue => "TU",
+        Weekday::Wed => "WE",
+        Weekday::Thu => "TH",
+        Weekday::Fri => "FR",
+        Weekday::Sat => "SA",
+        Weekday::Sun => "SU",
+    }
+}
+
+/// Parse an iCalendar DATE or DATE-TIME (`20240131`, `20240131T090000`,
+/// `20240131T090000Z`). The trailing Z is accepted but not converted.
+pub fn parse_ical_datetime(value: &str) -> Option<NaiveDateTime> {
+    let value = value.trim().trim_end_matches('Z');
+    if value.len() == 8 {
+        NaiveDate::parse_from_str(value, "%Y%m%d").ok().map(|d| d.and_time(NaiveTime::MIN))
+    } else {
+        NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()
+    }
+}
+
+fn add_months(year: i32, month: u32, months: i64) -> (i32, u32) {
+    let total = year as i64 * 12 + (month as i64 - 1) + months;
+    ((total / 12) as i32, (total % 12) as u32 + 1)
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    let (next_year, next_month) = add_months(year, month, 1);
+    NaiveDate::from_ymd_opt(next_year, next_month, 1)
+        .and_then(|d| d.pred_opt())
+        .map(|d| d.day())
+        .unwrap_or(28)
+}
+
+/// How a stored date string was written, so shifted dates keep the same shape
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateStyle {
+    /// `2024-01-31`
+    Date,
+    /// `2024-01-31T09:00` / `2024-01-31T09:00:00`
+    Local { seconds: bool },
+    /// `2024-01-31 09:00:00` (SQLite CURRENT_TIMESTAMP)
+    Sql,
+    /// `2024-01-31T09:00:00Z` / RFC 3339 with offset, kept as UTC wall time
+    Utc,
+}
+
+/// Parse the date formats used for task due dates and reminders
+pub fn parse_stored_datetime(value: &str) -> Option<(NaiveDateTime, DateStyle)> {
+    let value = value.trim();
+    if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(value) {
+        return Some((dt.naive_utc(), DateStyle::Utc));
+    }
+    if let Ok(dt) = NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S") {
+        return Some((dt, DateStyle::Local { seconds: true }));
+    }
+    if let Ok(dt) = NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M") {
+        return Some((dt, DateStyle::Local { seconds: false }));
+    }
+    if let Ok(dt) = NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S") {
+        return Some((dt, DateStyle::Sql));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Some((date.and_time(NaiveTime::MIN), DateStyle::Date));
+    }
+    None
+}
+
+pub fn format_stored_datetime(value: NaiveDateTime, style: DateStyle) -> String {
+    match style {
+        DateStyle::Date => value.format("%Y-%m-%d").to_string(),
+        DateStyle::Local { seconds: true } => value.format("%Y-%m-%dT%H:%M:%S").to_string(),
+        DateStyle::Local { seconds: false } => value.format("%Y-%m-%dT%H:%M").to_string(),
+        DateStyle::Sql => value.format("%Y-%m-%d %H:%M:%S").to_string(),
+        DateStyle::Utc => value.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dt(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn take(rule: &str, start: &str, n: usize) -> Vec<String> {
+        RecurrenceRule::parse(rule)
+            .unwrap()
+            .iter(dt(start))
+            .take(n)
+            .map(|d| d.format("%Y-%m-%d %H:%M").to_string())
+            .collect()
+    }
+
+    #[test]
+    fn daily_with_interval_and_count() {
+        assert_eq!(
+            take("FREQ=DAILY;INTERVAL=2;COUNT=3", "2024-01-30 08:00", 10),
+            vec!["2024-01-30 08:00", "2024-02-01 08:00", "2024-02-03 08:00"]
+        );
+    }
+
+    #[test]
+    fn weekly_on_given_weekdays() {
+        // 2024-01-03 is a Wednesday
+        assert_eq!(
+            take("RRULE:FREQ=WEEKLY;BYDAY=MO,WE,FR", "2024-01-03 09:30", 4),
+            vec!["2024-01-03 09:30", "2024-01-05 09:30", "2024-01-08 09:30", "2024-01-10 09:30"]
+        );
+        assert_eq!(
+            take("FREQ=WEEKLY;INTERVAL=2;BYDAY=TU", "2024-01-02 09:00", 3),
+            vec!["2024-01-02 09:00", "2024-01-16 09:00", "2024-01-30 09:00"]
+        );
+    }
+
+    #[test]
+    fn monthly_on_nth_weekday() {
+        assert_eq!(
+            take("FREQ=MONTHLY;BYDAY=2TU", "2024-01-09 10:00", 3),
+            vec!["2024-01-09 10:00", "2024-02-13 10:00", "2024-03-12 10:00"]
+        );
+        assert_eq!(
+            take("FREQ=MONTHLY;BYDAY=-1FR", "2024-01-26 10:00", 3),
+            vec!["2024-01-26 10:00", "2024-02-23 10:00", "2024-03-29 10:00"]
+        );
+    }
+
+    #[test]
+    fn monthly_skips_missing_days_and_respects_until() {
+        assert_eq!(
+            take("FREQ=MONTHLY;UNTIL=20240430T000000", "2024-01-31 12:00", 10),
+            vec!["2024-01-31 12:00", "2024-03-31 12:00"]
+        );
+    }
+
+    #[test]
+    fn round_trips_and_rejects_bad_rules() {
+        let rule = RecurrenceRule::parse("FREQ=MONTHLY;BYDAY=1MO;COUNT=5").unwrap();
+        assert_eq!(rule.to_string(), "FREQ=MONTHLY;BYDAY=1MO;COUNT=5");
+        assert!(RecurrenceRule::parse("INTERVAL=2").is_err());
+        assert!(RecurrenceRule::parse("FREQ=HOURLY").is_err());
+        assert!(RecurrenceRule::parse("FREQ=WEEKLY;BYDAY=2MO").is_err());
+    }
+
+    #[test]
+    fn rejects_non_ascii_weekdays_without_panicking() {
+        for rule in ["FREQ=MONTHLY;BYDAY=1MÖ", "FREQ=WEEKLY;BYDAY=ÖX", "FREQ=MONTHLY;BYDAY=1ÖX"] {
+            assert!(RecurrenceRule::parse(rule).is_err(), "{}", rule);
+        }
+    }
+
+    #[test]
+    fn large_intervals_are_capped_and_stop_at_the_end_of_time() {
+        assert!(RecurrenceRule::parse("FREQ=DAILY;INTERVAL=4294967295").is_err());
+        assert!(RecurrenceRule::parse(&format!("FREQ=DAILY;INTERVAL={}", MAX_INTERVAL + 1)).is_err());
+
+        let rule = RecurrenceRule::parse(&format!("FREQ=YEARLY;INTERVAL={}", MAX_INTERVAL)).unwrap();
+        let occurrences: Vec<NaiveDateTime> = rule.iter(dt("2024-01-01 09:00")).take(1000).collect();
+        assert!(occurrences.len() > 1 && occurrences.len() < 1000);
+        assert_eq!(occurrences[1], dt("3024-01-01 09:00"));
+
+        let rule = RecurrenceRule::parse(&format!("FREQ=WEEKLY;INTERVAL={}", MAX_INTERVAL)).unwrap();
+        assert!(rule.iter(dt("2024-01-01 09:00")).take(100_000).count() < 100_000);
+    }
+}
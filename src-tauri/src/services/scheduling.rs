@@ -0,0 +1,363 @@
+// Scheduling Service - conflicts, free/busy and free-slot search over events
+//
+// Everything is computed on UTC instants with recurring series expanded.
+// All-day events are treated as free time, like most calendars do, so a
+// holiday or a birthday does not block the day. Working hours are wall
+// times in the user's zone, so they follow DST.
+use crate::models::event::CalendarEvent;
+use crate::models::scheduling::{BusyBlock, EventConflict, FreeBusy, FreeSlotRequest, TimeSlot};
+use crate::services::database::Database;
+use crate::services::recurring_events::RecurringEventService;
+use crate::services::rrule::{format_stored_datetime, DateStyle};
+use crate::services::time_zones::{instant, user_zone, zoned_to_utc};
+use chrono::{Datelike, Duration, NaiveDateTime, NaiveTime, Timelike, Utc};
+use chrono_tz::Tz;
+use rusqlite::Result;
+
+/// How far ahead the occurrences of a recurring event are checked for conflicts
+const CONFLICT_HORIZON_DAYS: i64 = 180;
+/// Default search span of the free-slot finder
+const SLOT_SEARCH_DAYS: i64 = 14;
+const DEFAULT_SLOT_COUNT: usize = 5;
+/// Slots start on a quarter hour
+const SLOT_STEP_MINUTES: i64 = 15;
+
+fn invalid(message: String) -> rusqlite::Error {
+    rusqlite::Error::InvalidParameterName(message)
+}
+
+/// A range bound as a UTC instant; wall times are read in `zone`
+pub(crate) fn bound(value: &str, zone: Tz) -> Result<NaiveDateTime> {
+    instant(value, zone).ok_or_else(|| invalid(format!("Invalid date or time: {}", value)))
+}
+
+fn utc(at: NaiveDateTime) -> String {
+    format_stored_datetime(at, DateStyle::Utc)
+}
+
+/// A busy span and the events in it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Busy {
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+    pub event_ids: Vec<i64>,
+}
+
+/// Working hours in the user's zone
+#[derive(Debug, Clone)]
+pub struct WorkingHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+    /// ISO weekdays, 1 = Monday
+    pub days: Vec<u32>,
+}
+
+impl WorkingHours {
+    /// Parse "HH:MM" bounds and ISO weekdays; 09:00 to 17:00, Monday to Friday by default
+    pub fn parse(start: Option<&str>, end: Option<&str>, days: Option<&[u32]>) -> Result<Self> {
+        let time = |value: Option<&str>, default: &str| {
+            let value = value.unwrap_or(default);
+            NaiveTime::parse_from_str(value.trim(), "%H:%M").map_err(|_| invalid(format!("Invalid time of day '{}'", value)))
+        };
+        let hours = Self {
+            start: time(start, "09:00")?,
+            end: time(end, "17:00")?,
+            days: days.map(<[u32]>::to_vec).unwrap_or_else(|| vec![1, 2, 3, 4, 5]),
+        };
+        if hours.end <= hours.start {
+            return Err(invalid("Working hours must end after they start".to_string()));
+        }
+        if hours.days.iter().any(|day| !(1..=7).contains(day)) {
+            return Err(invalid("Working days are numbered 1 (Monday) to 7 (Sunday)".to_string()));
+        }
+        Ok(hours)
+    }
+
+    /// The working spans within [from, to), as UTC instants
+    pub fn windows(&self, from: NaiveDateTime, to: NaiveDateTime, zone: Tz) -> Vec<(NaiveDateTime, NaiveDateTime)> {
+        let mut windows = Vec::new();
+        // Start a day early: the working day in the zone may begin before `from` in UTC
+        let mut day = from.date() - Duration::days(1);
+        while day <= to.date() + Duration::days(1) {
+            if self.days.contains(&day.weekday().number_from_monday()) {
+                let span = zoned_to_utc(zone, day.and_time(self.start)).zip(zoned_to_utc(zone, day.and_time(self.end)));
+                if let Some((start, end)) = span {
+                    let (start, end) = (start.max(from), end.min(to));
+                    if start < end {
+                        windows.push((start, end));
+                    }
+                }
+            }
+            day += Duration::days(1);
+        }
+        windows
+    }
+}
+
+/// Sort and merge busy spans; overlapping and touching spans are joined
+pub fn merge_busy(mut spans: Vec<Busy>) -> Vec<Busy> {
+    spans.sort_by_key(|span| (span.start, span.end));
+    let mut merged: Vec<Busy> = Vec::new();
+    for span in spans {
+        match merged.last_mut() {
+            Some(last) if span.start <= last.end => {
+                last.end = last.end.max(span.end);
+                for id in span.event_ids {
+                    if !last.event_ids.contains(&id) {
+                        last.event_ids.push(id);
+                    }
+                }
+            }
+            _ => merged.push(span),
+        }
+    }
+    merged
+}
+
+/// The parts of [from, to) not covered by `busy`, which must be merged
+pub fn free_gaps(busy: &[Busy], from: NaiveDateTime, to: NaiveDateTime) -> Vec<(NaiveDateTime, NaiveDateTime)> {
+    let mut gaps = Vec::new();
+    let mut cursor = from;
+    for span in busy.iter().filter(|span| span.end > from && span.start < to) {
+        if span.start > cursor {
+            gaps.push((cursor, span.start));
+        }
+        cursor = cursor.max(span.end);
+    }
+    if cursor < to {
+        gaps.push((cursor, to));
+    }
+    gaps
+}
+
+/// Round up to the next slot step
+pub(crate) fn round_up(at: NaiveDateTime) -> NaiveDateTime {
+    let at = at.with_nanosecond(0).unwrap_or(at);
+    let step = SLOT_STEP_MINUTES * 60;
+    let past = at.num_seconds_from_midnight() as i64 % step;
+    if past == 0 {
+        at
+    } else {
+        at + Duration::seconds(step - past)
+    }
+}
+
+/// The earliest slot of `duration` in each free gap of the working windows,
+/// up to `count` slots
+pub fn find_slots(
+    busy: &[Busy],
+    windows: &[(NaiveDateTime, NaiveDateTime)],
+    duration: Duration,
+    count: usize,
+) -> Vec<(NaiveDateTime, NaiveDateTime)> {
+    windows
+        .iter()
+        .flat_map(|(start, end)| free_gaps(busy, *start, *end))
+        .filter_map(|(start, end)| {
+            let start = round_up(start);
+            (start + duration <= end).then(|| (start, start + duration))
+        })
+        .take(count)
+        .collect()
+}
+
+/// Busy spans of timed events, widened by `buffer` on both sides
+pub(crate) fn busy_spans(events: &[CalendarEvent], buffer: Duration, zone: Tz) -> Vec<Busy> {
+    events
+        .iter()
+        .filter(|e| !e.all_day)
+        .filter_map(|e| {
+            let start = instant(&e.start_time, zone)?;
+            let end = instant(&e.end_time, zone)?.max(start);
+            Some(Busy { start: start - buffer, end: end + buffer, event_ids: vec![e.series_id.unwrap_or(e.id)] })
+        })
+        .collect()
+}
+
+pub struct SchedulingService<'a> {
+    db: &'a Database,
+}
+
+impl<'a> SchedulingService<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    fn zone(&self) -> Tz {
+        user_zone(self.db.get_connection())
+    }
+
+    /// Timed events other than `exclude` (and its series) overlapping [start, end)
+    pub fn find_conflicts(&self, start: &str, end: &str, exclude: Option<i64>) -> Result<Vec<CalendarEvent>> {
+        let zone = self.zone();
+        let (from, to) = (bound(start, zone)?, bound(end, zone)?);
+        let family = exclude.map(|id| self.db.get_event(id)).transpose()?.map(|e| e.series_id.unwrap_or(e.id));
+        Ok(RecurringEventService::new(self.db)
+            .get_events_overlapping(from, to.max(from + Duration::seconds(1)))?
+            .into_iter()
+            .filter(|e| !e.all_day && !family.map(|id| e.id == id || e.series_id == Some(id)).unwrap_or(false))
+            .collect())
+    }
+
+    /// Other events overlapping an event; for a recurring event, its
+    /// occurrences over the next months are checked too
+    pub fn get_event_conflicts(&self, event_id: i64) -> Result<Vec<EventConflict>> {
+        let event = self.db.get_event(event_id)?;
+        if event.all_day {
+            return Ok(Vec::new());
+        }
+        let zone = self.zone();
+        let start = bound(&event.start_time, zone)?;
+        let end = bound(&event.end_time, zone)?.max(start + Duration::seconds(1));
+        let repeats = event.recurrence.as_deref().map(|r| !r.trim().is_empty()).unwrap_or(false) && event.occurrence_date.is_none();
+        let until = if repeats { start + Duration::days(CONFLICT_HORIZON_DAYS) } else { end };
+
+        let family = event.series_id.unwrap_or(event.id);
+        let is_own = |e: &CalendarEvent| e.id == family || e.series_id == Some(family);
+        let events = RecurringEventService::new(self.db).get_events_overlapping(start, until)?;
+        let (own, others): (Vec<_>, Vec<_>) = events.into_iter().filter(|e| !e.all_day).partition(|e| is_own(e));
+        let own: Vec<&CalendarEvent> = own.iter().filter(|e| repeats || e.id == event.id).collect();
+
+        let mut conflicts = Vec::new();
+        for occurrence in own {
+            let (Some(o_start), Some(o_end)) = (instant(&occurrence.start_time, zone), instant(&occurrence.end_time, zone)) else {
+                continue;
+            };
+            let o_end = o_end.max(o_start + Duration::seconds(1));
+            for other in &others {
+                let (Some(x_start), Some(x_end)) = (instant(&other.start_time, zone), instant(&other.end_time, zone)) else {
+                    continue;
+                };
+                if x_start < o_end && x_end.max(x_start + Duration::seconds(1)) > o_start {
+                    conflicts.push(EventConflict { occurrence_start: occurrence.start_time.clone(), event: other.clone() });
+                }
+            }
+        }
+        Ok(conflicts)
+    }
+
+    /// Busy blocks of timed events in [start, end) and the free time between them
+    pub fn get_free_busy(&self, start: &str, end: &str) -> Result<FreeBusy> {
+        let zone = self.zone();
+        let (from, to) = (bound(start, zone)?, bound(end, zone)?);
+        if to <= from {
+            return Err(invalid(format!("The range ends ({}) before it starts ({})", end, start)));
+        }
+        let events = RecurringEventService::new(self.db).get_events_overlapping(from, to)?;
+        let busy: Vec<Busy> = merge_busy(busy_spans(&events, Duration::zero(), zone))
+            .into_iter()
+            .map(|span| Busy { start: span.start.max(from), end: span.end.min(to), ..span })
+            .collect();
+        Ok(FreeBusy {
+            start: utc(from),
+            end: utc(to),
+            free: free_gaps(&busy, from, to).into_iter().map(|(start, end)| TimeSlot { start: utc(start), end: utc(end) }).collect(),
+            busy: busy
+                .into_iter()
+                .map(|span| BusyBlock { start: utc(span.start), end: utc(span.end), event_ids: span.event_ids })
+                .collect(),
+        })
+    }
+
+    /// The next free slots of the requested length within working hours,
+    /// keeping the buffer around other events
+    pub fn find_free_slots(&self, request: &FreeSlotRequest) -> Result<Vec<TimeSlot>> {
+        if request.duration_minutes <= 0 {
+            return Err(invalid("The slot length must be at least a minute".to_string()));
+        }
+        if request.buffer_minutes < 0 {
+            return Err(invalid("The buffer cannot be negative".to_string()));
+        }
+        let hours = WorkingHours::parse(request.work_start.as_deref(), request.work_end.as_deref(), request.work_days.as_deref())?;
+        let zone = self.zone();
+        let from = match request.start.as_deref() {
+            Some(start) => bound(start, zone)?,
+            None => Utc::now().naive_utc(),
+        };
+        let to = match request.end.as_deref() {
+            Some(end) => bound(end, zone)?,
+            None => from + Duration::days(SLOT_SEARCH_DAYS),
+        };
+        let buffer = Duration::minutes(request.buffer_minutes);
+
+        // Events just outside the range still push the buffer into it
+        let events = RecurringEventService::new(self.db).get_events_overlapping(from - buffer, to + buffer)?;
+        let busy = merge_busy(busy_spans(&events, buffer, zone));
+        let slots = find_slots(
+            &busy,
+            &hours.windows(from, to, zone),
+            Duration::minutes(request.duration_minutes),
+            request.count.unwrap_or(DEFAULT_SLOT_COUNT),
+        );
+        Ok(slots.into_iter().map(|(start, end)| TimeSlot { start: utc(start), end: utc(end) }).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dt(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn busy(start: &str, end: &str, id: i64) -> Busy {
+        Busy { start: dt(start), end: dt(end), event_ids: vec![id] }
+    }
+
+    #[test]
+    fn merges_busy_time_and_finds_gaps() {
+        let merged = merge_busy(vec![
+            busy("2024-05-06 13:00", "2024-05-06 14:00", 2),
+            busy("2024-05-06 09:00", "2024-05-06 10:00", 1),
+            busy("2024-05-06 09:30", "2024-05-06 11:00", 3),
+            busy("2024-05-06 11:00", "2024-05-06 11:30", 4),
+        ]);
+        assert_eq!(
+            merged,
+            vec![
+                Busy { event_ids: vec![1, 3, 4], ..busy("2024-05-06 09:00", "2024-05-06 11:30", 1) },
+                busy("2024-05-06 13:00", "2024-05-06 14:00", 2),
+            ]
+        );
+        let gaps = free_gaps(&merged, dt("2024-05-06 08:00"), dt("2024-05-06 18:00"));
+        assert_eq!(
+            gaps,
+            vec![
+                (dt("2024-05-06 08:00"), dt("2024-05-06 09:00")),
+                (dt("2024-05-06 11:30"), dt("2024-05-06 13:00")),
+                (dt("2024-05-06 14:00"), dt("2024-05-06 18:00")),
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_slots_in_working_hours() {
+        let hours = WorkingHours {
+            start: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+            end: NaiveTime::from_hms_opt(17, 0, 0).unwrap(),
+            days: vec![1, 2, 3, 4, 5],
+        };
+        let new_york: Tz = "America/New_York".parse().unwrap();
+        // Friday 8 March to Tuesday 12 March 2024; the clocks change on the Sunday
+        let windows = hours.windows(dt("2024-03-08 15:07"), dt("2024-03-12 23:00"), new_york);
+        assert_eq!(
+            windows,
+            vec![
+                (dt("2024-03-08 15:07"), dt("2024-03-08 22:00")),
+                (dt("2024-03-11 13:00"), dt("2024-03-11 21:00")),
+                (dt("2024-03-12 13:00"), dt("2024-03-12 21:00")),
+            ]
+        );
+        let booked = vec![busy("2024-03-08 15:00", "2024-03-08 21:30", 1), busy("2024-03-11 13:00", "2024-03-11 13:40", 2)];
+        let slots = find_slots(&booked, &windows, Duration::minutes(60), 3);
+        assert_eq!(
+            slots,
+            vec![
+                (dt("2024-03-11 13:45"), dt("2024-03-11 14:45")),
+                (dt("2024-03-12 13:00"), dt("2024-03-12 14:00")),
+            ]
+        );
+        assert_eq!(round_up(dt("2024-03-11 13:45")), dt("2024-03-11 13:45"));
+    }
+}
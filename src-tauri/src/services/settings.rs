@@ -1,3 +1,4 @@
+use crate::services::time_zones::{parse_zone, TIME_ZONE_SETTING};
 use rusqlite::{params, Connection, Result};
 use serde::{Deserialize, Serialize};
 
@@ -8,6 +9,17 @@ pub struct UserSettings {
     pub notifications_enabled: bool,
     pub auto_save_interval: i64,
     pub default_module: String,
+    /// What happens when a blocked task is moved to done: "warn" (it is done,
+    /// with its blockers reported) or "refuse"
+    #[serde(default = "default_blocked_task_policy")]
+    pub blocked_task_policy: String,
+    /// IANA zone dates and times are shown and entered in; None follows the system
+    #[serde(default)]
+    pub time_zone: Option<String>,
+}
+
+fn default_blocked_task_policy() -> String {
+    "warn".to_string()
 }
 
 impl Default for UserSettings {
@@ -18,6 +30,8 @@ impl Default for UserSettings {
             notifications_enabled: true,
             auto_save_interval: 2000, // 2 seconds
             default_module: "chat".to_string(),
+            blocked_task_policy: default_blocked_task_policy(),
+            time_zone: None,
         }
     }
 }
@@ -62,6 +76,8 @@ impl<'a> SettingsService<'a> {
             .unwrap_or_else(|| "2000".to_string());
         let default_module = self.get_setting("default_module")?
             .unwrap_or_else(|| "chat".to_string());
+        let blocked_task_policy = self.get_setting("blocked_task_policy")?
+            .unwrap_or_else(default_blocked_task_policy);
 
         Ok(UserSettings {
             theme,
@@ -69,6 +85,8 @@ impl<'a> SettingsService<'a> {
             notifications_enabled: notifications == "true",
             auto_save_interval: auto_save.parse().unwrap_or(2000),
             default_module,
+            blocked_task_policy,
+            time_zone: self.get_setting(TIME_ZONE_SETTING)?,
         })
     }
 
@@ -78,6 +96,16 @@ impl<'a> SettingsService<'a> {
         self.set_setting("notifications_enabled", &settings.notifications_enabled.to_string())?;
         self.set_setting("auto_save_interval", &settings.auto_save_interval.to_string())?;
         self.set_setting("default_module", &settings.default_module)?;
+        self.set_setting("blocked_task_policy", &settings.blocked_task_policy)?;
+        match settings.time_zone.as_deref().map(str::trim).filter(|zone| !zone.is_empty()) {
+            Some(zone) => {
+                let zone = parse_zone(zone).map_err(rusqlite::Error::InvalidParameterName)?;
+                self.set_setting(TIME_ZONE_SETTING, zone.name())?;
+            }
+            None => {
+                self.conn.execute("DELETE FROM settings WHERE key = ?1", params![TIME_ZONE_SETTING])?;
+            }
+        }
         Ok(())
     }
 
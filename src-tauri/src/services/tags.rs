@@ -0,0 +1,633 @@
+// Tag Service - normalized, hierarchical tags shared by every module
+use crate::models::tag::{Tag, TagSuggestion, TagWithCount, TaggedItem, TAGGABLE_TYPES};
+use rusqlite::{params, Connection, OptionalExtension, Result};
+
+/// Normalize a user-entered tag into its canonical path form.
+///
+/// Leading `#` markers and list bullets are stripped, whitespace inside each
+/// segment is collapsed and empty segments are dropped, so `" #Project / alpha "`
+/// becomes `"Project/alpha"`. Returns `None` when nothing usable is left.
+pub fn normalize_tag_name(raw: &str) -> Option<String> {
+    let mut trimmed = raw.trim();
+    for bullet in ["- ", "* ", "• "] {
+        if let Some(rest) = trimmed.strip_prefix(bullet) {
+            trimmed = rest.trim_start();
+        }
+    }
+    // Numbered list markers such as "1. " or "2) "
+    let digits = trimmed.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits > 0 {
+        let rest = &trimmed[digits..];
+        if let Some(rest) = rest.strip_prefix(". ").or_else(|| rest.strip_prefix(") ")) {
+            trimmed = rest.trim_start();
+        }
+    }
+    let trimmed = trimmed.trim_start_matches('#').trim();
+
+    let segments: Vec<String> = trimmed
+        .split('/')
+        .map(|segment| segment.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|segment| !segment.is_empty())
+        .collect();
+
+    if segments.is_empty() {
+        None
+    } else {
+        Some(segments.join("/"))
+    }
+}
+
+/// Parse the legacy free-form `tags` columns, which hold either a JSON array
+/// (as written by ingestion metadata) or a comma separated list.
+pub fn parse_tag_list(raw: &str) -> Vec<String> {
+    let raw = raw.trim();
+    let candidates: Vec<String> = if raw.starts_with('[') {
+        serde_json::from_str::<Vec<String>>(raw).unwrap_or_default()
+    } else {
+        raw.split(',').map(|s| s.to_string()).collect()
+    };
+
+    let mut tags: Vec<String> = Vec::new();
+    for candidate in candidates {
+        if let Some(name) = normalize_tag_name(&candidate) {
+            if !tags.iter().any(|t| t.eq_ignore_ascii_case(&name)) {
+                tags.push(name);
+            }
+        }
+    }
+    tags
+}
+
+fn parent_path(name: &str) -> Option<&str> {
+    name.rfind('/').map(|pos| &name[..pos])
+}
+
+fn leaf_name(name: &str) -> &str {
+    name.rfind('/').map(|pos| &name[pos + 1..]).unwrap_or(name)
+}
+
+/// A denormalized `tags` column still read elsewhere
+struct LegacyTags {
+    table: &'static str,
+    /// Column holding the item id
+    key: &'static str,
+    /// Stored as a JSON array rather than a comma-separated list
+    json: bool,
+}
+
+/// Where each item type keeps a copy of its tags outside `item_tags`: the
+/// document's own column and the extracted file metadata. Tasks, events and
+/// messages only ever kept their tags in `item_tags`.
+fn legacy_tags(item_type: &str) -> Option<LegacyTags> {
+    match item_type {
+        "document" => Some(LegacyTags { table: "documents", key: "id", json: false }),
+        "file" => Some(LegacyTags { table: "metadata", key: "file_id", json: true }),
+        _ => None,
+    }
+}
+
+fn invalid(message: String) -> rusqlite::Error {
+    rusqlite::Error::InvalidParameterName(message)
+}
+
+pub struct TagService<'a> {
+    conn: &'a Connection,
+}
+
+impl<'a> TagService<'a> {
+    pub fn new(conn: &'a Connection) -> Self {
+        Self { conn }
+    }
+
+    fn map_tag(row: &rusqlite::Row) -> Result<Tag> {
+        Ok(Tag {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            parent_id: row.get(2)?,
+            created_at: row.get(3)?,
+        })
+    }
+
+    fn validate_item_type(item_type: &str) -> Result<()> {
+        if TAGGABLE_TYPES.contains(&item_type) {
+            Ok(())
+        } else {
+            Err(invalid(format!("Items of type '{}' cannot be tagged", item_type)))
+        }
+    }
+
+    pub fn get_tag(&self, id: i64) -> Result<Tag> {
+        self.conn.query_row(
+            "SELECT id, name, parent_id, created_at FROM tags WHERE id = ?1",
+            params![id],
+            Self::map_tag,
+        )
+    }
+
+    pub fn find_tag_by_name(&self, name: &str) -> Result<Option<Tag>> {
+        self.conn
+            .query_row(
+                "SELECT id, name, parent_id, created_at FROM tags WHERE name = ?1",
+                params![name],
+                Self::map_tag,
+            )
+            .optional()
+    }
+
+    /// Get or create a tag by path, creating any missing ancestors.
+    /// Child names are always built from the stored parent name so that a
+    /// subtree shares one spelling of its prefix.
+    pub fn ensure_tag(&self, raw_name: &str) -> Result<Tag> {
+        let name = normalize_tag_name(raw_name)
+            .ok_or_else(|| invalid(format!("Invalid tag name: '{}'", raw_name)))?;
+
+        if let Some(existing) = self.find_tag_by_name(&name)? {
+            return Ok(existing);
+        }
+
+        let (parent_id, full_name) = match parent_path(&name) {
+            Some(parent) => {
+                let parent_tag = self.ensure_tag(parent)?;
+                let full_name = format!("{}/{}", parent_tag.name, leaf_name(&name));
+                (Some(parent_tag.id), full_name)
+            }
+            None => (None, name.clone()),
+        };
+
+        self.conn.execute(
+            "INSERT INTO tags (name, parent_id) VALUES (?1, ?2)",
+            params![full_name, parent_id],
+        )?;
+
+        let id = self.conn.last_insert_rowid();
+        self.get_tag(id)
+    }
+
+    /// All tags ordered by path, with direct item counts and counts that
+    /// include every nested tag (distinct items).
+    pub fn get_all_tags(&self) -> Result<Vec<TagWithCount>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT t.id, t.name, t.parent_id, t.created_at,
+                    (SELECT COUNT(*) FROM item_tags it WHERE it.tag_id = t.id),
+                    (SELECT COUNT(DISTINCT it.item_type || ':' || it.item_id)
+                     FROM item_tags it JOIN tags d ON d.id = it.tag_id
+                     WHERE d.id = t.id OR lower(substr(d.name, 1, length(t.name) + 1)) = lower(t.name || '/'))
+             FROM tags t
+             ORDER BY t.name COLLATE NOCASE",
+        )?;
+
+        let tags = stmt.query_map([], |row| {
+            Ok(TagWithCount {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                parent_id: row.get(2)?,
+                created_at: row.get(3)?,
+                item_count: row.get(4)?,
+                total_count: row.get(5)?,
+            })
+        })?;
+
+        tags.collect()
+    }
+
+    pub fn get_child_tags(&self, parent_id: i64) -> Result<Vec<Tag>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, name, parent_id, created_at FROM tags WHERE parent_id = ?1 ORDER BY name",
+        )?;
+        let tags = stmt.query_map(params![parent_id], Self::map_tag)?;
+        tags.collect()
+    }
+
+    pub fn get_tags_for_item(&self, item_type: &str, item_id: i64) -> Result<Vec<Tag>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT t.id, t.name, t.parent_id, t.created_at
+             FROM tags t JOIN item_tags it ON it.tag_id = t.id
+             WHERE it.item_type = ?1 AND it.item_id = ?2
+             ORDER BY t.name COLLATE NOCASE",
+        )?;
+        let tags = stmt.query_map(params![item_type, item_id], Self::map_tag)?;
+        tags.collect()
+    }
+
+    /// Items carrying a tag; with `include_nested` the items of every
+    /// descendant tag (e.g. `project/alpha` for `project`) are included.
+    pub fn get_items_for_tag(&self, tag_id: i64, include_nested: bool) -> Result<Vec<TaggedItem>> {
+        let tag = self.get_tag(tag_id)?;
+        let mut stmt = self.conn.prepare(
+            "SELECT it.item_type, it.item_id, it.tag_id, it.created_at
+             FROM item_tags it JOIN tags d ON d.id = it.tag_id
+             WHERE d.id = ?1 OR (?2 AND lower(substr(d.name, 1, length(?3) + 1)) = lower(?3 || '/'))
+             ORDER BY it.created_at DESC",
+        )?;
+
+        let items = stmt.query_map(params![tag.id, include_nested, tag.name], |row| {
+            Ok(TaggedItem {
+                item_type: row.get(0)?,
+                item_id: row.get(1)?,
+                tag_id: row.get(2)?,
+                created_at: row.get(3)?,
+            })
+        })?;
+
+        items.collect()
+    }
+
+    pub fn tag_item(&self, item_type: &str, item_id: i64, name: &str) -> Result<Tag> {
+        Self::validate_item_type(item_type)?;
+        let tag = self.ensure_tag(name)?;
+
+        self.conn.execute(
+            "INSERT OR IGNORE INTO item_tags (tag_id, item_type, item_id) VALUES (?1, ?2, ?3)",
+            params![tag.id, item_type, item_id],
+        )?;
+        self.refresh_legacy_tags(item_type, item_id)?;
+
+        Ok(tag)
+    }
+
+    pub fn untag_item(&self, item_type: &str, item_id: i64, tag_id: i64) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM item_tags WHERE tag_id = ?1 AND item_type = ?2 AND item_id = ?3",
+            params![tag_id, item_type, item_id],
+        )?;
+        self.refresh_legacy_tags(item_type, item_id)
+    }
+
+    /// Replace the full tag set of an item
+    pub fn set_item_tags(&self, item_type: &str, item_id: i64, names: &[String]) -> Result<Vec<Tag>> {
+        Self::validate_item_type(item_type)?;
+
+        self.conn.execute(
+            "DELETE FROM item_tags WHERE item_type = ?1 AND item_id = ?2",
+            params![item_type, item_id],
+        )?;
+
+        for name in names {
+            let tag = self.ensure_tag(name)?;
+            self.conn.execute(
+                "INSERT OR IGNORE INTO item_tags (tag_id, item_type, item_id) VALUES (?1, ?2, ?3)",
+                params![tag.id, item_type, item_id],
+            )?;
+        }
+
+        self.refresh_legacy_tags(item_type, item_id)?;
+        self.get_tags_for_item(item_type, item_id)
+    }
+
+    /// Remove every tag assignment and pending suggestion of a deleted item
+    pub fn delete_item_tags(&self, item_type: &str, item_id: i64) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM item_tags WHERE item_type = ?1 AND item_id = ?2",
+            params![item_type, item_id],
+        )?;
+        self.conn.execute(
+            "DELETE FROM tag_suggestions WHERE item_type = ?1 AND item_id = ?2",
+            params![item_type, item_id],
+        )?;
+        Ok(())
+    }
+
+    /// Rename a tag; nested tags follow it (`project` -> `work` turns
+    /// `project/alpha` into `work/alpha`). Renaming onto an existing tag is
+    /// refused - use `merge_tags` for that.
+    pub fn rename_tag(&self, id: i64, new_name: &str) -> Result<Tag> {
+        let tx = self.conn.unchecked_transaction()?;
+        let tag = self.rename_subtree(id, new_name)?;
+        tx.commit()?;
+        Ok(tag)
+    }
+
+    fn rename_subtree(&self, id: i64, new_name: &str) -> Result<Tag> {
+        let tag = self.get_tag(id)?;
+        let new_name = normalize_tag_name(new_name)
+            .ok_or_else(|| invalid(format!("Invalid tag name: '{}'", new_name)))?;
+
+        if new_name == tag.name {
+            return Ok(tag);
+        }
+        if new_name.to_lowercase().starts_with(&format!("{}/", tag.name.to_lowercase())) {
+            return Err(invalid(format!("Cannot move tag '{}' underneath itself", tag.name)));
+        }
+        if let Some(existing) = self.find_tag_by_name(&new_name)? {
+            if existing.id != tag.id {
+                return Err(invalid(format!(
+                    "Tag '{}' already exists; merge the tags instead",
+                    existing.name
+                )));
+            }
+        }
+
+        let (parent_id, full_name) = match parent_path(&new_name) {
+            Some(parent) => {
+                let parent_tag = self.ensure_tag(parent)?;
+                (Some(parent_tag.id), format!("{}/{}", parent_tag.name, leaf_name(&new_name)))
+            }
+            None => (None, new_name.clone()),
+        };
+
+        let affected = self.items_in_subtree(&tag.name)?;
+
+        // Rewrite the prefix of every descendant before renaming the tag itself
+        self.conn.execute(
+            "UPDATE tags SET name = ?1 || substr(name, length(?2) + 1)
+             WHERE lower(substr(name, 1, length(?2) + 1)) = lower(?2 || '/')",
+            params![full_name, tag.name],
+        )?;
+        self.conn.execute(
+            "UPDATE tags SET name = ?1, parent_id = ?2 WHERE id = ?3",
+            params![full_name, parent_id, id],
+        )?;
+
+        for (item_type, item_id) in affected {
+            self.refresh_legacy_tags(&item_type, item_id)?;
+        }
+
+        self.get_tag(id)
+    }
+
+    /// Merge `source_id` into `target_id`: items move to the target, nested
+    /// tags are re-homed under the target (merging again on name clashes) and
+    /// the source tag is removed.
+    pub fn merge_tags(&self, source_id: i64, target_id: i64) -> Result<Tag> {
+        let tx = self.conn.unchecked_transaction()?;
+        let tag = self.merge_into(source_id, target_id)?;
+        tx.commit()?;
+        Ok(tag)
+    }
+
+    fn merge_into(&self, source_id: i64, target_id: i64) -> Result<Tag> {
+        let source = self.get_tag(source_id)?;
+        let target = self.get_tag(target_id)?;
+
+        if source.id == target.id {
+            return Ok(target);
+        }
+        if target.name.to_lowercase().starts_with(&format!("{}/", source.name.to_lowercase())) {
+            return Err(invalid(format!(
+                "Cannot merge tag '{}' into its own child '{}'",
+                source.name, target.name
+            )));
+        }
+
+        for child in self.get_child_tags(source.id)? {
+            let new_name = format!("{}/{}", target.name, leaf_name(&child.name));
+            match self.find_tag_by_name(&new_name)? {
+                Some(existing) => {
+                    self.merge_into(child.id, existing.id)?;
+                }
+                None => {
+                    self.rename_subtree(child.id, &new_name)?;
+                }
+            }
+        }
+
+        let affected = self.items_in_subtree(&source.name)?;
+
+        self.conn.execute(
+            "INSERT OR IGNORE INTO item_tags (tag_id, item_type, item_id, created_at)
+             SELECT ?1, item_type, item_id, created_at FROM item_tags WHERE tag_id = ?2",
+            params![target.id, source.id],
+        )?;
+        self.conn.execute("DELETE FROM item_tags WHERE tag_id = ?1", params![source.id])?;
+        self.conn.execute("DELETE FROM tags WHERE id = ?1", params![source.id])?;
+
+        for (item_type, item_id) in affected {
+            self.refresh_legacy_tags(&item_type, item_id)?;
+        }
+
+        self.get_tag(target.id)
+    }
+
+    /// Delete a tag together with its nested tags and their assignments
+    pub fn delete_tag(&self, id: i64) -> Result<()> {
+        let tag = self.get_tag(id)?;
+        let affected = self.items_in_subtree(&tag.name)?;
+
+        let tx = self.conn.unchecked_transaction()?;
+        self.conn.execute(
+            "DELETE FROM item_tags WHERE tag_id IN (
+                SELECT id FROM tags WHERE id = ?1 OR lower(substr(name, 1, length(?2) + 1)) = lower(?2 || '/')
+             )",
+            params![tag.id, tag.name],
+        )?;
+        self.conn.execute(
+            "DELETE FROM tags WHERE id = ?1 OR lower(substr(name, 1, length(?2) + 1)) = lower(?2 || '/')",
+            params![tag.id, tag.name],
+        )?;
+        for (item_type, item_id) in affected {
+            self.refresh_legacy_tags(&item_type, item_id)?;
+        }
+        tx.commit()
+    }
+
+    // AI suggestions - kept apart from item_tags until the user accepts them
+
+    pub fn record_suggestions(
+        &self,
+        item_type: &str,
+        item_id: i64,
+        names: &[String],
+        source: &str,
+    ) -> Result<Vec<TagSuggestion>> {
+        Self::validate_item_type(item_type)?;
+        let applied: Vec<String> = self
+            .get_tags_for_item(item_type, item_id)?
+            .into_iter()
+            .map(|t| t.name.to_lowercase())
+            .collect();
+
+        for name in names.iter().filter_map(|n| normalize_tag_name(n)) {
+            if applied.contains(&name.to_lowercase()) {
+                continue;
+            }
+            self.conn.execute(
+                "INSERT OR IGNORE INTO tag_suggestions (item_type, item_id, name, source)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![item_type, item_id, name, source],
+            )?;
+        }
+
+        self.get_suggestions(item_type, item_id)
+    }
+
+    pub fn get_suggestion(&self, id: i64) -> Result<TagSuggestion> {
+        self.conn.query_row(
+            "SELECT id, item_type, item_id, name, source, created_at FROM tag_suggestions WHERE id = ?1",
+            params![id],
+            |row| {
+                Ok(TagSuggestion {
+                    id: row.get(0)?,
+                    item_type: row.get(1)?,
+                    item_id: row.get(2)?,
+                    name: row.get(3)?,
+                    source: row.get(4)?,
+                    created_at: row.get(5)?,
+                })
+            },
+        )
+    }
+
+    pub fn get_suggestions(&self, item_type: &str, item_id: i64) -> Result<Vec<TagSuggestion>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, item_type, item_id, name, source, created_at
+             FROM tag_suggestions WHERE item_type = ?1 AND item_id = ?2
+             ORDER BY created_at DESC, id",
+        )?;
+
+        let suggestions = stmt.query_map(params![item_type, item_id], |row| {
+            Ok(TagSuggestion {
+                id: row.get(0)?,
+                item_type: row.get(1)?,
+                item_id: row.get(2)?,
+                name: row.get(3)?,
+                source: row.get(4)?,
+                created_at: row.get(5)?,
+            })
+        })?;
+
+        suggestions.collect()
+    }
+
+    /// Turn a suggestion into a real tag assignment
+    pub fn accept_suggestion(&self, id: i64) -> Result<Tag> {
+        let suggestion = self.get_suggestion(id)?;
+        let tag = self.tag_item(&suggestion.item_type, suggestion.item_id, &suggestion.name)?;
+        self.conn.execute("DELETE FROM tag_suggestions WHERE id = ?1", params![id])?;
+        Ok(tag)
+    }
+
+    pub fn dismiss_suggestion(&self, id: i64) -> Result<()> {
+        self.conn.execute("DELETE FROM tag_suggestions WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    // Helpers
+
+    /// Every item carrying `name` or one of its nested tags
+    fn items_in_subtree(&self, name: &str) -> Result<Vec<(String, i64)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT DISTINCT it.item_type, it.item_id FROM item_tags it JOIN tags t ON t.id = it.tag_id
+             WHERE t.name = ?1 OR lower(substr(t.name, 1, length(?1) + 1)) = lower(?1 || '/')",
+        )?;
+        let items = stmt.query_map(params![name], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        items.collect()
+    }
+
+    /// Keep the denormalized `tags` columns in step with `item_tags` so
+    /// existing readers of the columns keep working.
+    fn refresh_legacy_tags(&self, item_type: &str, item_id: i64) -> Result<()> {
+        let Some(legacy) = legacy_tags(item_type) else {
+            return Ok(());
+        };
+
+        let names: Vec<String> = self
+            .get_tags_for_item(item_type, item_id)?
+            .into_iter()
+            .map(|t| t.name)
+            .collect();
+        let column = if legacy.json {
+            Some(serde_json::to_string(&names).map_err(|e| invalid(e.to_string()))?)
+        } else if names.is_empty() {
+            None
+        } else {
+            Some(names.join(", "))
+        };
+
+        self.conn.execute(
+            &format!("UPDATE {} SET tags = ?1 WHERE {} = ?2", legacy.table, legacy.key),
+            params![column, item_id],
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::document::CreateDocument;
+    use crate::services::database::Database;
+
+    fn document(db: &Database, tags: &str) -> i64 {
+        db.create_document(CreateDocument {
+            title: "Notes".into(),
+            content: String::new(),
+            tags: Some(tags.into()),
+            folder_id: None,
+        })
+        .unwrap()
+        .id
+    }
+
+    #[test]
+    fn normalizes_markers_whitespace_and_segments() {
+        assert_eq!(normalize_tag_name(" #Project / alpha "), Some("Project/alpha".to_string()));
+        assert_eq!(normalize_tag_name("- ##big   idea"), Some("big idea".to_string()));
+        assert_eq!(normalize_tag_name("2) todo//later/"), Some("todo/later".to_string()));
+        assert_eq!(normalize_tag_name("  #  "), None);
+        assert_eq!(normalize_tag_name(" / "), None);
+        assert_eq!(parse_tag_list("Work, work , #home"), vec!["Work", "home"]);
+        assert_eq!(parse_tag_list(r#"["a/b", " A/B "]"#), vec!["a/b"]);
+    }
+
+    #[test]
+    fn renaming_moves_nested_tags_whatever_their_case() {
+        let db = Database::in_memory().unwrap();
+        let tags = TagService::new(db.get_connection());
+        let work = tags.ensure_tag("Work").unwrap();
+        // Written before names were built from the stored parent
+        db.get_connection()
+            .execute("INSERT INTO tags (name, parent_id) VALUES ('work/x', ?1)", params![work.id])
+            .unwrap();
+        let document_id = document(&db, "work/x");
+
+        tags.rename_tag(work.id, "Home").unwrap();
+
+        assert!(tags.find_tag_by_name("Home/x").unwrap().is_some());
+        assert!(tags.find_tag_by_name("work/x").unwrap().is_none());
+        assert_eq!(db.get_document(document_id).unwrap().tags.as_deref(), Some("Home/x"));
+        assert!(tags.rename_tag(work.id, "Home/x/deeper").is_err());
+    }
+
+    #[test]
+    fn merging_rehomes_items_and_nested_tags() {
+        let db = Database::in_memory().unwrap();
+        let tags = TagService::new(db.get_connection());
+        let first = document(&db, "draft, old/notes");
+        let second = document(&db, "Old");
+        let old = tags.find_tag_by_name("old").unwrap().unwrap();
+        let draft = tags.find_tag_by_name("draft").unwrap().unwrap();
+
+        tags.merge_tags(old.id, draft.id).unwrap();
+
+        assert!(tags.find_tag_by_name("old").unwrap().is_none());
+        assert!(tags.find_tag_by_name("old/notes").unwrap().is_none());
+        assert_eq!(db.get_document(first).unwrap().tags.as_deref(), Some("draft, draft/notes"));
+        assert_eq!(db.get_document(second).unwrap().tags.as_deref(), Some("draft"));
+        let nested = tags.get_items_for_tag(draft.id, true).unwrap();
+        assert_eq!(nested.iter().filter(|item| item.item_id == first).count(), 2);
+    }
+
+    #[test]
+    fn file_metadata_tags_follow_renames_and_deletes() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.get_connection();
+        conn.execute("INSERT INTO files (filename, filepath) VALUES ('a.pdf', 'files/a.pdf')", []).unwrap();
+        let file_id = conn.last_insert_rowid();
+        conn.execute("INSERT INTO metadata (file_id, tags) VALUES (?1, '[]')", params![file_id]).unwrap();
+        let metadata_tags = || -> String {
+            conn.query_row("SELECT tags FROM metadata WHERE file_id = ?1", params![file_id], |row| row.get(0)).unwrap()
+        };
+
+        let tags = TagService::new(conn);
+        tags.set_item_tags("file", file_id, &["Work/report".to_string(), "scan".to_string()]).unwrap();
+        assert_eq!(metadata_tags(), r#"["scan","Work/report"]"#);
+
+        let work = tags.find_tag_by_name("Work").unwrap().unwrap();
+        tags.rename_tag(work.id, "Office").unwrap();
+        assert_eq!(metadata_tags(), r#"["Office/report","scan"]"#);
+
+        let scan = tags.find_tag_by_name("scan").unwrap().unwrap();
+        tags.delete_tag(scan.id).unwrap();
+        assert_eq!(metadata_tags(), r#"["Office/report"]"#);
+    }
+}
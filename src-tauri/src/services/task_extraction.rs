@@ -0,0 +1,303 @@
+// Task Extraction - turn notes, messages and documents into task drafts
+//
+// The model is asked for JSON matching `extraction_schema`; its answer is
+// validated here before anything reaches the user. Accepted drafts are
+// saved with `commit_drafts`, which links every task back to its source.
+use crate::models::quick_add::QuickAddItem;
+use crate::models::task::{CreateTask, Task};
+use crate::models::task_extraction::{CommittedTask, TaskDraft, TaskExtraction, TaskSource};
+use crate::services::database::Database;
+use crate::services::links::{CreateLink, LinksService};
+use crate::services::quick_add::parse_quick_add;
+use crate::services::rrule::{format_stored_datetime, parse_stored_datetime, DateStyle};
+use crate::services::tags::{normalize_tag_name, TagService};
+use chrono::{NaiveDate, NaiveTime};
+use rusqlite::{params, OptionalExtension, Result};
+use serde_json::{json, Value};
+
+pub const SOURCE_TYPES: [&str; 3] = ["message", "document", "file"];
+const MAX_TITLE_LEN: usize = 200;
+const NO_OWNER: [&str; 8] = ["me", "i", "you", "we", "unknown", "none", "n/a", "unassigned"];
+
+fn invalid(message: String) -> rusqlite::Error {
+    rusqlite::Error::InvalidParameterName(message)
+}
+
+/// JSON schema passed to Ollama's `format` so the reply is structured
+pub fn extraction_schema() -> Value {
+    let nullable = |kind: &str| json!({ "type": [kind, "null"] });
+    json!({
+        "type": "object",
+        "properties": {
+            "tasks": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "title": { "type": "string" },
+                        "description": nullable("string"),
+                        "priority": { "type": "string", "enum": ["low", "medium", "high"] },
+                        "due_date": nullable("string"),
+                        "owner": nullable("string"),
+                        "excerpt": nullable("string"),
+                    },
+                    "required": ["title", "priority", "due_date", "owner"],
+                },
+            },
+        },
+        "required": ["tasks"],
+    })
+}
+
+pub fn extraction_prompt(text: &str, source_date: NaiveDate) -> String {
+    format!(
+        "Extract the actionable tasks from the text below. It was written on {} ({}).\n\
+         Reply with JSON only: {{\"tasks\": [{{\"title\": short imperative title without bullets or numbering, \
+         \"description\": extra detail or null, \"priority\": \"low\"|\"medium\"|\"high\", \
+         \"due_date\": \"YYYY-MM-DD\" or \"YYYY-MM-DDTHH:MM\" resolved against the date above, or null, \
+         \"owner\": name of the person responsible or null, \"excerpt\": the sentence the task comes from}}]}}\n\
+         Return {{\"tasks\": []}} when there is nothing to do.\n\nText:\n{}\n\nJSON:",
+        source_date.format("%Y-%m-%d"),
+        source_date.format("%A"),
+        text.trim()
+    )
+}
+
+/// "1. ", "- [ ] ", "* " and similar list markers the model sometimes keeps
+fn strip_list_marker(title: &str) -> &str {
+    let mut title = title.trim();
+    loop {
+        let before = title;
+        for marker in ["- [ ] ", "- [x] ", "[ ] ", "- ", "* ", "• ", "TODO: ", "Task: "] {
+            title = title.strip_prefix(marker).unwrap_or(title).trim_start();
+        }
+        let digits = title.chars().take_while(|c| c.is_ascii_digit()).count();
+        if digits > 0 {
+            let rest = &title[digits..];
+            if let Some(rest) = rest.strip_prefix(". ").or_else(|| rest.strip_prefix(") ")) {
+                title = rest.trim_start();
+            }
+        }
+        if title == before {
+            return title;
+        }
+    }
+}
+
+/// A due date as stored, resolving words like "next friday" against the source date
+fn resolve_due(value: &str, source_date: NaiveDate) -> Option<String> {
+    if let Some((at, style)) = parse_stored_datetime(value) {
+        return Some(match style {
+            DateStyle::Date => format_stored_datetime(at, DateStyle::Date),
+            _ => at.format("%Y-%m-%dT%H:%M").to_string(),
+        });
+    }
+    let now = source_date.and_time(NaiveTime::from_hms_opt(9, 0, 0)?);
+    match parse_quick_add(&format!("task {}", value), now)?.item {
+        QuickAddItem::Task(task) if task.title == "task" => task.due_date,
+        _ => None,
+    }
+}
+
+/// Validate the model's reply. Items that fail are reported in `rejected`
+/// instead of being guessed at; an unreadable reply is an error.
+pub fn parse_extraction(response: &str, source_date: NaiveDate) -> std::result::Result<TaskExtraction, String> {
+    let start = response.find(['{', '[']).ok_or("The model did not return JSON")?;
+    let end = response.rfind(['}', ']']).filter(|end| *end > start).ok_or("The model did not return JSON")?;
+    let value: Value = serde_json::from_str(&response[start..=end]).map_err(|e| format!("The model returned invalid JSON: {}", e))?;
+    let items = value
+        .get("tasks")
+        .or(Some(&value))
+        .and_then(Value::as_array)
+        .ok_or("The model's JSON has no task list")?;
+
+    let mut drafts: Vec<TaskDraft> = Vec::new();
+    let mut rejected = Vec::new();
+    for item in items {
+        let text = |key: &str| {
+            item.get(key)
+                .and_then(Value::as_str)
+                .map(str::trim)
+                .filter(|s| !s.is_empty() && !s.eq_ignore_ascii_case("null"))
+                .map(str::to_string)
+        };
+
+        let title = match item.as_str().map(str::to_string).or_else(|| text("title")) {
+            Some(title) => strip_list_marker(&title).trim_end_matches('.').to_string(),
+            None => {
+                rejected.push(format!("No title: {}", item));
+                continue;
+            }
+        };
+        if title.is_empty() || title.chars().count() > MAX_TITLE_LEN {
+            rejected.push(format!("Unusable title: {}", title));
+            continue;
+        }
+        if drafts.iter().any(|d| d.task.title.eq_ignore_ascii_case(&title)) {
+            continue;
+        }
+
+        let priority = text("priority")
+            .map(|p| p.to_lowercase())
+            .filter(|p| ["low", "medium", "high"].contains(&p.as_str()))
+            .unwrap_or_else(|| "medium".to_string());
+        let due_date = match text("due_date") {
+            Some(due) => match resolve_due(&due, source_date) {
+                Some(due) => Some(due),
+                None => {
+                    rejected.push(format!("Ignored the due date '{}' of '{}'", due, title));
+                    None
+                }
+            },
+            None => None,
+        };
+        let owner = text("owner")
+            .and_then(|owner| normalize_tag_name(&owner.replace('/', " ")))
+            .filter(|owner| !NO_OWNER.contains(&owner.to_lowercase().as_str()));
+
+        drafts.push(TaskDraft {
+            task: CreateTask {
+                title,
+                description: text("description"),
+                status: Some("todo".to_string()),
+                priority: Some(priority),
+                due_date,
+                reminder_time: None,
+                parent_id: None,
+                recurrence: None,
+                estimate: None,
+                time_zone: None,
+            },
+            owner,
+            excerpt: text("excerpt"),
+        });
+    }
+
+    Ok(TaskExtraction {
+        drafts,
+        source_date: source_date.format("%Y-%m-%d").to_string(),
+        rejected,
+    })
+}
+
+pub struct TaskExtractionService<'a> {
+    db: &'a Database,
+}
+
+impl<'a> TaskExtractionService<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    fn validate_source(source: &TaskSource) -> Result<&'static str> {
+        match source.source_type.as_str() {
+            "message" => Ok("messages"),
+            "document" => Ok("documents"),
+            "file" => Ok("files"),
+            other => Err(invalid(format!(
+                "Tasks can only come from {}, not '{}'",
+                SOURCE_TYPES.join(", "),
+                other
+            ))),
+        }
+    }
+
+    /// The day the source was written, used to resolve relative due dates
+    pub fn source_date(&self, source: &TaskSource) -> Result<NaiveDate> {
+        let table = Self::validate_source(source)?;
+        let created_at: Option<String> = self
+            .db
+            .get_connection()
+            .query_row(&format!("SELECT created_at FROM {} WHERE id = ?1", table), params![source.source_id], |row| row.get(0))
+            .optional()?
+            .ok_or_else(|| invalid(format!("{} {} does not exist", source.source_type, source.source_id)))?;
+
+        Ok(created_at
+            .as_deref()
+            .and_then(parse_stored_datetime)
+            .map(|(at, _)| at.date())
+            .unwrap_or_else(|| chrono::Local::now().date_naive()))
+    }
+
+    /// Save the accepted drafts. Owners become `owner/<name>` tags and each
+    /// task is linked from its source so it can be traced back.
+    pub fn commit_drafts(&self, drafts: Vec<TaskDraft>, source: Option<&TaskSource>) -> Result<Vec<CommittedTask>> {
+        if let Some(source) = source {
+            self.source_date(source)?;
+        }
+
+        let conn = self.db.get_connection();
+        let tx = conn.unchecked_transaction()?;
+        let tags = TagService::new(conn);
+        let links = LinksService::new(conn);
+        let mut committed = Vec::new();
+        for draft in drafts {
+            if draft.task.title.trim().is_empty() {
+                return Err(invalid("Every task needs a title".to_string()));
+            }
+            let task: Task = self.db.create_task(draft.task)?;
+            let owner = draft.owner.as_deref().and_then(normalize_tag_name);
+            if let Some(owner) = &owner {
+                tags.tag_item("task", task.id, &format!("owner/{}", owner.replace('/', " ")))?;
+            }
+            let link_id = match source {
+                Some(source) => Some(
+                    links
+                        .create_link(CreateLink {
+                            source_type: source.source_type.clone(),
+                            source_id: source.source_id,
+                            target_type: "task".to_string(),
+                            target_id: task.id,
+                        })?
+                        .id,
+                ),
+                None => None,
+            };
+            committed.push(CommittedTask { task, owner, link_id });
+        }
+        tx.commit()?;
+
+        Ok(committed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_model_output() {
+        let friday = NaiveDate::from_ymd_opt(2024, 5, 3).unwrap();
+        let response = r#"Sure! Here are the tasks:
+            {"tasks": [
+                {"title": "1. Send the budget to finance.", "priority": "HIGH", "due_date": "next monday", "owner": "Dana"},
+                {"title": "- [ ] Book venue", "priority": "urgent", "due_date": "2024-05-10T14:00", "owner": "me"},
+                {"title": "send the budget to finance", "priority": "low", "due_date": null, "owner": null},
+                {"title": "", "priority": "low"},
+                {"title": "Call vendor", "priority": "low", "due_date": "whenever", "owner": "null"}
+            ]}"#;
+        let extraction = parse_extraction(response, friday).unwrap();
+        let titles: Vec<&str> = extraction.drafts.iter().map(|d| d.task.title.as_str()).collect();
+        assert_eq!(titles, vec!["Send the budget to finance", "Book venue", "Call vendor"]);
+
+        let first = &extraction.drafts[0];
+        assert_eq!(first.task.priority.as_deref(), Some("high"));
+        assert_eq!(first.task.due_date.as_deref(), Some("2024-05-06"));
+        assert_eq!(first.owner.as_deref(), Some("Dana"));
+
+        let second = &extraction.drafts[1];
+        assert_eq!(second.task.priority.as_deref(), Some("medium"));
+        assert_eq!(second.task.due_date.as_deref(), Some("2024-05-10T14:00"));
+        assert_eq!(second.owner, None);
+
+        assert_eq!(extraction.drafts[2].task.due_date, None);
+        assert_eq!(extraction.rejected.len(), 2);
+    }
+
+    #[test]
+    fn rejects_replies_without_json() {
+        let today = NaiveDate::from_ymd_opt(2024, 5, 3).unwrap();
+        assert!(parse_extraction("1. Do the thing\n2. Do another", today).is_err());
+        assert!(parse_extraction("[\"Water plants\"]", today).unwrap().drafts.len() == 1);
+    }
+}
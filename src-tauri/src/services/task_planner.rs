@@ -0,0 +1,612 @@
+// Task Planner - time-block open tasks into free calendar time
+//
+// Tasks are ranked by due date, then priority, then age, and each one takes
+// the earliest free working time left by the tasks ahead of it, split into
+// blocks when needed. The same tasks and calendar always give the same plan.
+// Blocks are ordinary events linked to their task in `links` and recorded in
+// `planned_blocks`. Re-planning moves a task's blocks still ahead in place, in
+// order, so they keep their ids, and leaves alone the ones already under way
+// or edited by hand.
+use crate::models::event::{CalendarEvent, CreateEvent};
+use crate::models::scheduling::{PlanRequest, PlannedBlock, PlannedTask, TaskPlan};
+use crate::models::task::Task;
+use crate::services::database::Database;
+use crate::services::links::{CreateLink, LinksService};
+use crate::services::recurring_events::RecurringEventService;
+use crate::services::rrule::{format_stored_datetime, parse_stored_datetime, DateStyle};
+use crate::services::scheduling::{bound, busy_spans, free_gaps, merge_busy, round_up, Busy, WorkingHours};
+use crate::services::settings::SettingsService;
+use crate::services::time_tracking::TimeTrackingService;
+use crate::services::time_zones::{instant, user_zone, utc_to_zoned, zoned_to_utc};
+use chrono::{Duration, NaiveDateTime, NaiveTime, Utc};
+use chrono_tz::Tz;
+use rusqlite::{params, Result};
+use std::collections::{HashMap, HashSet};
+
+/// Settings key holding the options of the last plan, as JSON
+pub const PLAN_SETTING: &str = "task_plan";
+const PLAN_DAYS: i64 = 14;
+const DEFAULT_MIN_BLOCK: i64 = 30;
+const DEFAULT_MAX_BLOCK: i64 = 120;
+
+fn invalid(message: String) -> rusqlite::Error {
+    rusqlite::Error::InvalidParameterName(message)
+}
+
+fn priority_rank(priority: &str) -> u8 {
+    match priority {
+        "high" => 0,
+        "low" => 2,
+        _ => 1,
+    }
+}
+
+/// A task waiting for time
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub task_id: i64,
+    /// When the task must be finished; a due date means the end of that day
+    pub due: Option<NaiveDateTime>,
+    pub priority: String,
+    pub minutes: i64,
+}
+
+/// Earliest due date first, then higher priority, then the older task
+pub fn rank(candidates: &mut [Candidate]) {
+    candidates.sort_by_key(|c| (c.due.is_none(), c.due, priority_rank(&c.priority), c.task_id));
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Placement {
+    pub blocks: Vec<(NaiveDateTime, NaiveDateTime)>,
+    pub unplanned_minutes: i64,
+}
+
+/// Block lengths in minutes
+#[derive(Debug, Clone, Copy)]
+pub struct BlockSizes {
+    pub min: i64,
+    pub max: i64,
+    pub buffer: i64,
+}
+
+/// Place ranked candidates, each into the earliest free working time left;
+/// `busy` must already include the buffer around events
+pub fn place(
+    candidates: &[Candidate],
+    busy: Vec<Busy>,
+    windows: &[(NaiveDateTime, NaiveDateTime)],
+    sizes: BlockSizes,
+) -> Vec<Placement> {
+    let buffer = Duration::minutes(sizes.buffer);
+    let mut busy = merge_busy(busy);
+    let mut placements = Vec::new();
+    for candidate in candidates {
+        let mut left = candidate.minutes;
+        let mut blocks = Vec::new();
+        'windows: for (from, to) in windows {
+            for (gap_start, gap_end) in free_gaps(&busy, *from, *to) {
+                let mut start = round_up(gap_start);
+                while left > 0 && start < gap_end {
+                    let length = left.min(sizes.max).min((gap_end - start).num_minutes());
+                    if length < sizes.min.min(left) {
+                        break;
+                    }
+                    blocks.push((start, start + Duration::minutes(length)));
+                    left -= length;
+                    start = round_up(start + Duration::minutes(length) + buffer);
+                }
+                if left == 0 {
+                    break 'windows;
+                }
+            }
+        }
+        busy.extend(blocks.iter().map(|(start, end)| Busy { start: *start - buffer, end: *end + buffer, event_ids: Vec::new() }));
+        busy = merge_busy(busy);
+        placements.push(Placement { blocks, unplanned_minutes: left });
+    }
+    placements
+}
+
+fn label(at: NaiveDateTime, zone: Tz) -> String {
+    utc_to_zoned(zone, at).format("%a %-d %b %H:%M").to_string()
+}
+
+/// Due dates without a time are due at the following midnight; show them as the date
+fn due_label(due: NaiveDateTime, zone: Tz) -> String {
+    let local = utc_to_zoned(zone, due);
+    if local.time() == NaiveTime::MIN {
+        (local - Duration::days(1)).format("%a %-d %b").to_string()
+    } else {
+        local.format("%a %-d %b %H:%M").to_string()
+    }
+}
+
+/// Why a task was ranked and placed the way it was
+fn explain(
+    candidate: &Candidate,
+    rank: usize,
+    placement: &Placement,
+    over_estimate: bool,
+    until: NaiveDateTime,
+    zone: Tz,
+) -> String {
+    let mut order = Vec::new();
+    match candidate.due {
+        Some(due) => order.push(format!("due {}", due_label(due, zone))),
+        None => order.push("no due date".to_string()),
+    }
+    order.push(format!("{} priority", candidate.priority));
+    if over_estimate {
+        order.push(format!("over its estimate, so {} more minutes", candidate.minutes))
+    } else {
+        order.push(format!("{} minutes left", candidate.minutes));
+    }
+    let ahead = match rank {
+        1 => "first in line".to_string(),
+        2 => "after 1 task ranked ahead".to_string(),
+        n => format!("after {} tasks ranked ahead", n - 1),
+    };
+    let mut reason = format!("#{} ({}); ", rank, order.join(", "));
+    match (placement.blocks.first(), placement.blocks.last()) {
+        (Some((start, _)), Some((_, end))) => {
+            reason.push_str(&format!("earliest free working time {}, from {}", ahead, label(*start, zone)));
+            if placement.blocks.len() > 1 {
+                reason.push_str(&format!(" in {} blocks", placement.blocks.len()));
+            }
+            if placement.unplanned_minutes > 0 {
+                reason.push_str(&format!("; {} minutes did not fit before {}", placement.unplanned_minutes, label(until, zone)));
+            }
+            if candidate.due.map(|due| *end > due).unwrap_or(false) {
+                reason.push_str("; ends after the due date, as there was not enough free time before it");
+            }
+        }
+        _ => reason.push_str(&format!("no free working time was left {} before {}", ahead, label(until, zone))),
+    }
+    reason
+}
+
+/// A stored block with its event
+struct Booked {
+    task_id: i64,
+    event: CalendarEvent,
+    reason: String,
+    pinned: bool,
+}
+
+pub struct TaskPlannerService<'a> {
+    db: &'a Database,
+}
+
+impl<'a> TaskPlannerService<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    fn booked(&self) -> Result<Vec<Booked>> {
+        let conn = self.db.get_connection();
+        let mut stmt = conn.prepare(
+            "SELECT b.task_id, b.event_id, b.planned_start, b.planned_end, b.reason, b.pinned FROM planned_blocks b
+             JOIN events e ON e.id = b.event_id ORDER BY e.start_time, b.event_id",
+        )?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get(4)?,
+                    row.get::<_, bool>(5)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>>>()?;
+        rows.into_iter()
+            .map(|(task_id, event_id, start, end, reason, edited)| {
+                let event = self.db.get_event(event_id)?;
+                let pinned = edited || event.start_time != start || event.end_time != end;
+                Ok(Booked { task_id, event, reason, pinned })
+            })
+            .collect()
+    }
+
+    fn remove(&self, block: &Booked) -> Result<()> {
+        let conn = self.db.get_connection();
+        conn.execute(
+            "DELETE FROM links WHERE source_type = 'task' AND source_id = ?1 AND target_type = 'event' AND target_id = ?2",
+            params![block.task_id, block.event.id],
+        )?;
+        self.db.delete_event(block.event.id)
+    }
+
+    fn book(&self, task: &Task, start: NaiveDateTime, end: NaiveDateTime, reason: &str, zone: Tz) -> Result<()> {
+        let event = self.db.create_event(CreateEvent {
+            title: task.title.clone(),
+            description: Some(reason.to_string()),
+            start_time: format_stored_datetime(start, DateStyle::Utc),
+            end_time: format_stored_datetime(end, DateStyle::Utc),
+            reminder_time: None,
+            recurrence: None,
+            location: None,
+            all_day: false,
+            exdates: Vec::new(),
+            time_zone: Some(zone.name().to_string()),
+        })?;
+        LinksService::new(self.db.get_connection()).create_link(CreateLink {
+            source_type: "task".to_string(),
+            source_id: task.id,
+            target_type: "event".to_string(),
+            target_id: event.id,
+        })?;
+        self.db.get_connection().execute(
+            "INSERT INTO planned_blocks (event_id, task_id, planned_start, planned_end, reason) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![event.id, task.id, event.start_time, event.end_time, reason],
+        )?;
+        Ok(())
+    }
+
+    /// Move an unpinned block to a new placement; one that is already there is left untouched
+    fn rebook(&self, block: &Booked, task: &Task, start: NaiveDateTime, end: NaiveDateTime, reason: &str, zone: Tz) -> Result<()> {
+        if instant(&block.event.start_time, zone) == Some(start) && instant(&block.event.end_time, zone) == Some(end) {
+            return Ok(());
+        }
+        let (start, end) = (format_stored_datetime(start, DateStyle::Utc), format_stored_datetime(end, DateStyle::Utc));
+        let conn = self.db.get_connection();
+        // Written directly, as `update_event` would pin the block
+        conn.execute(
+            "UPDATE events SET title = ?1, description = ?2, start_time = ?3, end_time = ?4 WHERE id = ?5",
+            params![task.title, reason, start, end, block.event.id],
+        )?;
+        conn.execute(
+            "UPDATE planned_blocks SET planned_start = ?1, planned_end = ?2, reason = ?3 WHERE event_id = ?4",
+            params![start, end, reason, block.event.id],
+        )?;
+        Ok(())
+    }
+
+    /// Blocks that have not ended yet
+    pub fn get_plan(&self) -> Result<Vec<PlannedBlock>> {
+        let now = Utc::now().naive_utc();
+        let zone = user_zone(self.db.get_connection());
+        Ok(self
+            .booked()?
+            .into_iter()
+            .filter(|b| instant(&b.event.end_time, zone).map(|end| end > now).unwrap_or(true))
+            .map(|b| PlannedBlock { task_id: b.task_id, event: b.event, reason: b.reason, pinned: b.pinned })
+            .collect())
+    }
+
+    /// Plan the tasks and keep the options, so later changes re-plan them
+    pub fn plan(&self, request: &PlanRequest) -> Result<TaskPlan> {
+        let plan = self.plan_from(request, Utc::now().naive_utc())?;
+        let saved = PlanRequest { start: None, end: None, ..request.clone() };
+        let json = serde_json::to_string(&saved).map_err(|e| invalid(e.to_string()))?;
+        SettingsService::new(self.db.get_connection()).set_setting(PLAN_SETTING, &json)?;
+        Ok(plan)
+    }
+
+    /// Plan again with the last options, if tasks have been planned
+    pub fn replan(&self) -> Result<Option<TaskPlan>> {
+        let saved = SettingsService::new(self.db.get_connection()).get_setting(PLAN_SETTING)?;
+        let Some(request) = saved.and_then(|json| serde_json::from_str::<PlanRequest>(&json).ok()) else {
+            return Ok(None);
+        };
+        self.plan_from(&request, Utc::now().naive_utc()).map(Some)
+    }
+
+    /// Remove the blocks still ahead and stop following changes
+    pub fn clear_plan(&self) -> Result<()> {
+        let now = Utc::now().naive_utc();
+        let zone = user_zone(self.db.get_connection());
+        let tx = self.db.get_connection().unchecked_transaction()?;
+        for block in self.booked()? {
+            if instant(&block.event.start_time, zone).map(|start| start >= now).unwrap_or(false) {
+                self.remove(&block)?;
+            }
+        }
+        tx.execute("DELETE FROM settings WHERE key = ?1", params![PLAN_SETTING])?;
+        tx.commit()
+    }
+
+    /// Plan from `now`; blocks that started before the range are history
+    pub fn plan_from(&self, request: &PlanRequest, now: NaiveDateTime) -> Result<TaskPlan> {
+        let conn = self.db.get_connection();
+        let zone = user_zone(conn);
+        let hours = WorkingHours::parse(request.work_start.as_deref(), request.work_end.as_deref(), request.work_days.as_deref())?;
+        let sizes = BlockSizes {
+            min: request.min_block_minutes.unwrap_or(DEFAULT_MIN_BLOCK),
+            max: request.max_block_minutes.unwrap_or(DEFAULT_MAX_BLOCK),
+            buffer: request.buffer_minutes,
+        };
+        if sizes.min <= 0 || sizes.max < sizes.min {
+            return Err(invalid("Blocks must be at least a minute and the longest no shorter than the shortest".to_string()));
+        }
+        if sizes.buffer < 0 {
+            return Err(invalid("The buffer cannot be negative".to_string()));
+        }
+        let from = match request.start.as_deref() {
+            Some(start) => bound(start, zone)?,
+            None => now,
+        };
+        let to = match request.end.as_deref() {
+            Some(end) => bound(end, zone)?,
+            None => from + Duration::days(PLAN_DAYS),
+        };
+        if to <= from {
+            return Err(invalid("The planning range ends before it starts".to_string()));
+        }
+
+        let open: HashMap<i64, Task> = self.db.get_all_tasks()?.into_iter().filter(|t| t.status != "done").map(|t| (t.id, t)).collect();
+        let selected = |task: &Task| {
+            task.estimate.unwrap_or(0) > 0 && request.task_ids.as_ref().map(|ids| ids.contains(&task.id)).unwrap_or(true)
+        };
+
+        let tx = conn.unchecked_transaction()?;
+        // Minutes already behind each task, and still booked ahead of it
+        let mut past: HashMap<i64, i64> = HashMap::new();
+        let mut booked: HashMap<i64, i64> = HashMap::new();
+        // Unpinned blocks ahead, per task in start order, to be moved to the new placement
+        let mut movable: HashMap<i64, Vec<Booked>> = HashMap::new();
+        for block in self.booked()? {
+            let (Some(start), Some(end)) = (instant(&block.event.start_time, zone), instant(&block.event.end_time, zone)) else {
+                continue;
+            };
+            let task = open.get(&block.task_id);
+            if start >= from {
+                // Moved blocks and blocks of tasks left out of this plan stay
+                let keep = task
+                    .map(|task| task.estimate.unwrap_or(0) > 0 && (block.pinned || !selected(task)))
+                    .unwrap_or(false);
+                if !keep {
+                    if task.map(|task| selected(task)).unwrap_or(false) {
+                        movable.entry(block.task_id).or_default().push(block);
+                    } else {
+                        self.remove(&block)?;
+                    }
+                    continue;
+                }
+            }
+            *past.entry(block.task_id).or_default() += (end.min(from) - start.min(from)).num_minutes();
+            *booked.entry(block.task_id).or_default() += (end.max(from) - start.max(from)).num_minutes();
+        }
+
+        let tracking = TimeTrackingService::new(self.db);
+        let mut candidates = Vec::new();
+        let mut over_estimate = HashSet::new();
+        for task in open.values().filter(|t| selected(t)) {
+            let tracked = tracking.tracked_seconds(task.id)? / 60;
+            // Without tracked time, the blocks that went by count as work done
+            let done = if tracked > 0 { tracked } else { past.get(&task.id).copied().unwrap_or(0) };
+            let ahead = booked.get(&task.id).copied().unwrap_or(0);
+            let mut minutes = task.estimate.unwrap_or(0) - done - ahead;
+            if minutes <= 0 {
+                if ahead > 0 {
+                    continue;
+                }
+                // Still open after its estimate ran out: it slipped, so give it another block
+                minutes = sizes.min;
+                over_estimate.insert(task.id);
+            }
+            candidates.push(Candidate { task_id: task.id, due: due_instant(task, zone), priority: task.priority.clone(), minutes });
+        }
+        rank(&mut candidates);
+
+        let buffer = Duration::minutes(sizes.buffer);
+        // The blocks about to be moved do not hold on to their current time
+        let moving: HashSet<i64> = movable.values().flatten().map(|block| block.event.id).collect();
+        let events: Vec<CalendarEvent> = RecurringEventService::new(self.db)
+            .get_events_overlapping(from - buffer, to + buffer)?
+            .into_iter()
+            .filter(|event| !moving.contains(&event.id))
+            .collect();
+        let busy = busy_spans(&events, buffer, zone);
+        let placements = place(&candidates, busy, &hours.windows(from, to, zone), sizes);
+
+        let mut tasks = Vec::new();
+        for (index, (candidate, placement)) in candidates.iter().zip(&placements).enumerate() {
+            let task = &open[&candidate.task_id];
+            let reason = explain(candidate, index + 1, placement, over_estimate.contains(&task.id), to, zone);
+            let mut previous = movable.remove(&task.id).unwrap_or_default().into_iter();
+            for (part, (start, end)) in placement.blocks.iter().enumerate() {
+                let reason = if placement.blocks.len() > 1 {
+                    format!("{} (block {} of {})", reason, part + 1, placement.blocks.len())
+                } else {
+                    reason.clone()
+                };
+                match previous.next() {
+                    Some(block) => self.rebook(&block, task, *start, *end, &reason, zone)?,
+                    None => self.book(task, *start, *end, &reason, zone)?,
+                }
+            }
+            for block in previous {
+                self.remove(&block)?;
+            }
+            tasks.push(PlannedTask {
+                task_id: task.id,
+                title: task.title.clone(),
+                rank: index + 1,
+                planned_minutes: candidate.minutes - placement.unplanned_minutes,
+                unplanned_minutes: placement.unplanned_minutes,
+                reason,
+            });
+        }
+        // Tasks that need no more time give up their blocks
+        for block in movable.into_values().flatten() {
+            self.remove(&block)?;
+        }
+        tx.commit()?;
+
+        let blocks = self
+            .booked()?
+            .into_iter()
+            .filter(|b| instant(&b.event.end_time, zone).map(|end| end > from).unwrap_or(true))
+            .map(|b| PlannedBlock { task_id: b.task_id, event: b.event, reason: b.reason, pinned: b.pinned })
+            .collect();
+        Ok(TaskPlan { tasks, blocks })
+    }
+}
+
+/// Re-plan after events or tasks changed; a failed re-plan keeps the old blocks
+pub fn follow_changes(db: &Database) {
+    if let Err(e) = TaskPlannerService::new(db).replan() {
+        eprintln!("The task plan could not follow a change: {}", e);
+    }
+}
+
+/// When a task is due; a due date without a time means the end of that day
+fn due_instant(task: &Task, zone: Tz) -> Option<NaiveDateTime> {
+    let due = task.due_date.as_deref()?;
+    let zone = task.time_zone.as_deref().and_then(|name| name.parse().ok()).unwrap_or(zone);
+    match parse_stored_datetime(due)? {
+        (date, DateStyle::Date) => zoned_to_utc(zone, date + Duration::days(1)),
+        _ => instant(due, zone),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dt(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn candidate(task_id: i64, due: Option<&str>, priority: &str, minutes: i64) -> Candidate {
+        Candidate { task_id, due: due.map(dt), priority: priority.into(), minutes }
+    }
+
+    #[test]
+    fn ranks_by_due_date_then_priority() {
+        let mut candidates = vec![
+            candidate(1, None, "high", 60),
+            candidate(2, Some("2024-05-10 17:00"), "low", 60),
+            candidate(3, Some("2024-05-08 17:00"), "low", 60),
+            candidate(4, Some("2024-05-10 17:00"), "high", 60),
+            candidate(5, None, "high", 60),
+            candidate(6, None, "medium", 60),
+        ];
+        rank(&mut candidates);
+        let order: Vec<i64> = candidates.iter().map(|c| c.task_id).collect();
+        assert_eq!(order, vec![3, 4, 2, 1, 5, 6]);
+    }
+
+    #[test]
+    fn splits_tasks_around_busy_time() {
+        let windows = vec![(dt("2024-05-06 09:00"), dt("2024-05-06 17:00")), (dt("2024-05-07 09:00"), dt("2024-05-07 17:00"))];
+        let meeting = Busy { start: dt("2024-05-06 10:45"), end: dt("2024-05-06 16:15"), event_ids: vec![9] };
+        let sizes = BlockSizes { min: 30, max: 120, buffer: 15 };
+        let candidates = vec![candidate(1, None, "high", 150), candidate(2, None, "medium", 60), candidate(3, None, "low", 900)];
+        let placements = place(&candidates, vec![meeting], &windows, sizes);
+        // 09:00-10:45 fits 105 minutes; the rest goes after the meeting
+        assert_eq!(placements[0].blocks, vec![(dt("2024-05-06 09:00"), dt("2024-05-06 10:45")), (dt("2024-05-06 16:15"), dt("2024-05-06 17:00"))]);
+        assert_eq!(placements[0].unplanned_minutes, 0);
+        // Buffers are kept around the first task's blocks
+        assert_eq!(placements[1].blocks, vec![(dt("2024-05-07 09:00"), dt("2024-05-07 10:00"))]);
+        assert_eq!(
+            placements[2].blocks,
+            vec![
+                (dt("2024-05-07 10:15"), dt("2024-05-07 12:15")),
+                (dt("2024-05-07 12:30"), dt("2024-05-07 14:30")),
+                (dt("2024-05-07 14:45"), dt("2024-05-07 16:45")),
+            ]
+        );
+        assert_eq!(placements[2].unplanned_minutes, 900 - 360);
+        // Deterministic
+        assert_eq!(place(&candidates, vec![Busy { start: dt("2024-05-06 10:45"), end: dt("2024-05-06 16:15"), event_ids: vec![9] }], &windows, sizes), placements);
+    }
+
+    fn event(db: &Database, title: &str, start: &str, end: &str) -> i64 {
+        db.create_event(CreateEvent {
+            title: title.into(),
+            description: None,
+            start_time: start.into(),
+            end_time: end.into(),
+            reminder_time: None,
+            recurrence: None,
+            location: None,
+            all_day: false,
+            exdates: Vec::new(),
+            time_zone: Some("UTC".into()),
+        })
+        .unwrap()
+        .id
+    }
+
+    fn spans(plan: &TaskPlan) -> Vec<(i64, &str, &str)> {
+        plan.blocks.iter().map(|b| (b.event.id, b.event.start_time.as_str(), b.event.end_time.as_str())).collect()
+    }
+
+    #[test]
+    fn replanning_moves_blocks_in_place_and_leaves_edited_ones() {
+        use crate::models::event::UpdateEvent;
+        use crate::models::task::CreateTask;
+
+        let db = Database::in_memory().unwrap();
+        SettingsService::new(db.get_connection()).set_setting("time_zone", "UTC").unwrap();
+        db.create_task(CreateTask {
+            title: "Write report".into(),
+            description: None,
+            status: None,
+            priority: None,
+            due_date: None,
+            reminder_time: None,
+            parent_id: None,
+            recurrence: None,
+            estimate: Some(60),
+            time_zone: None,
+        })
+        .unwrap();
+        let request = PlanRequest {
+            start: Some("2024-05-06T09:00:00Z".into()),
+            end: Some("2024-05-08T00:00:00Z".into()),
+            work_start: None,
+            work_end: None,
+            work_days: None,
+            buffer_minutes: 0,
+            min_block_minutes: None,
+            max_block_minutes: None,
+            task_ids: None,
+        };
+        let planner = TaskPlannerService::new(&db);
+        let now = dt("2024-05-06 08:00");
+
+        let first = planner.plan_from(&request, now).unwrap();
+        let id = first.blocks[0].event.id;
+        assert_eq!(spans(&first), vec![(id, "2024-05-06T09:00:00Z", "2024-05-06T10:00:00Z")]);
+
+        // An unrelated change leaves the block as it was
+        event(&db, "Lunch", "2024-05-06T12:00:00Z", "2024-05-06T13:00:00Z");
+        let again = planner.plan_from(&request, now).unwrap();
+        assert_eq!(spans(&again), spans(&first));
+        assert_eq!(again.blocks[0].event.description, first.blocks[0].event.description);
+
+        // A clash moves the same event rather than booking a new one
+        let standup = event(&db, "Standup", "2024-05-06T09:00:00Z", "2024-05-06T09:30:00Z");
+        let moved = planner.plan_from(&request, now).unwrap();
+        assert_eq!(spans(&moved), vec![(id, "2024-05-06T09:30:00Z", "2024-05-06T10:30:00Z")]);
+        assert!(!moved.blocks[0].pinned);
+
+        // Renaming the block pins it, even though its times did not change
+        db.update_event(
+            id,
+            UpdateEvent {
+                title: Some("Report, first draft".into()),
+                description: None,
+                start_time: None,
+                end_time: None,
+                reminder_time: None,
+                recurrence: None,
+                location: None,
+                all_day: None,
+                exdates: None,
+                time_zone: None,
+            },
+        )
+        .unwrap();
+        db.delete_event(standup).unwrap();
+        let kept = planner.plan_from(&request, now).unwrap();
+        assert_eq!(spans(&kept), vec![(id, "2024-05-06T09:30:00Z", "2024-05-06T10:30:00Z")]);
+        assert!(kept.blocks[0].pinned);
+        assert_eq!(kept.blocks[0].event.title, "Report, first draft");
+    }
+}
@@ -0,0 +1,358 @@
+// Task Structure Service - subtasks, checklists and blocked-by dependencies
+use crate::models::task::{ChecklistItem, Task, TaskDetails, TaskProgress, UpdateChecklistItem};
+use crate::services::database::Database;
+use crate::services::time_tracking::TimeTrackingService;
+use rusqlite::{params, Result};
+
+fn invalid(message: String) -> rusqlite::Error {
+    rusqlite::Error::InvalidParameterName(message)
+}
+
+pub struct TaskStructureService<'a> {
+    db: &'a Database,
+}
+
+impl<'a> TaskStructureService<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    fn query_tasks(&self, condition: &str, id: i64) -> Result<Vec<Task>> {
+        let conn = self.db.get_connection();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM tasks WHERE {} ORDER BY created_at, id",
+            Database::TASK_COLUMNS,
+            condition
+        ))?;
+        let tasks = stmt.query_map(params![id], Database::task_from_row)?;
+        tasks.collect()
+    }
+
+    // Subtasks
+
+    pub fn get_subtasks(&self, task_id: i64) -> Result<Vec<Task>> {
+        self.query_tasks("parent_id = ?1", task_id)
+    }
+
+    /// Ids of all subtasks below a task, at any depth
+    pub fn get_descendant_ids(&self, task_id: i64) -> Result<Vec<i64>> {
+        let conn = self.db.get_connection();
+        let mut stmt = conn.prepare(
+            "WITH RECURSIVE subtree(id) AS (
+                SELECT id FROM tasks WHERE parent_id = ?1
+                UNION
+                SELECT t.id FROM tasks t JOIN subtree s ON t.parent_id = s.id
+             )
+             SELECT id FROM subtree",
+        )?;
+        let ids = stmt.query_map(params![task_id], |row| row.get(0))?;
+        ids.collect()
+    }
+
+    /// Make a task a subtask of `parent_id`, or a top-level task when `None`
+    pub fn set_parent(&self, task_id: i64, parent_id: Option<i64>) -> Result<Task> {
+        self.db.get_task(task_id)?;
+        if let Some(parent_id) = parent_id {
+            self.db.get_task(parent_id)?;
+            if parent_id == task_id || self.get_descendant_ids(task_id)?.contains(&parent_id) {
+                return Err(invalid(format!(
+                    "Task {} cannot become a subtask of its own subtask {}",
+                    task_id, parent_id
+                )));
+            }
+        }
+
+        self.db.get_connection().execute(
+            "UPDATE tasks SET parent_id = ?1, updated_at = CURRENT_TIMESTAMP, version = version + 1 WHERE id = ?2",
+            params![parent_id, task_id],
+        )?;
+        self.db.get_task(task_id)
+    }
+
+    // Checklist items
+
+    fn map_checklist_item(row: &rusqlite::Row) -> Result<ChecklistItem> {
+        Ok(ChecklistItem {
+            id: row.get(0)?,
+            task_id: row.get(1)?,
+            text: row.get(2)?,
+            done: row.get::<_, i64>(3)? != 0,
+            position: row.get(4)?,
+            created_at: row.get(5)?,
+        })
+    }
+
+    pub fn get_checklist(&self, task_id: i64) -> Result<Vec<ChecklistItem>> {
+        let conn = self.db.get_connection();
+        let mut stmt = conn.prepare(
+            "SELECT id, task_id, text, done, position, created_at
+             FROM task_checklist_items WHERE task_id = ?1 ORDER BY position, id",
+        )?;
+        let items = stmt.query_map(params![task_id], Self::map_checklist_item)?;
+        items.collect()
+    }
+
+    pub fn get_checklist_item(&self, id: i64) -> Result<ChecklistItem> {
+        self.db.get_connection().query_row(
+            "SELECT id, task_id, text, done, position, created_at FROM task_checklist_items WHERE id = ?1",
+            params![id],
+            Self::map_checklist_item,
+        )
+    }
+
+    pub fn add_checklist_item(&self, task_id: i64, text: &str, done: bool) -> Result<ChecklistItem> {
+        self.db.get_task(task_id)?;
+        let conn = self.db.get_connection();
+        conn.execute(
+            "INSERT INTO task_checklist_items (task_id, text, done, position)
+             VALUES (?1, ?2, ?3, (SELECT COALESCE(MAX(position), -1) + 1 FROM task_checklist_items WHERE task_id = ?1))",
+            params![task_id, text.trim(), done as i64],
+        )?;
+        let id = conn.last_insert_rowid();
+        self.get_checklist_item(id)
+    }
+
+    pub fn update_checklist_item(&self, id: i64, update: UpdateChecklistItem) -> Result<ChecklistItem> {
+        let conn = self.db.get_connection();
+        if let Some(text) = &update.text {
+            conn.execute("UPDATE task_checklist_items SET text = ?1 WHERE id = ?2", params![text.trim(), id])?;
+        }
+        if let Some(done) = update.done {
+            conn.execute("UPDATE task_checklist_items SET done = ?1 WHERE id = ?2", params![done as i64, id])?;
+        }
+        if let Some(position) = update.position {
+            conn.execute("UPDATE task_checklist_items SET position = ?1 WHERE id = ?2", params![position, id])?;
+        }
+        self.get_checklist_item(id)
+    }
+
+    pub fn delete_checklist_item(&self, id: i64) -> Result<()> {
+        self.db.get_connection().execute("DELETE FROM task_checklist_items WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    // Dependencies
+
+    /// Record that `task_id` cannot be done before `blocked_by_id`.
+    /// Rejects dependencies that would make a task (indirectly) wait on itself.
+    pub fn add_dependency(&self, task_id: i64, blocked_by_id: i64) -> Result<()> {
+        self.db.get_task(task_id)?;
+        self.db.get_task(blocked_by_id)?;
+        if task_id == blocked_by_id {
+            return Err(invalid(format!("Task {} cannot block itself", task_id)));
+        }
+
+        let conn = self.db.get_connection();
+        let creates_cycle: bool = conn.query_row(
+            "WITH RECURSIVE chain(id) AS (
+                SELECT ?1
+                UNION
+                SELECT d.blocked_by_id FROM task_dependencies d JOIN chain c ON d.task_id = c.id
+             )
+             SELECT EXISTS (SELECT 1 FROM chain WHERE id = ?2)",
+            params![blocked_by_id, task_id],
+            |row| row.get(0),
+        )?;
+        if creates_cycle {
+            return Err(invalid(format!(
+                "Task {} already depends on task {}; this dependency would create a cycle",
+                blocked_by_id, task_id
+            )));
+        }
+
+        conn.execute(
+            "INSERT OR IGNORE INTO task_dependencies (task_id, blocked_by_id) VALUES (?1, ?2)",
+            params![task_id, blocked_by_id],
+        )?;
+        Ok(())
+    }
+
+    pub fn remove_dependency(&self, task_id: i64, blocked_by_id: i64) -> Result<()> {
+        self.db.get_connection().execute(
+            "DELETE FROM task_dependencies WHERE task_id = ?1 AND blocked_by_id = ?2",
+            params![task_id, blocked_by_id],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_blocked_by(&self, task_id: i64) -> Result<Vec<Task>> {
+        self.query_tasks("id IN (SELECT blocked_by_id FROM task_dependencies WHERE task_id = ?1)", task_id)
+    }
+
+    pub fn get_blocking(&self, task_id: i64) -> Result<Vec<Task>> {
+        self.query_tasks("id IN (SELECT task_id FROM task_dependencies WHERE blocked_by_id = ?1)", task_id)
+    }
+
+    /// Ids of the unfinished tasks a task is waiting on
+    pub fn get_open_blockers(&self, task_id: i64) -> Result<Vec<i64>> {
+        Ok(self
+            .get_blocked_by(task_id)?
+            .into_iter()
+            .filter(|t| t.status != "done")
+            .map(|t| t.id)
+            .collect())
+    }
+
+    // Rollups
+
+    pub fn get_progress(&self, task_id: i64) -> Result<TaskProgress> {
+        let task = self.db.get_task(task_id)?;
+        let conn = self.db.get_connection();
+
+        let mut completed = 0;
+        let mut total = 0;
+        for id in self.get_descendant_ids(task_id)? {
+            let status: String = conn.query_row("SELECT status FROM tasks WHERE id = ?1", params![id], |row| row.get(0))?;
+            total += 1;
+            if status == "done" {
+                completed += 1;
+            }
+        }
+        let (checked, items): (i64, i64) = conn.query_row(
+            "SELECT COALESCE(SUM(done), 0), COUNT(*) FROM task_checklist_items WHERE task_id = ?1",
+            params![task_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        completed += checked;
+        total += items;
+
+        let percent = if total > 0 {
+            (completed as f64 / total as f64 * 1000.0).round() / 10.0
+        } else if task.status == "done" {
+            100.0
+        } else {
+            0.0
+        };
+
+        Ok(TaskProgress { completed, total, percent })
+    }
+
+    pub fn get_task_details(&self, task_id: i64) -> Result<TaskDetails> {
+        let task = self.db.get_task(task_id)?;
+        let blocked_by = self.get_blocked_by(task_id)?;
+        let is_blocked = blocked_by.iter().any(|t| t.status != "done");
+
+        Ok(TaskDetails {
+            subtasks: self.get_subtasks(task_id)?,
+            checklist: self.get_checklist(task_id)?,
+            blocking: self.get_blocking(task_id)?,
+            progress: self.get_progress(task_id)?,
+            tracked_seconds: TimeTrackingService::new(self.db).tracked_seconds(task_id)?,
+            blocked_by,
+            is_blocked,
+            task,
+        })
+    }
+
+    /// Remove checklist items and dependencies of a task that is being deleted
+    pub fn delete_task_structure(&self, task_id: i64) -> Result<()> {
+        let conn = self.db.get_connection();
+        conn.execute("DELETE FROM task_checklist_items WHERE task_id = ?1", params![task_id])?;
+        conn.execute(
+            "DELETE FROM task_dependencies WHERE task_id = ?1 OR blocked_by_id = ?1",
+            params![task_id],
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::conflict::UpdateError;
+    use crate::models::task::{CreateTask, UpdateTask};
+    use crate::services::settings::SettingsService;
+
+    fn task(db: &Database, title: &str, parent_id: Option<i64>) -> i64 {
+        db.create_task(CreateTask {
+            title: title.into(),
+            description: None,
+            status: None,
+            priority: None,
+            due_date: None,
+            reminder_time: None,
+            parent_id,
+            recurrence: None,
+            estimate: None,
+            time_zone: None,
+        })
+        .unwrap()
+        .id
+    }
+
+    fn complete() -> UpdateTask {
+        UpdateTask {
+            title: None,
+            description: None,
+            status: Some("done".into()),
+            priority: None,
+            due_date: None,
+            reminder_time: None,
+            recurrence: None,
+            estimate: None,
+            time_zone: None,
+            position: None,
+            board_id: None,
+            expected_version: None,
+            expected_updated_at: None,
+        }
+    }
+
+    #[test]
+    fn dependencies_cannot_form_cycles() {
+        let db = Database::in_memory().unwrap();
+        let structure = TaskStructureService::new(&db);
+        let (design, build, ship) = (task(&db, "Design", None), task(&db, "Build", None), task(&db, "Ship", None));
+
+        assert!(structure.add_dependency(design, design).is_err());
+        structure.add_dependency(build, design).unwrap();
+        structure.add_dependency(ship, build).unwrap();
+        // Design would wait on Ship, which waits on Design through Build
+        assert!(structure.add_dependency(design, ship).is_err());
+        assert!(structure.add_dependency(build, ship).is_err());
+        assert_eq!(structure.get_open_blockers(ship).unwrap(), vec![build]);
+        assert!(structure.get_blocked_by(design).unwrap().is_empty());
+    }
+
+    #[test]
+    fn deleting_a_task_deletes_its_subtasks() {
+        let db = Database::in_memory().unwrap();
+        let structure = TaskStructureService::new(&db);
+        let parent = task(&db, "Release", None);
+        let child = task(&db, "Changelog", Some(parent));
+        let grandchild = task(&db, "Collect PRs", Some(child));
+        let other = task(&db, "Unrelated", None);
+        structure.add_checklist_item(grandchild, "Label them", false).unwrap();
+        structure.add_dependency(other, grandchild).unwrap();
+
+        assert!(structure.set_parent(parent, Some(grandchild)).is_err());
+        db.delete_task(parent).unwrap();
+
+        for id in [parent, child, grandchild] {
+            assert!(db.get_task(id).is_err());
+        }
+        assert!(structure.get_checklist(grandchild).unwrap().is_empty());
+        assert!(structure.get_blocked_by(other).unwrap().is_empty());
+        assert!(db.get_task(other).is_ok());
+    }
+
+    #[test]
+    fn blocked_tasks_complete_with_a_warning_or_are_refused() {
+        let db = Database::in_memory().unwrap();
+        let structure = TaskStructureService::new(&db);
+        let (blocker, blocked) = (task(&db, "Review", None), task(&db, "Merge", None));
+        structure.add_dependency(blocked, blocker).unwrap();
+
+        SettingsService::new(db.get_connection()).set_setting("blocked_task_policy", "refuse").unwrap();
+        match db.update_task(blocked, complete()) {
+            Err(UpdateError::Blocked { blocked_by, .. }) => assert_eq!(blocked_by, vec![blocker]),
+            other => panic!("expected the update to be refused, got {:?}", other.map(|t| t.status)),
+        }
+        assert_eq!(db.get_task(blocked).unwrap().status, "todo");
+
+        SettingsService::new(db.get_connection()).set_setting("blocked_task_policy", "warn").unwrap();
+        let done = db.update_task(blocked, complete()).unwrap();
+        assert_eq!(done.status, "done");
+        assert_eq!(done.open_blockers, vec![blocker]);
+    }
+}
@@ -0,0 +1,1024 @@
+// Task Transfer Service - import tasks from Trello, Todoist, todo.txt, CSV and
+// iCalendar (VTODO), export them to CSV, todo.txt, Taskwarrior JSON and iCalendar
+//
+// Parsing is kept separate from saving so an import can be previewed (dry
+// run) before anything is created. Every import is tracked as an
+// `ingestion_jobs` entry with job_type "task_import:<format>".
+use crate::models::ingestion::{CreateIngestionJob, IngestionJob, UpdateIngestionJob};
+use crate::models::quick_add::QuickAddItem;
+use crate::models::task::{ChecklistItem, CreateTask, Task};
+use crate::models::task_transfer::{ImportedChecklistItem, ImportedTask, TaskImportRequest, TaskImportResult};
+use crate::services::database::Database;
+use crate::services::ics::ICSService;
+use crate::services::ingestion_service::IngestionService;
+use crate::services::quick_add::parse_quick_add;
+use crate::services::recurring_tasks::normalize_recurrence;
+use crate::services::rrule::{format_stored_datetime, parse_stored_datetime, DateStyle, Frequency, RecurrenceRule};
+use crate::services::tags::TagService;
+use crate::services::task_structure::TaskStructureService;
+use crate::services::time_zones::{date_in_zone, instant, parse_zone, system_zone, user_zone};
+use chrono::{Local, Utc};
+use chrono_tz::Tz;
+use rusqlite::Result;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::Path;
+
+pub const IMPORT_FORMATS: [&str; 5] = ["trello", "todoist", "todotxt", "csv", "ics"];
+pub const EXPORT_FORMATS: [&str; 4] = ["csv", "todotxt", "taskwarrior", "ics"];
+const JOB_TYPE_PREFIX: &str = "task_import:";
+
+const CSV_HEADER: [&str; 10] =
+    ["id", "parent_id", "title", "description", "status", "priority", "due_date", "recurrence", "tags", "checklist"];
+
+type Parsed = std::result::Result<(Vec<ImportedTask>, Vec<String>), String>;
+
+fn invalid(message: String) -> rusqlite::Error {
+    rusqlite::Error::InvalidParameterName(message)
+}
+
+fn new_task(title: &str) -> ImportedTask {
+    ImportedTask {
+        title: title.trim().to_string(),
+        status: "todo".to_string(),
+        priority: "medium".to_string(),
+        ..Default::default()
+    }
+}
+
+fn non_empty(value: Option<&str>) -> Option<String> {
+    value.map(str::trim).filter(|v| !v.is_empty()).map(str::to_string)
+}
+
+/// Dates from other tools as Play stores them: dates stay dates, instants
+/// become UTC and wall times are kept for `create_task` to read in the user's zone
+fn normalize_due(value: &str) -> Option<String> {
+    let (at, style) = parse_stored_datetime(value)?;
+    Some(match style {
+        DateStyle::Date | DateStyle::Utc => format_stored_datetime(at, style),
+        _ => at.format("%Y-%m-%dT%H:%M").to_string(),
+    })
+}
+
+/// The zone a task's times were set in, for exports without a database
+fn task_zone(task: &Task) -> Tz {
+    task.time_zone.as_deref().and_then(|zone| parse_zone(zone).ok()).unwrap_or_else(system_zone)
+}
+
+/// Due dates written in words ("every monday", "tomorrow 5pm"), read with
+/// the quick-add parser
+fn due_from_words(words: &str) -> Option<(Option<String>, Option<String>)> {
+    let result = parse_quick_add(&format!("task {}", words), Local::now().naive_local())?;
+    match result.item {
+        QuickAddItem::Task(task) if task.title == "task" => Some((task.due_date, task.recurrence)),
+        _ => None,
+    }
+}
+
+fn status_from_list(name: &str) -> &'static str {
+    let name = name.to_lowercase();
+    if ["done", "complete", "finished", "shipped", "closed"].iter().any(|w| name.contains(w)) {
+        "done"
+    } else if ["progress", "doing", "wip", "review", "active"].iter().any(|w| name.contains(w)) {
+        "in_progress"
+    } else {
+        "todo"
+    }
+}
+
+fn priority_from_label(name: &str) -> Option<&'static str> {
+    match name.trim().to_lowercase().as_str() {
+        "high" | "urgent" | "critical" | "important" | "p1" | "high priority" => Some("high"),
+        "medium" | "p2" | "medium priority" => Some("medium"),
+        "low" | "p3" | "p4" | "someday" | "low priority" => Some("low"),
+        _ => None,
+    }
+}
+
+/// Todoist priorities p1 (most urgent) to p4 (none); the API numbers them the other way round
+fn priority_from_todoist(p: i64) -> &'static str {
+    match p {
+        1 => "high",
+        2 => "medium",
+        _ => "low",
+    }
+}
+
+// CSV
+
+/// Minimal RFC 4180 reader: quoted fields, doubled quotes and line breaks inside quotes
+pub fn parse_csv(text: &str) -> Vec<Vec<String>> {
+    let text = text.trim_start_matches('\u{feff}');
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => row.push(std::mem::take(&mut field)),
+            '\r' if !in_quotes => {}
+            '\n' if !in_quotes => {
+                row.push(std::mem::take(&mut field));
+                if row.iter().any(|f| !f.is_empty()) {
+                    rows.push(std::mem::take(&mut row));
+                } else {
+                    row.clear();
+                }
+            }
+            _ => field.push(c),
+        }
+    }
+    row.push(field);
+    if row.iter().any(|f| !f.is_empty()) {
+        rows.push(row);
+    }
+    rows
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+struct CsvTable {
+    header: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl CsvTable {
+    fn new(text: &str) -> std::result::Result<Self, String> {
+        let mut rows = parse_csv(text).into_iter();
+        let header = rows
+            .next()
+            .ok_or("The CSV file is empty")?
+            .into_iter()
+            .map(|h| h.trim().to_lowercase())
+            .collect();
+        Ok(Self { header, rows: rows.collect() })
+    }
+
+    fn has(&self, column: &str) -> bool {
+        self.header.iter().any(|h| h == column)
+    }
+
+    fn get<'r>(&self, row: &'r [String], column: &str) -> Option<&'r str> {
+        let index = self.header.iter().position(|h| h == column)?;
+        row.get(index).map(String::as_str).map(str::trim).filter(|v| !v.is_empty())
+    }
+}
+
+// Importers
+
+/// Work out the format of an export from its extension and content
+pub fn detect_format(path: &str, content: &str) -> Option<&'static str> {
+    let extension = Path::new(path).extension()?.to_str()?.to_lowercase();
+    match extension.as_str() {
+        "txt" => Some("todotxt"),
+        "ics" | "ical" => Some("ics"),
+        "json" => {
+            let value: Value = serde_json::from_str(content).ok()?;
+            if value.get("cards").is_some() && value.get("lists").is_some() {
+                Some("trello")
+            } else if value.get("items").is_some() || value.is_array() {
+                Some("todoist")
+            } else {
+                None
+            }
+        }
+        "csv" => {
+            let table = CsvTable::new(content).ok()?;
+            if table.has("type") && table.has("content") {
+                Some("todoist")
+            } else if table.has("title") {
+                Some("csv")
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+pub fn parse_import(format: &str, content: &str) -> Parsed {
+    match format {
+        "trello" => parse_trello(content),
+        "todoist" if content.trim_start().starts_with(['{', '[']) => parse_todoist_json(content),
+        "todoist" => parse_todoist_csv(content),
+        "todotxt" => Ok((parse_todotxt(content), Vec::new())),
+        "csv" => parse_play_csv(content),
+        "ics" => parse_ics(content),
+        _ => Err(format!("Unknown import format '{}'", format)),
+    }
+}
+
+/// Trello board export: lists become statuses, labels become tags (or the
+/// priority), checklists carry over with their state. Archived cards are skipped.
+pub fn parse_trello(content: &str) -> Parsed {
+    let board: Value = serde_json::from_str(content).map_err(|e| format!("Not a Trello board export: {}", e))?;
+    let str_of = |v: &Value, key: &str| v.get(key).and_then(Value::as_str).unwrap_or_default().to_string();
+    let flag = |v: &Value, key: &str| v.get(key).and_then(Value::as_bool).unwrap_or(false);
+    let empty = Vec::new();
+    let array = |key: &str| board.get(key).and_then(Value::as_array).unwrap_or(&empty);
+
+    let lists: HashMap<String, (String, bool)> = array("lists")
+        .iter()
+        .map(|list| (str_of(list, "id"), (str_of(list, "name"), flag(list, "closed"))))
+        .collect();
+
+    let mut checklists: HashMap<String, Vec<(f64, ImportedChecklistItem)>> = HashMap::new();
+    for checklist in array("checklists") {
+        let items = checklists.entry(str_of(checklist, "idCard")).or_default();
+        for item in checklist.get("checkItems").and_then(Value::as_array).unwrap_or(&empty) {
+            items.push((
+                item.get("pos").and_then(Value::as_f64).unwrap_or(0.0),
+                ImportedChecklistItem {
+                    text: str_of(item, "name"),
+                    done: str_of(item, "state") == "complete",
+                },
+            ));
+        }
+    }
+
+    let mut tasks = Vec::new();
+    let mut warnings = Vec::new();
+    for card in array("cards") {
+        let title = str_of(card, "name");
+        let (list_name, list_closed) = lists.get(&str_of(card, "idList")).cloned().unwrap_or_default();
+        if flag(card, "closed") || list_closed {
+            warnings.push(format!("Skipped archived card '{}'", title));
+            continue;
+        }
+
+        let mut task = new_task(&title);
+        task.description = non_empty(card.get("desc").and_then(Value::as_str));
+        task.status = if flag(card, "dueComplete") { "done" } else { status_from_list(&list_name) }.to_string();
+        if let Some(due) = card.get("due").and_then(Value::as_str) {
+            task.due_date = normalize_due(due);
+        }
+        for label in card.get("labels").and_then(Value::as_array).unwrap_or(&empty) {
+            let name = non_empty(label.get("name").and_then(Value::as_str))
+                .or_else(|| non_empty(label.get("color").and_then(Value::as_str)));
+            if let Some(name) = name {
+                match priority_from_label(&name) {
+                    Some(priority) => task.priority = priority.to_string(),
+                    None => task.tags.push(name),
+                }
+            }
+        }
+        if let Some(mut items) = checklists.remove(&str_of(card, "id")) {
+            items.sort_by(|a, b| a.0.total_cmp(&b.0));
+            task.checklist = items.into_iter().map(|(_, item)| item).collect();
+        }
+        tasks.push(task);
+    }
+
+    Ok((tasks, warnings))
+}
+
+/// Todoist CSV (project template) export: TYPE, CONTENT, DESCRIPTION,
+/// PRIORITY, INDENT, DATE. Labels are written inline as @label and
+/// indented tasks become subtasks.
+pub fn parse_todoist_csv(content: &str) -> Parsed {
+    let table = CsvTable::new(content)?;
+    if !table.has("content") {
+        return Err("Not a Todoist CSV export: no CONTENT column".to_string());
+    }
+
+    let mut tasks: Vec<ImportedTask> = Vec::new();
+    let mut warnings = Vec::new();
+    let mut parents: Vec<(i64, usize)> = Vec::new();
+    for row in &table.rows {
+        if table.get(row, "type").map(|t| t.to_lowercase()) != Some("task".to_string()) {
+            continue;
+        }
+        let content = table.get(row, "content").unwrap_or_default();
+        let (title, labels): (Vec<&str>, Vec<&str>) = content.split_whitespace().partition(|w| !w.starts_with('@') || w.len() == 1);
+
+        let mut task = new_task(&title.join(" "));
+        task.tags = labels.iter().map(|l| l.trim_start_matches('@').to_string()).collect();
+        task.description = non_empty(table.get(row, "description"));
+        if let Some(priority) = table.get(row, "priority").and_then(|p| p.parse().ok()) {
+            task.priority = priority_from_todoist(priority).to_string();
+        }
+        if let Some(date) = table.get(row, "date") {
+            match normalize_due(date).map(|due| (Some(due), None)).or_else(|| due_from_words(date)) {
+                Some((due, recurrence)) => {
+                    task.due_date = due;
+                    task.recurrence = recurrence;
+                }
+                None => warnings.push(format!("Could not read the date '{}' of '{}'", date, task.title)),
+            }
+        }
+
+        let indent: i64 = table.get(row, "indent").and_then(|i| i.parse().ok()).unwrap_or(1);
+        while parents.last().map(|(level, _)| *level >= indent).unwrap_or(false) {
+            parents.pop();
+        }
+        task.parent = parents.last().map(|(_, index)| *index);
+        parents.push((indent, tasks.len()));
+        tasks.push(task);
+    }
+
+    Ok((tasks, warnings))
+}
+
+/// Todoist JSON (Sync API / backup) with an `items` array. API priorities
+/// run from 4 (p1) to 1 (p4); projects become tags.
+pub fn parse_todoist_json(content: &str) -> Parsed {
+    let value: Value = serde_json::from_str(content).map_err(|e| format!("Not a Todoist JSON export: {}", e))?;
+    let empty = Vec::new();
+    let items = value
+        .get("items")
+        .and_then(Value::as_array)
+        .or_else(|| value.as_array())
+        .ok_or("Not a Todoist JSON export: no items")?;
+    let id_of = |v: Option<&Value>| match v {
+        Some(Value::String(s)) => Some(s.clone()),
+        Some(Value::Number(n)) => Some(n.to_string()),
+        _ => None,
+    };
+    let projects: HashMap<String, String> = value
+        .get("projects")
+        .and_then(Value::as_array)
+        .unwrap_or(&empty)
+        .iter()
+        .filter_map(|p| Some((id_of(p.get("id"))?, p.get("name")?.as_str()?.to_string())))
+        .filter(|(_, name)| name != "Inbox")
+        .collect();
+
+    let mut tasks = Vec::new();
+    let mut warnings = Vec::new();
+    let mut index_of: HashMap<String, usize> = HashMap::new();
+    let mut parent_ids = Vec::new();
+    for item in items {
+        if item.get("is_deleted").and_then(Value::as_bool).unwrap_or(false) {
+            continue;
+        }
+        let mut task = new_task(item.get("content").and_then(Value::as_str).unwrap_or_default());
+        task.description = non_empty(item.get("description").and_then(Value::as_str));
+        let checked = match item.get("checked") {
+            Some(Value::Bool(b)) => *b,
+            Some(Value::Number(n)) => n.as_i64() == Some(1),
+            _ => false,
+        };
+        if checked {
+            task.status = "done".to_string();
+        }
+        if let Some(priority) = item.get("priority").and_then(Value::as_i64) {
+            task.priority = priority_from_todoist(5 - priority).to_string();
+        }
+        if let Some(due) = item.get("due").filter(|d| d.is_object()) {
+            task.due_date = due.get("date").and_then(Value::as_str).and_then(normalize_due);
+            let recurring = due.get("is_recurring").and_then(Value::as_bool).unwrap_or(false);
+            if let Some(words) = due.get("string").and_then(Value::as_str).filter(|_| recurring) {
+                match due_from_words(words).and_then(|(_, recurrence)| recurrence) {
+                    Some(recurrence) => task.recurrence = Some(recurrence),
+                    None => warnings.push(format!("Could not read the recurrence '{}' of '{}'", words, task.title)),
+                }
+            }
+        }
+        for label in item.get("labels").and_then(Value::as_array).unwrap_or(&empty) {
+            if let Some(label) = label.as_str() {
+                task.tags.push(label.to_string());
+            }
+        }
+        if let Some(project) = id_of(item.get("project_id")).and_then(|id| projects.get(&id)) {
+            task.tags.push(project.clone());
+        }
+
+        if let Some(id) = id_of(item.get("id")) {
+            index_of.insert(id, tasks.len());
+        }
+        parent_ids.push(id_of(item.get("parent_id")));
+        tasks.push(task);
+    }
+
+    // Parents may come after their children in the export
+    for (task, parent_id) in tasks.iter_mut().zip(parent_ids) {
+        task.parent = parent_id.and_then(|id| index_of.get(&id).copied());
+    }
+
+    Ok((tasks, warnings))
+}
+
+/// todo.txt: "x" marks done, (A)-(C) set the priority, +project and
+/// @context become tags, due: and rec: set the due date and recurrence.
+pub fn parse_todotxt(content: &str) -> Vec<ImportedTask> {
+    let is_date = |w: &str| chrono::NaiveDate::parse_from_str(w, "%Y-%m-%d").is_ok();
+    let mut tasks = Vec::new();
+
+    for line in content.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        let mut words: &[&str] = &line.split_whitespace().collect::<Vec<_>>();
+        let mut task = new_task("");
+
+        if words.first() == Some(&"x") {
+            task.status = "done".to_string();
+            words = &words[1..];
+            // completion date, then creation date
+            while words.first().map(|w| is_date(w)).unwrap_or(false) {
+                words = &words[1..];
+            }
+        }
+        if let Some(priority) = words.first().and_then(|w| todotxt_priority(w)) {
+            task.priority = priority.to_string();
+            words = &words[1..];
+        }
+        if words.first().map(|w| is_date(w)).unwrap_or(false) {
+            words = &words[1..];
+        }
+
+        let mut title = Vec::new();
+        for word in words {
+            if let Some(tag) = word.strip_prefix('+').or_else(|| word.strip_prefix('@')).filter(|t| !t.is_empty()) {
+                task.tags.push(tag.to_string());
+            } else if let Some(due) = word.strip_prefix("due:") {
+                task.due_date = normalize_due(due);
+            } else if let Some(rec) = word.strip_prefix("rec:") {
+                task.recurrence = todotxt_recurrence(rec);
+            } else if let Some(priority) = word.strip_prefix("pri:").and_then(|p| todotxt_priority(&format!("({})", p))) {
+                task.priority = priority.to_string();
+            } else if !word.starts_with("t:") {
+                title.push(*word);
+            }
+        }
+        task.title = title.join(" ");
+        if !task.title.is_empty() {
+            tasks.push(task);
+        }
+    }
+
+    tasks
+}
+
+fn todotxt_priority(word: &str) -> Option<&'static str> {
+    let letter = word.strip_prefix('(')?.strip_suffix(')')?;
+    match letter {
+        "A" => Some("high"),
+        "B" => Some("medium"),
+        l if l.len() == 1 && l.chars().all(|c| c.is_ascii_uppercase()) => Some("low"),
+        _ => None,
+    }
+}
+
+/// "1w", "+2d", "3m", "1y", "1b" (business days) as an RRULE
+fn todotxt_recurrence(rec: &str) -> Option<String> {
+    let rec = rec.trim_start_matches('+');
+    let (amount, unit) = rec.split_at(rec.len().checked_sub(1)?);
+    let interval: u32 = if amount.is_empty() { 1 } else { amount.parse().ok()? };
+    let rule = match unit {
+        "d" => format!("FREQ=DAILY;INTERVAL={}", interval),
+        "w" => format!("FREQ=WEEKLY;INTERVAL={}", interval),
+        "m" => format!("FREQ=MONTHLY;INTERVAL={}", interval),
+        "y" => format!("FREQ=YEARLY;INTERVAL={}", interval),
+        "b" => "FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR".to_string(),
+        _ => return None,
+    };
+    RecurrenceRule::parse(&rule).ok().map(|rule| rule.to_string())
+}
+
+/// Play's own CSV export, so tasks can round-trip
+pub fn parse_play_csv(content: &str) -> Parsed {
+    let table = CsvTable::new(content)?;
+    if !table.has("title") {
+        return Err("The CSV file needs a 'title' column".to_string());
+    }
+
+    let mut tasks = Vec::new();
+    let mut warnings = Vec::new();
+    let mut index_of: HashMap<String, usize> = HashMap::new();
+    let mut parent_ids = Vec::new();
+    for (line, row) in table.rows.iter().enumerate() {
+        let title = match table.get(row, "title") {
+            Some(title) => title,
+            None => {
+                warnings.push(format!("Skipped row {} without a title", line + 2));
+                continue;
+            }
+        };
+        let mut task = new_task(title);
+        task.description = non_empty(table.get(row, "description"));
+        if let Some(status) = table.get(row, "status") {
+            task.status = status.to_string();
+        }
+        if let Some(priority) = table.get(row, "priority") {
+            task.priority = priority.to_lowercase();
+        }
+        task.due_date = table.get(row, "due_date").and_then(normalize_due);
+        task.recurrence = non_empty(table.get(row, "recurrence"));
+        task.tags = table
+            .get(row, "tags")
+            .map(|tags| tags.split(',').filter_map(|t| non_empty(Some(t))).collect())
+            .unwrap_or_default();
+        task.checklist = table
+            .get(row, "checklist")
+            .map(|list| {
+                list.lines()
+                    .filter_map(|line| {
+                        let line = line.trim();
+                        let (done, text) = match line.get(..4) {
+                            Some("[x] ") | Some("[X] ") => (true, &line[4..]),
+                            Some("[ ] ") => (false, &line[4..]),
+                            _ => (false, line),
+                        };
+                        non_empty(Some(text)).map(|text| ImportedChecklistItem { text, done })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        if let Some(id) = table.get(row, "id") {
+            index_of.insert(id.to_string(), tasks.len());
+        }
+        parent_ids.push(table.get(row, "parent_id").map(str::to_string));
+        tasks.push(task);
+    }
+
+    for (task, parent_id) in tasks.iter_mut().zip(parent_ids) {
+        task.parent = parent_id.and_then(|id| index_of.get(&id).copied());
+    }
+
+    Ok((tasks, warnings))
+}
+
+/// iCalendar VTODOs, as written by Play and by calendar and todo apps.
+/// Floating due times are kept as wall time, so no zone is needed here.
+pub fn parse_ics(content: &str) -> Parsed {
+    let import = ICSService::with_zone(system_zone())
+        .import_tasks_from_ics(content)
+        .map_err(|e| format!("Not an iCalendar file: {}", e))?;
+    if import.tasks.is_empty() {
+        return Err("The calendar has no tasks (VTODO)".to_string());
+    }
+    Ok((import.tasks, import.warnings))
+}
+
+// Exporters
+
+/// A task with the related data the export formats need
+pub struct ExportTask {
+    pub task: Task,
+    pub tags: Vec<String>,
+    pub checklist: Vec<ChecklistItem>,
+}
+
+pub fn export_csv(tasks: &[ExportTask]) -> String {
+    let mut out = CSV_HEADER.join(",");
+    out.push('\n');
+    for item in tasks {
+        let task = &item.task;
+        let checklist: Vec<String> = item
+            .checklist
+            .iter()
+            .map(|c| format!("[{}] {}", if c.done { "x" } else { " " }, c.text))
+            .collect();
+        let fields = [
+            task.id.to_string(),
+            task.parent_id.map(|id| id.to_string()).unwrap_or_default(),
+            task.title.clone(),
+            task.description.clone().unwrap_or_default(),
+            task.status.clone(),
+            task.priority.clone(),
+            task.due_date.clone().unwrap_or_default(),
+            task.recurrence.clone().unwrap_or_default(),
+            item.tags.join(", "),
+            checklist.join("\n"),
+        ];
+        out.push_str(&fields.iter().map(|f| csv_field(f)).collect::<Vec<_>>().join(","));
+        out.push('\n');
+    }
+    out
+}
+
+/// One line per task. todo.txt has no checklists or descriptions, so those
+/// are left out; multi-word tags are joined with underscores.
+pub fn export_todotxt(tasks: &[ExportTask]) -> String {
+    let mut out = String::new();
+    for item in tasks {
+        let task = &item.task;
+        let mut parts: Vec<String> = Vec::new();
+        let priority = match task.priority.as_str() {
+            "high" => "A",
+            "medium" => "B",
+            _ => "C",
+        };
+        if task.status == "done" {
+            parts.push("x".to_string());
+            // todo.txt needs the completion date before the creation date
+            if let Some((completed, _)) = parse_stored_datetime(&task.updated_at) {
+                parts.push(completed.format("%Y-%m-%d").to_string());
+            }
+        } else {
+            parts.push(format!("({})", priority));
+        }
+        if let Some((created, _)) = parse_stored_datetime(&task.created_at) {
+            parts.push(created.format("%Y-%m-%d").to_string());
+        }
+        parts.push(task.title.split_whitespace().collect::<Vec<_>>().join(" "));
+        parts.extend(item.tags.iter().map(|tag| format!("+{}", tag.split_whitespace().collect::<Vec<_>>().join("_"))));
+        if let Some(due) = task.due_date.as_deref().and_then(|due| date_in_zone(due, task_zone(task))) {
+            parts.push(format!("due:{}", due));
+        }
+        if let Some(rec) = task.recurrence.as_deref().and_then(simple_interval) {
+            parts.push(format!("rec:{}{}", rec.0, rec.1.chars().next().unwrap_or('d')));
+        }
+        if task.status == "done" {
+            parts.push(format!("pri:{}", priority));
+        }
+        out.push_str(&parts.join(" "));
+        out.push('\n');
+    }
+    out
+}
+
+/// Plain "every N units" rules; anything with BY* parts has no simple form
+fn simple_interval(rule: &str) -> Option<(u32, &'static str)> {
+    let rule = RecurrenceRule::parse(rule).ok()?;
+    if !rule.by_day.is_empty() || !rule.by_month_day.is_empty() || !rule.by_month.is_empty() || !rule.by_set_pos.is_empty() {
+        return None;
+    }
+    let unit = match rule.freq {
+        Frequency::Daily => "days",
+        Frequency::Weekly => "weeks",
+        Frequency::Monthly => "months",
+        Frequency::Yearly => "years",
+    };
+    Some((rule.interval, unit))
+}
+
+/// Taskwarrior `task import` JSON
+pub fn export_taskwarrior(tasks: &[ExportTask]) -> String {
+    let tw_time = |value: &str, zone: Tz| -> Option<String> {
+        Some(instant(value, zone)?.format("%Y%m%dT%H%M%SZ").to_string())
+    };
+
+    let entries: Vec<Value> = tasks
+        .iter()
+        .map(|item| {
+            let task = &item.task;
+            let zone = task_zone(task);
+            let mut entry = json!({
+                "uuid": uuid::Uuid::new_v4().to_string(),
+                "description": task.title,
+                "status": if task.status == "done" { "completed" } else { "pending" },
+                "priority": match task.priority.as_str() { "high" => "H", "low" => "L", _ => "M" },
+                "entry": tw_time(&task.created_at, zone),
+                "modified": tw_time(&task.updated_at, zone),
+            });
+            if task.status == "done" {
+                entry["end"] = json!(tw_time(&task.updated_at, zone));
+            }
+            if let Some(due) = task.due_date.as_deref().and_then(|due| tw_time(due, zone)) {
+                entry["due"] = json!(due);
+                // Taskwarrior only repeats tasks that have a due date
+                if let Some((interval, unit)) = task.recurrence.as_deref().and_then(simple_interval) {
+                    entry["status"] = json!("recurring");
+                    entry["recur"] = json!(match (interval, unit) {
+                        (1, "days") => "daily".to_string(),
+                        (1, "weeks") => "weekly".to_string(),
+                        (1, "months") => "monthly".to_string(),
+                        (1, "years") => "yearly".to_string(),
+                        _ => format!("{}{}", interval, unit),
+                    });
+                }
+            }
+            if !item.tags.is_empty() {
+                entry["tags"] = json!(item.tags.iter().map(|t| t.split_whitespace().collect::<Vec<_>>().join("_")).collect::<Vec<_>>());
+            }
+            let mut annotations = Vec::new();
+            if let Some(description) = task.description.as_deref().filter(|d| !d.trim().is_empty()) {
+                annotations.push(description.trim().to_string());
+            }
+            annotations.extend(item.checklist.iter().map(|c| format!("[{}] {}", if c.done { "x" } else { " " }, c.text)));
+            if !annotations.is_empty() {
+                entry["annotations"] = json!(annotations
+                    .into_iter()
+                    .map(|description| json!({ "entry": tw_time(&task.created_at, zone), "description": description }))
+                    .collect::<Vec<_>>());
+            }
+            entry
+        })
+        .collect();
+
+    serde_json::to_string_pretty(&entries).unwrap_or_else(|_| "[]".to_string())
+}
+
+pub struct TaskTransferService<'a> {
+    db: &'a Database,
+}
+
+impl<'a> TaskTransferService<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    /// Start a tracked import. With `dry_run` the job only records what
+    /// would be imported and ends as "previewed"; it can be run for real
+    /// later with `run_import_job`.
+    pub fn import_tasks(&self, request: TaskImportRequest) -> Result<TaskImportResult> {
+        let content = std::fs::read_to_string(&request.path)
+            .map_err(|e| invalid(format!("Could not read {}: {}", request.path, e)))?;
+        let format = match request.format.as_deref().map(str::trim).filter(|f| !f.is_empty()) {
+            Some(format) if IMPORT_FORMATS.contains(&format) => format.to_string(),
+            Some(format) => return Err(invalid(format!("Unknown import format '{}'", format))),
+            None => detect_format(&request.path, &content)
+                .ok_or_else(|| invalid(format!("Could not tell which tool exported {}", request.path)))?
+                .to_string(),
+        };
+
+        let job = IngestionService::new(self.db).create_ingestion_job(CreateIngestionJob {
+            source_path: request.path.clone(),
+            job_type: format!("{}{}", JOB_TYPE_PREFIX, format),
+        })?;
+        self.run(job.id, &format, &content, request.dry_run)
+    }
+
+    /// Whether an ingestion job is a task import rather than a file ingestion
+    pub fn is_import_job(job: &IngestionJob) -> bool {
+        job.job_type.starts_with(JOB_TYPE_PREFIX)
+    }
+
+    /// Run a previewed task import job for real. Any other status is
+    /// refused, so a job can only ever create its tasks once.
+    pub fn run_import_job(&self, job_id: i64) -> Result<TaskImportResult> {
+        let job = IngestionService::new(self.db).get_ingestion_job(job_id)?;
+        let format = job
+            .job_type
+            .strip_prefix(JOB_TYPE_PREFIX)
+            .ok_or_else(|| invalid(format!("Job {} is not a task import", job_id)))?
+            .to_string();
+        if job.status != "previewed" {
+            return Err(invalid(format!("Task import {} is {}; only previewed imports can be run", job_id, job.status)));
+        }
+        let content = std::fs::read_to_string(&job.source_path)
+            .map_err(|e| invalid(format!("Could not read {}: {}", job.source_path, e)))?;
+        self.run(job_id, &format, &content, false)
+    }
+
+    /// The tasks and the job's final status are written together, so a
+    /// failure part way leaves the job as it was and creates nothing.
+    fn run(&self, job_id: i64, format: &str, content: &str, dry_run: bool) -> Result<TaskImportResult> {
+        let tx = self.db.get_connection().unchecked_transaction()?;
+        let jobs = IngestionService::new(self.db);
+        jobs.update_ingestion_job(job_id, UpdateIngestionJob {
+            status: Some("running".to_string()),
+            started_at: Some(Utc::now().to_rfc3339()),
+            ..Default::default()
+        })?;
+
+        let (tasks, mut warnings) = match parse_import(format, content) {
+            Ok(parsed) => parsed,
+            Err(message) => {
+                jobs.update_ingestion_job(job_id, UpdateIngestionJob {
+                    status: Some("failed".to_string()),
+                    error_message: Some(message.clone()),
+                    completed_at: Some(Utc::now().to_rfc3339()),
+                    ..Default::default()
+                })?;
+                tx.commit()?;
+                return Err(invalid(message));
+            }
+        };
+        jobs.update_ingestion_job(job_id, UpdateIngestionJob {
+            total_files: Some(tasks.len() as i64),
+            ..Default::default()
+        })?;
+
+        let mut created_task_ids = Vec::new();
+        if !dry_run {
+            let mut created: Vec<Option<i64>> = Vec::new();
+            for (index, task) in tasks.iter().enumerate() {
+                let parent_id = task.parent.and_then(|parent| created.get(parent).copied().flatten());
+                match self.create(task, parent_id) {
+                    Ok(id) => {
+                        created.push(Some(id));
+                        created_task_ids.push(id);
+                    }
+                    Err(e) => {
+                        created.push(None);
+                        warnings.push(format!("Task {} ('{}') was not imported: {}", index + 1, task.title, e));
+                    }
+                }
+                if index % 50 == 49 {
+                    jobs.update_ingestion_job(job_id, UpdateIngestionJob {
+                        processed_files: Some(created_task_ids.len() as i64),
+                        progress: Some((index + 1) as f64 / tasks.len() as f64 * 100.0),
+                        ..Default::default()
+                    })?;
+                }
+            }
+        }
+
+        let job = jobs.update_ingestion_job(job_id, UpdateIngestionJob {
+            status: Some(if dry_run { "previewed" } else { "completed" }.to_string()),
+            processed_files: Some(created_task_ids.len() as i64),
+            error_count: Some((tasks.len() - created_task_ids.len()) as i64 * (!dry_run as i64)),
+            progress: Some(if dry_run { 0.0 } else { 100.0 }),
+            completed_at: Some(Utc::now().to_rfc3339()),
+            error_message: if warnings.is_empty() { None } else { Some(warnings.join("\n")) },
+            ..Default::default()
+        })?;
+        tx.commit()?;
+
+        Ok(TaskImportResult {
+            job,
+            format: format.to_string(),
+            dry_run,
+            tasks,
+            created_task_ids,
+            warnings,
+        })
+    }
+
+    fn create(&self, imported: &ImportedTask, parent_id: Option<i64>) -> Result<i64> {
+        if imported.title.is_empty() {
+            return Err(invalid("Task has no title".to_string()));
+        }
+        let priority = if ["low", "medium", "high"].contains(&imported.priority.as_str()) {
+            imported.priority.clone()
+        } else {
+            "medium".to_string()
+        };
+        // An unreadable rule should not cost the whole task
+        let recurrence = normalize_recurrence(imported.recurrence.as_deref()).ok().flatten();
+
+        let task = self.db.create_task(CreateTask {
+            title: imported.title.clone(),
+            description: imported.description.clone(),
+            status: Some(imported.status.clone()),
+            priority: Some(priority),
+            due_date: imported.due_date.clone(),
+            reminder_time: imported.reminder_time.clone(),
+            parent_id,
+            recurrence,
+            estimate: imported.estimate,
+            time_zone: imported.time_zone.clone(),
+        })?;
+
+        if !imported.tags.is_empty() {
+            TagService::new(self.db.get_connection()).set_item_tags("task", task.id, &imported.tags)?;
+        }
+        let structure = TaskStructureService::new(self.db);
+        for item in &imported.checklist {
+            structure.add_checklist_item(task.id, &item.text, item.done)?;
+        }
+        Ok(task.id)
+    }
+
+    /// Export tasks (all when `task_ids` is None) as "csv", "todotxt", "taskwarrior" or "ics"
+    pub fn export_tasks(&self, format: &str, task_ids: Option<&[i64]>) -> Result<String> {
+        if !EXPORT_FORMATS.contains(&format) {
+            return Err(invalid(format!("Unknown export format '{}'", format)));
+        }
+        let mut tasks = self.db.get_all_tasks()?;
+        if let Some(ids) = task_ids {
+            tasks.retain(|task| ids.contains(&task.id));
+        }
+        tasks.sort_by_key(|task| task.id);
+
+        let tags = TagService::new(self.db.get_connection());
+        let structure = TaskStructureService::new(self.db);
+        let items = tasks
+            .into_iter()
+            .map(|task| {
+                Ok(ExportTask {
+                    tags: tags.get_tags_for_item("task", task.id)?.into_iter().map(|t| t.name).collect(),
+                    checklist: structure.get_checklist(task.id)?,
+                    task,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(match format {
+            "csv" => export_csv(&items),
+            "todotxt" => export_todotxt(&items),
+            "ics" => ICSService::with_zone(user_zone(self.db.get_connection()))
+                .export_tasks_to_ics(&items)
+                .map_err(|e| invalid(e.to_string()))?,
+            _ => export_taskwarrior(&items),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_trello_boards() {
+        let board = r#"{
+            "lists": [{"id": "l1", "name": "Doing"}, {"id": "l2", "name": "Old", "closed": true}],
+            "cards": [
+                {"id": "c1", "name": "Ship it", "desc": "", "idList": "l1", "due": "2024-05-01",
+                 "labels": [{"name": "Urgent", "color": "red"}, {"name": "", "color": "green"}]},
+                {"id": "c2", "name": "Gone", "idList": "l2"}
+            ],
+            "checklists": [{"idCard": "c1", "checkItems": [
+                {"name": "second", "state": "incomplete", "pos": 2},
+                {"name": "first", "state": "complete", "pos": 1}]}]
+        }"#;
+        let (tasks, warnings) = parse_trello(board).unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(warnings.len(), 1);
+        let task = &tasks[0];
+        assert_eq!((task.status.as_str(), task.priority.as_str()), ("in_progress", "high"));
+        assert_eq!(task.tags, vec!["green"]);
+        assert_eq!(task.due_date.as_deref(), Some("2024-05-01"));
+        assert_eq!(task.checklist[0], ImportedChecklistItem { text: "first".into(), done: true });
+    }
+
+    #[test]
+    fn reads_todoist_csv_with_subtasks_and_labels() {
+        let csv = "TYPE,CONTENT,DESCRIPTION,PRIORITY,INDENT,DATE\n\
+                   section,Work,,,,\n\
+                   task,Write report @work,\"Q1, Q2\",1,1,2024-05-02\n\
+                   task,Gather numbers,,4,2,\n\
+                   task,Standup,,2,1,every weekday\n";
+        let (tasks, warnings) = parse_todoist_csv(csv).unwrap();
+        assert!(warnings.is_empty());
+        assert_eq!(tasks.len(), 3);
+        assert_eq!(tasks[0].title, "Write report");
+        assert_eq!(tasks[0].tags, vec!["work"]);
+        assert_eq!(tasks[0].description.as_deref(), Some("Q1, Q2"));
+        assert_eq!(tasks[1].parent, Some(0));
+        assert_eq!(tasks[1].priority, "low");
+        assert_eq!(tasks[2].recurrence.as_deref(), Some("FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR"));
+    }
+
+    #[test]
+    fn reads_todotxt_and_round_trips_csv() {
+        let tasks = parse_todotxt("x 2024-05-02 2024-05-01 Call mom +Family @phone pri:A\n(B) 2024-05-01 Pay rent due:2024-06-01 rec:1m http://bank\n");
+        assert_eq!(tasks[0].status, "done");
+        assert_eq!(tasks[0].priority, "high");
+        assert_eq!(tasks[0].tags, vec!["Family", "phone"]);
+        assert_eq!(tasks[1].title, "Pay rent http://bank");
+        assert_eq!(tasks[1].due_date.as_deref(), Some("2024-06-01"));
+        assert_eq!(tasks[1].recurrence.as_deref(), Some("FREQ=MONTHLY"));
+
+        let task = |id, parent_id| Task {
+            id,
+            title: "Plan, \"draft\"".into(),
+            description: Some("line one\nline two".into()),
+            status: "todo".into(),
+            priority: "high".into(),
+            due_date: Some("2024-06-01".into()),
+            reminder_time: None,
+            parent_id,
+            recurrence: None,
+            series_id: None,
+            occurrence: 1,
+            estimate: None,
+            position: 1.0,
+            version: 1,
+            open_blockers: Vec::new(),
+            created_at: "2024-05-01 10:00:00".into(),
+            updated_at: "2024-05-01 10:00:00".into(),
+            time_zone: None,
+        };
+        let checklist = vec![ChecklistItem { id: 1, task_id: 7, text: "outline".into(), done: true, position: 0, created_at: String::new() }];
+        let csv = export_csv(&[
+            ExportTask { task: task(7, None), tags: vec!["work".into(), "q2".into()], checklist },
+            ExportTask { task: task(8, Some(7)), tags: vec![], checklist: vec![] },
+        ]);
+        let (tasks, _) = parse_play_csv(&csv).unwrap();
+        assert_eq!(tasks[0].title, "Plan, \"draft\"");
+        assert_eq!(tasks[0].description.as_deref(), Some("line one\nline two"));
+        assert_eq!(tasks[0].tags, vec!["work", "q2"]);
+        assert_eq!(tasks[0].checklist, vec![ImportedChecklistItem { text: "outline".into(), done: true }]);
+        assert_eq!(tasks[1].parent, Some(0));
+    }
+
+    #[test]
+    fn previewed_imports_run_once() {
+        let db = Database::in_memory().unwrap();
+        let path = std::env::temp_dir().join(format!("play-import-{}.txt", std::process::id()));
+        std::fs::write(&path, "(A) Call the bank +errands\nx 2024-05-01 Pay rent\n").unwrap();
+        let service = TaskTransferService::new(&db);
+        let before = db.get_all_tasks().unwrap().len();
+
+        let preview = service
+            .import_tasks(TaskImportRequest { path: path.display().to_string(), format: None, dry_run: true })
+            .unwrap();
+        assert_eq!(preview.job.status, "previewed");
+        assert_eq!(db.get_all_tasks().unwrap().len(), before);
+
+        let run = service.run_import_job(preview.job.id).unwrap();
+        assert_eq!(run.job.status, "completed");
+        assert_eq!(run.created_task_ids.len(), 2);
+
+        assert!(service.run_import_job(preview.job.id).is_err());
+        let job = IngestionService::new(&db).get_ingestion_job(preview.job.id).unwrap();
+        assert_eq!(job.status, "completed");
+        assert_eq!(db.get_all_tasks().unwrap().len(), before + 2);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,348 @@
+// Template Service - reusable document skeletons with placeholders
+use crate::models::document::{CreateDocument, Document};
+use crate::models::template::{CreateFromTemplate, CreateTemplate, DocumentTemplate, UpdateTemplate};
+use crate::services::database::Database;
+use crate::services::links::{CreateLink, LinksService};
+use chrono::{DateTime, Datelike, Local};
+use regex::Regex;
+use rusqlite::{params, Result};
+use std::collections::HashMap;
+
+/// Built-in templates: (name, description, title pattern, tags, content)
+const BUILTIN_TEMPLATES: [(&str, &str, &str, &str, &str); 3] = [
+    (
+        "Meeting notes",
+        "Agenda, notes, decisions and action items for a meeting",
+        "Meeting notes {{date}}",
+        "meeting",
+        "# {{title}}\n\n**Date:** {{weekday}}, {{date}} {{time}}\n\n## Agenda\n\n{{linked_events}}\n\n## Notes\n\n\n## Decisions\n\n\n## Action items\n\n{{linked_tasks}}\n",
+    ),
+    (
+        "Project brief",
+        "Goals, scope, timeline and risks for a new project",
+        "Project brief: {{project}}",
+        "project",
+        "# {{title}}\n\n**Owner:** {{owner}}\n**Created:** {{date}}\n\n## Summary\n\n{{ai: Write a two sentence summary of a project called {{project}}.}}\n\n## Goals\n\n\n## Scope\n\n\n## Timeline\n\n\n## Risks\n\n\n## Related tasks\n\n{{linked_tasks}}\n\n## References\n\n{{linked_documents}}\n",
+    ),
+    (
+        "Weekly report",
+        "What happened this week and what comes next",
+        "Weekly report {{year}}-W{{week}}",
+        "report/weekly",
+        "# {{title}}\n\n**Week:** {{week}} ({{date}})\n\n## Highlights\n\n{{ai: Summarize the progress on the tasks below in three bullet points.}}\n\n## Tasks\n\n{{linked_tasks}}\n\n## Meetings\n\n{{linked_events}}\n\n## Next week\n\n",
+    ),
+];
+
+/// An `{{ai: ...}}` placeholder awaiting a model response
+#[derive(Debug, Clone)]
+pub struct AiSection {
+    pub placeholder: String,
+    pub prompt: String,
+}
+
+/// A template with every variable resolved except its AI sections
+#[derive(Debug, Clone)]
+pub struct RenderedTemplate {
+    pub title: String,
+    pub content: String,
+    pub tags: Option<String>,
+    pub ai_sections: Vec<AiSection>,
+}
+
+pub struct TemplateService<'a> {
+    db: &'a Database,
+}
+
+impl<'a> TemplateService<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    /// Insert any built-in template that is missing. Edited built-ins are left alone.
+    pub fn initialize_builtin_templates(&self) -> Result<()> {
+        let conn = self.db.get_connection();
+        for (name, description, title_pattern, tags, content) in BUILTIN_TEMPLATES {
+            conn.execute(
+                "INSERT OR IGNORE INTO document_templates (name, description, title_pattern, content, tags, builtin)
+                 VALUES (?1, ?2, ?3, ?4, ?5, 1)",
+                params![name, description, title_pattern, content, tags],
+            )?;
+        }
+        Ok(())
+    }
+
+    fn map_template(row: &rusqlite::Row) -> Result<DocumentTemplate> {
+        Ok(DocumentTemplate {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            description: row.get(2)?,
+            title_pattern: row.get(3)?,
+            content: row.get(4)?,
+            tags: row.get(5)?,
+            builtin: row.get::<_, i64>(6)? != 0,
+            created_at: row.get(7)?,
+            updated_at: row.get(8)?,
+        })
+    }
+
+    pub fn create_template(&self, template: CreateTemplate) -> Result<DocumentTemplate> {
+        let conn = self.db.get_connection();
+        conn.execute(
+            "INSERT INTO document_templates (name, description, title_pattern, content, tags)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![template.name, template.description, template.title_pattern, template.content, template.tags],
+        )?;
+
+        let id = conn.last_insert_rowid();
+        self.get_template(id)
+    }
+
+    pub fn get_template(&self, id: i64) -> Result<DocumentTemplate> {
+        self.db.get_connection().query_row(
+            "SELECT id, name, description, title_pattern, content, tags, builtin, created_at, updated_at
+             FROM document_templates WHERE id = ?1",
+            params![id],
+            Self::map_template,
+        )
+    }
+
+    pub fn get_all_templates(&self) -> Result<Vec<DocumentTemplate>> {
+        let conn = self.db.get_connection();
+        let mut stmt = conn.prepare(
+            "SELECT id, name, description, title_pattern, content, tags, builtin, created_at, updated_at
+             FROM document_templates ORDER BY builtin DESC, name",
+        )?;
+        let templates = stmt.query_map([], Self::map_template)?;
+        templates.collect()
+    }
+
+    pub fn update_template(&self, id: i64, update: UpdateTemplate) -> Result<DocumentTemplate> {
+        let mut updates = Vec::new();
+        let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+
+        if let Some(name) = &update.name {
+            updates.push("name = ?");
+            params_vec.push(Box::new(name.clone()));
+        }
+        if let Some(description) = &update.description {
+            updates.push("description = ?");
+            params_vec.push(Box::new(description.clone()));
+        }
+        if let Some(title_pattern) = &update.title_pattern {
+            updates.push("title_pattern = ?");
+            params_vec.push(Box::new(title_pattern.clone()));
+        }
+        if let Some(content) = &update.content {
+            updates.push("content = ?");
+            params_vec.push(Box::new(content.clone()));
+        }
+        if let Some(tags) = &update.tags {
+            updates.push("tags = ?");
+            params_vec.push(Box::new(tags.clone()));
+        }
+
+        updates.push("updated_at = CURRENT_TIMESTAMP");
+
+        let query = format!("UPDATE document_templates SET {} WHERE id = ?", updates.join(", "));
+        params_vec.push(Box::new(id));
+
+        let params_refs: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|p| p.as_ref()).collect();
+        self.db.get_connection().execute(&query, params_refs.as_slice())?;
+
+        self.get_template(id)
+    }
+
+    pub fn delete_template(&self, id: i64) -> Result<()> {
+        let template = self.get_template(id)?;
+        if template.builtin {
+            return Err(rusqlite::Error::InvalidParameterName(format!(
+                "Built-in template '{}' cannot be deleted",
+                template.name
+            )));
+        }
+        self.db.get_connection().execute("DELETE FROM document_templates WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    /// Resolve every placeholder of a template except `{{ai: ...}}` sections,
+    /// which are returned for the caller to fill (they need Ollama, which must
+    /// not be awaited while the database is locked).
+    pub fn render(&self, request: &CreateFromTemplate, now: DateTime<Local>) -> Result<RenderedTemplate> {
+        let template = self.get_template(request.template_id)?;
+
+        let mut variables: HashMap<String, String> = HashMap::new();
+        variables.insert("date".to_string(), now.format("%Y-%m-%d").to_string());
+        variables.insert("time".to_string(), now.format("%H:%M").to_string());
+        variables.insert("datetime".to_string(), now.format("%Y-%m-%d %H:%M").to_string());
+        variables.insert("weekday".to_string(), now.format("%A").to_string());
+        variables.insert("week".to_string(), format!("{:02}", now.iso_week().week()));
+        variables.insert("year".to_string(), now.iso_week().year().to_string());
+        variables.insert("linked_tasks".to_string(), self.render_tasks(&request.task_ids)?);
+        variables.insert("linked_events".to_string(), self.render_events(&request.event_ids)?);
+        variables.insert("linked_documents".to_string(), self.render_documents(&request.document_ids)?);
+        // Caller supplied values win over the built-in ones
+        for (key, value) in &request.variables {
+            variables.insert(key.trim().to_string(), value.clone());
+        }
+
+        let title = match request.title.as_ref().filter(|t| !t.trim().is_empty()) {
+            Some(title) => title.trim().to_string(),
+            None => {
+                let pattern = template.title_pattern.as_deref().unwrap_or(&template.name);
+                substitute(pattern, &variables).0
+            }
+        };
+        variables.insert("title".to_string(), title.clone());
+
+        let (content, ai_sections) = substitute(&template.content, &variables);
+
+        Ok(RenderedTemplate {
+            title,
+            content,
+            tags: template.tags,
+            ai_sections,
+        })
+    }
+
+    /// Create the document for a rendered template and link it to every item
+    /// the request referenced. `ai_outputs` maps AI placeholders to their text.
+    pub fn create_document(
+        &self,
+        rendered: RenderedTemplate,
+        ai_outputs: &HashMap<String, String>,
+        request: &CreateFromTemplate,
+    ) -> Result<Document> {
+        let mut content = rendered.content;
+        for section in &rendered.ai_sections {
+            let replacement = ai_outputs.get(&section.placeholder).cloned().unwrap_or_default();
+            content = content.replacen(&section.placeholder, replacement.trim(), 1);
+        }
+
+        let document = self.db.create_document(CreateDocument {
+            title: rendered.title,
+            content,
+            tags: rendered.tags,
+            folder_id: request.folder_id,
+        })?;
+
+        let links = LinksService::new(self.db.get_connection());
+        let targets = [("task", &request.task_ids), ("event", &request.event_ids), ("document", &request.document_ids)];
+        for (target_type, ids) in targets {
+            for id in ids.iter() {
+                links.create_link(CreateLink {
+                    source_type: "document".to_string(),
+                    source_id: document.id,
+                    target_type: target_type.to_string(),
+                    target_id: *id,
+                })?;
+            }
+        }
+
+        Ok(document)
+    }
+
+    fn render_tasks(&self, ids: &[i64]) -> Result<String> {
+        let mut lines = Vec::new();
+        for id in ids {
+            let task = self.db.get_task(*id)?;
+            let check = if task.status == "done" { "x" } else { " " };
+            let due = task.due_date.map(|d| format!(" (due {})", d)).unwrap_or_default();
+            lines.push(format!("- [{}] {}{}", check, task.title, due));
+        }
+        Ok(lines.join("\n"))
+    }
+
+    fn render_events(&self, ids: &[i64]) -> Result<String> {
+        let mut lines = Vec::new();
+        for id in ids {
+            let event = self.db.get_event(*id)?;
+            lines.push(format!("- {} ({} - {})", event.title, event.start_time, event.end_time));
+        }
+        Ok(lines.join("\n"))
+    }
+
+    fn render_documents(&self, ids: &[i64]) -> Result<String> {
+        let mut lines = Vec::new();
+        for id in ids {
+            let document = self.db.get_document(*id)?;
+            lines.push(format!("- {}", document.title));
+        }
+        Ok(lines.join("\n"))
+    }
+}
+
+/// Prompt sent to Ollama for one AI section, with the rest of the rendered
+/// document as context so the model can refer to linked tasks and events.
+pub fn ai_section_prompt(rendered: &RenderedTemplate, section: &AiSection) -> String {
+    let mut context = rendered.content.clone();
+    for other in &rendered.ai_sections {
+        context = context.replacen(&other.placeholder, "", 1);
+    }
+    format!(
+        "You are filling in one section of the document \"{}\".\n\nDocument so far:\n{}\n\nInstruction: {}\n\nRespond with the section text only, in Markdown, without a heading.",
+        rendered.title,
+        context.trim(),
+        section.prompt
+    )
+}
+
+/// Replace `{{name}}` placeholders with their values. Unknown placeholders are
+/// kept verbatim. `{{ai: prompt}}` sections get their own variables resolved
+/// and are returned, left in place, for the caller to fill.
+pub fn substitute(text: &str, variables: &HashMap<String, String>) -> (String, Vec<AiSection>) {
+    let ai_re = Regex::new(r"\{\{\s*ai\s*:((?:[^{}]|\{\{[^{}]*\}\})*)\}\}").unwrap();
+    let var_re = Regex::new(r"\{\{\s*([A-Za-z0-9_.\-]+)\s*\}\}").unwrap();
+
+    let replace_vars = |input: &str| -> String {
+        var_re
+            .replace_all(input, |caps: &regex::Captures| {
+                variables.get(&caps[1]).cloned().unwrap_or_else(|| caps[0].to_string())
+            })
+            .into_owned()
+    };
+
+    let mut output = String::new();
+    let mut ai_sections = Vec::new();
+    let mut last = 0;
+    for caps in ai_re.captures_iter(text) {
+        let whole = caps.get(0).unwrap();
+        output.push_str(&replace_vars(&text[last..whole.start()]));
+
+        let prompt = replace_vars(caps[1].trim());
+        let placeholder = format!("{{{{ai#{}}}}}", ai_sections.len());
+        output.push_str(&placeholder);
+        ai_sections.push(AiSection { placeholder, prompt });
+
+        last = whole.end();
+    }
+    output.push_str(&replace_vars(&text[last..]));
+
+    (output, ai_sections)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn substitutes_known_and_keeps_unknown_placeholders() {
+        let (out, ai) = substitute("# {{ title }} on {{date}} by {{owner}}", &vars(&[("title", "Sync"), ("date", "2024-05-01")]));
+        assert_eq!(out, "# Sync on 2024-05-01 by {{owner}}");
+        assert!(ai.is_empty());
+    }
+
+    #[test]
+    fn extracts_ai_sections_with_resolved_prompts() {
+        let (out, ai) = substitute(
+            "Intro\n{{ai: Summarize {{project}} briefly}}\nEnd",
+            &vars(&[("project", "Apollo")]),
+        );
+        assert_eq!(ai.len(), 1);
+        assert_eq!(ai[0].prompt, "Summarize Apollo briefly");
+        assert_eq!(out, format!("Intro\n{}\nEnd", ai[0].placeholder));
+    }
+}
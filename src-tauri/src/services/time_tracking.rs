@@ -0,0 +1,459 @@
+// Time Tracking Service - task timers, manual time entries and reports
+//
+// Entries are stored in UTC like the rest of the timestamps. A running timer
+// is simply an entry without `ended_at`, so it survives restarts; a unique
+// partial index guarantees there is never more than one.
+use crate::models::event::{CreateEvent, UpdateEvent};
+use crate::models::time_entry::{CreateTimeEntry, TimeEntry, TimeReport, TimeReportRow, UpdateTimeEntry};
+use crate::services::database::Database;
+use crate::services::rrule::{format_stored_datetime, parse_stored_datetime, DateStyle};
+use crate::services::tags::TagService;
+use crate::services::time_zones::{user_zone, utc_to_zoned, zoned_to_utc};
+use chrono::{Datelike, Duration, NaiveDateTime, Utc};
+use chrono_tz::Tz;
+use rusqlite::{params, OptionalExtension, Result};
+use std::collections::HashMap;
+
+const SQL_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+fn invalid(message: String) -> rusqlite::Error {
+    rusqlite::Error::InvalidParameterName(message)
+}
+
+fn now_utc() -> NaiveDateTime {
+    Utc::now().naive_utc()
+}
+
+/// Read a user-supplied time. Times without an offset are wall-clock times
+/// in the user's zone; SQL timestamps and RFC 3339 values are already UTC.
+fn to_utc(value: &str, zone: Tz) -> Result<NaiveDateTime> {
+    let (at, style) = parse_stored_datetime(value).ok_or_else(|| invalid(format!("Invalid time: {}", value)))?;
+    match style {
+        DateStyle::Sql | DateStyle::Utc => Ok(at),
+        DateStyle::Date | DateStyle::Local { .. } => {
+            zoned_to_utc(zone, at).ok_or_else(|| invalid(format!("Time {} does not exist in {}", value, zone.name())))
+        }
+    }
+}
+
+/// Read a stored timestamp; a corrupt one is an error rather than a guess
+fn parse_sql(value: &str) -> Result<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(value, SQL_FORMAT)
+        .map_err(|_| invalid(format!("Time entry has an invalid timestamp: '{}'", value)))
+}
+
+pub struct TimeTrackingService<'a> {
+    db: &'a Database,
+}
+
+impl<'a> TimeTrackingService<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    fn zone(&self) -> Tz {
+        user_zone(self.db.get_connection())
+    }
+
+    const COLUMNS: &'static str = "id, task_id, started_at, ended_at, note, event_id, created_at";
+
+    fn map_entry(row: &rusqlite::Row) -> Result<TimeEntry> {
+        let started_at: String = row.get(2)?;
+        let ended_at: Option<String> = row.get(3)?;
+        let end = match ended_at.as_deref() {
+            Some(ended_at) => parse_sql(ended_at)?,
+            None => now_utc(),
+        };
+        Ok(TimeEntry {
+            id: row.get(0)?,
+            task_id: row.get(1)?,
+            duration_seconds: (end - parse_sql(&started_at)?).num_seconds().max(0),
+            started_at,
+            ended_at,
+            note: row.get(4)?,
+            event_id: row.get(5)?,
+            created_at: row.get(6)?,
+        })
+    }
+
+    fn query_entries(&self, condition: &str, params: &[&dyn rusqlite::ToSql]) -> Result<Vec<TimeEntry>> {
+        let conn = self.db.get_connection();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM time_entries WHERE {} ORDER BY started_at DESC, id DESC",
+            Self::COLUMNS,
+            condition
+        ))?;
+        let entries = stmt.query_map(params, Self::map_entry)?;
+        entries.collect()
+    }
+
+    pub fn get_time_entry(&self, id: i64) -> Result<TimeEntry> {
+        self.db.get_connection().query_row(
+            &format!("SELECT {} FROM time_entries WHERE id = ?1", Self::COLUMNS),
+            params![id],
+            Self::map_entry,
+        )
+    }
+
+    pub fn get_task_time_entries(&self, task_id: i64) -> Result<Vec<TimeEntry>> {
+        self.query_entries("task_id = ?1", &[&task_id])
+    }
+
+    // Timers
+
+    pub fn get_running_timer(&self) -> Result<Option<TimeEntry>> {
+        self.db
+            .get_connection()
+            .query_row(
+                &format!("SELECT {} FROM time_entries WHERE ended_at IS NULL", Self::COLUMNS),
+                [],
+                Self::map_entry,
+            )
+            .optional()
+    }
+
+    /// Start a timer on a task. A timer already running on another task is
+    /// stopped first; starting the task that is already running is a no-op.
+    pub fn start_timer(&self, task_id: i64, note: Option<String>) -> Result<TimeEntry> {
+        self.db.get_task(task_id)?;
+        if let Some(running) = self.get_running_timer()? {
+            if running.task_id == task_id {
+                return Ok(running);
+            }
+            self.stop_timer()?;
+        }
+
+        let conn = self.db.get_connection();
+        conn.execute(
+            "INSERT INTO time_entries (task_id, started_at, note) VALUES (?1, ?2, ?3)",
+            params![task_id, now_utc().format(SQL_FORMAT).to_string(), note],
+        )?;
+        self.get_time_entry(conn.last_insert_rowid())
+    }
+
+    /// Stop the running timer, if any, and return the finished entry
+    pub fn stop_timer(&self) -> Result<Option<TimeEntry>> {
+        let running = match self.get_running_timer()? {
+            Some(running) => running,
+            None => return Ok(None),
+        };
+        self.db.get_connection().execute(
+            "UPDATE time_entries SET ended_at = ?1 WHERE id = ?2",
+            params![now_utc().format(SQL_FORMAT).to_string(), running.id],
+        )?;
+        let entry = self.get_time_entry(running.id)?;
+        self.sync_event(&entry)?;
+        Ok(Some(entry))
+    }
+
+    // Manual entries
+
+    pub fn add_time_entry(&self, entry: CreateTimeEntry) -> Result<TimeEntry> {
+        self.db.get_task(entry.task_id)?;
+        let (started_at, ended_at) = Self::validate_range(&entry.started_at, &entry.ended_at, self.zone())?;
+
+        let conn = self.db.get_connection();
+        conn.execute(
+            "INSERT INTO time_entries (task_id, started_at, ended_at, note) VALUES (?1, ?2, ?3, ?4)",
+            params![entry.task_id, started_at, ended_at, entry.note],
+        )?;
+        self.get_time_entry(conn.last_insert_rowid())
+    }
+
+    pub fn update_time_entry(&self, id: i64, update: UpdateTimeEntry) -> Result<TimeEntry> {
+        let current = self.get_time_entry(id)?;
+        let conn = self.db.get_connection();
+
+        if update.started_at.is_some() || update.ended_at.is_some() {
+            let started_at = match &update.started_at {
+                Some(started_at) => to_utc(started_at, self.zone())?.format(SQL_FORMAT).to_string(),
+                None => current.started_at.clone(),
+            };
+            match update.ended_at.as_deref().or(current.ended_at.as_deref()) {
+                Some(ended_at) => {
+                    let (started_at, ended_at) = Self::validate_range(&started_at, ended_at, self.zone())?;
+                    conn.execute(
+                        "UPDATE time_entries SET started_at = ?1, ended_at = ?2 WHERE id = ?3",
+                        params![started_at, ended_at, id],
+                    )?;
+                }
+                // A running timer can be moved back, but not into the future
+                None => {
+                    if parse_sql(&started_at)? > now_utc() {
+                        return Err(invalid("A running timer cannot start in the future".to_string()));
+                    }
+                    conn.execute("UPDATE time_entries SET started_at = ?1 WHERE id = ?2", params![started_at, id])?;
+                }
+            }
+        }
+        if let Some(note) = &update.note {
+            conn.execute("UPDATE time_entries SET note = ?1 WHERE id = ?2", params![note, id])?;
+        }
+
+        let entry = self.get_time_entry(id)?;
+        self.sync_event(&entry)?;
+        Ok(entry)
+    }
+
+    pub fn delete_time_entry(&self, id: i64) -> Result<()> {
+        self.db.get_connection().execute("DELETE FROM time_entries WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    fn validate_range(started_at: &str, ended_at: &str, zone: Tz) -> Result<(String, String)> {
+        let start = to_utc(started_at, zone)?;
+        let end = to_utc(ended_at, zone)?;
+        if end <= start {
+            return Err(invalid(format!("Time entry ends ({}) before it starts ({})", ended_at, started_at)));
+        }
+        Ok((start.format(SQL_FORMAT).to_string(), end.format(SQL_FORMAT).to_string()))
+    }
+
+    // Calendar
+
+    /// Show a time entry on the calendar by creating an event for it.
+    /// A running entry gets an event up to now that grows when it stops.
+    pub fn create_event_for_entry(&self, id: i64) -> Result<TimeEntry> {
+        let entry = self.get_time_entry(id)?;
+        if entry.event_id.is_some() {
+            return Ok(entry);
+        }
+        let task = self.db.get_task(entry.task_id)?;
+        let (start, end) = Self::event_range(&entry)?;
+        let event = self.db.create_event(CreateEvent {
+            title: task.title,
+            description: entry.note.clone(),
+            start_time: start,
+            end_time: end,
+            reminder_time: None,
+            recurrence: None,
+            location: None,
+            all_day: false,
+            exdates: Vec::new(),
+            time_zone: None,
+        })?;
+        self.link_entry_to_event(id, Some(event.id))
+    }
+
+    /// Attach a time entry to an existing event, or detach it with None
+    pub fn link_entry_to_event(&self, id: i64, event_id: Option<i64>) -> Result<TimeEntry> {
+        self.get_time_entry(id)?;
+        if let Some(event_id) = event_id {
+            self.db.get_event(event_id)?;
+        }
+        self.db.get_connection().execute(
+            "UPDATE time_entries SET event_id = ?1 WHERE id = ?2",
+            params![event_id, id],
+        )?;
+        self.get_time_entry(id)
+    }
+
+    fn event_range(entry: &TimeEntry) -> Result<(String, String)> {
+        let start = parse_sql(&entry.started_at)?;
+        let end = start + Duration::seconds(entry.duration_seconds);
+        Ok((format_stored_datetime(start, DateStyle::Utc), format_stored_datetime(end, DateStyle::Utc)))
+    }
+
+    /// Keep a linked event's times in step with its entry
+    fn sync_event(&self, entry: &TimeEntry) -> Result<()> {
+        if let Some(event_id) = entry.event_id {
+            let (start, end) = Self::event_range(entry)?;
+            self.db.update_event(
+                event_id,
+                UpdateEvent {
+                    title: None,
+                    description: None,
+                    start_time: Some(start),
+                    end_time: Some(end),
+                    reminder_time: None,
+                    recurrence: None,
+                    location: None,
+                    all_day: None,
+                    exdates: None,
+                    time_zone: None,
+                },
+            )?;
+        }
+        Ok(())
+    }
+
+    // Reports
+
+    /// Seconds tracked on a task, including a running timer
+    pub fn tracked_seconds(&self, task_id: i64) -> Result<i64> {
+        Ok(self.get_task_time_entries(task_id)?.iter().map(|e| e.duration_seconds).sum())
+    }
+
+    /// Tracked time grouped by "task", "tag" or "week", for entries that
+    /// start within the optional range (local dates or times)
+    pub fn get_report(&self, group_by: &str, start: Option<&str>, end: Option<&str>) -> Result<TimeReport> {
+        if !["task", "tag", "week"].contains(&group_by) {
+            return Err(invalid(format!("Unknown report grouping '{}'", group_by)));
+        }
+        let zone = self.zone();
+        let start = start.map(|s| to_utc(s, zone)).transpose()?.map(|t| t.format(SQL_FORMAT).to_string());
+        let end = end.map(|e| to_utc(e, zone)).transpose()?.map(|t| t.format(SQL_FORMAT).to_string());
+        let entries = self.query_entries(
+            "(?1 IS NULL OR started_at >= ?1) AND (?2 IS NULL OR started_at < ?2)",
+            &[&start, &end],
+        )?;
+
+        let tags = TagService::new(self.db.get_connection());
+        let mut rows: HashMap<String, TimeReportRow> = HashMap::new();
+        for entry in &entries {
+            let groups: Vec<(String, String, Option<i64>)> = match group_by {
+                "task" => {
+                    let task = self.db.get_task(entry.task_id)?;
+                    vec![(task.id.to_string(), task.title, task.estimate)]
+                }
+                "tag" => {
+                    let names: Vec<(String, String, Option<i64>)> = tags
+                        .get_tags_for_item("task", entry.task_id)?
+                        .into_iter()
+                        .map(|tag| (tag.name.clone(), tag.name, None))
+                        .collect();
+                    if names.is_empty() {
+                        vec![(String::new(), "Untagged".to_string(), None)]
+                    } else {
+                        names
+                    }
+                }
+                _ => {
+                    let local = utc_to_zoned(zone, parse_sql(&entry.started_at)?).date();
+                    let week = local.iso_week();
+                    let monday = local - Duration::days(local.weekday().num_days_from_monday() as i64);
+                    vec![(
+                        format!("{}-W{:02}", week.year(), week.week()),
+                        format!("Week of {}", monday.format("%Y-%m-%d")),
+                        None,
+                    )]
+                }
+            };
+
+            for (key, label, estimate_minutes) in groups {
+                let row = rows.entry(key.clone()).or_insert_with(|| TimeReportRow {
+                    key,
+                    label,
+                    seconds: 0,
+                    entries: 0,
+                    estimate_minutes,
+                });
+                row.seconds += entry.duration_seconds;
+                row.entries += 1;
+            }
+        }
+
+        let mut rows: Vec<TimeReportRow> = rows.into_values().collect();
+        if group_by == "week" {
+            rows.sort_by(|a, b| a.key.cmp(&b.key));
+        } else {
+            rows.sort_by(|a, b| b.seconds.cmp(&a.seconds).then_with(|| a.label.cmp(&b.label)));
+        }
+
+        Ok(TimeReport {
+            group_by: group_by.to_string(),
+            total_seconds: entries.iter().map(|e| e.duration_seconds).sum(),
+            rows,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::task::CreateTask;
+
+    fn task(db: &Database, title: &str, estimate: Option<i64>) -> i64 {
+        db.create_task(CreateTask {
+            title: title.into(),
+            description: None,
+            status: None,
+            priority: None,
+            due_date: None,
+            reminder_time: None,
+            parent_id: None,
+            recurrence: None,
+            estimate,
+            time_zone: None,
+        })
+        .unwrap()
+        .id
+    }
+
+    fn entry(tracking: &TimeTrackingService, task_id: i64, started_at: &str, ended_at: &str) -> TimeEntry {
+        tracking
+            .add_time_entry(CreateTimeEntry {
+                task_id,
+                started_at: started_at.into(),
+                ended_at: ended_at.into(),
+                note: None,
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn only_one_timer_runs_at_a_time() {
+        let db = Database::in_memory().unwrap();
+        let tracking = TimeTrackingService::new(&db);
+        let (writing, review) = (task(&db, "Writing", None), task(&db, "Review", None));
+
+        let first = tracking.start_timer(writing, None).unwrap();
+        assert!(first.ended_at.is_none());
+        assert_eq!(tracking.start_timer(writing, None).unwrap().id, first.id);
+
+        let second = tracking.start_timer(review, Some("second pass".into())).unwrap();
+        assert!(tracking.get_time_entry(first.id).unwrap().ended_at.is_some());
+        assert_eq!(tracking.get_running_timer().unwrap().map(|e| e.id), Some(second.id));
+
+        let stopped = tracking.stop_timer().unwrap().unwrap();
+        assert_eq!(stopped.id, second.id);
+        assert!(stopped.ended_at.is_some());
+        assert!(tracking.get_running_timer().unwrap().is_none());
+        assert!(tracking.stop_timer().unwrap().is_none());
+    }
+
+    #[test]
+    fn totals_add_up_per_task_and_tag() {
+        let db = Database::in_memory().unwrap();
+        let tracking = TimeTrackingService::new(&db);
+        let (writing, review) = (task(&db, "Writing", Some(120)), task(&db, "Review", None));
+        TagService::new(db.get_connection()).tag_item("task", writing, "work").unwrap();
+        entry(&tracking, writing, "2024-05-06 09:00:00", "2024-05-06 10:00:00");
+        entry(&tracking, writing, "2024-05-07 09:00:00", "2024-05-07 09:30:00");
+        entry(&tracking, review, "2024-05-07 11:00:00", "2024-05-07 11:15:00");
+        entry(&tracking, review, "2024-04-30 11:00:00", "2024-04-30 12:00:00");
+        assert!(tracking
+            .add_time_entry(CreateTimeEntry {
+                task_id: review,
+                started_at: "2024-05-07 12:00:00".into(),
+                ended_at: "2024-05-07 11:00:00".into(),
+                note: None,
+            })
+            .is_err());
+
+        assert_eq!(tracking.tracked_seconds(writing).unwrap(), 5400);
+
+        let report = tracking.get_report("task", Some("2024-05-06 00:00:00"), Some("2024-05-13 00:00:00")).unwrap();
+        assert_eq!(report.total_seconds, 6300);
+        let rows: Vec<(&str, i64, i64)> = report.rows.iter().map(|r| (r.label.as_str(), r.seconds, r.entries)).collect();
+        assert_eq!(rows, vec![("Writing", 5400, 2), ("Review", 900, 1)]);
+        assert_eq!(report.rows[0].estimate_minutes, Some(120));
+
+        let report = tracking.get_report("tag", Some("2024-05-06 00:00:00"), None).unwrap();
+        let rows: Vec<(&str, i64)> = report.rows.iter().map(|r| (r.label.as_str(), r.seconds)).collect();
+        assert_eq!(rows, vec![("work", 5400), ("Untagged", 900)]);
+    }
+
+    #[test]
+    fn corrupt_timestamps_are_errors() {
+        let db = Database::in_memory().unwrap();
+        let tracking = TimeTrackingService::new(&db);
+        let writing = task(&db, "Writing", None);
+        db.get_connection()
+            .execute("INSERT INTO time_entries (task_id, started_at) VALUES (?1, 'yesterday-ish')", params![writing])
+            .unwrap();
+
+        assert!(tracking.get_running_timer().is_err());
+        assert!(tracking.tracked_seconds(writing).is_err());
+    }
+}
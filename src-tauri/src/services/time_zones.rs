@@ -0,0 +1,175 @@
+// Time Zones - the user's zone and conversions between wall-clock time and UTC
+//
+// Timed events and task due dates are stored as UTC instants
+// ("YYYY-MM-DDTHH:MM:SSZ") next to the IANA zone they belong to, and
+// all-day values stay plain dates. Times given without an offset are wall
+// time in that zone, or in the user's zone from settings.
+use crate::models::event::CreateEvent;
+use crate::services::rrule::{format_stored_datetime, parse_stored_datetime, DateStyle};
+use crate::services::settings::SettingsService;
+use chrono::{Duration, LocalResult, NaiveDateTime, Offset, TimeZone};
+use chrono_tz::Tz;
+use rusqlite::Connection;
+
+/// Settings key holding the user's IANA zone; unset means the system zone
+pub const TIME_ZONE_SETTING: &str = "time_zone";
+
+pub fn parse_zone(name: &str) -> Result<Tz, String> {
+    name.trim().parse::<Tz>().map_err(|_| format!("Unknown time zone '{}'", name))
+}
+
+/// The zone named on an event or task, or `fallback` when it names none
+pub fn zone_or(name: Option<&str>, fallback: Tz) -> Result<Tz, String> {
+    match name.map(str::trim).filter(|n| !n.is_empty()) {
+        Some(name) => parse_zone(name),
+        None => Ok(fallback),
+    }
+}
+
+/// The zone this machine is set to, or UTC when it cannot be told
+pub fn system_zone() -> Tz {
+    iana_time_zone::get_timezone()
+        .ok()
+        .and_then(|name| name.parse().ok())
+        .unwrap_or(Tz::UTC)
+}
+
+/// The zone wall times are entered and shown in
+pub fn user_zone(conn: &Connection) -> Tz {
+    SettingsService::new(conn)
+        .get_setting(TIME_ZONE_SETTING)
+        .ok()
+        .flatten()
+        .and_then(|name| parse_zone(&name).ok())
+        .unwrap_or_else(system_zone)
+}
+
+/// Wall time in `tz` to UTC. Ambiguous times take the first (daylight)
+/// instant; times in a spring-forward gap use the offset from before the gap.
+pub fn zoned_to_utc(tz: Tz, local: NaiveDateTime) -> Option<NaiveDateTime> {
+    match tz.from_local_datetime(&local) {
+        LocalResult::Single(at) | LocalResult::Ambiguous(at, _) => Some(at.naive_utc()),
+        LocalResult::None => {
+            let before = tz.from_local_datetime(&(local - Duration::hours(3))).earliest()?;
+            Some(local - Duration::seconds(before.offset().fix().local_minus_utc() as i64))
+        }
+    }
+}
+
+pub fn utc_to_zoned(tz: Tz, utc: NaiveDateTime) -> NaiveDateTime {
+    tz.from_utc_datetime(&utc).naive_local()
+}
+
+/// Bring a date or time into stored form: dates stay dates, instants become
+/// UTC and wall times are read in `tz`. SQLite timestamps are already UTC.
+pub fn normalize_time(value: &str, tz: Tz) -> Result<String, String> {
+    let (at, style) = parse_stored_datetime(value).ok_or_else(|| format!("Invalid date or time: {}", value))?;
+    let utc = match style {
+        DateStyle::Date => return Ok(format_stored_datetime(at, DateStyle::Date)),
+        DateStyle::Utc | DateStyle::Sql => at,
+        DateStyle::Local { .. } => zoned_to_utc(tz, at).ok_or_else(|| format!("{} does not exist in {}", value, tz.name()))?,
+    };
+    Ok(format_stored_datetime(utc, DateStyle::Utc))
+}
+
+/// A stored value as a UTC instant; dates and wall times are read in `tz`
+pub fn instant(value: &str, tz: Tz) -> Option<NaiveDateTime> {
+    match parse_stored_datetime(value)? {
+        (at, DateStyle::Utc | DateStyle::Sql) => Some(at),
+        (at, _) => zoned_to_utc(tz, at),
+    }
+}
+
+/// `normalize_time` for optional fields; an empty value clears the field
+pub fn normalize_optional(value: Option<&str>, tz: Tz) -> Result<Option<String>, String> {
+    value
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(|v| normalize_time(v, tz))
+        .transpose()
+}
+
+/// Bring an event's times into stored form. Timed events are kept in their
+/// own zone, or `fallback` when they have none; all-day events are dates only.
+pub fn normalize_event(event: &mut CreateEvent, fallback: Tz) -> Result<(), String> {
+    let zone = zone_or(event.time_zone.as_deref(), fallback)?;
+    let convert = |value: &str| {
+        if event.all_day {
+            date_in_zone(value, zone).ok_or_else(|| format!("Invalid date: {}", value))
+        } else {
+            normalize_time(value, zone)
+        }
+    };
+    event.start_time = convert(&event.start_time)?;
+    event.end_time = convert(&event.end_time)?;
+    event.exdates = event.exdates.iter().map(|d| convert(d)).collect::<Result<_, _>>()?;
+    event.reminder_time = normalize_optional(event.reminder_time.as_deref(), zone)?;
+    event.time_zone = if event.all_day { None } else { Some(zone.name().to_string()) };
+    Ok(())
+}
+
+/// The calendar date of a stored value as seen in `tz`
+pub fn date_in_zone(value: &str, tz: Tz) -> Option<String> {
+    let (at, style) = parse_stored_datetime(value)?;
+    let local = match style {
+        DateStyle::Utc | DateStyle::Sql => utc_to_zoned(tz, at),
+        DateStyle::Date | DateStyle::Local { .. } => at,
+    };
+    Some(local.format("%Y-%m-%d").to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_wall_times_across_dst() {
+        let new_york = parse_zone("America/New_York").unwrap();
+        assert_eq!(normalize_time("2024-03-08T09:00", new_york).unwrap(), "2024-03-08T14:00:00Z");
+        assert_eq!(normalize_time("2024-03-11T09:00", new_york).unwrap(), "2024-03-11T13:00:00Z");
+        // 02:30 does not exist on the spring-forward night
+        assert_eq!(normalize_time("2024-03-10T02:30", new_york).unwrap(), "2024-03-10T07:30:00Z");
+        // 01:30 happens twice in November; the first (daylight) one wins
+        assert_eq!(normalize_time("2024-11-03T01:30", new_york).unwrap(), "2024-11-03T05:30:00Z");
+        assert_eq!(normalize_time("2024-03-11T09:00:00+05:30", new_york).unwrap(), "2024-03-11T03:30:00Z");
+        assert_eq!(normalize_time("2024-03-11", new_york).unwrap(), "2024-03-11");
+        assert!(normalize_time("next week", new_york).is_err());
+        assert!(parse_zone("Mars/Olympus").is_err());
+    }
+
+    #[test]
+    fn events_keep_their_zone() {
+        let mut event = CreateEvent {
+            title: "Call".into(),
+            description: None,
+            start_time: "2024-07-01T09:00".into(),
+            end_time: "2024-07-01T10:00".into(),
+            reminder_time: Some(String::new()),
+            recurrence: None,
+            location: None,
+            all_day: false,
+            exdates: Vec::new(),
+            time_zone: Some("Europe/Berlin".into()),
+        };
+        normalize_event(&mut event, Tz::UTC).unwrap();
+        assert_eq!((event.start_time.as_str(), event.end_time.as_str()), ("2024-07-01T07:00:00Z", "2024-07-01T08:00:00Z"));
+        assert_eq!(event.reminder_time, None);
+        assert_eq!(event.time_zone.as_deref(), Some("Europe/Berlin"));
+
+        event.all_day = true;
+        event.end_time = "2024-07-02".into();
+        normalize_event(&mut event, Tz::UTC).unwrap();
+        assert_eq!((event.start_time.as_str(), event.end_time.as_str()), ("2024-07-01", "2024-07-02"));
+        assert_eq!(event.time_zone, None);
+
+        event.time_zone = Some("Nowhere/Special".into());
+        assert!(normalize_event(&mut event, Tz::UTC).is_err());
+    }
+
+    #[test]
+    fn dates_follow_the_zone() {
+        let kolkata = parse_zone("Asia/Kolkata").unwrap();
+        assert_eq!(date_in_zone("2024-05-06T20:00:00Z", kolkata).as_deref(), Some("2024-05-07"));
+        assert_eq!(date_in_zone("2024-05-06", kolkata).as_deref(), Some("2024-05-06"));
+    }
+}
@@ -0,0 +1,270 @@
+// Uploads - chunked, resumable uploads into the blob store
+//
+// A session reserves a staging file of the announced size; chunks arrive in
+// any order, each with its SHA-256, and are written at their offset. The
+// chunks received so far are recorded, so an interrupted upload resumes by
+// sending only the missing ones. Finishing checks the whole file against an
+// optional SHA-256 and moves it into the blob store.
+use crate::services::blob_store::{hash_bytes, hash_file, Blob, BlobStore};
+use chrono::{Duration, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use std::fs::{self, OpenOptions};
+use std::io::{Seek, SeekFrom, Write};
+use std::path::PathBuf;
+
+pub const DEFAULT_CHUNK_SIZE: i64 = 4 * 1024 * 1024;
+/// Largest chunk accepted in one IPC call
+pub const MAX_CHUNK_SIZE: i64 = 16 * 1024 * 1024;
+/// Sessions untouched for this long are discarded
+const SESSION_DAYS: i64 = 7;
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct UploadSession {
+    pub id: String,
+    pub filename: String,
+    pub mimetype: String,
+    pub total_size: i64,
+    pub chunk_size: i64,
+    pub chunk_count: i64,
+    /// Indexes of the chunks received so far
+    pub received: Vec<i64>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+fn invalid(message: String) -> rusqlite::Error {
+    rusqlite::Error::InvalidParameterName(message)
+}
+
+fn io_error(e: std::io::Error) -> rusqlite::Error {
+    invalid(e.to_string())
+}
+
+fn chunk_count(total_size: i64, chunk_size: i64) -> i64 {
+    ((total_size + chunk_size - 1) / chunk_size).max(1)
+}
+
+/// The size chunk `index` must have: full chunks, then whatever is left
+fn expected_chunk_size(total_size: i64, chunk_size: i64, index: i64) -> Option<i64> {
+    if index < 0 || index >= chunk_count(total_size, chunk_size) {
+        return None;
+    }
+    Some((total_size - index * chunk_size).min(chunk_size))
+}
+
+pub struct UploadService<'a> {
+    conn: &'a Connection,
+    store: BlobStore<'a>,
+}
+
+impl<'a> UploadService<'a> {
+    /// Uploads into the blob store at `blob_root`
+    pub fn new(conn: &'a Connection, blob_root: PathBuf) -> std::io::Result<Self> {
+        Ok(Self { conn, store: BlobStore::new(conn, blob_root)? })
+    }
+
+    fn staged_path(&self, id: &str) -> PathBuf {
+        self.store.staging_dir().join(format!("upload-{}", id))
+    }
+
+    pub fn get_session(&self, id: &str) -> Result<UploadSession> {
+        let session = self
+            .conn
+            .query_row(
+                "SELECT id, filename, mimetype, total_size, chunk_size, created_at, updated_at FROM upload_sessions WHERE id = ?1",
+                params![id],
+                |row| {
+                    let (total_size, chunk_size): (i64, i64) = (row.get(3)?, row.get(4)?);
+                    Ok(UploadSession {
+                        id: row.get(0)?,
+                        filename: row.get(1)?,
+                        mimetype: row.get(2)?,
+                        total_size,
+                        chunk_size,
+                        chunk_count: chunk_count(total_size, chunk_size),
+                        received: Vec::new(),
+                        created_at: row.get(5)?,
+                        updated_at: row.get(6)?,
+                    })
+                },
+            )
+            .optional()?;
+        let mut session = session.ok_or_else(|| invalid(format!("Upload {} not found or expired", id)))?;
+        let mut stmt = self.conn.prepare("SELECT chunk_index FROM upload_chunks WHERE session_id = ?1 ORDER BY chunk_index")?;
+        session.received = stmt.query_map(params![id], |row| row.get(0))?.collect::<Result<_>>()?;
+        Ok(session)
+    }
+
+    /// Open a session for a file of `total_size` bytes
+    pub fn start(&self, filename: &str, mimetype: &str, total_size: i64, chunk_size: Option<i64>) -> Result<UploadSession> {
+        self.discard_stale()?;
+        let chunk_size = chunk_size.unwrap_or(DEFAULT_CHUNK_SIZE);
+        if !(1..=MAX_CHUNK_SIZE).contains(&chunk_size) {
+            return Err(invalid(format!("Chunks must be between 1 byte and {} bytes", MAX_CHUNK_SIZE)));
+        }
+        if total_size < 0 {
+            return Err(invalid("File size cannot be negative".to_string()));
+        }
+        if filename.trim().is_empty() {
+            return Err(invalid("An upload needs a file name".to_string()));
+        }
+        let id = uuid::Uuid::new_v4().to_string();
+        // Reserved up front; chunks are written at their offsets
+        let staged = fs::File::create(self.staged_path(&id)).map_err(io_error)?;
+        staged.set_len(total_size as u64).map_err(io_error)?;
+        self.conn.execute(
+            "INSERT INTO upload_sessions (id, filename, mimetype, total_size, chunk_size) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![id, filename.trim(), mimetype, total_size, chunk_size],
+        )?;
+        self.get_session(&id)
+    }
+
+    /// Write chunk `index`, after checking its size and SHA-256. Sending a
+    /// chunk again (when resuming) overwrites it.
+    pub fn put_chunk(&self, id: &str, index: i64, content: &[u8], sha256: &str) -> Result<UploadSession> {
+        let session = self.get_session(id)?;
+        let expected = expected_chunk_size(session.total_size, session.chunk_size, index)
+            .ok_or_else(|| invalid(format!("Chunk {} is out of range; the upload has {} chunks", index, session.chunk_count)))?;
+        if content.len() as i64 != expected {
+            return Err(invalid(format!("Chunk {} should be {} bytes, got {}", index, expected, content.len())));
+        }
+        let hash = hash_bytes(content);
+        if !hash.eq_ignore_ascii_case(sha256.trim()) {
+            return Err(invalid(format!("Chunk {} failed its checksum; send it again", index)));
+        }
+
+        let mut staged = OpenOptions::new().write(true).open(self.staged_path(id)).map_err(io_error)?;
+        staged.seek(SeekFrom::Start((index * session.chunk_size) as u64)).map_err(io_error)?;
+        staged.write_all(content).map_err(io_error)?;
+        staged.sync_data().map_err(io_error)?;
+
+        self.conn.execute(
+            "INSERT OR REPLACE INTO upload_chunks (session_id, chunk_index, size, sha256) VALUES (?1, ?2, ?3, ?4)",
+            params![id, index, expected, hash],
+        )?;
+        self.conn.execute("UPDATE upload_sessions SET updated_at = CURRENT_TIMESTAMP WHERE id = ?1", params![id])?;
+        self.get_session(id)
+    }
+
+    /// Check the file is complete (and matches `sha256`, if given) and move it
+    /// into the blob store. The session is closed either way once it is complete.
+    pub fn finish(&self, id: &str, sha256: Option<&str>) -> Result<(UploadSession, Blob)> {
+        let session = self.get_session(id)?;
+        let missing = session.chunk_count - session.received.len() as i64;
+        if missing > 0 && session.total_size > 0 {
+            return Err(invalid(format!("{} of {} chunks are still missing", missing, session.chunk_count)));
+        }
+        let staged = self.staged_path(id);
+        if let Some(expected) = sha256.map(str::trim).filter(|s| !s.is_empty()) {
+            let actual = hash_file(&staged).map_err(io_error)?;
+            if !actual.eq_ignore_ascii_case(expected) {
+                self.cancel(id)?;
+                return Err(invalid("The uploaded file does not match its checksum; upload it again".to_string()));
+            }
+        }
+        let blob = self.store.store_staged(&staged, &session.filename)?;
+        self.close(id)?;
+        Ok((session, blob))
+    }
+
+    fn close(&self, id: &str) -> Result<()> {
+        self.conn.execute("DELETE FROM upload_chunks WHERE session_id = ?1", params![id])?;
+        self.conn.execute("DELETE FROM upload_sessions WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    /// Abandon an upload and its staged data
+    pub fn cancel(&self, id: &str) -> Result<()> {
+        let staged = self.staged_path(id);
+        if staged.exists() {
+            fs::remove_file(staged).map_err(io_error)?;
+        }
+        self.close(id)
+    }
+
+    fn discard_stale(&self) -> Result<()> {
+        let cutoff = (Utc::now() - Duration::days(SESSION_DAYS)).format("%Y-%m-%d %H:%M:%S").to_string();
+        let mut stmt = self.conn.prepare("SELECT id FROM upload_sessions WHERE updated_at < ?1")?;
+        let stale: Vec<String> = stmt.query_map(params![cutoff], |row| row.get(0))?.collect::<Result<_>>()?;
+        for id in stale {
+            self.cancel(&id)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn last_chunk_holds_the_rest() {
+        assert_eq!(chunk_count(10, 4), 3);
+        assert_eq!(chunk_count(8, 4), 2);
+        assert_eq!(chunk_count(0, 4), 1);
+        assert_eq!(expected_chunk_size(10, 4, 0), Some(4));
+        assert_eq!(expected_chunk_size(10, 4, 2), Some(2));
+        assert_eq!(expected_chunk_size(10, 4, 3), None);
+        assert_eq!(expected_chunk_size(10, 4, -1), None);
+        assert_eq!(expected_chunk_size(0, 4, 0), Some(0));
+    }
+
+    fn temp_root() -> PathBuf {
+        std::env::temp_dir().join(format!("play-uploads-{}", uuid::Uuid::new_v4()))
+    }
+
+    #[test]
+    fn chunks_resume_in_any_order_and_finish_into_the_blob_store() {
+        let db = crate::services::database::Database::in_memory().unwrap();
+        let root = temp_root();
+        let content = b"0123456789";
+        let chunk = |index: usize| &content[index * 4..(index * 4 + 4).min(content.len())];
+
+        let uploads = UploadService::new(db.get_connection(), root.clone()).unwrap();
+        let session = uploads.start("digits.txt", "text/plain", 10, Some(4)).unwrap();
+        assert!(uploads.put_chunk(&session.id, 2, chunk(2), &hash_bytes(b"wrong")).is_err());
+        assert!(uploads.get_session(&session.id).unwrap().received.is_empty());
+        uploads.put_chunk(&session.id, 2, chunk(2), &hash_bytes(chunk(2))).unwrap();
+        uploads.put_chunk(&session.id, 0, chunk(0), &hash_bytes(chunk(0)).to_uppercase()).unwrap();
+
+        // After a restart the session is still there and says what is missing
+        let uploads = UploadService::new(db.get_connection(), root.clone()).unwrap();
+        assert_eq!(uploads.get_session(&session.id).unwrap().received, vec![0, 2]);
+        assert!(uploads.finish(&session.id, None).is_err());
+        uploads.put_chunk(&session.id, 0, chunk(0), &hash_bytes(chunk(0))).unwrap();
+        let resumed = uploads.put_chunk(&session.id, 1, chunk(1), &hash_bytes(chunk(1))).unwrap();
+        assert_eq!(resumed.received, vec![0, 1, 2]);
+
+        let (_, blob) = uploads.finish(&session.id, Some(&hash_bytes(content))).unwrap();
+        assert_eq!(blob.hash, hash_bytes(content));
+        let store = BlobStore::new(db.get_connection(), root.clone()).unwrap();
+        assert_eq!(fs::read(store.full_path(&blob)).unwrap(), content);
+        assert!(uploads.get_session(&session.id).is_err());
+
+        // The same bytes uploaded again share the blob
+        let again = uploads.start("copy.txt", "text/plain", 10, Some(16)).unwrap();
+        uploads.put_chunk(&again.id, 0, content, &hash_bytes(content)).unwrap();
+        let (_, shared) = uploads.finish(&again.id, None).unwrap();
+        assert_eq!((shared.path.as_str(), shared.refcount), (blob.path.as_str(), 2));
+        assert_eq!(fs::read_dir(store.staging_dir()).unwrap().count(), 0);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn a_file_that_fails_its_checksum_is_discarded() {
+        let db = crate::services::database::Database::in_memory().unwrap();
+        let root = temp_root();
+        let uploads = UploadService::new(db.get_connection(), root.clone()).unwrap();
+
+        let session = uploads.start("notes.txt", "text/plain", 5, None).unwrap();
+        uploads.put_chunk(&session.id, 0, b"hello", &hash_bytes(b"hello")).unwrap();
+        assert!(uploads.finish(&session.id, Some(&hash_bytes(b"world"))).is_err());
+        assert!(!uploads.staged_path(&session.id).exists());
+        assert!(uploads.get_session(&session.id).is_err());
+        let blobs: i64 = db.get_connection().query_row("SELECT COUNT(*) FROM blobs", [], |row| row.get(0)).unwrap();
+        assert_eq!(blobs, 0);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
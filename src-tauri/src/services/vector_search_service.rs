@@ -1,6 +1,7 @@
 use crate::models::vector_index::{VectorIndex, CreateVectorIndex, SimilaritySearchResult, VectorIndexStats};
 use crate::services::database::Database;
 use crate::services::ollama::OllamaService;
+use crate::services::outline::{chunk_by_section, section_from_metadata, SectionChunk};
 use rusqlite::{params, Result};
 use std::sync::Arc;
 use tokio::sync::Mutex;
@@ -151,6 +152,7 @@ impl<'a> VectorSearchService<'a> {
                     content_type: entry.content_type,
                     content: entry.content,
                     similarity_score: similarity,
+                    section: section_from_metadata(entry.metadata.as_deref()),
                     metadata: entry.metadata,
                 });
             }
@@ -183,24 +185,24 @@ impl<'a> VectorSearchService<'a> {
 
     /// Index content by generating embeddings and storing them
     pub async fn index_content(&self, content_id: i64, content_type: &str, content: &str, model: Option<&str>) -> Result<Vec<VectorIndex>> {
-        // Split content into chunks if it's too long
-        let chunks = self.chunk_content(content, 1000); // 1000 characters per chunk
+        // Split content into per-section chunks of up to 1000 characters
+        let chunks = self.chunk_content(content, 1000);
         let mut entries = Vec::new();
 
         for (chunk_index, chunk) in chunks.iter().enumerate() {
-            // Generate embedding for this chunk
-            let embedding = self.generate_embedding(chunk, model).await
+            // Embed the chunk together with its heading path
+            let embedding = self.generate_embedding(&chunk.embedding_text, model).await
                 .map_err(|e| rusqlite::Error::InvalidParameterName(e))?;
 
             // Create vector index entry
             let entry = CreateVectorIndex {
                 content_id,
                 content_type: content_type.to_string(),
-                content: chunk.clone(),
+                content: chunk.text.clone(),
                 embedding_vector: embedding,
                 model_name: model.unwrap_or("nomic-embed-text").to_string(),
                 chunk_index: Some(chunk_index as i64),
-                metadata: Some(format!("chunk_length:{}", chunk.len())),
+                metadata: Some(chunk.metadata.clone()),
             };
 
             let created_entry = self.create_vector_entry(entry)?;
@@ -277,35 +279,9 @@ impl<'a> VectorSearchService<'a> {
         }
     }
 
-    /// Split content into chunks
-    fn chunk_content(&self, content: &str, max_chunk_size: usize) -> Vec<String> {
-        if content.len() <= max_chunk_size {
-            return vec![content.to_string()];
-        }
-
-        let mut chunks = Vec::new();
-        let mut start = 0;
-
-        while start < content.len() {
-            let end = std::cmp::min(start + max_chunk_size, content.len());
-            let chunk = &content[start..end];
-            
-            // Try to break at word boundary
-            let actual_end = if end < content.len() {
-                if let Some(space_pos) = chunk.rfind(' ') {
-                    start + space_pos
-                } else {
-                    end
-                }
-            } else {
-                end
-            };
-
-            chunks.push(content[start..actual_end].to_string());
-            start = if actual_end == end { end } else { actual_end + 1 };
-        }
-
-        chunks
+    /// Split content into chunks that follow the document's heading structure
+    fn chunk_content(&self, content: &str, max_chunk_size: usize) -> Vec<SectionChunk> {
+        chunk_by_section(content, max_chunk_size)
     }
 
     /// Parse embedding response from Ollama
@@ -3,24 +3,30 @@ import { useState } from 'react';
 import { Brain, Loader2, X } from 'lucide-react';
 import { aiAPI } from '../../lib/ai';
 import { useTaskStore } from '../../store/useTaskStore';
+import type { TaskDraft, TaskSource } from '../../types/taskExtraction';
 
 interface AITaskGeneratorProps {
   onClose?: () => void;
+  /** Message, document or file the text comes from; tasks are linked back to it */
+  source?: TaskSource;
+  initialText?: string;
 }
 
-export default function AITaskGenerator({ onClose }: AITaskGeneratorProps) {
-  const [text, setText] = useState('');
+export default function AITaskGenerator({ onClose, source, initialText = '' }: AITaskGeneratorProps) {
+  const [text, setText] = useState(initialText);
   const [loading, setLoading] = useState(false);
-  const [generatedTasks, setGeneratedTasks] = useState<string[]>([]);
-  const { createTask } = useTaskStore();
+  const [generatedTasks, setGeneratedTasks] = useState<TaskDraft[]>([]);
+  const [accepted, setAccepted] = useState<boolean[]>([]);
+  const { loadTasks } = useTaskStore();
 
   const handleGenerate = async () => {
     if (!text.trim()) return;
 
     setLoading(true);
     try {
-      const tasks = await aiAPI.generateTasks(text);
-      setGeneratedTasks(tasks);
+      const extraction = await aiAPI.generateTasks(text, source);
+      setGeneratedTasks(extraction.drafts);
+      setAccepted(extraction.drafts.map(() => true));
     } catch (error) {
       console.error('Task generation failed:', error);
       alert('AI task generation failed. Make sure Ollama is running: ollama serve');
@@ -30,18 +36,14 @@ export default function AITaskGenerator({ onClose }: AITaskGeneratorProps) {
   };
 
   const handleCreateTasks = async () => {
-    for (const taskTitle of generatedTasks) {
-      await createTask({
-        title: taskTitle,
-        description: `Generated from: ${text.substring(0, 100)}...`,
-        status: 'todo',
-        priority: 'medium',
-        due_date: null,
-        reminder_time: null,
-      });
+    const drafts = generatedTasks.filter((_, index) => accepted[index]);
+    if (drafts.length > 0) {
+      await aiAPI.commitTaskDrafts(drafts, source);
+      await loadTasks();
     }
-    
+
     setGeneratedTasks([]);
+    setAccepted([]);
     setText('');
     if (onClose) onClose();
   };
@@ -96,17 +98,30 @@ export default function AITaskGenerator({ onClose }: AITaskGeneratorProps) {
           <div className="mt-6 space-y-3">
             <h3 className="font-semibold">Generated Tasks ({generatedTasks.length})</h3>
             <div className="space-y-2 max-h-60 overflow-y-auto">
-              {generatedTasks.map((task, index) => (
-                <div key={index} className="p-3 bg-accent rounded-lg text-sm">
-                  {task}
-                </div>
+              {generatedTasks.map((draft, index) => (
+                <label key={index} className="flex items-start gap-3 p-3 bg-accent rounded-lg text-sm cursor-pointer">
+                  <input
+                    type="checkbox"
+                    checked={accepted[index] ?? false}
+                    onChange={(e) => setAccepted(accepted.map((value, i) => (i === index ? e.target.checked : value)))}
+                    className="mt-1"
+                  />
+                  <div>
+                    <div className="font-medium">{draft.task.title}</div>
+                    <div className="text-xs text-muted-foreground">
+                      {[draft.task.priority, draft.task.due_date && `due ${draft.task.due_date}`, draft.owner && `@${draft.owner}`]
+                        .filter(Boolean)
+                        .join(' · ')}
+                    </div>
+                  </div>
+                </label>
               ))}
             </div>
             <button
               onClick={handleCreateTasks}
               className="w-full px-4 py-2 bg-primary text-primary-foreground rounded-lg hover:opacity-90 transition-opacity"
             >
-              Add All Tasks to Board
+              Add Selected Tasks
             </button>
           </div>
         )}
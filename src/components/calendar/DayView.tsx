@@ -1,12 +1,12 @@
 // Day View - per prd.md §3️⃣.E
-import { format, isSameDay } from 'date-fns';
+import { format, isSameDay, parseISO } from 'date-fns';
 import { useCalendarStore } from '../../store/useCalendarStore';
 
 export default function DayView() {
   const { currentDate, events } = useCalendarStore();
 
   const dayEvents = events.filter((event) =>
-    isSameDay(new Date(event.start_time), currentDate)
+    isSameDay(parseISO(event.start_time), currentDate)
   );
 
   // Generate hourly slots
@@ -25,7 +25,7 @@ export default function DayView() {
         <div className="space-y-2">
           {hours.map((hour) => {
             const hourEvents = dayEvents.filter((event) => {
-              const eventHour = new Date(event.start_time).getHours();
+              const eventHour = parseISO(event.start_time).getHours();
               return eventHour === hour;
             });
 
@@ -42,8 +42,8 @@ export default function DayView() {
                     >
                       <div className="font-medium text-sm">{event.title}</div>
                       <div className="text-xs opacity-75">
-                        {format(new Date(event.start_time), 'h:mm a')} -{' '}
-                        {format(new Date(event.end_time), 'h:mm a')}
+                        {format(parseISO(event.start_time), 'h:mm a')} -{' '}
+                        {format(parseISO(event.end_time), 'h:mm a')}
                       </div>
                     </div>
                   ))}
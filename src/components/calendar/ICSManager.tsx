@@ -6,6 +6,7 @@ import { save, open } from '@tauri-apps/api/dialog';
 import { writeTextFile, readTextFile } from '@tauri-apps/api/fs';
 import { useCalendarStore } from '../../store/useCalendarStore';
 import { startOfMonth, endOfMonth } from 'date-fns';
+import { IcsImportSummary } from '../../types/event';
 
 export default function ICSManager() {
   const [loading, setLoading] = useState(false);
@@ -53,9 +54,18 @@ export default function ICSManager() {
       }
 
       const icsContent = await readTextFile(filePath);
-      const count = await invoke<number>('import_calendar_from_ics', { icsContent });
+      // Keyed by path, so importing the same file again updates its events
+      const removeMissing = confirm('Remove events that were imported from this file before but are no longer in it?');
+      const summary = await invoke<IcsImportSummary>('import_calendar_from_ics', {
+        icsContent,
+        options: { source: filePath, remove_missing: removeMissing },
+      });
 
-      alert(`✅ Imported ${count} events successfully!`);
+      alert(
+        `✅ Import finished: ${summary.created} created, ${summary.updated} updated, ` +
+          `${summary.unchanged} unchanged, ${summary.removed} removed` +
+          (summary.warnings.length ? `\n\n${summary.warnings.length} warning(s):\n${summary.warnings.join('\n')}` : '')
+      );
       
       // Reload events
       const start = startOfMonth(currentDate);
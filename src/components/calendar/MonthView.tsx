@@ -1,5 +1,5 @@
 // Month View - per prd.md §3️⃣.E
-import { startOfMonth, endOfMonth, eachDayOfInterval, format, isSameMonth, isToday, isSameDay } from 'date-fns';
+import { startOfMonth, endOfMonth, eachDayOfInterval, format, isSameMonth, isToday, isSameDay, parseISO } from 'date-fns';
 import { useCalendarStore } from '../../store/useCalendarStore';
 
 export default function MonthView() {
@@ -34,7 +34,7 @@ export default function MonthView() {
         ))}
         {days.map((day) => {
           const dayEvents = events.filter((event) =>
-            isSameDay(new Date(event.start_time), day)
+            isSameDay(parseISO(event.start_time), day)
           );
           const isCurrentMonth = isSameMonth(day, currentDate);
           const isTodayDate = isToday(day);
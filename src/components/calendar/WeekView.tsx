@@ -1,5 +1,5 @@
 // Week View - per prd.md §3️⃣.E
-import { startOfWeek, endOfWeek, eachDayOfInterval, format, isSameDay, isToday } from 'date-fns';
+import { startOfWeek, endOfWeek, eachDayOfInterval, format, isSameDay, isToday, parseISO } from 'date-fns';
 import { useCalendarStore } from '../../store/useCalendarStore';
 
 export default function WeekView() {
@@ -14,7 +14,7 @@ export default function WeekView() {
       <div className="grid grid-cols-7 gap-2 flex-1">
         {days.map((day) => {
           const dayEvents = events.filter((event) =>
-            isSameDay(new Date(event.start_time), day)
+            isSameDay(parseISO(event.start_time), day)
           );
           const isTodayDate = isToday(day);
 
@@ -39,7 +39,7 @@ export default function WeekView() {
                   >
                     <div className="font-medium truncate">{event.title}</div>
                     <div className="text-xs opacity-75">
-                      {format(new Date(event.start_time), 'h:mm a')}
+                      {format(parseISO(event.start_time), 'h:mm a')}
                     </div>
                   </div>
                 ))}
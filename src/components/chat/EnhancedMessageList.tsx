@@ -2,6 +2,8 @@
 import { useEffect, useRef } from 'react';
 import { useEnhancedChatStore } from '../../store/useEnhancedChatStore';
 import { User, Bot, Brain, Loader2 } from 'lucide-react';
+import FilePreview from '../common/FilePreview';
+import { parseAttachmentIds } from '../../lib/files';
 
 export default function EnhancedMessageList() {
   const { 
@@ -127,7 +129,14 @@ export default function EnhancedMessageList() {
                 </div>
 
                 {/* Attachments */}
-                {message.attachments && (
+                {message.attachments && parseAttachmentIds(message.attachments) && (
+                  <div className="mt-2 flex flex-wrap gap-2">
+                    {parseAttachmentIds(message.attachments)!.map((id) => (
+                      <FilePreview key={id} fileId={id} compact />
+                    ))}
+                  </div>
+                )}
+                {message.attachments && !parseAttachmentIds(message.attachments) && (
                   <div className="mt-2 text-xs opacity-70">
                     📎 {message.attachments}
                   </div>
@@ -2,6 +2,8 @@
 import { useEffect, useRef } from 'react';
 import { useEnhancedChatStore } from '../../store/useEnhancedChatStore';
 import { User, Bot } from 'lucide-react';
+import FilePreview from '../common/FilePreview';
+import { parseAttachmentIds } from '../../lib/files';
 
 export default function MessageList() {
   const { messages, loading } = useEnhancedChatStore();
@@ -199,7 +201,14 @@ export default function MessageList() {
               </div>
 
               {/* Attachments */}
-              {message.attachments && (
+              {message.attachments && parseAttachmentIds(message.attachments) && (
+                <div className="mt-2 flex flex-wrap gap-2">
+                  {parseAttachmentIds(message.attachments)!.map((id) => (
+                    <FilePreview key={id} fileId={id} compact />
+                  ))}
+                </div>
+              )}
+              {message.attachments && !parseAttachmentIds(message.attachments) && (
                 <div className={`mt-2 text-xs ${
                   isAI ? 'text-slate-500' : 'text-blue-100'
                 }`}>
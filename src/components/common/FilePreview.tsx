@@ -0,0 +1,61 @@
+// File Preview - thumbnail or text excerpt of a stored file
+import { useEffect, useState } from 'react';
+import { FileText, File as FileIcon } from 'lucide-react';
+import { filesAPI, FileMetadata, FilePreview as Preview, thumbnailUrl } from '../../lib/files';
+
+interface FilePreviewProps {
+  fileId: number;
+  compact?: boolean;
+}
+
+export default function FilePreview({ fileId, compact = false }: FilePreviewProps) {
+  const [metadata, setMetadata] = useState<FileMetadata | null>(null);
+  const [preview, setPreview] = useState<Preview | null>(null);
+  const [imageUrl, setImageUrl] = useState<string | null>(null);
+
+  useEffect(() => {
+    let cancelled = false;
+    let url: string | null = null;
+
+    filesAPI.getFileMetadata(fileId).then((file) => !cancelled && setMetadata(file)).catch(console.error);
+    filesAPI
+      .getFilePreview(fileId)
+      .then((result) => {
+        if (cancelled) return;
+        url = thumbnailUrl(result);
+        setPreview(result);
+        setImageUrl(url);
+      })
+      .catch(console.error);
+
+    return () => {
+      cancelled = true;
+      if (url) URL.revokeObjectURL(url);
+    };
+  }, [fileId]);
+
+  const name = metadata?.filename ?? `File ${fileId}`;
+  const size = compact ? 'w-24' : 'w-40';
+  // PDF pages are not rendered; the thumbnail is the largest image on page 1
+  const title = preview?.kind === 'pdf_page' ? `${name} (image from page 1)` : name;
+
+  return (
+    <div className={`${size} rounded-md border border-border bg-background overflow-hidden text-foreground`} title={title}>
+      {imageUrl ? (
+        <img src={imageUrl} alt={name} className="w-full h-24 object-cover" />
+      ) : preview?.kind === 'text' && preview.excerpt && !compact ? (
+        <p className="h-24 p-2 text-[10px] leading-snug text-muted-foreground overflow-hidden whitespace-pre-line">
+          {preview.excerpt}
+        </p>
+      ) : (
+        <div className="h-24 flex items-center justify-center text-muted-foreground">
+          {preview?.kind === 'text' ? <FileText className="w-8 h-8" /> : <FileIcon className="w-8 h-8" />}
+        </div>
+      )}
+      <div className="px-2 py-1 text-xs truncate">
+        {name}
+        {preview?.page_count ? <span className="text-muted-foreground"> · {preview.page_count} pp.</span> : null}
+      </div>
+    </div>
+  );
+}
@@ -1,11 +1,13 @@
 // AI API - Frontend interface for AI features
 import { invoke } from '@tauri-apps/api/tauri';
+import type { CommittedTask, TaskDraft, TaskExtraction, TaskSource } from '../types/taskExtraction';
 
 export interface AIService {
   checkConnection: () => Promise<boolean>;
   summarizeText: (text: string) => Promise<string>;
   rewriteText: (text: string, style: string) => Promise<string>;
-  generateTasks: (text: string) => Promise<string[]>;
+  generateTasks: (text: string, source?: TaskSource) => Promise<TaskExtraction>;
+  commitTaskDrafts: (drafts: TaskDraft[], source?: TaskSource) => Promise<CommittedTask[]>;
   generateEmbedding: (text: string) => Promise<number[]>;
   indexContent: (contentType: string, contentId: number, text: string) => Promise<void>;
   semanticSearch: (query: string, limit: number) => Promise<Array<[number, string, number]>>;
@@ -31,8 +33,12 @@ export const aiAPI: AIService = {
     return await invoke<string>('rewrite', { text, style });
   },
 
-  generateTasks: async (text: string) => {
-    return await invoke<string[]>('generate_tasks', { text });
+  generateTasks: async (text: string, source?: TaskSource) => {
+    return await invoke<TaskExtraction>('generate_tasks', { text, source: source ?? null });
+  },
+
+  commitTaskDrafts: async (drafts: TaskDraft[], source?: TaskSource) => {
+    return await invoke<CommittedTask[]>('commit_task_drafts', { drafts, source: source ?? null });
   },
 
   generateEmbedding: async (text: string) => {
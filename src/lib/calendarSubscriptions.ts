@@ -0,0 +1,26 @@
+// Calendar Subscriptions API - read-only calendar layers from ICS feeds
+import { invoke } from '@tauri-apps/api/tauri';
+import { CalendarSubscription, CreateSubscription, UpdateSubscription } from '../types/calendarSubscription';
+
+export const calendarSubscriptionsAPI = {
+  create: (subscription: CreateSubscription): Promise<CalendarSubscription> => {
+    return invoke('create_calendar_subscription', { subscription });
+  },
+
+  getAll: (): Promise<CalendarSubscription[]> => {
+    return invoke('get_calendar_subscriptions');
+  },
+
+  update: (id: number, update: UpdateSubscription): Promise<CalendarSubscription> => {
+    return invoke('update_calendar_subscription', { id, update });
+  },
+
+  // Also deletes the events that came from the subscription
+  delete: (id: number): Promise<void> => {
+    return invoke('delete_calendar_subscription', { id });
+  },
+
+  refresh: (id: number): Promise<CalendarSubscription> => {
+    return invoke('refresh_calendar_subscription', { id });
+  },
+};
@@ -0,0 +1,24 @@
+// Digests API - morning agenda and weekly review
+import { invoke } from '@tauri-apps/api/tauri';
+import { listen, UnlistenFn } from '@tauri-apps/api/event';
+import { Digest, DigestKind, DigestSchedule } from '../types/digest';
+
+export const digestsAPI = {
+  getSchedule: (): Promise<DigestSchedule> => {
+    return invoke('get_digest_schedule');
+  },
+
+  updateSchedule: (schedule: DigestSchedule): Promise<DigestSchedule> => {
+    return invoke('update_digest_schedule', { schedule });
+  },
+
+  // Build a digest now; save=false previews it without creating a document
+  generate: (kind: DigestKind, save = true): Promise<Digest> => {
+    return invoke('generate_digest', { kind, save });
+  },
+
+  // Called for every digest delivered on schedule
+  onReady: (handler: (digest: Digest) => void): Promise<UnlistenFn> => {
+    return listen<Digest>('digest-ready', (event) => handler(event.payload));
+  },
+};
@@ -6,9 +6,39 @@ export interface FileMetadata {
   filepath: string;
   filesize: number;
   mimetype: string;
+  // SHA-256 of the content; files with the same hash share their bytes
+  hash?: string | null;
   created_at: string;
 }
 
+export interface UploadSession {
+  id: string;
+  filename: string;
+  mimetype: string;
+  total_size: number;
+  chunk_size: number;
+  chunk_count: number;
+  // Indexes of the chunks received so far
+  received: number[];
+  created_at: string;
+  updated_at: string;
+}
+
+// 'pdf_page' is the largest image on a PDF's first page, not a rendering of
+// the page; PDFs whose first page has no image get a 'text' excerpt
+export type FilePreviewKind = 'image' | 'pdf_page' | 'text' | 'none';
+
+export interface FilePreview {
+  file_id: number;
+  kind: FilePreviewKind;
+  // PNG thumbnail, for images and the image on a PDF's first page
+  thumbnail?: number[] | null;
+  width?: number | null;
+  height?: number | null;
+  excerpt?: string | null;
+  page_count?: number | null;
+}
+
 export const filesAPI = {
   uploadFile: async (filename: string, content: Uint8Array, mimetype: string): Promise<FileMetadata> => {
     return invoke('upload_file', {
@@ -33,8 +63,84 @@ export const filesAPI = {
   listFiles: (limit?: number): Promise<FileMetadata[]> => {
     return invoke('list_files', { limit });
   },
+
+  // Import from disk without passing the bytes through the webview
+  importFileFromPath: (path: string, mimetype?: string): Promise<FileMetadata> => {
+    return invoke('import_file_from_path', { path, mimetype });
+  },
+
+  // At most 16 MB per call; shorter at the end of the file
+  readFileRange: (id: number, offset: number, length: number): Promise<number[]> => {
+    return invoke('read_file_range', { id, offset, length });
+  },
+
+  startUpload: (filename: string, mimetype: string, totalSize: number, chunkSize?: number): Promise<UploadSession> => {
+    return invoke('start_upload', { filename, mimetype, totalSize, chunkSize });
+  },
+
+  getUpload: (id: string): Promise<UploadSession> => {
+    return invoke('get_upload', { id });
+  },
+
+  uploadChunk: (id: string, index: number, content: Uint8Array, sha256: string): Promise<UploadSession> => {
+    return invoke('upload_chunk', { id, index, content: Array.from(content), sha256 });
+  },
+
+  finishUpload: (id: string, sha256?: string): Promise<FileMetadata> => {
+    return invoke('finish_upload', { id, sha256 });
+  },
+
+  cancelUpload: (id: string): Promise<void> => {
+    return invoke('cancel_upload', { id });
+  },
+
+  // Generated on first use and cached until the file is deleted
+  getFilePreview: (id: number): Promise<FilePreview> => {
+    return invoke('get_file_preview', { id });
+  },
+
+  // Upload a File in chunks; pass the id of an interrupted session to resume it
+  uploadLargeFile: async (
+    file: File,
+    resumeId?: string,
+    onProgress?: (received: number, total: number) => void,
+  ): Promise<FileMetadata> => {
+    const session = resumeId
+      ? await filesAPI.getUpload(resumeId)
+      : await filesAPI.startUpload(file.name, file.type || 'application/octet-stream', file.size);
+    const received = new Set(session.received);
+    for (let index = 0; index < session.chunk_count; index++) {
+      if (received.has(index)) continue;
+      const start = index * session.chunk_size;
+      const chunk = new Uint8Array(await file.slice(start, start + session.chunk_size).arrayBuffer());
+      await filesAPI.uploadChunk(session.id, index, chunk, await sha256Hex(chunk));
+      received.add(index);
+      onProgress?.(received.size, session.chunk_count);
+    }
+    return filesAPI.finishUpload(session.id);
+  },
 };
 
+export async function sha256Hex(content: Uint8Array): Promise<string> {
+  const digest = await crypto.subtle.digest('SHA-256', content);
+  return Array.from(new Uint8Array(digest))
+    .map((b) => b.toString(16).padStart(2, '0'))
+    .join('');
+}
+
+// Object URL for a preview thumbnail; revoke it when no longer shown
+export function thumbnailUrl(preview: FilePreview): string | null {
+  if (!preview.thumbnail) return null;
+  return URL.createObjectURL(new Blob([new Uint8Array(preview.thumbnail)], { type: 'image/png' }));
+}
+
+// Message attachments given as comma-separated file ids, or null for other text
+export function parseAttachmentIds(attachments: string): number[] | null {
+  const parts = attachments.split(',').map((part) => part.trim()).filter(Boolean);
+  if (parts.length === 0 || !parts.every((part) => /^\d+$/.test(part))) return null;
+  return parts.map(Number);
+}
+
 export function formatFileSize(bytes: number): string {
   if (bytes === 0) return '0 Bytes';
 
@@ -0,0 +1,29 @@
+// Reminders API - upcoming reminders, snooze and dismiss
+import { invoke } from '@tauri-apps/api/tauri';
+import { listen, UnlistenFn } from '@tauri-apps/api/event';
+import { Reminder } from '../types/reminder';
+
+export const remindersAPI = {
+  // Pending reminders for the next `days` days (one by default)
+  getUpcoming: (days?: number): Promise<Reminder[]> => {
+    return invoke('get_upcoming_reminders', { days: days ?? null });
+  },
+
+  // One-off reminder not tied to a task or event; time is RFC 3339
+  schedule: (title: string, body: string, time: string): Promise<Reminder> => {
+    return invoke('schedule_reminder', { title, body, time });
+  },
+
+  snooze: (id: number, minutes: number): Promise<Reminder> => {
+    return invoke('snooze_reminder', { id, minutes });
+  },
+
+  dismiss: (id: number): Promise<Reminder> => {
+    return invoke('dismiss_reminder', { id });
+  },
+
+  // Called for every reminder as its notification is shown
+  onFired: (handler: (reminder: Reminder) => void): Promise<UnlistenFn> => {
+    return listen<Reminder>('reminder-fired', (event) => handler(event.payload));
+  },
+};
@@ -0,0 +1,45 @@
+// Scheduling API - event conflicts, free/busy, free-slot search and task planning
+import { invoke } from '@tauri-apps/api/tauri';
+import { CalendarEvent } from '../types/event';
+import {
+  EventConflict,
+  FreeBusy,
+  FreeSlotRequest,
+  PlannedBlock,
+  PlanRequest,
+  TaskPlan,
+  TimeSlot,
+} from '../types/scheduling';
+
+export const schedulingAPI = {
+  getFreeBusy: (start: string, end: string): Promise<FreeBusy> => {
+    return invoke('get_free_busy', { start, end });
+  },
+
+  findFreeSlots: (request: FreeSlotRequest): Promise<TimeSlot[]> => {
+    return invoke('find_free_slots', { request });
+  },
+
+  getEventConflicts: (id: number): Promise<EventConflict[]> => {
+    return invoke('get_event_conflicts', { id });
+  },
+
+  // Check a time before saving; excludeId is the event being moved
+  checkConflicts: (start: string, end: string, excludeId?: number): Promise<CalendarEvent[]> => {
+    return invoke('check_event_conflicts', { start, end, excludeId: excludeId ?? null });
+  },
+
+  // Time-block open tasks into free calendar time
+  planTasks: (request: PlanRequest): Promise<TaskPlan> => {
+    return invoke('plan_tasks', { request });
+  },
+
+  getTaskPlan: (): Promise<PlannedBlock[]> => {
+    return invoke('get_task_plan');
+  },
+
+  // Removes the blocks still ahead and stops re-planning
+  clearTaskPlan: (): Promise<void> => {
+    return invoke('clear_task_plan');
+  },
+};
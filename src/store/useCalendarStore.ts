@@ -2,6 +2,7 @@
 import { create } from 'zustand';
 import { invoke } from '@tauri-apps/api/tauri';
 import { CalendarEvent, CreateEvent, UpdateEvent } from '../types/event';
+import { EventConflict, PlannedBlock, ScheduledEvent } from '../types/scheduling';
 
 interface CalendarState {
   events: CalendarEvent[];
@@ -9,78 +10,128 @@ interface CalendarState {
   error: string | null;
   currentView: 'day' | 'week' | 'month';
   currentDate: Date;
+  range: { start: string; end: string } | null;
+  // Events overlapping the last event created or updated
+  conflicts: EventConflict[];
   
   // Actions
   loadEvents: (start: string, end: string) => Promise<void>;
   createEvent: (event: CreateEvent) => Promise<CalendarEvent>;
   updateEvent: (id: number, update: UpdateEvent) => Promise<void>;
   deleteEvent: (id: number) => Promise<void>;
+  editOccurrence: (seriesId: number, occurrenceDate: string, update: UpdateEvent) => Promise<void>;
+  deleteOccurrence: (seriesId: number, occurrenceDate: string) => Promise<void>;
+  updateSeriesFrom: (seriesId: number, fromDate: string, update: UpdateEvent) => Promise<void>;
+  deleteSeriesFrom: (seriesId: number, fromDate: string) => Promise<void>;
   setView: (view: 'day' | 'week' | 'month') => void;
   setCurrentDate: (date: Date) => void;
 }
 
-export const useCalendarStore = create<CalendarState>((set) => ({
-  events: [],
-  loading: false,
-  error: null,
-  currentView: 'month',
-  currentDate: new Date(),
-
-  loadEvents: async (start: string, end: string) => {
-    set({ loading: true, error: null });
-    try {
-      const events = await invoke<CalendarEvent[]>('get_events_in_range', { start, end });
-      set({ events, loading: false });
-    } catch (error) {
-      console.error('Failed to load events:', error);
-      set({ error: String(error), loading: false });
-    }
-  },
-
-  createEvent: async (event: CreateEvent) => {
+export const useCalendarStore = create<CalendarState>((set, get) => {
+  // Changing part of a series moves occurrences around, so reload the visible range
+  const changeSeries = async (command: string, args: Record<string, unknown>) => {
     try {
-      const newEvent = await invoke<CalendarEvent>('create_event', { event });
-      set((state) => ({ events: [...state.events, newEvent] }));
-      return newEvent;
+      await invoke(command, args);
+      const range = get().range;
+      if (range) {
+        await get().loadEvents(range.start, range.end);
+      }
     } catch (error) {
-      console.error('Failed to create event:', error);
+      console.error(`Failed to ${command.replace(/_/g, ' ')}:`, error);
       set({ error: String(error) });
       throw error;
     }
-  },
+  };
 
-  updateEvent: async (id: number, update: UpdateEvent) => {
-    try {
-      const updated = await invoke<CalendarEvent>('update_event', { id, update });
-      set((state) => ({
-        events: state.events.map((e) => (e.id === id ? updated : e)),
-      }));
-    } catch (error) {
-      console.error('Failed to update event:', error);
-      set({ error: String(error) });
-      throw error;
+  // The task plan may move its blocks to make room, so reload them when there are any
+  const reloadPlanned = async () => {
+    const range = get().range;
+    const blocks = await invoke<PlannedBlock[]>('get_task_plan');
+    if (range && blocks.length > 0) {
+      await get().loadEvents(range.start, range.end);
     }
-  },
+  };
 
-  deleteEvent: async (id: number) => {
-    try {
-      await invoke('delete_event', { id });
-      set((state) => ({
-        events: state.events.filter((e) => e.id !== id),
-      }));
-    } catch (error) {
-      console.error('Failed to delete event:', error);
-      set({ error: String(error) });
-      throw error;
-    }
-  },
+  return {
+    events: [],
+    loading: false,
+    error: null,
+    currentView: 'month',
+    currentDate: new Date(),
+    range: null,
+    conflicts: [],
+
+    loadEvents: async (start: string, end: string) => {
+      set({ loading: true, error: null, range: { start, end } });
+      try {
+        const events = await invoke<CalendarEvent[]>('get_events_in_range', { start, end });
+        set({ events, loading: false });
+      } catch (error) {
+        console.error('Failed to load events:', error);
+        set({ error: String(error), loading: false });
+      }
+    },
+
+    createEvent: async (event: CreateEvent) => {
+      try {
+        const { event: newEvent, conflicts } = await invoke<ScheduledEvent>('create_event', { event });
+        set((state) => ({ events: [...state.events, newEvent], conflicts }));
+        await reloadPlanned();
+        return newEvent;
+      } catch (error) {
+        console.error('Failed to create event:', error);
+        set({ error: String(error) });
+        throw error;
+      }
+    },
+
+    updateEvent: async (id: number, update: UpdateEvent) => {
+      try {
+        const { event: updated, conflicts } = await invoke<ScheduledEvent>('update_event', { id, update });
+        set((state) => ({
+          events: state.events.map((e) => (e.id === id ? updated : e)),
+          conflicts,
+        }));
+        await reloadPlanned();
+      } catch (error) {
+        console.error('Failed to update event:', error);
+        set({ error: String(error) });
+        throw error;
+      }
+    },
+
+    deleteEvent: async (id: number) => {
+      try {
+        await invoke('delete_event', { id });
+        set((state) => ({
+          events: state.events.filter((e) => e.id !== id && e.series_id !== id),
+        }));
+      } catch (error) {
+        console.error('Failed to delete event:', error);
+        set({ error: String(error) });
+        throw error;
+      }
+    },
+
+    editOccurrence: (seriesId: number, occurrenceDate: string, update: UpdateEvent) =>
+      changeSeries('edit_event_occurrence', { seriesId, occurrenceDate, update }),
+
+    deleteOccurrence: (seriesId: number, occurrenceDate: string) =>
+      changeSeries('delete_event_occurrence', { seriesId, occurrenceDate }),
+
+    updateSeriesFrom: (seriesId: number, fromDate: string, update: UpdateEvent) =>
+      changeSeries('update_event_series_from', { seriesId, fromDate, update }),
+
+    deleteSeriesFrom: (seriesId: number, fromDate: string) =>
+      changeSeries('delete_event_series_from', { seriesId, fromDate }),
 
-  setView: (view: 'day' | 'week' | 'month') => {
-    set({ currentView: view });
-  },
+    setView: (view: 'day' | 'week' | 'month') => {
+      set({ currentView: view });
+    },
 
-  setCurrentDate: (date: Date) => {
-    set({ currentDate: date });
-  },
-}));
+    setCurrentDate: (date: Date) => {
+      set({ currentDate: date });
+    },
+  };
+});
 
@@ -1,7 +1,8 @@
 // Document Store - per ARCHITECTURE.md state management pattern
 import { create } from 'zustand';
 import { invoke } from '@tauri-apps/api/tauri';
-import { Document, CreateDocument } from '../types/document';
+import { Document, CreateDocument, UpdateDocument } from '../types/document';
+import { isUpdateConflict, updateErrorMessage } from '../types/conflict';
 
 interface DocumentState {
   documents: Document[];
@@ -9,6 +10,8 @@ interface DocumentState {
   loading: boolean;
   saving: boolean;
   error: string | null;
+  // Stored copy of a document whose save was rejected because it changed elsewhere
+  conflict: Document | null;
   
   // Actions
   loadDocuments: (limit?: number) => Promise<void>;
@@ -17,14 +20,16 @@ interface DocumentState {
   updateDocument: (id: number, content: string, title?: string) => Promise<void>;
   deleteDocument: (id: number) => Promise<void>;
   setCurrentDocument: (doc: Document | null) => void;
+  clearConflict: () => void;
 }
 
-export const useDocumentStore = create<DocumentState>((set) => ({
+export const useDocumentStore = create<DocumentState>((set, get) => ({
   documents: [],
   currentDocument: null,
   loading: false,
   saving: false,
   error: null,
+  conflict: null,
 
   loadDocuments: async (limit?: number) => {
     set({ loading: true, error: null });
@@ -68,10 +73,13 @@ export const useDocumentStore = create<DocumentState>((set) => ({
   updateDocument: async (id: number, content: string, title?: string) => {
     set({ saving: true, error: null });
     try {
-      const update = {
+      const known = get().currentDocument;
+      const update: UpdateDocument = {
         content: content,
         title: title || undefined,
         tags: undefined,
+        expected_version: known?.id === id ? known.version : undefined,
+        expected_updated_at: known?.id === id ? known.updated_at : undefined,
       };
       
       const updated = await invoke<Document>('update_document', { id, update });
@@ -80,10 +88,15 @@ export const useDocumentStore = create<DocumentState>((set) => ({
         documents: state.documents.map((d) => (d.id === id ? updated : d)),
         currentDocument: state.currentDocument?.id === id ? updated : state.currentDocument,
         saving: false,
+        conflict: null,
       }));
     } catch (error) {
       console.error('Failed to update document:', error);
-      set({ error: String(error), saving: false });
+      if (isUpdateConflict<Document>(error)) {
+        set({ error: updateErrorMessage(error), conflict: error.current, saving: false });
+      } else {
+        set({ error: updateErrorMessage(error), saving: false });
+      }
       throw error;
     }
   },
@@ -106,5 +119,9 @@ export const useDocumentStore = create<DocumentState>((set) => ({
   setCurrentDocument: (doc: Document | null) => {
     set({ currentDocument: doc });
   },
+
+  clearConflict: () => {
+    set({ conflict: null });
+  },
 }));
 
@@ -7,6 +7,9 @@ export interface UserSettings {
   notifications_enabled: boolean;
   auto_save_interval: number;
   default_module: 'chat' | 'documents' | 'tasks' | 'calendar';
+  blocked_task_policy: 'warn' | 'refuse';
+  /** IANA zone for dates and times; null follows the system */
+  time_zone?: string | null;
 }
 
 interface SettingsState {
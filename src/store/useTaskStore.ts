@@ -1,7 +1,8 @@
 // Task Store - per ARCHITECTURE.md state management pattern
 import { create } from 'zustand';
 import { invoke } from '@tauri-apps/api/tauri';
-import { Task, CreateTask, UpdateTask } from '../types/task';
+import { Task, CreateTask, TaskStatus, UpdateTask } from '../types/task';
+import { updateErrorMessage } from '../types/conflict';
 
 interface TaskState {
   tasks: Task[];
@@ -13,7 +14,7 @@ interface TaskState {
   createTask: (task: CreateTask) => Promise<Task>;
   updateTask: (id: number, update: UpdateTask) => Promise<void>;
   deleteTask: (id: number) => Promise<void>;
-  moveTask: (taskId: number, newStatus: 'todo' | 'in_progress' | 'done') => Promise<void>;
+  moveTask: (taskId: number, newStatus: TaskStatus) => Promise<void>;
 }
 
 export const useTaskStore = create<TaskState>((set, get) => ({
@@ -50,9 +51,13 @@ export const useTaskStore = create<TaskState>((set, get) => ({
       set((state) => ({
         tasks: state.tasks.map((t) => (t.id === id ? updated : t)),
       }));
+      // Completing a recurring task creates its next occurrence
+      if (updated.recurrence && update.status === 'done') {
+        await get().loadTasks();
+      }
     } catch (error) {
       console.error('Failed to update task:', error);
-      set({ error: String(error) });
+      set({ error: updateErrorMessage(error) });
       throw error;
     }
   },
@@ -70,7 +75,7 @@ export const useTaskStore = create<TaskState>((set, get) => ({
     }
   },
 
-  moveTask: async (taskId: number, newStatus: 'todo' | 'in_progress' | 'done') => {
+  moveTask: async (taskId: number, newStatus: TaskStatus) => {
     await get().updateTask(taskId, { status: newStatus });
   },
 }));
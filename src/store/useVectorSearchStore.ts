@@ -1,5 +1,6 @@
 import { create } from 'zustand';
 import { invoke } from '@tauri-apps/api/tauri';
+import { SectionRef } from '../types/document';
 
 export interface VectorIndexEntry {
   id: number;
@@ -27,6 +28,7 @@ export interface SimilaritySearchResult {
   content: string;
   similarity_score: number;
   metadata?: string;
+  section?: SectionRef | null;
 }
 
 export interface VectorIndexStats {
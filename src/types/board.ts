@@ -0,0 +1,69 @@
+// Kanban board types - aligned with Rust backend models/board.rs
+import type { Task, TaskStatus } from './task';
+
+export type SwimlaneGrouping = 'priority' | 'tag';
+
+export interface BoardColumn {
+  id: number;
+  board_id: number;
+  name: string;
+  status: TaskStatus;
+  position: number;
+  wip_limit?: number | null;
+}
+
+export interface Board {
+  id: number;
+  name: string;
+  swimlane_by?: SwimlaneGrouping | null;
+  columns: BoardColumn[];
+  created_at: string;
+  updated_at: string;
+}
+
+export interface CreateBoardColumn {
+  name: string;
+  status: TaskStatus;
+  wip_limit?: number | null;
+}
+
+export interface CreateBoard {
+  name: string;
+  swimlane_by?: SwimlaneGrouping | null;
+  columns: CreateBoardColumn[];
+}
+
+export interface UpdateBoard {
+  name?: string;
+  // Empty string removes swimlanes
+  swimlane_by?: SwimlaneGrouping | '';
+}
+
+export interface UpdateBoardColumn {
+  name?: string;
+  status?: TaskStatus;
+  // 0 removes the limit
+  wip_limit?: number;
+}
+
+// Drop target: directly after `after_id` and before `before_id`
+export interface MoveTask {
+  board_id: number;
+  status: TaskStatus;
+  after_id?: number | null;
+  before_id?: number | null;
+  expected_version?: number;
+}
+
+export interface BoardLane {
+  key: string;
+  label: string;
+  // Tasks per column, in column order
+  cells: Task[][];
+}
+
+export interface BoardView {
+  board: Board;
+  lanes: BoardLane[];
+  column_counts: number[];
+}
@@ -0,0 +1,26 @@
+// Calendar subscription types - ICS feeds shown as read-only layers
+export interface CalendarSubscription {
+  id: number;
+  name: string;
+  // File path, file:// URL or http(s) URL on localhost
+  url: string;
+  refresh_minutes: number;
+  enabled: boolean;
+  last_refreshed_at?: string | null;
+  last_error?: string | null;
+  event_count: number;
+  created_at: string;
+}
+
+export interface CreateSubscription {
+  name: string;
+  url: string;
+  refresh_minutes?: number | null;
+}
+
+export interface UpdateSubscription {
+  name?: string;
+  url?: string;
+  refresh_minutes?: number;
+  enabled?: boolean;
+}
@@ -0,0 +1,17 @@
+// Typed update error - aligned with Rust backend models/conflict.rs
+export type UpdateError<T> =
+  | { kind: 'conflict'; message: string; current: T }
+  | { kind: 'blocked'; message: string; current: T; blocked_by: number[] }
+  | { kind: 'wip_limit'; message: string; current: T; status: string; limit: number }
+  | { kind: 'failed'; message: string };
+
+export function isUpdateConflict<T>(error: unknown): error is { kind: 'conflict'; message: string; current: T } {
+  return typeof error === 'object' && error !== null && (error as { kind?: string }).kind === 'conflict';
+}
+
+export function updateErrorMessage(error: unknown): string {
+  if (typeof error === 'object' && error !== null && 'message' in error) {
+    return String((error as { message: unknown }).message);
+  }
+  return String(error);
+}
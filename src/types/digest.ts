@@ -0,0 +1,31 @@
+// Digest types - the morning agenda and the weekly review
+export type DigestKind = 'morning' | 'weekly';
+
+export interface DigestSchedule {
+  morning_enabled: boolean;
+  // "HH:MM" in the user's zone
+  morning_time: string;
+  weekly_enabled: boolean;
+  // ISO weekday, 1 = Monday
+  weekly_day: number;
+  weekly_time: string;
+  // Have Ollama summarize the weekly review
+  summarize: boolean;
+  folder_id?: number | null;
+}
+
+export interface DigestSection {
+  heading: string;
+  items: string[];
+}
+
+export interface Digest {
+  kind: DigestKind;
+  title: string;
+  start: string;
+  end: string;
+  sections: DigestSection[];
+  summary?: string | null;
+  headline: string;
+  document_id?: number | null;
+}
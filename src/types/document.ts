@@ -5,6 +5,8 @@ export interface Document {
   content: string;
   version: number;
   tags?: string | null;
+  folder_id?: number | null;
+  position: number;
   created_at: string;
   updated_at: string;
 }
@@ -13,6 +15,16 @@ export interface CreateDocument {
   title: string;
   content: string;
   tags?: string | null;
+  folder_id?: number | null;
+}
+
+export interface UpdateDocument {
+  title?: string;
+  content?: string;
+  tags?: string;
+  // Optimistic concurrency: rejected with a conflict if the document changed
+  expected_version?: number;
+  expected_updated_at?: string;
 }
 
 export interface DocumentVersion {
@@ -23,3 +35,27 @@ export interface DocumentVersion {
   created_at: string;
 }
 
+
+// Document outline - sections split at headings
+export interface OutlineSection {
+  index: number;
+  level: number;
+  heading: string;
+  path: string[];
+  anchor: string;
+  content: string;
+  word_count: number;
+}
+
+export interface DocumentOutline {
+  document_id: number;
+  title: string;
+  sections: OutlineSection[];
+}
+
+export interface SectionRef {
+  index: number;
+  heading: string;
+  path: string[];
+  anchor: string;
+}
@@ -7,6 +7,19 @@ export interface CalendarEvent {
   end_time: string;
   reminder_time?: string | null;
   recurrence?: string | null;
+  location?: string | null;
+  // All-day events use plain dates ("YYYY-MM-DD"); end_date is exclusive
+  all_day: boolean;
+  // Start times of cancelled occurrences
+  exdates: string[];
+  // Set on occurrences of a recurring series (expanded or edited)
+  series_id?: number | null;
+  // Start of the occurrence within its series, used to edit or delete it
+  occurrence_date?: string | null;
+  // Events from a calendar subscription are read-only
+  subscription_id?: number | null;
+  // Timed events are stored in UTC ("...Z") and repeat in this IANA zone
+  time_zone?: string | null;
   created_at: string;
 }
 
@@ -17,6 +30,11 @@ export interface CreateEvent {
   end_time: string;
   reminder_time?: string | null;
   recurrence?: string | null;
+  location?: string | null;
+  all_day?: boolean;
+  exdates?: string[];
+  // Zone for times without an offset; defaults to the user's zone
+  time_zone?: string | null;
 }
 
 export interface UpdateEvent {
@@ -26,5 +44,23 @@ export interface UpdateEvent {
   end_time?: string;
   reminder_time?: string | null;
   recurrence?: string | null;
+  location?: string | null;
+  all_day?: boolean;
+  exdates?: string[];
+  time_zone?: string | null;
 }
 
+export interface IcsImportOptions {
+  // Events are matched by UID within a source, so re-importing it updates them
+  source?: string | null;
+  // Delete events previously imported from this source that are gone from it
+  remove_missing?: boolean;
+}
+
+export interface IcsImportSummary {
+  created: number;
+  updated: number;
+  unchanged: number;
+  removed: number;
+  warnings: string[];
+}
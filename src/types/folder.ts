@@ -0,0 +1,51 @@
+// Folder and collection types - aligned with Rust backend folders / collections tables
+export interface Folder {
+  id: number;
+  name: string;
+  parent_id?: number | null;
+  position: number;
+  created_at: string;
+  updated_at: string;
+}
+
+export interface CreateFolder {
+  name: string;
+  parent_id?: number | null;
+}
+
+export interface FolderNode {
+  folder: Folder;
+  document_count: number;
+  children: FolderNode[];
+}
+
+export interface CollectionQuery {
+  tags?: string[];
+  tag_mode?: 'any' | 'all' | null;
+  created_after?: string | null;
+  created_before?: string | null;
+  updated_after?: string | null;
+  updated_before?: string | null;
+  linked_type?: string | null;
+  linked_id?: number | null;
+  folder_id?: number | null;
+  text?: string | null;
+}
+
+export interface Collection {
+  id: number;
+  name: string;
+  query: CollectionQuery;
+  created_at: string;
+  updated_at: string;
+}
+
+export interface CreateCollection {
+  name: string;
+  query: CollectionQuery;
+}
+
+export interface UpdateCollection {
+  name?: string | null;
+  query?: CollectionQuery | null;
+}
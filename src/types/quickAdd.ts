@@ -0,0 +1,19 @@
+// Quick-add types - one line of text parsed into a task or an event
+import type { CalendarEvent, CreateEvent } from './event';
+import type { CreateTask, Task } from './task';
+
+export type QuickAddSource = 'parser' | 'ai' | 'plain';
+
+export type QuickAddItem =
+  | ({ kind: 'task' } & CreateTask)
+  | ({ kind: 'event' } & CreateEvent);
+
+export interface QuickAddResult {
+  item: QuickAddItem;
+  tags: string[];
+  source: QuickAddSource;
+}
+
+export type QuickAddCreated =
+  | { kind: 'task'; task: Task; tags: string[]; source: QuickAddSource }
+  | { kind: 'event'; event: CalendarEvent; tags: string[]; source: QuickAddSource };
@@ -0,0 +1,18 @@
+// Reminder types - the persistent queue behind task and event notifications
+export type ReminderStatus = 'pending' | 'fired' | 'dismissed' | 'missed';
+
+export interface Reminder {
+  id: number;
+  item_type: 'task' | 'event' | 'custom';
+  // Series occurrences carry the id of their series
+  item_id?: number | null;
+  // Start of the series occurrence; empty otherwise
+  occurrence: string;
+  title: string;
+  body: string;
+  // UTC, "YYYY-MM-DDTHH:MM:SSZ"
+  fire_at: string;
+  snoozed_until?: string | null;
+  status: ReminderStatus;
+  fired_at?: string | null;
+}
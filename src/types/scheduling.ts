@@ -0,0 +1,82 @@
+// Scheduling types - conflicts, free/busy and free slots; times are UTC
+import { CalendarEvent } from './event';
+
+export interface TimeSlot {
+  start: string;
+  end: string;
+}
+
+export interface BusyBlock {
+  start: string;
+  end: string;
+  event_ids: number[];
+}
+
+export interface FreeBusy {
+  start: string;
+  end: string;
+  busy: BusyBlock[];
+  free: TimeSlot[];
+}
+
+export interface EventConflict {
+  // Start of the clashing occurrence of the event that was checked
+  occurrence_start: string;
+  event: CalendarEvent;
+}
+
+export interface ScheduledEvent {
+  event: CalendarEvent;
+  conflicts: EventConflict[];
+}
+
+export interface FreeSlotRequest {
+  duration_minutes: number;
+  start?: string | null;
+  end?: string | null;
+  // "HH:MM" in the user's zone; 09:00 to 17:00 by default
+  work_start?: string | null;
+  work_end?: string | null;
+  // ISO weekdays, 1 = Monday; Monday to Friday by default
+  work_days?: number[] | null;
+  buffer_minutes?: number;
+  count?: number | null;
+}
+
+// Options for planning tasks into the calendar; kept so later changes re-plan
+export interface PlanRequest {
+  start?: string | null;
+  end?: string | null;
+  work_start?: string | null;
+  work_end?: string | null;
+  work_days?: number[] | null;
+  buffer_minutes?: number;
+  // Shortest and longest block a task is split into; 30 and 120 by default
+  min_block_minutes?: number | null;
+  max_block_minutes?: number | null;
+  // Only plan these tasks; every open task with an estimate by default
+  task_ids?: number[] | null;
+}
+
+export interface PlannedBlock {
+  task_id: number;
+  event: CalendarEvent;
+  // Why the task landed here
+  reason: string;
+  // Edited by hand; re-planning leaves it alone
+  pinned: boolean;
+}
+
+export interface PlannedTask {
+  task_id: number;
+  title: string;
+  rank: number;
+  planned_minutes: number;
+  unplanned_minutes: number;
+  reason: string;
+}
+
+export interface TaskPlan {
+  tasks: PlannedTask[];
+  blocks: PlannedBlock[];
+}
@@ -0,0 +1,30 @@
+// Tag types - aligned with Rust backend tags / item_tags tables
+export type TaggableType = 'document' | 'task' | 'event' | 'file' | 'message';
+
+export interface Tag {
+  id: number;
+  name: string; // full path, e.g. "project/alpha"
+  parent_id?: number | null;
+  created_at: string;
+}
+
+export interface TagWithCount extends Tag {
+  item_count: number;
+  total_count: number;
+}
+
+export interface TaggedItem {
+  item_type: TaggableType;
+  item_id: number;
+  tag_id: number;
+  created_at: string;
+}
+
+export interface TagSuggestion {
+  id: number;
+  item_type: TaggableType;
+  item_id: number;
+  name: string;
+  source: 'ai' | 'metadata';
+  created_at: string;
+}
@@ -1,31 +1,99 @@
 // Task types - aligned with ARCHITECTURE.md database schema
+// Built-in statuses; boards can define their own workflow statuses
+export type TaskStatus = 'todo' | 'in_progress' | 'done' | (string & {});
+
 export interface Task {
   id: number;
   title: string;
   description?: string | null;
-  status: 'todo' | 'in_progress' | 'done';
+  status: TaskStatus;
   priority: 'low' | 'medium' | 'high';
   due_date?: string | null;
   reminder_time?: string | null;
+  // Zone the due time was set in; timed due dates are stored in UTC
+  time_zone?: string | null;
+  parent_id?: number | null;
+  // RFC 5545 RRULE, e.g. "FREQ=WEEKLY;BYDAY=MO,TH"
+  recurrence?: string | null;
+  // Id of the first task of a recurring series and this task's 1-based place in it
+  series_id?: number | null;
+  occurrence: number;
+  // Estimated effort in minutes
+  estimate?: number | null;
+  // Fractional order within the task's board column
+  position: number;
+  version: number;
   created_at: string;
   updated_at: string;
+  // Unfinished blockers, reported when an update completed the task anyway ("warn" policy)
+  open_blockers?: number[];
 }
 
 export interface CreateTask {
   title: string;
   description?: string | null;
-  status?: 'todo' | 'in_progress' | 'done';
+  status?: TaskStatus;
   priority?: 'low' | 'medium' | 'high';
   due_date?: string | null;
   reminder_time?: string | null;
+  parent_id?: number | null;
+  recurrence?: string | null;
+  estimate?: number | null;
+  // Zone for times without an offset; defaults to the user's zone
+  time_zone?: string | null;
 }
 
 export interface UpdateTask {
   title?: string;
   description?: string | null;
-  status?: 'todo' | 'in_progress' | 'done';
+  status?: TaskStatus;
   priority?: 'low' | 'medium' | 'high';
   due_date?: string | null;
   reminder_time?: string | null;
+  // An empty string stops the recurrence
+  recurrence?: string | null;
+  // Minutes; 0 clears the estimate
+  estimate?: number;
+  time_zone?: string | null;
+  // Board column order; a status change without one appends to the column
+  position?: number;
+  // Board the move is made on; its WIP limits apply. Without one, every
+  // board showing the new status is checked
+  board_id?: number;
+  // Optimistic concurrency: rejected with a conflict if the task changed
+  expected_version?: number;
+  expected_updated_at?: string;
+}
+
+export interface ChecklistItem {
+  id: number;
+  task_id: number;
+  text: string;
+  done: boolean;
+  position: number;
+  created_at: string;
+}
+
+export interface UpdateChecklistItem {
+  text?: string;
+  done?: boolean;
+  position?: number;
+}
+
+export interface TaskProgress {
+  completed: number;
+  total: number;
+  percent: number;
+}
+
+export interface TaskDetails {
+  task: Task;
+  subtasks: Task[];
+  checklist: ChecklistItem[];
+  blocked_by: Task[];
+  blocking: Task[];
+  progress: TaskProgress;
+  is_blocked: boolean;
+  tracked_seconds: number;
 }
 
@@ -0,0 +1,30 @@
+// AI task extraction types - aligned with Rust backend models/task_extraction.rs
+import type { CreateTask, Task } from './task';
+
+export type TaskSourceType = 'message' | 'document' | 'file';
+
+export interface TaskSource {
+  source_type: TaskSourceType;
+  source_id: number;
+}
+
+export interface TaskDraft {
+  task: CreateTask;
+  owner?: string | null;
+  /** The sentence the task was taken from */
+  excerpt?: string | null;
+}
+
+export interface TaskExtraction {
+  drafts: TaskDraft[];
+  /** Date relative due dates were resolved against */
+  source_date: string;
+  /** Model items that failed validation */
+  rejected: string[];
+}
+
+export interface CommittedTask {
+  task: Task;
+  owner?: string | null;
+  link_id?: number | null;
+}
@@ -0,0 +1,45 @@
+// Task import/export types - aligned with Rust backend models/task_transfer.rs
+import type { IngestionJob } from '../store/useIngestionStore';
+import type { Task, TaskStatus } from './task';
+
+export type TaskImportFormat = 'trello' | 'todoist' | 'todotxt' | 'csv' | 'ics';
+export type TaskExportFormat = 'csv' | 'todotxt' | 'taskwarrior' | 'ics';
+
+export interface ImportedChecklistItem {
+  text: string;
+  done: boolean;
+}
+
+export interface ImportedTask {
+  title: string;
+  description?: string | null;
+  status: TaskStatus;
+  priority: Task['priority'];
+  due_date?: string | null;
+  recurrence?: string | null;
+  reminder_time?: string | null;
+  /** IANA zone of the due time, when the source names one */
+  time_zone?: string | null;
+  /** Estimated effort in minutes */
+  estimate?: number | null;
+  tags: string[];
+  checklist: ImportedChecklistItem[];
+  /** Index of the parent task within the same import */
+  parent?: number | null;
+}
+
+export interface TaskImportRequest {
+  path: string;
+  /** Detected from the file when omitted */
+  format?: TaskImportFormat | null;
+  dry_run?: boolean;
+}
+
+export interface TaskImportResult {
+  job: IngestionJob;
+  format: TaskImportFormat;
+  dry_run: boolean;
+  tasks: ImportedTask[];
+  created_task_ids: number[];
+  warnings: string[];
+}
@@ -0,0 +1,39 @@
+// Document template types - aligned with Rust backend document_templates table
+export interface DocumentTemplate {
+  id: number;
+  name: string;
+  description?: string | null;
+  title_pattern?: string | null;
+  content: string;
+  tags?: string | null;
+  builtin: boolean;
+  created_at: string;
+  updated_at: string;
+}
+
+export interface CreateTemplate {
+  name: string;
+  description?: string | null;
+  title_pattern?: string | null;
+  content: string;
+  tags?: string | null;
+}
+
+export interface UpdateTemplate {
+  name?: string;
+  description?: string;
+  title_pattern?: string;
+  content?: string;
+  tags?: string;
+}
+
+export interface CreateFromTemplate {
+  template_id: number;
+  title?: string | null;
+  folder_id?: number | null;
+  task_ids?: number[];
+  event_ids?: number[];
+  document_ids?: number[];
+  variables?: Record<string, string>;
+  skip_ai?: boolean;
+}
@@ -0,0 +1,42 @@
+// Time tracking types - timers and manual entries per task
+export interface TimeEntry {
+  id: number;
+  task_id: number;
+  // UTC, "YYYY-MM-DD HH:MM:SS"
+  started_at: string;
+  // null while the timer is running
+  ended_at?: string | null;
+  duration_seconds: number;
+  note?: string | null;
+  event_id?: number | null;
+  created_at: string;
+}
+
+export interface CreateTimeEntry {
+  task_id: number;
+  started_at: string;
+  ended_at: string;
+  note?: string | null;
+}
+
+export interface UpdateTimeEntry {
+  started_at?: string;
+  ended_at?: string;
+  note?: string;
+}
+
+export type TimeReportGrouping = 'task' | 'tag' | 'week';
+
+export interface TimeReportRow {
+  key: string;
+  label: string;
+  seconds: number;
+  entries: number;
+  estimate_minutes?: number | null;
+}
+
+export interface TimeReport {
+  group_by: TimeReportGrouping;
+  rows: TimeReportRow[];
+  total_seconds: number;
+}